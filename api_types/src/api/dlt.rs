@@ -53,6 +53,9 @@ pub struct ApiGetNetworksResponse {
 pub struct Course {
     pub course: Decimal,
     pub date: String,
+    /// The fiat currency of the course, e.g. `EUR`. Not set for courses in `EUR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 // requests
@@ -63,6 +66,10 @@ pub struct Course {
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema, utoipa::IntoParams))]
 pub struct GetCourseRequestQueries {
     pub network_key: String,
+    /// The fiat currency of the course, e.g. `EUR`. Defaults to `EUR` if not set. The course in the
+    /// response states the currency it is in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -79,6 +86,10 @@ pub struct GetCourseHistoryRequestQueries {
     pub network_key: String,
     pub from_date: Option<String>,
     pub to_date: Option<String>,
+    /// The fiat currency of the courses, e.g. `EUR`. Defaults to `EUR` if not set. Each course in
+    /// the response states the currency it is in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -19,10 +19,19 @@ mod type_conversions;
 #[cfg(feature = "viviswap-kyc")]
 use sdk::types::File;
 
+use jni::{
+    JNIEnv, JavaVM,
+    objects::{GlobalRef, JClass, JObject, JString},
+};
 use once_cell::sync::OnceCell;
 use sdk::core::Sdk;
+use sdk::core::access_token::AccessTokenProvider;
+use sdk::types::newtypes::AccessToken;
 use std::sync::Arc;
-use tokio::{runtime::Runtime, sync::RwLock};
+use tokio::{
+    runtime::Runtime,
+    sync::{RwLock, broadcast::error::RecvError},
+};
 
 #[doc = r"Sdk handle with atomic reference count and read write lock"]
 type SdkWrapper = Arc<RwLock<Sdk>>;
@@ -62,19 +71,129 @@ fn get_or_init_sdk() -> &'static SdkWrapper {
     })
 }
 
+/// Forwards the requests for new access tokens from the SDK to the `AccessTokenProvider` implemented in Java.
+struct JavaAccessTokenProvider {
+    vm: JavaVM,
+    provider: GlobalRef,
+}
+
+#[async_trait::async_trait]
+impl AccessTokenProvider for JavaAccessTokenProvider {
+    async fn get_access_token(&self) -> Result<AccessToken, String> {
+        let mut env = self.vm.attach_current_thread().map_err(|e| format!("{e:#?}"))?;
+        let access_token = match env.call_method(&self.provider, "getAccessToken", "()Ljava/lang/String;", &[]) {
+            Ok(value) => value.l().map_err(|e| format!("{e:#?}"))?,
+            Err(e) => {
+                // clear the exception thrown by the provider, the request fails instead
+                let _ = env.exception_clear();
+                return Err(format!("{e:#?}"));
+            }
+        };
+        if access_token.is_null() {
+            return Err(String::from("The access token provider did not return an access token"));
+        }
+        let access_token: String = env
+            .get_string(&JString::from(access_token))
+            .map_err(|e| format!("{e:#?}"))?
+            .into();
+        if access_token.is_empty() {
+            return Err(String::from("The access token provider did not return an access token"));
+        }
+        AccessToken::try_from(access_token).map_err(|e| format!("{e:#?}"))
+    }
+}
+
+/// Set the Java `AccessTokenProvider` on the SDK. This function is written by hand, since `jnigen_macro` does
+/// not support Java objects as arguments.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_com_etospheres_etopay_AccessTokenProviders_setAccessTokenProviderJni<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    provider: JObject<'local>,
+) {
+    let provider = env.get_java_vm().and_then(|vm| {
+        Ok(JavaAccessTokenProvider {
+            vm,
+            provider: env.new_global_ref(provider)?,
+        })
+    });
+    match provider {
+        Ok(provider) => runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            sdk.set_access_token_provider(provider);
+        }),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("{e:#?}"));
+        }
+    }
+}
+
+/// Subscribe the Java `EventListener` to the events of the SDK. This function is written by hand, since
+/// `jnigen_macro` does not support Java objects as arguments.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_com_etospheres_etopay_EventListeners_subscribeJni<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    listener: JObject<'local>,
+) {
+    let listener = env.get_java_vm().and_then(|vm| Ok((vm, env.new_global_ref(listener)?)));
+    let (vm, listener) = match listener {
+        Ok(listener) => listener,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("{e:#?}"));
+            return;
+        }
+    };
+
+    let mut events = runtime().block_on(async { get_or_init_sdk().read().await.subscribe_events() });
+    runtime().spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("The event listener missed {missed} events");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let result = serde_json::to_string(&event)
+                .map_err(|e| format!("{e:#?}"))
+                .and_then(|event| notify_event_listener(&vm, &listener, event));
+            if let Err(e) = result {
+                log::warn!("Could not notify the event listener: {e}");
+            }
+        }
+    });
+}
+
+/// Call `onEvent` of the Java `EventListener` with the JSON-encoded event
+fn notify_event_listener(vm: &JavaVM, listener: &GlobalRef, event: String) -> Result<(), String> {
+    let mut env = vm.attach_current_thread().map_err(|e| format!("{e:#?}"))?;
+    let event = env.new_string(event).map_err(|e| format!("{e:#?}"))?;
+    if let Err(e) = env.call_method(listener, "onEvent", "(Ljava/lang/String;)V", &[(&event).into()]) {
+        // clear the exception thrown by the listener, it must not end the subscription
+        let _ = env.exception_clear();
+        return Err(format!("{e:#?}"));
+    }
+    Ok(())
+}
+
 /// Main object that contains all the functionality for interfacing with the ETOPaySdk.
 #[jnigen_macro::generate("com.etospheres.etopay.ETOPaySdk")]
 mod ffi {
     use super::*;
+    use sdk::core::events::{DEFAULT_EVENT_POLL_INTERVAL, run_event_poller};
     use sdk::{
         WalletError,
         core::Config,
         share::Share,
         types::{
             CryptoAmount,
+            currencies::FiatCurrency,
             newtypes::{AccessToken, EncryptionPin, PlainPassword},
         },
     };
+    use std::time::Duration;
     use type_conversions::GasCostEstimationEntity;
     use type_conversions::PurchaseDetailsEntity;
 
@@ -101,6 +220,19 @@ mod ffi {
         result.map_err(|e| format!("{e:#?}"))
     }
 
+    /// Exports the last lines of the log files, for example to attach them to a support ticket.
+    ///
+    /// @param lines The maximum number of lines to export
+    ///
+    /// @return The lines separated by newlines, oldest first. Empty if the logs are not written to a file.
+    pub fn exportLogs(lines: i64) -> Result<String, String> {
+        let result = runtime().block_on(async move {
+            let sdk = get_or_init_sdk().read().await;
+            sdk.export_logs(lines.max(0) as usize)
+        });
+        result.map(|lines| lines.join("\n")).map_err(|e| format!("{e:#?}"))
+    }
+
     /// Fetch available currencies and corresponding networks.
     ///
     /// @return Serialized string of ApiNetworks as value
@@ -116,6 +248,22 @@ mod ffi {
         }
     }
 
+    /// Fetch available currencies and corresponding networks together with the time they were fetched.
+    ///
+    /// @return Serialized string of the ApiNetworks as value, the time they were fetched as updated_at and
+    ///         whether fetching failed and the last known networks are returned as stale
+    pub fn getNetworksCached() -> Result<String, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            sdk.get_networks_cached().await
+        });
+
+        match result {
+            Ok(value) => serde_json::to_string(&value).map_err(|e| format!("{e:#?}")),
+            Err(e) => Err(format!("{e:#?}")),
+        }
+    }
+
     /// Selects the network for the ETOPay SDK.
     ///
     /// @param network_key The input string representing the network key
@@ -127,6 +275,35 @@ mod ffi {
         result.map_err(|e| format!("{e:#?}"))
     }
 
+    /// Adds a custom network for the current user, after validating it against its node.
+    /// Purchases are disabled for custom networks.
+    ///
+    /// @param network The network as JSON string, in the same format as returned by `getNetworks`
+    /// @return The stored network as JSON string
+    pub fn addCustomNetwork(network: String) -> Result<String, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            let network = serde_json::from_str(&network).map_err(|e| sdk::Error::Parse(e.to_string()))?;
+            sdk.add_custom_network(network).await
+        });
+
+        match result {
+            Ok(value) => serde_json::to_string(&value).map_err(|e| format!("{e:#?}")),
+            Err(e) => Err(format!("{e:#?}")),
+        }
+    }
+
+    /// Removes a custom network of the current user.
+    ///
+    /// @param network_key The key of the custom network
+    pub fn removeCustomNetwork(network_key: String) -> Result<(), String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            sdk.remove_custom_network(&network_key).await
+        });
+        result.map_err(|e| format!("{e:#?}"))
+    }
+
     /// Destructor for the SDK handle
     #[public_name = "close"]
     pub fn destroy() {
@@ -305,6 +482,40 @@ mod ffi {
         result.map_err(|e| format!("{e:#?}"))
     }
 
+    /// Fetches the current balance of the base crypto currency on the wallet together with the time it was fetched
+    ///
+    /// @param pin The input string representing the pin.
+    /// @return Serialized string of the balance as value, the time it was fetched as updated_at and
+    ///         whether fetching failed and the last known balance is returned as stale
+    pub fn getWalletBalanceCached(pin: String) -> Result<String, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            let pin = EncryptionPin::try_from_string(pin)?;
+            sdk.get_balance_cached(&pin).await
+        });
+
+        match result {
+            Ok(value) => serde_json::to_string(&value).map_err(|e| format!("{e:#?}")),
+            Err(e) => Err(format!("{e:#?}")),
+        }
+    }
+
+    /// Fetches the current balance on the wallet of the given network, without changing the selected network
+    ///
+    /// @param pin The input string representing the pin.
+    /// @param network_key The key of the network.
+    /// @return The current balance as a double precision floating point number
+    pub fn getWalletBalanceFor(pin: String, network_key: String) -> Result<f64, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            let pin = EncryptionPin::try_from_string(pin)?;
+            sdk.get_balance_for(&pin, &network_key)
+                .await
+                .and_then(|v| f64::try_from(v).map_err(Into::into))
+        });
+        result.map_err(|e| format!("{e:#?}"))
+    }
+
     /// Initialize the KYC process for Postident by generating a case id.
     ///
     /// <p>
@@ -387,7 +598,7 @@ mod ffi {
         purchase_type: String,
     ) -> Result<String, String> {
         let result = runtime().block_on(async move {
-            let sdk = get_or_init_sdk().write().await;
+            let mut sdk = get_or_init_sdk().write().await;
             let amount = CryptoAmount::try_from(amount)?;
             sdk.create_purchase_request(&receiver, amount, &product_hash, &app_data, &purchase_type)
                 .await
@@ -747,13 +958,41 @@ mod ffi {
     #[public_name = "pinVerify"]
     pub fn verifyPin(pin: String) -> Result<(), String> {
         let result = runtime().block_on(async move {
-            let sdk = get_or_init_sdk().write().await;
+            let mut sdk = get_or_init_sdk().write().await;
             let pin = EncryptionPin::try_from_string(pin)?;
             sdk.verify_pin(&pin).await
         });
         result.map_err(|e| format!("{e:#?}"))
     }
 
+    /// Unlocks the wallet, so that wallet operations reuse the wallet instead of deriving the keys
+    /// again on every call. While the wallet is unlocked, the SDK polls for the events delivered to
+    /// the listeners subscribed with `EventListeners.subscribe`.
+    ///
+    /// @param pin The pin of the wallet
+    /// @param ttl_secs How long the wallet stays unlocked, in seconds
+    pub fn unlockWallet(pin: String, ttl_secs: i64) -> Result<(), String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let ttl = Duration::from_secs(u64::try_from(ttl_secs).unwrap_or_default());
+            sdk.unlock_wallet(&pin, ttl).await
+        });
+        result.map_err(|e| format!("{e:#?}"))?;
+
+        runtime().spawn(run_event_poller(get_or_init_sdk().clone(), DEFAULT_EVENT_POLL_INTERVAL));
+        Ok(())
+    }
+
+    /// Locks the wallet again and stops polling for events
+    pub fn lockWallet() -> Result<(), String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            sdk.lock_wallet()
+        });
+        result.map_err(|e| format!("{e:#?}"))
+    }
+
     /// Resets the pin for the wallet using the wallet password
     ///
     /// @param new_pin The new pin to be set for the wallet
@@ -1025,7 +1264,7 @@ mod ffi {
     #[public_name = "getExchangeRate"]
     pub fn exchangeRateViviswap() -> Result<f64, String> {
         let result = runtime().block_on(async move {
-            let sdk = get_or_init_sdk().write().await;
+            let mut sdk = get_or_init_sdk().write().await;
             sdk.get_exchange_rate()
                 .await
                 .and_then(|amount| Ok(f64::try_from(amount)?))
@@ -1033,6 +1272,55 @@ mod ffi {
         result.map_err(|e| format!("{e:#?}"))
     }
 
+    /// Gets the current exchange rate for the cryptocurrency to EURO together with the time it was fetched
+    ///
+    /// @return Serialized string of the exchange rate as value, the time it was fetched as updated_at and
+    ///         whether fetching failed and the last known exchange rate is returned as stale
+    pub fn getExchangeRateCached() -> Result<String, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            sdk.get_exchange_rate_cached().await
+        });
+
+        match result {
+            Ok(value) => serde_json::to_string(&value).map_err(|e| format!("{e:#?}")),
+            Err(e) => Err(format!("{e:#?}")),
+        }
+    }
+
+    /// Fetches the current balance on the wallet of the selected network, valued in a fiat currency
+    ///
+    /// @param pin The input string representing the pin.
+    /// @param currency The fiat currency, one of `EUR`, `USD` or `CHF`.
+    /// @return The value of the balance as a double precision floating point number
+    pub fn getFiatBalance(pin: String, currency: String) -> Result<f64, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let currency = FiatCurrency::try_from(currency)?;
+            let value = sdk.get_fiat_balance(&pin, currency).await?;
+            Ok::<_, sdk::Error>(f64::try_from(value.value)?)
+        });
+        result.map_err(|e| format!("{e:#?}"))
+    }
+
+    /// Fetches the balances on all networks and returns their total value in a fiat currency.
+    /// Networks whose balance or exchange rate cannot be fetched are not included.
+    ///
+    /// @param pin The input string representing the pin.
+    /// @param currency The fiat currency, one of `EUR`, `USD` or `CHF`.
+    /// @return The total value as a double precision floating point number
+    pub fn getPortfolioValue(pin: String, currency: String) -> Result<f64, String> {
+        let result = runtime().block_on(async move {
+            let mut sdk = get_or_init_sdk().write().await;
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let currency = FiatCurrency::try_from(currency)?;
+            let portfolio = sdk.get_portfolio_value(&pin, currency).await?;
+            Ok::<_, sdk::Error>(f64::try_from(portfolio.total)?)
+        });
+        result.map_err(|e| format!("{e:#?}"))
+    }
+
     /// Deletes the user in ETOPay. Hazmat!
     ///
     /// @param pin The wallet pin for confirmation. Optional in case there is an active wallet.
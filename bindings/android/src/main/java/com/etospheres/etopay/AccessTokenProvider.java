@@ -0,0 +1,16 @@
+package com.etospheres.etopay;
+
+/**
+ * Provides new access tokens when the current one is expired or rejected by the backend.
+ * 
+ * The SDK may call the provider from any thread, so implementations must be thread-safe.
+ */
+@FunctionalInterface
+public interface AccessTokenProvider {
+    /**
+     * Get a new access token.
+     * 
+     * @return The new access token, or an empty string if the user needs to log in again.
+     */
+    String getAccessToken() throws Exception;
+}
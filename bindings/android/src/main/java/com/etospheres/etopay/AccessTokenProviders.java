@@ -0,0 +1,25 @@
+package com.etospheres.etopay;
+
+/**
+ * Registers the {@link AccessTokenProvider} used by the ETOPaySdk.
+ */
+public final class AccessTokenProviders {
+    static {
+        System.loadLibrary("etopaysdk");
+    }
+
+    private AccessTokenProviders() {
+    }
+
+    private static native void setAccessTokenProviderJni(AccessTokenProvider provider);
+
+    /**
+     * Set a provider for new access tokens. The SDK calls it when the current access token is expired
+     * or rejected by the backend, and retries the request once with the new access token.
+     * 
+     * @param provider The provider of new access tokens.
+     */
+    public static void set(AccessTokenProvider provider) throws Exception {
+        setAccessTokenProviderJni(provider);
+    }
+}
@@ -0,0 +1,16 @@
+package com.etospheres.etopay;
+
+/**
+ * Receives the events of the ETOPaySdk, e.g. when the balance changed or a transaction was confirmed.
+ * 
+ * The listener is called from a background thread, so implementations must be thread-safe.
+ */
+@FunctionalInterface
+public interface EventListener {
+    /**
+     * Called for every event.
+     * 
+     * @param event The JSON-encoded event, e.g. {@code {"type": "NetworkChanged", "network_key": "IOTA"}}
+     */
+    void onEvent(String event);
+}
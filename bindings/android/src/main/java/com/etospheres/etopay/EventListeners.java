@@ -0,0 +1,26 @@
+package com.etospheres.etopay;
+
+/**
+ * Subscribes {@link EventListener}s to the events of the ETOPaySdk.
+ */
+public final class EventListeners {
+    static {
+        System.loadLibrary("etopaysdk");
+    }
+
+    private EventListeners() {
+    }
+
+    private static native void subscribeJni(EventListener listener);
+
+    /**
+     * Subscribe a listener to the events of the SDK. Apart from the network selection, the events are
+     * detected by polling in the background while the wallet is unlocked with
+     * {@link ETOPaySdk#unlockWallet(String, long)}.
+     * 
+     * @param listener The listener to call for every event.
+     */
+    public static void subscribe(EventListener listener) throws Exception {
+        subscribeJni(listener);
+    }
+}
@@ -14,22 +14,56 @@
 //! The conversion of types between Swift and Rust is done in the `type_conversion.rs` module.
 
 use crate::ffi::{
-    CaseDetailsResponse, File, GasCostEstimation, IdentityOfficialDocumentData, IdentityPersonalDocumentData,
-    NewCaseIdResponse, NewViviswapUser, Protocol, PurchaseDetails, TxStatus, ViviswapAddressDetail, ViviswapDeposit,
-    ViviswapKycStatus, ViviswapPartiallyKycDetails, ViviswapWithdrawal, WalletTxStatus,
+    CachedAmount, CaseDetailsResponse, File, GasCostEstimation, IdentityOfficialDocumentData,
+    IdentityPersonalDocumentData, NewCaseIdResponse, NewViviswapUser, Protocol, PurchaseDetails, TxStatus,
+    ViviswapAddressDetail, ViviswapDeposit, ViviswapKycStatus, ViviswapPartiallyKycDetails, ViviswapWithdrawal,
+    WalletTxStatus,
 };
+use sdk::core::events::{DEFAULT_EVENT_POLL_INTERVAL, SdkEvent, run_event_poller};
 use sdk::core::{Config, Sdk};
 use sdk::types::CryptoAmount;
+use sdk::types::currencies::FiatCurrency;
 use sdk::types::networks::ApiProtocol;
 use sdk::types::newtypes::{AccessToken, EncryptionPin, PlainPassword};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock, broadcast, broadcast::error::RecvError, mpsc, oneshot};
+
+/// A request of the SDK for a new access token, answered with `provide_access_token`
+type AccessTokenRequest = oneshot::Sender<Result<AccessToken, String>>;
+
+/// Passes the requests for new access tokens from the SDK to the Swift app, which waits for them
+/// with `next_access_token_request`.
+struct ChannelAccessTokenProvider(mpsc::Sender<AccessTokenRequest>);
+
+#[async_trait::async_trait]
+impl sdk::core::access_token::AccessTokenProvider for ChannelAccessTokenProvider {
+    async fn get_access_token(&self) -> Result<AccessToken, String> {
+        let (sender, receiver) = oneshot::channel();
+        self.0
+            .send(sender)
+            .await
+            .map_err(|_| String::from("Access token requests are no longer received"))?;
+        receiver
+            .await
+            .map_err(|_| String::from("The access token request was not answered"))?
+    }
+}
+
+/// The requests of the SDK for new access tokens that the Swift app has not answered yet
+#[derive(Default)]
+struct AccessTokenRequests {
+    receiver: Mutex<Option<mpsc::Receiver<AccessTokenRequest>>>,
+    pending: Mutex<Option<AccessTokenRequest>>,
+}
 
 /// Struct representing the ETOPay SDK with an inner data structure wrapped in an atomic reference count and read-write lock.
 /// Utilizes atomic reference counting (`Arc`) and a read-write lock (`RwLock`) to provide thread-safe access to the inner data structure,
 /// allowing multiple threads to concurrently read from or write to the ETOPay SDK while ensuring data integrity and preventing data races.
 pub struct ETOPaySdk {
     inner: Arc<RwLock<sdk::core::Sdk>>,
+    access_token_requests: AccessTokenRequests,
+    events: Mutex<Option<broadcast::Receiver<SdkEvent>>>,
 }
 
 impl ETOPaySdk {
@@ -42,6 +76,8 @@ impl ETOPaySdk {
     pub fn new() -> Self {
         Self {
             inner: Arc::new(RwLock::new(sdk::core::Sdk::default())),
+            access_token_requests: AccessTokenRequests::default(),
+            events: Mutex::new(None),
         }
     }
     /// Sets the configuration as a JSON-encoded string.
@@ -69,6 +105,23 @@ impl ETOPaySdk {
             .map_err(|err| format!("{:#?}", err))
     }
 
+    /// Export the last lines of the log files, for example to attach them to a support ticket.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The maximum number of lines to export.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the lines separated by newlines, oldest first. Empty if the logs are not written to a file.
+    /// * Err - if the log files cannot be read.
+    pub async fn export_logs(&self, lines: u32) -> Result<String, String> {
+        let sdk = self.inner.read().await;
+        sdk.export_logs(lines as usize)
+            .map(|lines| lines.join("\n"))
+            .map_err(|err| format!("{:#?}", err))
+    }
+
     /// Fetch available networks.
     ///
     /// # Returns
@@ -86,6 +139,20 @@ impl ETOPaySdk {
         .map_err(|err| format!("{:#?}", err))
     }
 
+    /// Fetch available networks together with the time they were fetched.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the networks, marked as stale if fetching failed and the last known networks are returned.
+    /// * Err - if there is an error fetching the networks and none are cached.
+    pub async fn get_networks_cached(&self) -> Result<CachedNetworks, String> {
+        let mut sdk = self.inner.write().await;
+        sdk.get_networks_cached()
+            .await
+            .map(CachedNetworks::from)
+            .map_err(|err| format!("{:#?}", err))
+    }
+
     /// Selects the network for the ETOPay SDK.
     ///
     /// # Arguments
@@ -101,6 +168,44 @@ impl ETOPaySdk {
         sdk.set_network(network_key).await.map_err(|e| format!("{e:#?}"))
     }
 
+    /// Adds a custom network for the current user, after validating it against its node.
+    /// Purchases are disabled for custom networks.
+    ///
+    /// # Arguments
+    ///
+    /// * `network` - The network as JSON string, in the same format as the `ApiNetwork` of the SDK.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the stored network.
+    /// * Err - if the network is invalid or cannot be validated.
+    pub async fn add_custom_network(&self, network: String) -> Result<Network, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let network = serde_json::from_str(&network).map_err(|e| sdk::Error::Parse(e.to_string()))?;
+            sdk.add_custom_network(network).await.map(Into::into)
+        }
+        .await
+        .map_err(|err| format!("{:#?}", err))
+    }
+
+    /// Removes a custom network of the current user.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_key` - The key of the custom network.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - if the network is removed successfully.
+    /// * Err - if something went wrong.
+    pub async fn remove_custom_network(&self, network_key: String) -> Result<(), String> {
+        let mut sdk = self.inner.write().await;
+        sdk.remove_custom_network(&network_key)
+            .await
+            .map_err(|e| format!("{e:#?}"))
+    }
+
     /// Destructor for the SDK handle
     ///
     /// # Arguments
@@ -174,6 +279,64 @@ impl ETOPaySdk {
         .map_err(|err| format!("{:#?}", err))
     }
 
+    /// Lets the SDK request new access tokens from the app
+    ///
+    /// The SDK requests a new access token when the current one is expired or rejected by the
+    /// backend, and retries the request once with the new access token. The app waits for the
+    /// requests with `next_access_token_request` and answers each with `provide_access_token`.
+    pub async fn enable_access_token_requests(&self) -> Result<(), String> {
+        let (sender, receiver) = mpsc::channel(1);
+        *self.access_token_requests.receiver.lock().await = Some(receiver);
+        let mut sdk = self.inner.write().await;
+        sdk.set_access_token_provider(ChannelAccessTokenProvider(sender));
+        Ok(())
+    }
+
+    /// Waits until the SDK needs a new access token, which is then passed to `provide_access_token`
+    ///
+    /// # Returns
+    ///
+    /// * Ok - if a new access token is needed.
+    /// * Err - if access token requests are not enabled.
+    pub async fn next_access_token_request(&self) -> Result<(), String> {
+        let mut receiver = self.access_token_requests.receiver.lock().await;
+        let request = match receiver.as_mut() {
+            Some(receiver) => receiver.recv().await,
+            None => None,
+        }
+        .ok_or_else(|| String::from("Access token requests are not enabled"))?;
+        *self.access_token_requests.pending.lock().await = Some(request);
+        Ok(())
+    }
+
+    /// Answers the last request for a new access token
+    ///
+    /// # Arguments
+    ///
+    /// * `access_token` - The new access token, or an empty string if the user has to log in again.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - if the access token was passed to the SDK.
+    /// * Err - if no access token was requested.
+    pub async fn provide_access_token(&self, access_token: String) -> Result<(), String> {
+        let request = self
+            .access_token_requests
+            .pending
+            .lock()
+            .await
+            .take()
+            .ok_or_else(|| String::from("No access token was requested"))?;
+        let access_token = if access_token.is_empty() {
+            Err(String::from("The app did not provide an access token"))
+        } else {
+            AccessToken::try_from(access_token).map_err(|err| format!("{:#?}", err))
+        };
+        // the request is dropped if the backend call was cancelled in the meantime
+        let _ = request.send(access_token);
+        Ok(())
+    }
+
     /// Gets the kyc verification status
     ///
     /// # Arguments
@@ -365,6 +528,50 @@ impl ETOPaySdk {
         .map_err(|err| format!("{:#?}", err))
     }
 
+    /// Get the balance of the user together with the time it was fetched
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN for the wallet.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the balance, marked as stale if fetching failed and the last known balance is returned.
+    /// * Err - if there is an error initializing the wallet and no balance is cached.
+    pub async fn get_balance_cached(&self, pin: String) -> Result<CachedAmount, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let balance = sdk.get_balance_cached(&pin).await?;
+            Ok::<_, sdk::Error>(CachedAmount::new(f64::try_from(balance.value)?, &balance))
+        }
+        .await
+        .map_err(|err| format!("{:#?}", err))
+    }
+
+    /// Fetches the current balance on the wallet of the given network, without changing the selected network
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin for verification
+    /// * `network_key` - The key of the network
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the balance as a f64 if successful.
+    /// * Err - if the network is unknown or there is an error initializing the wallet.
+    pub async fn get_balance_for(&self, pin: String, network_key: String) -> Result<f64, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            sdk.get_balance_for(&pin, &network_key)
+                .await
+                .and_then(|v| f64::try_from(v).map_err(Into::into))
+        }
+        .await
+        .map_err(|err| format!("{:#?}", err))
+    }
+
     /// Starts KYC verification with Postident
     ///
     /// Note: This method is only available if the SDK is compiled with support for postident.
@@ -444,7 +651,7 @@ impl ETOPaySdk {
         app_data: String,
         purchase_type: String,
     ) -> Result<String, String> {
-        let sdk = self.inner.write().await;
+        let mut sdk = self.inner.write().await;
         async move {
             let amount = CryptoAmount::try_from(amount)?;
             sdk.create_purchase_request(&receiver, amount, &product_hash, &app_data, &purchase_type)
@@ -805,7 +1012,7 @@ impl ETOPaySdk {
     /// * Err - if there is an error for initializing the repository, initializing the user, initializing the wallet, password is missing,
     ///   pin or password is incorrect.
     pub async fn verify_pin(&self, pin: String) -> Result<(), String> {
-        let sdk = self.inner.write().await;
+        let mut sdk = self.inner.write().await;
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
             sdk.verify_pin(&pin).await
@@ -814,6 +1021,74 @@ impl ETOPaySdk {
         .map_err(|err| format!("{:#?}", err))
     }
 
+    /// Unlocks the wallet
+    ///
+    /// Wallet operations reuse the wallet instead of deriving the keys again on every call. While
+    /// the wallet is unlocked, the SDK polls for the events delivered to the listeners subscribed
+    /// with `subscribe_events`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin of the wallet.
+    /// * `ttl_secs` - How long the wallet stays unlocked, in seconds.
+    ///
+    /// # Returns
+    ///
+    /// * Ok - empty if the wallet is unlocked.
+    /// * Err - if the user is not initialized or the pin is incorrect.
+    pub async fn unlock_wallet(&self, pin: String, ttl_secs: u64) -> Result<(), String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            sdk.unlock_wallet(&pin, Duration::from_secs(ttl_secs)).await
+        }
+        .await
+        .map_err(|err| format!("{:#?}", err))?;
+
+        tokio::spawn(run_event_poller(self.inner.clone(), DEFAULT_EVENT_POLL_INTERVAL));
+        Ok(())
+    }
+
+    /// Locks the wallet again and stops polling for events
+    ///
+    /// # Returns
+    ///
+    /// * Ok - empty if the wallet is locked.
+    /// * Err - if the user is not initialized.
+    pub async fn lock_wallet(&self) -> Result<(), String> {
+        let mut sdk = self.inner.write().await;
+        sdk.lock_wallet().map_err(|err| format!("{:#?}", err))
+    }
+
+    /// Subscribes to the events of the SDK
+    ///
+    /// Events emitted from now on are buffered until they are received with `next_event`.
+    /// Subscribing again drops the events that were not received yet.
+    pub async fn subscribe_events(&self) {
+        let events = self.inner.read().await.subscribe_events();
+        *self.events.lock().await = Some(events);
+    }
+
+    /// Waits for the next event of the SDK
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the JSON-encoded event.
+    /// * Err - if the app did not subscribe to the events with `subscribe_events`.
+    pub async fn next_event(&self) -> Result<String, String> {
+        let mut events = self.events.lock().await;
+        let events = events
+            .as_mut()
+            .ok_or_else(|| String::from("Not subscribed to the events"))?;
+        loop {
+            match events.recv().await {
+                Ok(event) => return serde_json::to_string(&event).map_err(|err| format!("{:#?}", err)),
+                Err(RecvError::Lagged(missed)) => log::warn!("The app missed {missed} events"),
+                Err(RecvError::Closed) => return Err(String::from("The SDK no longer emits events")),
+            }
+        }
+    }
+
     /// Resets the wallet pin
     ///
     /// # Arguments
@@ -1115,13 +1390,74 @@ impl ETOPaySdk {
     /// * Ok - the exchange rate as f32 if successful.
     /// * Err - if viviswap API error.
     pub async fn get_exchange_rate(&self) -> Result<f64, String> {
-        let sdk = self.inner.write().await;
+        let mut sdk = self.inner.write().await;
         sdk.get_exchange_rate()
             .await
             .and_then(|v| Ok(f64::try_from(v)?))
             .map_err(|err| format!("{:#?}", err))
     }
 
+    /// Gets exchange rate from SDK together with the time it was fetched
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the exchange rate, marked as stale if fetching failed and the last known exchange rate is returned.
+    /// * Err - if viviswap API error and no exchange rate is cached.
+    pub async fn get_exchange_rate_cached(&self) -> Result<CachedAmount, String> {
+        let mut sdk = self.inner.write().await;
+        sdk.get_exchange_rate_cached()
+            .await
+            .and_then(|v| Ok(CachedAmount::new(f64::try_from(v.value)?, &v)))
+            .map_err(|err| format!("{:#?}", err))
+    }
+
+    /// Fetches the current balance on the wallet of the selected network, valued in a fiat currency
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin for verification
+    /// * `currency` - The fiat currency, one of `EUR`, `USD` or `CHF`
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the value of the balance as a f64 if successful.
+    /// * Err - if the currency is not supported or the balance or exchange rate cannot be fetched.
+    pub async fn get_fiat_balance(&self, pin: String, currency: String) -> Result<f64, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let currency = FiatCurrency::try_from(currency)?;
+            let value = sdk.get_fiat_balance(&pin, currency).await?;
+            Ok::<_, sdk::Error>(f64::try_from(value.value)?)
+        }
+        .await
+        .map_err(|err| format!("{:#?}", err))
+    }
+
+    /// Fetches the balances on all networks and returns their total value in a fiat currency.
+    /// Networks whose balance or exchange rate cannot be fetched are not included.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin for verification
+    /// * `currency` - The fiat currency, one of `EUR`, `USD` or `CHF`
+    ///
+    /// # Returns
+    ///
+    /// * Ok - the total value as a f64 if successful.
+    /// * Err - if the currency is not supported or the balances cannot be fetched.
+    pub async fn get_portfolio_value(&self, pin: String, currency: String) -> Result<f64, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let currency = FiatCurrency::try_from(currency)?;
+            let portfolio = sdk.get_portfolio_value(&pin, currency).await?;
+            Ok::<_, sdk::Error>(f64::try_from(portfolio.total)?)
+        }
+        .await
+        .map_err(|err| format!("{:#?}", err))
+    }
+
     /// Creates withdraw with viviswap
     ///
     /// # Arguments
@@ -1595,6 +1931,26 @@ impl WalletTxInfo {
     }
 }
 
+pub struct CachedNetworks {
+    pub networks: Vec<sdk::types::networks::ApiNetwork>,
+    pub updated_at: String,
+    pub stale: bool,
+}
+
+impl CachedNetworks {
+    pub fn networks(&self) -> Vec<Network> {
+        self.networks.iter().cloned().map(Network::from).collect()
+    }
+
+    pub fn updated_at(&self) -> String {
+        self.updated_at.clone()
+    }
+
+    pub fn stale(&self) -> bool {
+        self.stale
+    }
+}
+
 pub struct Network {
     pub key: String,
     pub is_testnet: bool,
@@ -47,6 +47,13 @@ pub mod ffi {
         pub invalid_reasons: Vec<String>,
     }
 
+    #[swift_bridge(swift_repr = "struct")]
+    pub struct CachedAmount {
+        pub value: f64,
+        pub updated_at: String,
+        pub stale: bool,
+    }
+
     #[swift_bridge(swift_repr = "struct")]
     pub struct GasCostEstimation {
         pub max_fee_per_gas: String,
@@ -280,6 +287,14 @@ pub mod ffi {
         fn protocol_contract_address(&self) -> Option<String>;
     }
 
+    extern "Rust" {
+        type CachedNetworks;
+
+        fn networks(&self) -> Vec<Network>;
+        fn updated_at(&self) -> String;
+        fn stale(&self) -> bool;
+    }
+
     // Export Rust functions with the above shared types for Swift.
     extern "Rust" {
         type ETOPaySdk;
@@ -289,10 +304,18 @@ pub mod ffi {
 
         #[swift_bridge(swift_name = "setConfig")]
         async fn set_config(&self, config: String) -> Result<(), String>;
+        #[swift_bridge(swift_name = "exportLogs")]
+        async fn export_logs(&self, lines: u32) -> Result<String, String>;
         #[swift_bridge(swift_name = "getNetworks")]
         async fn get_networks(&self) -> Result<Vec<Network>, String>;
+        #[swift_bridge(swift_name = "getNetworksCached")]
+        async fn get_networks_cached(&self) -> Result<CachedNetworks, String>;
         #[swift_bridge(swift_name = "setNetwork")]
         async fn set_network(&self, network_key: String) -> Result<(), String>;
+        #[swift_bridge(swift_name = "addCustomNetwork")]
+        async fn add_custom_network(&self, network: String) -> Result<Network, String>;
+        #[swift_bridge(swift_name = "removeCustomNetwork")]
+        async fn remove_custom_network(&self, network_key: String) -> Result<(), String>;
         async fn destroy(&self) -> Result<(), String>;
         #[swift_bridge(swift_name = "createNewUser")]
         async fn create_new_user(&self, username: String) -> Result<(), String>;
@@ -300,6 +323,12 @@ pub mod ffi {
         async fn init_user(&self, username: String) -> Result<(), String>;
         #[swift_bridge(swift_name = "refreshAccessToken")]
         async fn refresh_access_token(&self, access_token: String) -> Result<(), String>;
+        #[swift_bridge(swift_name = "enableAccessTokenRequests")]
+        async fn enable_access_token_requests(&self) -> Result<(), String>;
+        #[swift_bridge(swift_name = "nextAccessTokenRequest")]
+        async fn next_access_token_request(&self) -> Result<(), String>;
+        #[swift_bridge(swift_name = "provideAccessToken")]
+        async fn provide_access_token(&self, access_token: String) -> Result<(), String>;
         #[swift_bridge(swift_name = "isKycVerified")]
         async fn is_kyc_verified(&self, username: String) -> Result<bool, String>;
         #[swift_bridge(swift_name = "verifyMnemonic")]
@@ -323,6 +352,10 @@ pub mod ffi {
         async fn generate_new_address(&self, pin: String) -> Result<String, String>;
         #[swift_bridge(swift_name = "getWalletBalance")]
         async fn get_balance(&self, pin: String) -> Result<f64, String>;
+        #[swift_bridge(swift_name = "getWalletBalanceCached")]
+        async fn get_balance_cached(&self, pin: String) -> Result<CachedAmount, String>;
+        #[swift_bridge(swift_name = "getWalletBalanceFor")]
+        async fn get_balance_for(&self, pin: String, network_key: String) -> Result<f64, String>;
 
         // functions for postident, actual implementation is hidden behind feature flag
         #[swift_bridge(swift_name = "initKycVerificationForPostident")]
@@ -409,6 +442,14 @@ pub mod ffi {
         ) -> Result<(), String>;
         #[swift_bridge(swift_name = "verifyPin")]
         async fn verify_pin(&self, pin: String) -> Result<(), String>;
+        #[swift_bridge(swift_name = "unlockWallet")]
+        async fn unlock_wallet(&self, pin: String, ttl_secs: u64) -> Result<(), String>;
+        #[swift_bridge(swift_name = "lockWallet")]
+        async fn lock_wallet(&self) -> Result<(), String>;
+        #[swift_bridge(swift_name = "subscribeEvents")]
+        async fn subscribe_events(&self);
+        #[swift_bridge(swift_name = "nextEvent")]
+        async fn next_event(&self) -> Result<String, String>;
         #[swift_bridge(swift_name = "resetPin")]
         async fn reset_pin(&self, pin: String, new_pin: String) -> Result<(), String>;
         #[swift_bridge(swift_name = "setWalletPassword")]
@@ -450,6 +491,12 @@ pub mod ffi {
         async fn get_swap_details(&self, order_id: String) -> Result<Order, String>;
         #[swift_bridge(swift_name = "getExchangeRate")]
         async fn get_exchange_rate(&self) -> Result<f64, String>;
+        #[swift_bridge(swift_name = "getExchangeRateCached")]
+        async fn get_exchange_rate_cached(&self) -> Result<CachedAmount, String>;
+        #[swift_bridge(swift_name = "getFiatBalance")]
+        async fn get_fiat_balance(&self, pin: String, currency: String) -> Result<f64, String>;
+        #[swift_bridge(swift_name = "getPortfolioValue")]
+        async fn get_portfolio_value(&self, pin: String, currency: String) -> Result<f64, String>;
         #[swift_bridge(swift_name = "deleteUser")]
         async fn delete_user(&self, pin: Option<String>) -> Result<(), String>;
         #[swift_bridge(swift_name = "getSwapList")]
@@ -254,6 +254,26 @@ impl From<WalletTransaction> for crate::ffi_functions::WalletTxInfo {
     }
 }
 
+impl From<sdk::types::users::Cached<Vec<sdk::types::networks::ApiNetwork>>> for crate::ffi_functions::CachedNetworks {
+    fn from(value: sdk::types::users::Cached<Vec<sdk::types::networks::ApiNetwork>>) -> Self {
+        crate::ffi_functions::CachedNetworks {
+            networks: value.value,
+            updated_at: value.updated_at.to_rfc3339(),
+            stale: value.stale,
+        }
+    }
+}
+
+impl ffi::CachedAmount {
+    pub fn new<T>(value: f64, cached: &sdk::types::users::Cached<T>) -> Self {
+        ffi::CachedAmount {
+            value,
+            updated_at: cached.updated_at.to_rfc3339(),
+            stale: cached.stale,
+        }
+    }
+}
+
 impl From<sdk::types::networks::ApiNetwork> for crate::ffi_functions::Network {
     fn from(value: sdk::types::networks::ApiNetwork) -> Self {
         crate::ffi_functions::Network {
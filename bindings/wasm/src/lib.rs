@@ -9,16 +9,53 @@ use crate::utils::set_panic_hook;
 #[cfg(feature = "viviswap-kyc")]
 use sdk::types::File;
 
+use sdk::core::events::{DEFAULT_EVENT_POLL_INTERVAL, run_event_poller};
 use sdk::{
     core::{Config, Sdk},
     types::{
         CryptoAmount,
+        currencies::FiatCurrency,
         newtypes::{AccessToken, EncryptionPin, PlainPassword},
     },
 };
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{RwLock, broadcast::error::RecvError};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{js_sys, spawn_local};
+
+#[cfg(target_arch = "wasm32")]
+use sdk::core::access_token::AccessTokenProvider;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_futures::JsFuture;
+
+/// Access token provider calling a JavaScript function that returns a `Promise<string>`
+#[cfg(target_arch = "wasm32")]
+struct JsAccessTokenProvider(js_sys::Function);
+
+#[cfg(target_arch = "wasm32")]
+#[async_trait::async_trait(?Send)]
+impl AccessTokenProvider for JsAccessTokenProvider {
+    async fn get_access_token(&self) -> Result<AccessToken, String> {
+        let promise = self.0.call0(&JsValue::NULL).map_err(|e| format!("{e:?}"))?;
+        let access_token = JsFuture::from(js_sys::Promise::resolve(&promise))
+            .await
+            .map_err(|e| format!("{e:?}"))?
+            .as_string()
+            .ok_or_else(|| String::from("The access token provider did not return a string"))?;
+        AccessToken::try_from(access_token).map_err(|e| format!("{e:#?}"))
+    }
+}
+
+/// Wait until the user storage completed the writes of an SDK call, then return the result of the
+/// call. The IndexedDB storage writes asynchronously, so this makes sure that secrets like the
+/// local share or the wrong pin attempts are persisted before the promise resolves.
+async fn persisted<T>(sdk: &Sdk, result: sdk::Result<T>) -> sdk::Result<T> {
+    let flushed = sdk.flush_user_storage().await;
+    let value = result?;
+    flushed?;
+    Ok(value)
+}
 
 /// Main object that contains all the functionality for interfacing with the ETOPaySdk.
 #[wasm_bindgen]
@@ -63,7 +100,15 @@ impl ETOPaySdk {
         let mut sdk = self.inner.write().await;
         Config::from_json(&config)
             .and_then(|r| sdk.set_config(r))
-            .map_err(|err| format!("{:#?}", err))
+            .map_err(|err| format!("{:#?}", err))?;
+
+        // prefer IndexedDB over the local storage, it is also available in web workers
+        #[cfg(target_arch = "wasm32")]
+        sdk.initialize_indexed_db_repository()
+            .await
+            .map_err(|err| format!("{:#?}", err))?;
+
+        Ok(())
     }
 
     /// Selects the network for the ETOPay SDK.
@@ -93,6 +138,46 @@ impl ETOPaySdk {
         Ok(networks)
     }
 
+    /// Fetch available networks together with the time they were fetched.
+    ///
+    /// @returns {Promise<CachedNetworks>} The networks, marked as stale if fetching failed and the last known networks are returned
+    #[wasm_bindgen(skip_jsdoc, js_name = "getNetworksCached")]
+    pub async fn get_networks_cached(&self) -> Result<CachedNetworks, String> {
+        let mut sdk = self.inner.write().await;
+        sdk.get_networks_cached()
+            .await
+            .map(CachedNetworks::from)
+            .map_err(|e| format!("{e:#?}"))
+    }
+
+    /// Adds a custom network for the current user, after validating it against its node.
+    /// Purchases are disabled for custom networks.
+    ///
+    /// @param {string} network - The network as JSON string, in the same format as the `ApiNetwork` of the SDK.
+    /// @returns {Promise<Network>} The stored network
+    #[wasm_bindgen(skip_jsdoc, js_name = "addCustomNetwork")]
+    pub async fn add_custom_network(&self, network: String) -> Result<Network, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let network = serde_json::from_str(&network).map_err(|e| sdk::Error::Parse(e.to_string()))?;
+            Ok(Network::from(sdk.add_custom_network(network).await?))
+        }
+        .await
+        .map_err(|e: sdk::Error| format!("{e:#?}"))
+    }
+
+    /// Removes a custom network of the current user.
+    ///
+    /// @param {string} network_key - The key of the custom network.
+    /// @returns {Promise<void>}
+    #[wasm_bindgen(skip_jsdoc, js_name = "removeCustomNetwork")]
+    pub async fn remove_custom_network(&self, network_key: String) -> Result<(), String> {
+        let mut sdk = self.inner.write().await;
+        sdk.remove_custom_network(&network_key)
+            .await
+            .map_err(|e| format!("{e:#?}"))
+    }
+
     /// Initializes the etopay logger
     /// @param {Level} level - The log level.
     /// @returns {void}
@@ -109,7 +194,8 @@ impl ETOPaySdk {
     #[wasm_bindgen(skip_jsdoc, js_name = "createNewUser")]
     pub async fn create_new_user(&self, username: String) -> Result<(), String> {
         let mut sdk = self.inner.write().await;
-        sdk.create_new_user(&username).await.map_err(|e| format!("{e:#?}"))
+        let result = sdk.create_new_user(&username).await;
+        persisted(&sdk, result).await.map_err(|e| format!("{e:#?}"))
     }
 
     /// Initializes an existing user in the SDK
@@ -141,6 +227,20 @@ impl ETOPaySdk {
         .map_err(|e| format!("{e:#?}"))
     }
 
+    /// Set a function that provides new access tokens.
+    ///
+    /// The SDK calls it when the current access token is expired or rejected by the backend, and
+    /// retries the request once with the new access token.
+    ///
+    /// @param {() => Promise<string>} provider - The function returning a new access token.
+    /// @returns {Promise<void>}
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen(skip_jsdoc, js_name = "setAccessTokenProvider")]
+    pub async fn set_access_token_provider(&self, provider: js_sys::Function) {
+        let mut sdk = self.inner.write().await;
+        sdk.set_access_token_provider(JsAccessTokenProvider(provider));
+    }
+
     /// Fetches the kyc verification status for the user
     ///
     /// @param {string} username - The input string representing the username.
@@ -163,7 +263,8 @@ impl ETOPaySdk {
         let mut sdk = self.inner.write().await;
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
-            sdk.create_wallet_from_new_mnemonic(&pin).await
+            let result = sdk.create_wallet_from_new_mnemonic(&pin).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -179,7 +280,8 @@ impl ETOPaySdk {
         let mut sdk = self.inner.write().await;
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
-            sdk.create_wallet_from_existing_mnemonic(&pin, &mnemonic).await
+            let result = sdk.create_wallet_from_existing_mnemonic(&pin, &mnemonic).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -203,7 +305,8 @@ impl ETOPaySdk {
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
             let backup_password = PlainPassword::try_from_string(backup_password)?;
-            sdk.create_wallet_from_backup(&pin, &backup, &backup_password).await
+            let result = sdk.create_wallet_from_backup(&pin, &backup, &backup_password).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -237,7 +340,8 @@ impl ETOPaySdk {
         let mut sdk = self.inner.write().await;
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
-            sdk.delete_wallet(&pin).await
+            let result = sdk.delete_wallet(&pin).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -294,6 +398,42 @@ impl ETOPaySdk {
         .map_err(|e| format!("{e:#?}"))
     }
 
+    /// Fetches the current balance of the base crypto network on the wallet together with the time it was fetched
+    ///
+    /// @param {string} pin - The input string representing the pin.
+    ///
+    /// @returns {Promise<CachedAmount>} The current balance, marked as stale if fetching failed and the last known balance is returned
+    #[wasm_bindgen(skip_jsdoc, js_name = "getWalletBalanceCached")]
+    pub async fn get_wallet_balance_cached(&self, pin: String) -> Result<CachedAmount, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let balance = sdk.get_balance_cached(&pin).await?;
+            Ok::<_, sdk::Error>(CachedAmount::new(f64::try_from(balance.value)?, &balance))
+        }
+        .await
+        .map_err(|e| format!("{e:#?}"))
+    }
+
+    /// Fetches the current balance on the wallet of the given network, without changing the selected network
+    ///
+    /// @param {string} pin - The input string representing the pin.
+    /// @param {string} network_key - The key of the network.
+    ///
+    /// @returns {Promise<number>} The current balance as a double precision floating point number
+    #[wasm_bindgen(skip_jsdoc, js_name = "getWalletBalanceFor")]
+    pub async fn get_wallet_balance_for(&self, pin: String, network_key: String) -> Result<f64, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            sdk.get_balance_for(&pin, &network_key)
+                .await
+                .and_then(|v| f64::try_from(v).map_err(Into::into))
+        }
+        .await
+        .map_err(|e| format!("{e:#?}"))
+    }
+
     /// Initialize the KYC process for Postident
     ///
     /// @remarks
@@ -370,7 +510,7 @@ impl ETOPaySdk {
         app_data: String,
         purchase_type: String,
     ) -> Result<String, String> {
-        let sdk = self.inner.write().await;
+        let mut sdk = self.inner.write().await;
         async move {
             let amount = CryptoAmount::try_from(amount)?;
             sdk.create_purchase_request(&receiver, amount, &product_hash, &app_data, &purchase_type)
@@ -433,7 +573,8 @@ impl ETOPaySdk {
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
             let new_password = PlainPassword::try_from_string(new_password)?;
-            sdk.set_wallet_password(&pin, &new_password).await
+            let result = sdk.set_wallet_password(&pin, &new_password).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -455,15 +596,72 @@ impl ETOPaySdk {
     /// @returns {Promise<void>}
     #[wasm_bindgen(skip_jsdoc, js_name = "verifyPin")]
     pub async fn verify_pin(&self, pin: String) -> Result<(), String> {
-        let sdk = self.inner.write().await;
+        let mut sdk = self.inner.write().await;
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
-            sdk.verify_pin(&pin).await
+            let result = sdk.verify_pin(&pin).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
     }
 
+    /// Unlock the wallet, so that wallet operations reuse the wallet instead of deriving the keys
+    /// again on every call. While the wallet is unlocked, the SDK polls for the events delivered to
+    /// {@link subscribeEvents}.
+    ///
+    /// @param {string} pin - The pin of the wallet
+    /// @param {number} ttl_secs - How long the wallet stays unlocked, in seconds
+    /// @returns {Promise<void>}
+    #[wasm_bindgen(skip_jsdoc, js_name = "unlockWallet")]
+    pub async fn unlock_wallet(&self, pin: String, ttl_secs: u32) -> Result<(), String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let result = sdk.unlock_wallet(&pin, Duration::from_secs(ttl_secs.into())).await;
+            persisted(&sdk, result).await
+        }
+        .await
+        .map_err(|e| format!("{e:#?}"))?;
+
+        spawn_local(run_event_poller(self.inner.clone(), DEFAULT_EVENT_POLL_INTERVAL));
+        Ok(())
+    }
+
+    /// Lock the wallet again and stop polling for events
+    ///
+    /// @returns {Promise<void>}
+    #[wasm_bindgen(skip_jsdoc, js_name = "lockWallet")]
+    pub async fn lock_wallet(&self) -> Result<(), String> {
+        let mut sdk = self.inner.write().await;
+        sdk.lock_wallet().map_err(|e| format!("{e:#?}"))
+    }
+
+    /// Subscribe to the events of the SDK, e.g. `{"type": "BalanceChanged", "network_key": "IOTA", "balance": "25.0"}`.
+    ///
+    /// @param {(event: string) => void} callback - Called with every event as a JSON-encoded string
+    /// @returns {Promise<void>}
+    #[wasm_bindgen(skip_jsdoc, js_name = "subscribeEvents")]
+    pub async fn subscribe_events(&self, callback: js_sys::Function) {
+        let mut events = self.inner.read().await.subscribe_events();
+        spawn_local(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => match serde_json::to_string(&event) {
+                        Ok(event) => {
+                            if let Err(e) = callback.call1(&JsValue::NULL, &JsValue::from_str(&event)) {
+                                log::warn!("The event callback failed: {e:?}");
+                            }
+                        }
+                        Err(e) => log::warn!("Could not serialize event {event:?}: {e}"),
+                    },
+                    Err(RecvError::Lagged(missed)) => log::warn!("The event callback missed {missed} events"),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
     /// Change the pin used to encrypt the wallet password
     ///
     /// @param {string} pin - The old pin
@@ -475,7 +673,8 @@ impl ETOPaySdk {
         async move {
             let pin = EncryptionPin::try_from_string(pin)?;
             let new_pin = EncryptionPin::try_from_string(new_pin)?;
-            sdk.change_pin(&pin, &new_pin).await
+            let result = sdk.change_pin(&pin, &new_pin).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -500,7 +699,8 @@ impl ETOPaySdk {
         async move {
             let amount = CryptoAmount::try_from(amount)?;
             let pin = EncryptionPin::try_from_string(pin)?;
-            sdk.send_amount(&pin, &address, amount, data).await
+            let result = sdk.send_amount(&pin, &address, amount, data).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -569,13 +769,64 @@ impl ETOPaySdk {
     /// @returns {Promise<number>} The exchange rate as a floating point number
     #[wasm_bindgen(skip_jsdoc, js_name = "getExchangeRate")]
     pub async fn get_exchange_rate(&self) -> Result<f64, String> {
-        let sdk = self.inner.write().await;
+        let mut sdk = self.inner.write().await;
         sdk.get_exchange_rate()
             .await
             .and_then(|amount| Ok(f64::try_from(amount)?))
             .map_err(|e| format!("{e:#?}"))
     }
 
+    /// Gets the current exchange rate for the cryptocurrency to EURO together with the time it was fetched
+    ///
+    /// @returns {Promise<CachedAmount>} The exchange rate, marked as stale if fetching failed and the last known exchange rate is returned
+    #[wasm_bindgen(skip_jsdoc, js_name = "getExchangeRateCached")]
+    pub async fn get_exchange_rate_cached(&self) -> Result<CachedAmount, String> {
+        let mut sdk = self.inner.write().await;
+        sdk.get_exchange_rate_cached()
+            .await
+            .and_then(|rate| Ok(CachedAmount::new(f64::try_from(rate.value)?, &rate)))
+            .map_err(|e| format!("{e:#?}"))
+    }
+
+    /// Fetches the current balance on the wallet of the selected network, valued in a fiat currency
+    ///
+    /// @param {string} pin - The input string representing the pin.
+    /// @param {string} currency - The fiat currency, one of `EUR`, `USD` or `CHF`.
+    ///
+    /// @returns {Promise<number>} The value of the balance as a double precision floating point number
+    #[wasm_bindgen(skip_jsdoc, js_name = "getFiatBalance")]
+    pub async fn get_fiat_balance(&self, pin: String, currency: String) -> Result<f64, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let currency = FiatCurrency::try_from(currency)?;
+            let value = sdk.get_fiat_balance(&pin, currency).await?;
+            Ok(f64::try_from(value.value)?)
+        }
+        .await
+        .map_err(|e: sdk::Error| format!("{e:#?}"))
+    }
+
+    /// Fetches the balances on all networks and returns their total value in a fiat currency.
+    /// Networks whose balance or exchange rate cannot be fetched are not included.
+    ///
+    /// @param {string} pin - The input string representing the pin.
+    /// @param {string} currency - The fiat currency, one of `EUR`, `USD` or `CHF`.
+    ///
+    /// @returns {Promise<number>} The total value as a double precision floating point number
+    #[wasm_bindgen(skip_jsdoc, js_name = "getPortfolioValue")]
+    pub async fn get_portfolio_value(&self, pin: String, currency: String) -> Result<f64, String> {
+        let mut sdk = self.inner.write().await;
+        async move {
+            let pin = EncryptionPin::try_from_string(pin)?;
+            let currency = FiatCurrency::try_from(currency)?;
+            let portfolio = sdk.get_portfolio_value(&pin, currency).await?;
+            Ok(f64::try_from(portfolio.total)?)
+        }
+        .await
+        .map_err(|e: sdk::Error| format!("{e:#?}"))
+    }
+
     /// Deletes the user in etopay. Hazmat!
     ///
     /// @param {string} pin - The wallet pin for confirmation. Optional in case there is an active wallet.
@@ -588,7 +839,8 @@ impl ETOPaySdk {
                 Some(p) => Some(EncryptionPin::try_from_string(p)?),
                 None => None,
             };
-            sdk.delete_user(encryption_pin.as_ref()).await
+            let result = sdk.delete_user(encryption_pin.as_ref()).await;
+            persisted(&sdk, result).await
         }
         .await
         .map_err(|e| format!("{e:#?}"))
@@ -1110,7 +1362,8 @@ impl ETOPaySdk {
         let mut sdk = self.inner.write().await;
 
         let share: sdk::share::Share = share.parse().map_err(|e| format!("{e:#?}"))?;
-        sdk.set_recovery_share(share).await.map_err(|err| format!("{:#?}", err))
+        let result = sdk.set_recovery_share(share).await;
+        persisted(&sdk, result).await.map_err(|err| format!("{:#?}", err))
     }
 
     /// Get the preferred network.
@@ -91,7 +91,7 @@ pub enum Protocol {
 }
 
 #[wasm_bindgen(getter_with_clone, inspectable)]
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Network {
     pub key: String,
     pub is_testnet: bool,
@@ -137,6 +137,48 @@ impl From<sdk::types::networks::ApiNetwork> for Network {
     }
 }
 
+/// The networks together with the time they were fetched
+#[wasm_bindgen(getter_with_clone, inspectable)]
+pub struct CachedNetworks {
+    /// The networks
+    pub networks: Vec<Network>,
+    /// When the networks were fetched, in RFC 3339 format
+    pub updated_at: String,
+    /// Whether fetching failed and the networks are the last known networks instead
+    pub stale: bool,
+}
+
+impl From<sdk::types::users::Cached<Vec<sdk::types::networks::ApiNetwork>>> for CachedNetworks {
+    fn from(value: sdk::types::users::Cached<Vec<sdk::types::networks::ApiNetwork>>) -> Self {
+        CachedNetworks {
+            networks: value.value.into_iter().map(Network::from).collect(),
+            updated_at: value.updated_at.to_rfc3339(),
+            stale: value.stale,
+        }
+    }
+}
+
+/// An amount together with the time it was fetched
+#[wasm_bindgen(getter_with_clone, inspectable)]
+pub struct CachedAmount {
+    /// The amount
+    pub value: f64,
+    /// When the amount was fetched, in RFC 3339 format
+    pub updated_at: String,
+    /// Whether fetching failed and the amount is the last known amount instead
+    pub stale: bool,
+}
+
+impl CachedAmount {
+    pub fn new<T>(value: f64, cached: &sdk::types::users::Cached<T>) -> Self {
+        CachedAmount {
+            value,
+            updated_at: cached.updated_at.to_rfc3339(),
+            stale: cached.stale,
+        }
+    }
+}
+
 #[wasm_bindgen(getter_with_clone, inspectable)]
 pub struct TxList {
     pub txs: Vec<TxInfo>,
@@ -3,11 +3,68 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use sdk::core::{Config, Sdk};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::js_sys;
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+fn config() -> Config {
+    Config::from_json(r#"{"backend_url": "http://localhost", "auth_provider": "standalone"}"#).unwrap()
+}
+
+/// A username that is not used by a previous test run in the same browser profile
+fn unique_username(prefix: &str) -> String {
+    format!("{prefix}_{}", js_sys::Date::now())
+}
+
+/// Read an item of `window.localStorage`
+fn local_storage_item(key: &str) -> JsValue {
+    let local_storage = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("localStorage")).unwrap();
+    let get_item: js_sys::Function = js_sys::Reflect::get(&local_storage, &JsValue::from_str("getItem"))
+        .unwrap()
+        .into();
+    get_item.call1(&local_storage, &JsValue::from_str(key)).unwrap()
+}
+
 #[wasm_bindgen_test]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+async fn users_are_persisted_in_indexed_db() {
+    // Arrange
+    let username = unique_username("indexed_db_user");
+    let mut sdk = Sdk::new(config()).unwrap();
+    sdk.initialize_indexed_db_repository().await.unwrap();
+
+    // Act
+    sdk.create_new_user(&username).await.unwrap();
+    sdk.flush_user_storage().await.unwrap();
+
+    // Assert
+    let mut other_sdk = Sdk::new(config()).unwrap();
+    other_sdk.initialize_indexed_db_repository().await.unwrap();
+    assert!(other_sdk.create_new_user(&username).await.is_err());
+    assert!(local_storage_item(&format!("etopay.local.user.{username}")).is_null());
+}
+
+#[wasm_bindgen_test]
+async fn users_are_moved_from_local_storage_to_indexed_db() {
+    // Arrange: without IndexedDB, the user is stored in the local storage
+    let username = unique_username("local_storage_user");
+    let key = format!("etopay.local.user.{username}");
+    let mut sdk = Sdk::new(config()).unwrap();
+    sdk.create_new_user(&username).await.unwrap();
+    assert!(!local_storage_item(&key).is_null());
+
+    // Act
+    sdk.initialize_indexed_db_repository().await.unwrap();
+
+    // Assert
+    assert!(local_storage_item(&key).is_null());
+    assert!(sdk.create_new_user(&username).await.is_err());
+    sdk.flush_user_storage().await.unwrap();
+}
@@ -75,6 +75,10 @@ pub enum WalletError {
     /// Failed to wait for confirming the transaction status
     #[error("FailToConfirmTransactionStatus: Failed to confirm tx status for {0} within {1} seconds.")]
     FailToConfirmTransactionStatus(String, u64),
+
+    /// The node does not know the coin type
+    #[error("UnknownCoinType: {0}")]
+    UnknownCoinType(String),
 }
 
 impl From<rust_decimal::Error> for WalletError {
@@ -8,8 +8,8 @@ pub mod types;
 
 pub use error::{Result, WalletError};
 pub use wallet::*;
-pub use wallet_evm::{WalletImplEvm, WalletImplEvmErc20};
-pub use wallet_rebased::WalletImplIotaRebased;
+pub use wallet_evm::{WalletImplEvm, WalletImplEvmErc20, erc20_decimals, evm_chain_id};
+pub use wallet_rebased::{WalletImplIotaRebased, iota_rebased_coin_decimals};
 
 /// Re-export the bip39 crate so that our users can create [`bip39::Mnemonic`]s
 pub use bip39;
@@ -21,9 +21,10 @@ impl InMemKeystore {
     pub fn import_from_mnemonic(
         &mut self,
         mnemonic: Mnemonic,
+        passphrase: &str,
         derivation_path: DerivationPath,
     ) -> Result<IotaAddress, RebasedError> {
-        let seed = Seed::new(&mnemonic, "");
+        let seed = Seed::new(&mnemonic, passphrase);
 
         let indexes = derivation_path.into_iter().map(|i| i.into()).collect::<Vec<_>>();
         let derived = slip10_ed25519::derive_ed25519_private_key(seed.as_bytes(), &indexes);
@@ -41,6 +41,13 @@ pub trait CoinReadApi {
         // optional type names for the coin (e.g., 0x168da5bf1f48dafc111b0a488fa454aca95e0b5e::usdc::USDC), default to 0x2::iota::IOTA if not specified.
         coin_type: Option<String>,
     ) -> RpcResult<Balance>;
+
+    /// Return the metadata of a coin type, or `None` if the coin type does not exist.
+    async fn get_coin_metadata(
+        &self,
+        // type name for the coin (e.g., 0x168da5bf1f48dafc111b0a488fa454aca95e0b5e::usdc::USDC)
+        coin_type: String,
+    ) -> RpcResult<Option<IotaCoinMetadata>>;
 }
 
 impl CoinReadApi for RpcClient {
@@ -95,6 +102,42 @@ impl CoinReadApi for RpcClient {
         let body: RawRpcResponse<Balance> = response.json().await?;
         body.into_result()
     }
+
+    async fn get_coin_metadata(
+        &self,
+        // type name for the coin (e.g., 0x168da5bf1f48dafc111b0a488fa454aca95e0b5e::usdc::USDC)
+        coin_type: String,
+    ) -> RpcResult<Option<IotaCoinMetadata>> {
+        let request_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "iotax_getCoinMetadata",
+            "params": [coin_type]
+        });
+
+        let response = self.client.post(self.url.clone()).json(&request_body).send().await?;
+
+        let body: RawRpcResponse<Option<IotaCoinMetadata>> = response.json().await?;
+        body.into_result()
+    }
+}
+
+/// Metadata of a coin type
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IotaCoinMetadata {
+    /// Number of decimal places the coin uses.
+    pub decimals: u8,
+    /// Name for the token
+    pub name: String,
+    /// Symbol for the token
+    pub symbol: String,
+    /// Description of the token
+    pub description: String,
+    /// URL for the token logo
+    pub icon_url: Option<String>,
+    /// Object id for the CoinMetadata object
+    pub id: Option<ObjectID>,
 }
 
 /// `next_cursor` points to the last item in the page;
@@ -1,6 +1,7 @@
 use super::error::Result;
 use crate::types::{CryptoAmount, GasCostEstimation, WalletTransaction};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 /// The intended transaction to perform. Used to perform the transaction and estimate gas fees.
@@ -16,7 +17,7 @@ pub struct TransactionIntent {
 }
 
 /// Options that can be given to customize the mnemonic derivation
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MnemonicDerivationOption {
     pub account: u32,
     pub index: u32,
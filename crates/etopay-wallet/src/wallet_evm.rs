@@ -6,8 +6,7 @@ use crate::types::{CryptoAmount, GasCostEstimation, WalletTransaction, WalletTxS
 use alloy::eips::BlockNumberOrTag;
 use alloy::network::{Ethereum, EthereumWallet, TransactionBuilder};
 use alloy::rpc::types::TransactionRequest;
-use alloy::signers::local::MnemonicBuilder;
-use alloy::signers::local::coins_bip39::English;
+use alloy::signers::local::PrivateKeySigner;
 use alloy::sol_types::SolCall;
 use alloy::{
     primitives::Address,
@@ -21,7 +20,7 @@ use alloy_provider::fillers::{
 };
 use alloy_provider::{Identity, RootProvider, WalletProvider};
 use async_trait::async_trait;
-use bip39::Mnemonic;
+use bip39::{Mnemonic, Seed};
 use chrono::{TimeZone, Utc};
 use log::info;
 use reqwest::Url;
@@ -54,24 +53,25 @@ pub struct WalletImplEvm {
 }
 
 impl WalletImplEvm {
-    /// Creates a new [`WalletImplEvm`] from the specified [`Mnemonic`].
+    /// Creates a new [`WalletImplEvm`] from the specified [`Mnemonic`] and BIP39 passphrase.
+    /// An empty passphrase derives the same keys as no passphrase.
     #[allow(clippy::result_large_err)]
     pub fn new(
         mnemonic: Mnemonic,
+        passphrase: &str,
         node_urls: &[String],
         chain_id: u64,
         decimals: u32,
         coin_type: u32,
         options: &MnemonicDerivationOption,
     ) -> Result<Self> {
-        // Ase mnemonic to create a Signer
-        let wallet = MnemonicBuilder::<English>::default()
-            .phrase(mnemonic.as_ref().to_string())
-            // Child key at derivation path: m/44'/{coin_type}'/{account}'/{change}/{index}.
-            .derivation_path(format!("m/44'/{}'/{}'/0/{}", coin_type, options.account, options.index))?
-            // // Use this if your mnemonic is encrypted.
-            // .password(password)
-            .build()?;
+        // Derive the signing key from the BIP39 seed, which takes the wordlist language of the mnemonic into account.
+        // Child key at derivation path: m/44'/{coin_type}'/{account}'/{change}/{index}.
+        let seed = Seed::new(&mnemonic, passphrase);
+        let derivation_path = format!("m/44'/{}'/{}'/0/{}", coin_type, options.account, options.index)
+            .parse::<bip32::DerivationPath>()?;
+        let xprv = bip32::XPrv::derive_from_path(seed.as_bytes(), &derivation_path)?;
+        let wallet = PrivateKeySigner::from_signing_key(xprv.private_key().clone());
 
         // construct the ProviderBuilder
         let url =
@@ -336,6 +336,21 @@ impl WalletUser for WalletImplEvm {
     }
 }
 
+/// Fetches the chain id of the EVM node at `node_url`.
+pub async fn evm_chain_id(node_url: &str) -> Result<u64> {
+    let url = Url::parse(node_url).map_err(|e| WalletError::Parse(format!("could not parse the url: {e:?}")))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    Ok(provider.get_chain_id().await?)
+}
+
+/// Fetches the number of decimals of the ERC20 contract at `contract_address` from the EVM node at `node_url`.
+pub async fn erc20_decimals(node_url: &str, contract_address: &str) -> Result<u32> {
+    let url = Url::parse(node_url).map_err(|e| WalletError::Parse(format!("could not parse the url: {e:?}")))?;
+    let provider = ProviderBuilder::new().connect_http(url);
+    let contract = Erc20Contract::new(contract_address.parse()?, &provider);
+    Ok(u32::from(contract.decimals().call().await?))
+}
+
 alloy::sol!(
     /// Interface of the ERC20 standard as defined in [the EIP].
     ///
@@ -350,6 +365,7 @@ alloy::sol!(
        event Approval(address indexed owner, address indexed spender, uint256 value);
 
        function totalSupply() external view returns (uint256);
+       function decimals() external view returns (uint8);
        function transfer(address to, uint256 amount) external returns (bool);
        function allowance(address owner, address spender) external view returns (uint256);
        function approve(address spender, uint256 amount) external returns (bool);
@@ -364,10 +380,11 @@ pub struct WalletImplEvmErc20 {
     contract_address: Address,
 }
 impl WalletImplEvmErc20 {
-    /// Creates a new [`WalletImplEvm`] from the specified [`Mnemonic`].
-    #[allow(clippy::result_large_err)]
+    /// Creates a new [`WalletImplEvm`] from the specified [`Mnemonic`] and BIP39 passphrase.
+    #[allow(clippy::result_large_err, clippy::too_many_arguments)]
     pub fn new(
         mnemonic: Mnemonic,
+        passphrase: &str,
         node_urls: &[String],
         chain_id: u64,
         decimals: u32,
@@ -376,7 +393,7 @@ impl WalletImplEvmErc20 {
         options: &MnemonicDerivationOption,
     ) -> Result<Self> {
         Ok(Self {
-            inner: WalletImplEvm::new(mnemonic, node_urls, chain_id, decimals, coin_type, options)?,
+            inner: WalletImplEvm::new(mnemonic, passphrase, node_urls, chain_id, decimals, coin_type, options)?,
             contract_address: contract_address.parse()?,
         })
     }
@@ -545,6 +562,7 @@ mod tests {
         let mnemonic = Mnemonic::from_phrase(mnemonic_phrase.as_ref(), Language::English).expect("invalid mnemonic");
         let wallet = WalletImplEvm::new(
             mnemonic,
+            "",
             &node_url,
             chain_id,
             ETH_DECIMALS,
@@ -564,6 +582,7 @@ mod tests {
         let mnemonic = Mnemonic::from_phrase(mnemonic_phrase.as_ref(), Language::English).expect("invalid mnemonic");
         WalletImplEvm::new(
             mnemonic,
+            "",
             &[node_url],
             chain_id,
             ETH_DECIMALS,
@@ -587,6 +606,34 @@ mod tests {
         assert_eq!(parsed, expected);
     }
 
+    #[tokio::test]
+    async fn test_get_address_with_passphrase() {
+        //Arrange
+        let mnemonic = Mnemonic::from_phrase(
+            "inject danger program federal spice bitter term garbage coyote breeze thought funny",
+            Language::English,
+        )
+        .unwrap();
+        let wallet_user = WalletImplEvm::new(
+            mnemonic,
+            "LEDGER321",
+            &[String::from("https://sepolia.mode.network")],
+            31337,
+            ETH_DECIMALS,
+            ETH_COIN_TYPE,
+            &MnemonicDerivationOption { account: 0, index: 1 },
+        )
+        .unwrap();
+
+        // Act
+        let addr_raw = wallet_user.get_address().await.unwrap();
+
+        // Assert
+        let parsed = Address::parse_checksummed(addr_raw, None).unwrap();
+        let expected = alloy_primitives::address!("0x231a3D0a05d13FAf93078C779FeeD3752ea1350C");
+        assert_eq!(parsed, expected);
+    }
+
     #[tokio::test]
     async fn test_get_balance() {
         //Arrange
@@ -55,9 +55,11 @@ impl std::fmt::Debug for WalletImplIotaRebased {
 }
 
 impl WalletImplIotaRebased {
-    /// Creates a new [`WalletImpl`] from the specified [`Config`] and [`Mnemonic`].
+    /// Creates a new [`WalletImpl`] from the specified [`Config`], [`Mnemonic`] and BIP39 passphrase.
+    /// An empty passphrase derives the same keys as no passphrase.
     pub async fn new(
         mnemonic: Mnemonic,
+        passphrase: &str,
         coin_type: &str,
         decimals: u32,
         node_url: &[String],
@@ -66,6 +68,7 @@ impl WalletImplIotaRebased {
         let mut keystore2 = rebased::InMemKeystore::default();
         keystore2.import_from_mnemonic(
             mnemonic,
+            passphrase,
             format!("m/44'/4218'/{}'/0'/{}'", options.account, options.index).parse::<bip32::DerivationPath>()?,
         )?;
 
@@ -81,6 +84,16 @@ impl WalletImplIotaRebased {
 }
 
 /// Convert a [`u128`] to [`CryptoAmount`] while taking the decimals into account.
+/// Fetches the number of decimals of `coin_type` from the IOTA Rebased node at `node_url`.
+pub async fn iota_rebased_coin_decimals(node_url: &str, coin_type: &str) -> Result<u32> {
+    let client = RpcClient::new(node_url).await?;
+    let metadata = client
+        .get_coin_metadata(coin_type.to_string())
+        .await?
+        .ok_or_else(|| WalletError::UnknownCoinType(coin_type.to_string()))?;
+    Ok(u32::from(metadata.decimals))
+}
+
 #[allow(clippy::result_large_err)]
 fn convert_u128_to_crypto_amount(value: u128, decimals: u32) -> Result<CryptoAmount> {
     let rust_decimal = convert_u128_to_rust_decimal(value, decimals)?;
@@ -22,12 +22,12 @@ pub async fn init_sdk() -> (Sdk, CleanUp) {
         .expect("EXAMPLES_BACKEND_URL environment variable need to be set to run the examples");
 
     // construct the config to use for the SDK
-    let config = Config {
-        backend_url: backend_url.parse().expect("EXAMPLES_BACKEND_URL must be a valid URL"),
-        path_prefix: Path::new(&cleanup.path_prefix).into(),
-        auth_provider: "standalone".to_string(),
-        log_level: log::LevelFilter::Debug,
-    };
+    let config = Config::new(
+        Path::new(&cleanup.path_prefix),
+        "standalone",
+        backend_url.parse().expect("EXAMPLES_BACKEND_URL must be a valid URL"),
+        log::LevelFilter::Debug,
+    );
     let mut sdk = Sdk::new(config).expect("should not fail to initialize sdk"); // set the backend url if the environment variable is set
 
     // generate access token
@@ -0,0 +1,540 @@
+//! Shared HTTP client for the backend
+//!
+//! All calls to the backend go through [`BackendCall`], which reuses the connections of a single
+//! [`reqwest::Client`], applies the timeouts from [`HttpConfig`], retries idempotent `GET`
+//! requests on transient failures, refreshes the access token with the provider set on the SDK
+//! and maps the response status to an [`ApiError`].
+//!
+
+use super::error::{ApiError, Result};
+use crate::core::Config;
+use crate::core::access_token::AccessTokenRefresher;
+use crate::core::config::HttpConfig;
+use crate::telemetry::{TelemetryEvent, TelemetryHook};
+use crate::types::newtypes::AccessToken;
+use chrono::Utc;
+use log::{debug, error, info, warn};
+use rand::Rng;
+use reqwest::{Method, StatusCode, header::RETRY_AFTER};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The client shared by all backend calls, together with the configuration it was built with
+static SHARED_CLIENT: Mutex<Option<(HttpConfig, reqwest::Client)>> = Mutex::new(None);
+
+/// Get the shared client, or build a new one if the http configuration changed
+fn shared_client(http: &HttpConfig) -> Result<reqwest::Client> {
+    let mut shared = SHARED_CLIENT.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some((config, client)) = shared.as_ref() {
+        if config == http {
+            return Ok(client.clone());
+        }
+    }
+
+    debug!("Building http client with {http:?}");
+    let builder = reqwest::Client::builder();
+    // timeouts are not supported by the browser fetch API
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder
+        .timeout(Duration::from_millis(http.timeout_ms))
+        .connect_timeout(Duration::from_millis(http.connect_timeout_ms));
+    let client = builder.build()?;
+
+    *shared = Some((http.clone(), client.clone()));
+    Ok(client)
+}
+
+/// Access tokens expiring within this time are refreshed before sending the request
+const ACCESS_TOKEN_EXPIRY_LEEWAY: Duration = Duration::from_secs(30);
+
+/// helper object for calling the backend (reduces boiler-plate code)
+pub(crate) struct BackendCall {
+    request_builder: Result<reqwest::RequestBuilder>,
+    access_token: AccessToken,
+    access_token_refresher: Option<Arc<AccessTokenRefresher>>,
+    telemetry: TelemetryHook,
+    method: reqwest::Method,
+    url: String,
+    expected_status: StatusCode,
+    http: HttpConfig,
+}
+
+impl BackendCall {
+    pub fn new(config: &Config, access_token: &AccessToken, method: reqwest::Method, url_path: &str) -> Self {
+        let url = format!("{}{url_path}", config.backend_url);
+        debug!("{method} {url_path}");
+
+        let request_builder = shared_client(&config.http).map(|client| {
+            client
+                .request(method.clone(), &url)
+                .header("X-APP-NAME", &config.auth_provider)
+        });
+
+        Self {
+            request_builder,
+            access_token: access_token.clone(),
+            access_token_refresher: config.access_token_refresher.clone(),
+            telemetry: config.telemetry.clone(),
+            method,
+            url: url_path.to_string(),
+            expected_status: StatusCode::OK,
+            http: config.http.clone(),
+        }
+    }
+
+    pub fn with_query<T: serde::Serialize + ?Sized>(self, query: &T) -> Self {
+        Self {
+            request_builder: self.request_builder.map(|builder| builder.query(query)),
+            ..self
+        }
+    }
+
+    pub fn with_body<T: serde::Serialize + ?Sized>(self, body: &T) -> Self {
+        Self {
+            request_builder: self.request_builder.map(|builder| builder.json(body)),
+            ..self
+        }
+    }
+
+    /// Set the status code of a successful response, defaults to [`StatusCode::OK`]
+    pub fn with_expected_status(self, expected_status: StatusCode) -> Self {
+        Self {
+            expected_status,
+            ..self
+        }
+    }
+
+    /// Send the request with a valid access token. If an access token provider is set, an expired
+    /// token is refreshed before sending and the request is sent once more with a new token if the
+    /// backend rejects the token.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(method = %self.method, path = %self.url))
+    )]
+    async fn send(self) -> Result<(reqwest::Response, String)> {
+        let request_builder = self.request_builder?;
+        let refresher = self.access_token_refresher;
+        let mut access_token = refresher
+            .as_ref()
+            .and_then(|refresher| refresher.latest())
+            .unwrap_or(self.access_token);
+
+        if let Some(refresher) = &refresher {
+            if access_token.expires_within(ACCESS_TOKEN_EXPIRY_LEEWAY) {
+                if let Some(new_access_token) = refresher.refresh(&access_token).await {
+                    access_token = new_access_token;
+                }
+            }
+        }
+
+        let response = send_with_retries(
+            &request_builder,
+            &access_token,
+            &self.method,
+            &self.url,
+            &self.http,
+            &self.telemetry,
+        )
+        .await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            if let Some(refresher) = &refresher {
+                if let Some(new_access_token) = refresher.refresh(&access_token).await {
+                    info!("Retrying {} {} with a new access token", self.method.as_str(), self.url);
+                    let response = send_with_retries(
+                        &request_builder,
+                        &new_access_token,
+                        &self.method,
+                        &self.url,
+                        &self.http,
+                        &self.telemetry,
+                    )
+                    .await?;
+                    return Ok((response, self.url));
+                }
+            }
+        }
+        Ok((response, self.url))
+    }
+
+    async fn execute_inner<R, F, Fut>(self, ok_transform: F) -> Result<R>
+    where
+        F: FnOnce(reqwest::Response) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let method = self.method.clone();
+        let expected_status = self.expected_status;
+        let (response, url) = self.send().await?;
+
+        match response.status() {
+            status if status == expected_status => ok_transform(response).await,
+            StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
+            StatusCode::TOO_MANY_REQUESTS => Err(ApiError::TooManyRequests {
+                retry_after: retry_after(&response),
+            }),
+            StatusCode::SERVICE_UNAVAILABLE => Err(ApiError::ServiceUnavailable {
+                retry_after: retry_after(&response),
+            }),
+            status => {
+                let text = response.text().await?;
+                error!(
+                    "Failed to {} {}: Response status: {}, Response text: {}",
+                    method.as_str(),
+                    url,
+                    status,
+                    text
+                );
+                Err(ApiError::UnexpectedResponse {
+                    code: status,
+                    body: text,
+                })
+            }
+        }
+    }
+
+    /// Execute this request and parse the response as JSON
+    pub async fn execute_parse<R: for<'de> serde::Deserialize<'de>>(self) -> Result<R> {
+        // since we cannot pass async closures, lets just define the function here
+        async fn parse<R: for<'de> serde::Deserialize<'de>>(response: reqwest::Response) -> Result<R> {
+            Ok(response.json::<R>().await?)
+        }
+
+        self.execute_inner(parse).await
+    }
+
+    /// Execute this request and parse the response as JSON, or return [`None`] if the resource
+    /// does not exist
+    pub async fn execute_parse_optional<R: for<'de> serde::Deserialize<'de>>(self) -> Result<Option<R>> {
+        match self.execute_parse().await {
+            Ok(response) => Ok(Some(response)),
+            Err(ApiError::UnexpectedResponse {
+                code: StatusCode::NOT_FOUND,
+                ..
+            }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Execute this request and ignore the return content
+    pub async fn execute(self) -> Result<()> {
+        // since we cannot pass async closures, lets just define the function here
+        async fn ok(_response: reqwest::Response) -> Result<()> {
+            Ok(())
+        }
+
+        self.execute_inner(ok).await
+    }
+}
+
+/// Send the request, retrying `GET` requests on timeouts, connection errors and
+/// `429`, `502`, `503` and `504` responses.
+async fn send_with_retries(
+    request_builder: &reqwest::RequestBuilder,
+    access_token: &AccessToken,
+    method: &Method,
+    url: &str,
+    http: &HttpConfig,
+    telemetry: &TelemetryHook,
+) -> Result<reqwest::Response> {
+    let max_retries = if method == Method::GET { http.max_retries } else { 0 };
+    let method = method.as_str();
+
+    let first_started_at = Utc::now();
+    let mut attempt = 0;
+    loop {
+        let request = request_builder
+            .try_clone()
+            .ok_or_else(|| ApiError::Parse(format!("could not clone the request {method} {url}")))?
+            .bearer_auth(access_token.as_str());
+
+        let started_at = Utc::now();
+        let result = request.send().await;
+        let elapsed_ms = (Utc::now() - started_at).num_milliseconds();
+
+        let delay = match &result {
+            Ok(response) => {
+                debug!(
+                    "{method} {url}: Response status: {}, took {elapsed_ms} ms (attempt {})",
+                    response.status(),
+                    attempt + 1
+                );
+                retry_delay(response, attempt, http)
+            }
+            Err(e) => {
+                warn!(
+                    "{method} {url}: Request failed after {elapsed_ms} ms (attempt {}): {e}",
+                    attempt + 1
+                );
+                is_transient(e).then(|| backoff(attempt, http))
+            }
+        };
+
+        match delay {
+            Some(delay) if attempt < max_retries => {
+                info!("Retrying {method} {url} in {} ms", delay.as_millis());
+                sleep(delay).await;
+                attempt += 1;
+            }
+            _ => {
+                telemetry.record(|| TelemetryEvent::BackendCall {
+                    method: method.to_string(),
+                    path: url.to_string(),
+                    status: result.as_ref().ok().map(|response| response.status().as_u16()),
+                    attempts: attempt + 1,
+                    duration_ms: (Utc::now() - first_started_at).num_milliseconds().max(0) as u64,
+                });
+                return Ok(result?);
+            }
+        }
+    }
+}
+
+/// Whether the request failed for a reason that may go away when retrying
+fn is_transient(error: &reqwest::Error) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    if error.is_connect() {
+        return true;
+    }
+    error.is_timeout()
+}
+
+/// The delay before retrying a request with this response, or [`None`] if it should not be retried
+fn retry_delay(response: &reqwest::Response, attempt: u32, http: &HttpConfig) -> Option<Duration> {
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => match retry_after(response) {
+            // do not wait longer than configured, the caller gets the error with the delay instead
+            Some(delay) => (delay <= Duration::from_millis(http.max_retry_delay_ms)).then_some(delay),
+            None => Some(backoff(attempt, http)),
+        },
+        StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => Some(backoff(attempt, http)),
+        _ => None,
+    }
+}
+
+/// Exponential backoff with jitter: a random delay between half and the full exponential delay
+fn backoff(attempt: u32, http: &HttpConfig) -> Duration {
+    let max_delay_ms = http
+        .retry_base_delay_ms
+        .saturating_mul(2u64.saturating_pow(attempt))
+        .min(http.max_retry_delay_ms);
+    let delay_ms = rand::rng().random_range(max_delay_ms / 2..=max_delay_ms);
+    Duration::from_millis(delay_ms)
+}
+
+/// Parse the `Retry-After` header, given either in seconds or as an HTTP date
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default())
+}
+
+/// Wait for the duration without blocking the executor
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Wait for the duration without blocking the executor
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    use wasm_bindgen_futures::{JsFuture, js_sys::Promise, wasm_bindgen::JsValue};
+
+    let millis = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let scheduled = web_sys::window()
+            .and_then(|window| {
+                window
+                    .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+                    .ok()
+            })
+            .is_some();
+        if !scheduled {
+            // retry right away if no timer is available
+            let _ = resolve.call0(&JsValue::UNDEFINED);
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing_utils::{AUTH_PROVIDER, HEADER_X_APP_NAME, TOKEN, set_config};
+
+    fn fast_retries(config: &mut Config) {
+        config.http.retry_base_delay_ms = 1;
+        config.http.max_retry_delay_ms = 10;
+    }
+
+    #[tokio::test]
+    async fn test_get_is_retried_after_service_unavailable() {
+        // Arrange
+        let (mut srv, mut config, _cleanup) = set_config().await;
+        fast_retries(&mut config);
+
+        let unavailable = srv
+            .mock("GET", "/api/resource")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .with_status(503)
+            .with_header("retry-after", "0")
+            .expect(2)
+            .create();
+        let ok = srv
+            .mock("GET", "/api/resource")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .with_status(200)
+            .with_body("42")
+            .expect(1)
+            .create();
+
+        // Act
+        let response = BackendCall::new(&config, &TOKEN, Method::GET, "/resource")
+            .execute_parse::<u32>()
+            .await;
+
+        // Assert
+        assert_eq!(response.unwrap(), 42);
+        unavailable.assert();
+        ok.assert();
+    }
+
+    #[rstest::rstest]
+    #[case::too_many_requests(429, ApiError::TooManyRequests { retry_after: Some(Duration::from_secs(120)) })]
+    #[case::service_unavailable(503, ApiError::ServiceUnavailable { retry_after: Some(Duration::from_secs(120)) })]
+    #[tokio::test]
+    async fn test_retry_after_exceeding_max_delay_is_returned(#[case] status_code: usize, #[case] expected: ApiError) {
+        // Arrange
+        let (mut srv, mut config, _cleanup) = set_config().await;
+        fast_retries(&mut config);
+
+        let mock_server = srv
+            .mock("GET", "/api/resource")
+            .with_status(status_code)
+            .with_header("retry-after", "120")
+            .expect(1)
+            .create();
+
+        // Act
+        let response = BackendCall::new(&config, &TOKEN, Method::GET, "/resource")
+            .execute()
+            .await;
+
+        // Assert
+        assert_eq!(response.unwrap_err().to_string(), expected.to_string());
+        mock_server.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_gives_up_after_max_retries() {
+        // Arrange
+        let (mut srv, mut config, _cleanup) = set_config().await;
+        fast_retries(&mut config);
+        config.http.max_retries = 2;
+
+        let mock_server = srv.mock("GET", "/api/resource").with_status(502).expect(3).create();
+
+        // Act
+        let response = BackendCall::new(&config, &TOKEN, Method::GET, "/resource")
+            .execute()
+            .await;
+
+        // Assert
+        assert!(matches!(
+            response,
+            Err(ApiError::UnexpectedResponse {
+                code: StatusCode::BAD_GATEWAY,
+                ..
+            })
+        ));
+        mock_server.assert();
+    }
+
+    #[tokio::test]
+    async fn test_backend_call_is_reported_to_telemetry() {
+        // Arrange
+        let (mut srv, mut config, _cleanup) = set_config().await;
+        fast_retries(&mut config);
+        config.http.max_retries = 1;
+        let telemetry = std::sync::Arc::new(crate::telemetry::testing::RecordingTelemetry::default());
+        config.telemetry = TelemetryHook::new(Some(telemetry.clone()));
+
+        let mock_server = srv
+            .mock("GET", "/api/telemetry-resource")
+            .with_status(502)
+            .expect(2)
+            .create();
+
+        // Act
+        let response = BackendCall::new(&config, &TOKEN, Method::GET, "/telemetry-resource")
+            .execute()
+            .await;
+
+        // Assert
+        response.unwrap_err();
+        mock_server.assert();
+        let events = telemetry.events.lock().unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            TelemetryEvent::BackendCall {
+                method,
+                path,
+                status: Some(502),
+                attempts: 2,
+                ..
+            } if method == "GET" && path == "/telemetry-resource"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried() {
+        // Arrange
+        let (mut srv, mut config, _cleanup) = set_config().await;
+        fast_retries(&mut config);
+
+        let mock_server = srv.mock("POST", "/api/resource").with_status(503).expect(1).create();
+
+        // Act
+        let response = BackendCall::new(&config, &TOKEN, Method::POST, "/resource")
+            .with_body(&42)
+            .execute()
+            .await;
+
+        // Assert
+        assert!(matches!(
+            response,
+            Err(ApiError::ServiceUnavailable { retry_after: None })
+        ));
+        mock_server.assert();
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_none_for_optional_resources() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+
+        let mock_server = srv.mock("GET", "/api/resource").with_status(404).expect(1).create();
+
+        // Act
+        let response = BackendCall::new(&config, &TOKEN, Method::GET, "/resource")
+            .execute_parse_optional::<u32>()
+            .await;
+
+        // Assert
+        assert_eq!(response.unwrap(), None);
+        mock_server.assert();
+    }
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let http = HttpConfig::default();
+        for attempt in 0..20 {
+            let delay = backoff(attempt, &http);
+            assert!(delay <= Duration::from_millis(http.max_retry_delay_ms));
+        }
+        let first = backoff(0, &http);
+        assert!(first >= Duration::from_millis(http.retry_base_delay_ms / 2));
+    }
+}
@@ -1,16 +1,21 @@
 //! Connects to the dlt-service and puts the user address.
 
+use super::client::BackendCall;
 use super::error::{ApiError, Result};
-use crate::{core::config::Config, types::newtypes::AccessToken};
+use crate::{
+    core::config::Config,
+    types::{currencies::FiatCurrency, newtypes::AccessToken},
+};
 use api_types::api::{
     dlt::{
-        AddressQueryParameters, ApiGetNetworksResponse, GetCourseRequestQueries, GetCourseResponse,
-        SetUserAddressRequest,
+        AddressQueryParameters, ApiGetNetworksResponse, Course, GetCourseHistoryRequestQueries,
+        GetCourseHistoryResponse, GetCourseRequestQueries, GetCourseResponse, SetUserAddressRequest,
     },
     networks::ApiNetwork,
 };
-use log::{debug, error, info};
-use reqwest::StatusCode;
+use chrono::NaiveDate;
+use log::info;
+use reqwest::{Method, StatusCode};
 use rust_decimal::Decimal;
 
 /// Puts the user crypto currency address in the backend
@@ -32,46 +37,20 @@ pub async fn put_user_address(
     network_key: &str,
     address: &str,
 ) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/address");
     let query = AddressQueryParameters {
         network_key: network_key.to_string(),
     };
-
-    info!("Used url: {url:#?}");
     let body = SetUserAddressRequest {
         address: address.to_string(),
     };
     info!("Putting user address {address}");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .put(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .query(&query)
-        .json(&body)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    match response.status() {
-        StatusCode::CREATED => (),
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to put user address: Response status: {}, Response text: {}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
-
-    Ok(())
+    BackendCall::new(config, access_token, Method::PUT, "/user/address")
+        .with_query(&query)
+        .with_body(&body)
+        .with_expected_status(StatusCode::CREATED)
+        .execute()
+        .await
 }
 
 /// Get networks from backend.
@@ -92,40 +71,12 @@ pub async fn put_user_address(
 /// * [`ApiError::MissingAccessToken`] if the request is unauthorized.
 /// * [`ApiError::UnexpectedResponse`] if an unhandled error occurs.
 pub async fn get_networks(config: &Config, access_token: &AccessToken) -> Result<Vec<ApiNetwork>> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/config/networks");
-
-    info!("Used url: {url:#?}");
     info!("Getting networks ..");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
+    let response: ApiGetNetworksResponse = BackendCall::new(config, access_token, Method::GET, "/config/networks")
+        .execute_parse()
         .await?;
-    debug!("Response: {response:#?}");
-
-    match response.status() {
-        StatusCode::OK => {
-            let networks = response.json::<ApiGetNetworksResponse>().await?.networks;
-            Ok(networks)
-        }
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get networks from backend: Response status: {}, Response text: {:?}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    Ok(response.networks)
 }
 
 /// Get exchange rate.
@@ -146,40 +97,114 @@ pub async fn get_networks(config: &Config, access_token: &AccessToken) -> Result
 pub async fn get_exchange_rate(config: &Config, access_token: &AccessToken, network_key: String) -> Result<Decimal> {
     info!("get_exchange_rate for network_key = {:?}", network_key);
 
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/courses");
+    let query = GetCourseRequestQueries {
+        network_key,
+        currency: None,
+    };
+
+    let response: GetCourseResponse = BackendCall::new(config, access_token, Method::GET, "/courses")
+        .with_query(&query)
+        .execute_parse()
+        .await?;
+    Ok(response.course.course.0)
+}
 
-    let query = GetCourseRequestQueries { network_key };
+/// Get exchange rate in a fiat currency.
+///
+/// # Arguments
+///
+/// * `config` - The configuration object.
+/// * `access_token` - The access token for authentication.
+/// * `network_key` - The input string representing the network key.
+/// * `currency` - The fiat currency of the exchange rate.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the price of one unit of the network's currency in `currency`.
+///
+/// # Errors
+///
+/// This function can return an `Error` if the request fails, if the response status is unauthorized
+/// or if the exchange rate is in another currency.
+pub async fn get_exchange_rate_in(
+    config: &Config,
+    access_token: &AccessToken,
+    network_key: String,
+    currency: FiatCurrency,
+) -> Result<Decimal> {
+    info!("get_exchange_rate_in for network_key = {network_key:?} and currency = {currency}");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .query(&query)
-        .send()
+    let query = GetCourseRequestQueries {
+        network_key,
+        currency: Some(currency.to_string()),
+    };
+
+    let response: GetCourseResponse = BackendCall::new(config, access_token, Method::GET, "/courses")
+        .with_query(&query)
+        .execute_parse()
         .await?;
-    debug!("Response: {response:#?}");
+    check_currency(&response.course, currency)?;
+    Ok(response.course.course.0)
+}
 
-    match response.status() {
-        StatusCode::OK => {
-            let course = response.json::<GetCourseResponse>().await?.course;
-            Ok(course.course.0)
-        }
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get exchange rate from backend: Response status: {}, Response text: {:?}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
+/// Get the history of exchange rates in a fiat currency.
+///
+/// # Arguments
+///
+/// * `config` - The configuration object.
+/// * `access_token` - The access token for authentication.
+/// * `network_key` - The input string representing the network key.
+/// * `currency` - The fiat currency of the exchange rates.
+/// * `from_date` - The first day of the history.
+/// * `to_date` - The last day of the history.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the exchange rates of the period, each with the date it applies to.
+///
+/// # Errors
+///
+/// This function can return an `Error` if the request fails, if the response status is unauthorized
+/// or if an exchange rate is in another currency.
+pub async fn get_exchange_rate_history(
+    config: &Config,
+    access_token: &AccessToken,
+    network_key: String,
+    currency: FiatCurrency,
+    from_date: NaiveDate,
+    to_date: NaiveDate,
+) -> Result<Vec<Course>> {
+    info!("get_exchange_rate_history for network_key = {network_key:?} from {from_date} to {to_date}");
+
+    let query = GetCourseHistoryRequestQueries {
+        network_key,
+        from_date: Some(from_date.to_string()),
+        to_date: Some(to_date.to_string()),
+        currency: Some(currency.to_string()),
+    };
+
+    let response: GetCourseHistoryResponse = BackendCall::new(config, access_token, Method::GET, "/courses/history")
+        .with_query(&query)
+        .execute_parse()
+        .await?;
+    for course in &response.courses {
+        check_currency(course, currency)?;
     }
+    Ok(response.courses)
+}
+
+/// Makes sure that a course is in the requested currency, instead of relabeling it. Courses
+/// without a currency are in `EUR`.
+fn check_currency(course: &Course, currency: FiatCurrency) -> Result<()> {
+    let expected = currency.to_string();
+    let actual = course.currency.as_deref().unwrap_or("EUR");
+    if !actual.eq_ignore_ascii_case(&expected) {
+        return Err(ApiError::CurrencyMismatch {
+            expected,
+            actual: actual.to_string(),
+        });
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -332,4 +357,113 @@ mod tests {
         }
         mock_server.assert();
     }
+
+    #[tokio::test]
+    async fn test_get_exchange_rate_in_sends_currency() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+
+        let mut response = example_exchange_rate_response();
+        response.course.currency = Some(String::from("CHF"));
+        let body = serde_json::to_string(&response).unwrap();
+        let mock_server = srv
+            .mock("GET", "/api/courses")
+            .match_header("authorization", format!("Bearer {}", TOKEN.as_str()).as_str())
+            .match_query(Matcher::Exact("network_key=IOTA&currency=CHF".to_string()))
+            .with_status(200)
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let response = get_exchange_rate_in(&config, &TOKEN, "IOTA".to_string(), FiatCurrency::Chf).await;
+
+        // Assert
+        assert_eq!(response.unwrap(), example_exchange_rate_response().course.course.0);
+        mock_server.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_exchange_rate_in_rejects_other_currency() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+
+        // a backend ignoring the currency returns the exchange rate in EUR
+        let body = serde_json::to_string(&example_exchange_rate_response()).unwrap();
+        let mock_server = srv
+            .mock("GET", "/api/courses")
+            .match_query(Matcher::Exact("network_key=IOTA&currency=CHF".to_string()))
+            .with_status(200)
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let response = get_exchange_rate_in(&config, &TOKEN, "IOTA".to_string(), FiatCurrency::Chf).await;
+
+        // Assert
+        assert!(matches!(
+            response,
+            Err(ApiError::CurrencyMismatch { expected, actual }) if expected == "CHF" && actual == "EUR"
+        ));
+        mock_server.assert();
+    }
+
+    #[rstest::rstest]
+    #[case(200, Ok(()))]
+    #[case(401, Err(ApiError::MissingAccessToken))]
+    #[case(500, Err(ApiError::UnexpectedResponse {
+        code: StatusCode::INTERNAL_SERVER_ERROR,
+        body: "".to_string()
+    }))]
+    #[tokio::test]
+    async fn test_get_exchange_rate_history(#[case] status_code: usize, #[case] expected: Result<()>) {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+
+        let body = serde_json::to_string(&GetCourseHistoryResponse {
+            courses: vec![Course {
+                currency: Some(String::from("USD")),
+                ..example_exchange_rate_response().course
+            }],
+        })
+        .unwrap();
+
+        let mut mock_server = srv
+            .mock("GET", "/api/courses/history")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .match_header("authorization", format!("Bearer {}", TOKEN.as_str()).as_str())
+            .match_query(Matcher::Exact(
+                "network_key=IOTA&from_date=2024-05-01&to_date=2024-05-02&currency=USD".to_string(),
+            ))
+            .with_status(status_code);
+        if status_code == 200 {
+            mock_server = mock_server.with_body(&body);
+        }
+        let mock_server = mock_server.expect(1).create();
+
+        // Act
+        let response = get_exchange_rate_history(
+            &config,
+            &TOKEN,
+            "IOTA".to_string(),
+            FiatCurrency::Usd,
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+        )
+        .await;
+
+        // Assert
+        match expected {
+            Ok(()) => {
+                let courses = response.unwrap();
+                assert_eq!(courses.len(), 1);
+                assert_eq!(courses[0].course, example_exchange_rate_response().course.course);
+            }
+            Err(ref expected_err) => {
+                assert_eq!(response.err().unwrap().to_string(), expected_err.to_string());
+            }
+        }
+        mock_server.assert();
+    }
 }
@@ -1,4 +1,5 @@
 use reqwest::StatusCode;
+use std::time::Duration;
 
 /// A [`core::result::Result`] with [`ApiError`] as its error variant.
 pub type Result<T> = core::result::Result<T, ApiError>;
@@ -22,6 +23,29 @@ pub enum ApiError {
     #[error("Unexpected response: code: {code}, body: {body}")]
     UnexpectedResponse { code: StatusCode, body: String },
 
+    /// The backend rejected the request because of too many requests
+    #[error("Too many requests, retry after: {retry_after:?}")]
+    TooManyRequests {
+        /// The delay requested by the backend with the `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+
+    /// The backend is temporarily unavailable
+    #[error("Service unavailable, retry after: {retry_after:?}")]
+    ServiceUnavailable {
+        /// The delay requested by the backend with the `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+
+    /// The backend returned an exchange rate in another currency than requested
+    #[error("Exchange rate in {actual} instead of {expected}")]
+    CurrencyMismatch {
+        /// The requested currency
+        expected: String,
+        /// The currency of the returned exchange rate
+        actual: String,
+    },
+
     /// Error raises if something is wrong with the shares (e.g. not encrypted)
     #[error("Share error: {0}")]
     Share(String),
@@ -5,11 +5,12 @@
 //! and returns the KYC status response.
 //!
 
-use super::error::{ApiError, Result};
+use super::client::BackendCall;
+use super::error::Result;
 use crate::{core::Config, types::newtypes::AccessToken};
 use api_types::api::kyc::KycStatusResponse;
-use log::{debug, error, info};
-use reqwest::StatusCode;
+use log::info;
+use reqwest::Method;
 
 /// Checking kyc status
 ///
@@ -31,44 +32,19 @@ pub async fn check_kyc_status(
     access_token: &AccessToken,
     username: &str,
 ) -> Result<KycStatusResponse> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/kyc/check-status");
-    info!("Used url: {url:#?}");
     info!("Used username: {username}");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-
-    let kyc_status = match response.status() {
-        StatusCode::OK => response.json::<KycStatusResponse>().await?,
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to check kyc status: Response status: {}, Response text: {}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
-
-    Ok(kyc_status)
+    BackendCall::new(config, access_token, Method::GET, "/kyc/check-status")
+        .execute_parse()
+        .await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::error::ApiError;
     use crate::testing_utils::{AUTH_PROVIDER, HEADER_X_APP_NAME, TOKEN, USERNAME, set_config};
+    use reqwest::StatusCode;
 
     fn example_kyc_status_response() -> KycStatusResponse {
         KycStatusResponse {
@@ -2,6 +2,7 @@
 //!
 //!
 
+pub(crate) mod client;
 pub mod dlt;
 pub mod kyc;
 
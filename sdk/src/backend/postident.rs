@@ -3,11 +3,12 @@
 //! This module provides functions for interacting with the Postident service.
 //! It includes functions for getting a new case ID, retrieving case details, and updating case status.
 
-use super::error::{ApiError, Result};
+use super::client::BackendCall;
+use super::error::Result;
 use crate::{core::Config, types::newtypes::AccessToken};
 use api_types::api::postident::{CaseDetailsResponse, NewCaseIdResponse, UpdateCaseStatusRequest};
-use log::{debug, error, info};
-use reqwest::StatusCode;
+use log::info;
+use reqwest::{Method, StatusCode};
 
 /// Get a new case ID from the Postident service.
 ///
@@ -21,38 +22,11 @@ use reqwest::StatusCode;
 ///
 /// Returns a `Result` containing the `NewCaseIdResponse` if successful, or an `Error` if an error occurs.
 pub async fn get_new_case_id(config: &Config, access_token: &AccessToken) -> Result<NewCaseIdResponse> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/postident/get-new-case-id");
-    info!("Used url: {url:#?}");
     info!("Get new case id");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-
-    let kyc_status = match response.status() {
-        StatusCode::OK => response.json::<NewCaseIdResponse>().await?,
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get new case id: Response status: {}, Response text: {:?}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
-
-    Ok(kyc_status)
+    BackendCall::new(config, access_token, Method::GET, "/postident/get-new-case-id")
+        .execute_parse()
+        .await
 }
 
 /// Get case details from the Postident service.
@@ -67,37 +41,11 @@ pub async fn get_new_case_id(config: &Config, access_token: &AccessToken) -> Res
 ///
 /// Returns a `Result` containing the `CaseDetailsResponse` if successful, or an `Error` if an error occurs.
 pub async fn get_case_details(config: &Config, access_token: &AccessToken) -> Result<CaseDetailsResponse> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/postident/get-case-details");
-    info!("Used url: {url:#?}");
-    info!("Get new case id");
+    info!("Get case details");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    let case_details = match response.status() {
-        StatusCode::OK => response.json::<CaseDetailsResponse>().await?,
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get case details: Response status: {}, Response text: {}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
-
-    Ok(case_details)
+    BackendCall::new(config, access_token, Method::GET, "/postident/get-case-details")
+        .execute_parse()
+        .await
 }
 
 /// Update the status of a case in the Postident service.
@@ -113,47 +61,23 @@ pub async fn get_case_details(config: &Config, access_token: &AccessToken) -> Re
 ///
 /// Returns `Ok(())` if the case status is successfully updated, or an `Error` if an error occurs.
 pub async fn update_case_status(config: &Config, access_token: &AccessToken, case_id: &str) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/postident/update-case-status");
-    info!("Used url: {url:#?}");
     info!("Update case status for {case_id}");
 
     let request = UpdateCaseStatusRequest {
         case_id: case_id.into(),
     };
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .json(&request)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    match response.status() {
-        StatusCode::ACCEPTED => (),
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to update case status: Response status: {}, Response text: {:?}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
-
-    Ok(())
+    BackendCall::new(config, access_token, Method::POST, "/postident/update-case-status")
+        .with_body(&request)
+        .with_expected_status(StatusCode::ACCEPTED)
+        .execute()
+        .await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::error::ApiError;
     use crate::testing_utils::{
         AUTH_PROVIDER, HEADER_X_APP_NAME, TOKEN, example_case_details, example_new_case_id, set_config,
     };
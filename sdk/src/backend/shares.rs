@@ -1,10 +1,11 @@
+use super::client::BackendCall;
 use super::error::{ApiError, Result};
 use crate::types::newtypes::AccessToken;
 use crate::{core::Config, share::Share};
 use api_types::api::user::GetShareResponse;
 use api_types::api::user::PutSharesRequest;
-use log::{debug, error, info};
-use reqwest::StatusCode;
+use log::info;
+use reqwest::Method;
 use secrecy::ExposeSecret;
 
 /// Uploads the backup and recovery shares
@@ -30,10 +31,6 @@ pub async fn upload_shares(
     backup_share: &Share,
     recovery_share: &Share,
 ) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/shares");
-    info!("Used url: {url:#?}");
-
     // Double check if the share is encrypted
     if !backup_share.is_encrypted() {
         return Err(ApiError::Share("Backup share is not encrypted".to_string()));
@@ -46,32 +43,10 @@ pub async fn upload_shares(
 
     info!("Uploading backup shares");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .put(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .json(&body)
-        .send()
-        .await?;
-    debug!("Upload backup share response: {response:#?}");
-
-    match response.status() {
-        StatusCode::OK => Ok(()),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to upload the backup share: Response status: {}, Response text: {:?}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    BackendCall::new(config, access_token, Method::PUT, "/user/shares")
+        .with_body(&body)
+        .execute()
+        .await
 }
 
 /// Download the backup share
@@ -96,42 +71,19 @@ pub async fn download_backup_share(
     access_token: &AccessToken,
     username: &str,
 ) -> Result<Option<Share>> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/shares/backup");
-    info!("Used url: {url:#?}");
-
     info!("Downloading backup share for user {}", username);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
+    let response: Option<GetShareResponse> = BackendCall::new(config, access_token, Method::GET, "/user/shares/backup")
+        .execute_parse_optional()
         .await?;
-    debug!("Download backup share response: {response:#?}");
-
-    match response.status() {
-        StatusCode::OK => {
-            let str_share = response.json::<GetShareResponse>().await?.share;
-            let backup_share = str_share.parse::<Share>().map_err(|e| ApiError::Parse(e.to_string()))?;
-            Ok(Some(backup_share))
-        }
-        StatusCode::NOT_FOUND => Ok(None),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to download the backup share: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    response
+        .map(|response| {
+            response
+                .share
+                .parse::<Share>()
+                .map_err(|e| ApiError::Parse(e.to_string()))
+        })
+        .transpose()
 }
 
 /// Download the recovery share
@@ -156,42 +108,20 @@ pub async fn download_recovery_share(
     access_token: &AccessToken,
     username: &str,
 ) -> Result<Option<Share>> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/shares/recovery");
-    info!("Used url: {url:#?}");
-
     info!("Downloading recovery share for user {}", username);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
-        .await?;
-    debug!("Download recovery share response: {response:#?}");
-
-    match response.status() {
-        StatusCode::OK => {
-            let str_share = response.json::<GetShareResponse>().await?.share;
-            let recovery_share = str_share.parse::<Share>().map_err(|e| ApiError::Parse(e.to_string()))?;
-            Ok(Some(recovery_share))
-        }
-        StatusCode::NOT_FOUND => Ok(None),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to download the recovery share: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    let response: Option<GetShareResponse> =
+        BackendCall::new(config, access_token, Method::GET, "/user/shares/recovery")
+            .execute_parse_optional()
+            .await?;
+    response
+        .map(|response| {
+            response
+                .share
+                .parse::<Share>()
+                .map_err(|e| ApiError::Parse(e.to_string()))
+        })
+        .transpose()
 }
 
 /// Delete user shares
@@ -210,37 +140,11 @@ pub async fn download_recovery_share(
 ///
 /// Returns an `ApiError::MissingAccessToken` if the request is unauthorized, or an `ApiError::UnexpectedResponse` if an unhandled error occurs.
 pub async fn delete_shares(config: &Config, access_token: &AccessToken, username: &str) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/shares");
-    info!("Used url: {url:#?}");
-
     info!("Deleting shares for user {}", username);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .delete(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
-        .await?;
-    debug!("Delete shares response: {response:#?}");
-
-    match response.status() {
-        StatusCode::OK => Ok(()),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to delete user shares: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    BackendCall::new(config, access_token, Method::DELETE, "/user/shares")
+        .execute()
+        .await
 }
 
 #[cfg(test)]
@@ -250,6 +154,7 @@ mod tests {
         AUTH_PROVIDER, ENCRYPTED_SHARE, HEADER_X_APP_NAME, NOT_ENCRYPTED_SHARE, TOKEN, USERNAME, set_config,
     };
     use mockito::Matcher;
+    use reqwest::StatusCode;
     use secrecy::ExposeSecret;
 
     fn example_share_response() -> GetShareResponse {
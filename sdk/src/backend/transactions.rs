@@ -17,15 +17,16 @@
 //! For more information, see the individual function documentation.
 //!
 
-use super::error::{ApiError, Result};
+use super::client::BackendCall;
+use super::error::Result;
 use crate::{core::config::Config, types::newtypes::AccessToken};
 use api_types::api::transactions::{
     ApiApplicationMetadata, CommitTransactionRequest, CreateTransactionRequest, CreateTransactionResponse,
     GetTransactionDetailsResponse, GetTransactionStatusRequest, GetTxsDetailsResponse, TxsDetailsQuery,
 };
 use etopay_wallet::types::CryptoAmount;
-use log::{debug, error, info};
-use reqwest::StatusCode;
+use log::info;
+use reqwest::{Method, StatusCode};
 
 /// Create new transaction
 ///
@@ -53,45 +54,19 @@ pub async fn create_new_transaction(
     amount: CryptoAmount,
     metadata: ApiApplicationMetadata,
 ) -> Result<CreateTransactionResponse> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/transactions/create");
-
     let body = CreateTransactionRequest {
         amount: amount.inner().into(),
         network_key,
         receiver: receiver.into(),
         application_metadata: metadata,
     };
-    info!("Used url: {url:#?}");
     info!("Create new transaction to {receiver} with the amount of {amount:?}");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .json(&body)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    let tx_response = match response.status() {
-        StatusCode::CREATED => response.json::<CreateTransactionResponse>().await?,
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to create a new transaction: Response status: {}, Response text: {}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
-
-    Ok(tx_response)
+    BackendCall::new(config, access_token, Method::POST, "/transactions/create")
+        .with_body(&body)
+        .with_expected_status(StatusCode::CREATED)
+        .execute_parse()
+        .await
 }
 
 /// Commit transaction
@@ -112,40 +87,17 @@ pub async fn create_new_transaction(
 ///
 /// Returns an `Error::Unauthorized` if the request is unauthorized, or an `Error::UnhandledError` if an unhandled error occurs.
 pub async fn commit_transaction(config: &Config, access_token: &AccessToken, index: &str, tx_id: &str) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/transactions/commit");
     let body = CommitTransactionRequest {
         index: index.into(),
         transaction_id: tx_id.into(),
     };
-    info!("Used url: {url:#?}");
     info!("Commit transaction for {index}");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .json(&body)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    match response.status() {
-        StatusCode::ACCEPTED => Ok(()),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to commit transaction `{index}`: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    BackendCall::new(config, access_token, Method::POST, "/transactions/commit")
+        .with_body(&body)
+        .with_expected_status(StatusCode::ACCEPTED)
+        .execute()
+        .await
 }
 
 /// Get transaction details
@@ -169,36 +121,13 @@ pub async fn get_transaction_details(
     access_token: &AccessToken,
     index: &str,
 ) -> Result<GetTransactionDetailsResponse> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/transactions/details");
     let query = GetTransactionStatusRequest { index: index.into() };
     info!("Get transaction status for {index}");
-    info!("Used url: {url:#?}");
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .query(&query)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    match response.status() {
-        StatusCode::OK => Ok(response.json::<GetTransactionDetailsResponse>().await?),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get transaction `{index}` details: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+
+    BackendCall::new(config, access_token, Method::GET, "/transactions/details")
+        .with_query(&query)
+        .execute_parse()
+        .await
 }
 
 /// Get transaction list (paginated)
@@ -224,8 +153,6 @@ pub async fn get_transactions_list(
     start: u32,
     limit: u32,
 ) -> Result<GetTxsDetailsResponse> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/transactions/txs-details");
     let query = TxsDetailsQuery {
         date: None,
         partner: None,
@@ -233,41 +160,18 @@ pub async fn get_transactions_list(
         start,
         limit,
     };
-
     info!("Get transaction list");
-    info!("Used url: {url:#?}");
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .query(&query)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-    let tx_response = match response.status() {
-        StatusCode::OK => response.json::<GetTxsDetailsResponse>().await?,
-        StatusCode::UNAUTHORIZED => return Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get a list of transactions: Response status: {}, Response text: {}",
-                status, text
-            );
-            return Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            });
-        }
-    };
 
-    Ok(tx_response)
+    BackendCall::new(config, access_token, Method::GET, "/transactions/txs-details")
+        .with_query(&query)
+        .execute_parse()
+        .await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::error::ApiError;
     use crate::testing_utils::{
         AMOUNT, AUTH_PROVIDER, HEADER_X_APP_NAME, IOTA_NETWORK_KEY, LIMIT, RECEIVER, START, TOKEN, TX_INDEX,
         example_tx_details, example_tx_metadata, set_config,
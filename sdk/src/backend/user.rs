@@ -3,11 +3,12 @@
 //! The `delete_user_account` function is used to delete a user account by sending a DELETE request to the backend API.
 //! It requires a `Config` object, an access token, and the username of the user to be deleted.
 
-use super::error::{ApiError, Result};
+use super::client::BackendCall;
+use super::error::Result;
 use crate::{core::Config, types::newtypes::AccessToken};
 use api_types::api::dlt::{GetPreferredNetworkResponse, SetPreferredNetworkRequest};
-use log::{debug, error, info};
-use reqwest::StatusCode;
+use log::info;
+use reqwest::{Method, StatusCode};
 
 /// Delete the user
 ///
@@ -25,36 +26,12 @@ use reqwest::StatusCode;
 ///
 /// Returns an `Err` variant of [`ApiError`] if there is an error deleting the user account.
 pub async fn delete_user_account(config: &Config, access_token: &AccessToken) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user");
-    info!("Used url: {url:#?}");
     info!("Deleting user account");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .delete(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-
-    match response.status() {
-        StatusCode::ACCEPTED => Ok(()),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to delete user account: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    BackendCall::new(config, access_token, Method::DELETE, "/user")
+        .with_expected_status(StatusCode::ACCEPTED)
+        .execute()
+        .await
 }
 
 /// Set user's preferred currency
@@ -78,39 +55,15 @@ pub async fn set_preferred_network(
     access_token: &AccessToken,
     network_key: Option<String>,
 ) -> Result<()> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/network");
-    info!("Used url: {url:#?}");
     info!("Setting preferred network");
 
     let body = SetPreferredNetworkRequest { network_key };
 
-    let client = reqwest::Client::new();
-    let response = client
-        .put(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .json(&body)
-        .send()
-        .await?;
-    debug!("Response: {response:#?}");
-
-    match response.status() {
-        StatusCode::ACCEPTED => Ok(()),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to set preferred currency: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    BackendCall::new(config, access_token, Method::PUT, "/user/network")
+        .with_body(&body)
+        .with_expected_status(StatusCode::ACCEPTED)
+        .execute()
+        .await
 }
 
 /// Get user's preferred network (id)
@@ -129,40 +82,17 @@ pub async fn set_preferred_network(
 ///
 /// Returns an `Err` variant of [`ApiError`] if there is an error getting the preferred network (id).
 pub async fn get_preferred_network(config: &Config, access_token: &AccessToken) -> Result<Option<String>> {
-    let base_url = &config.backend_url;
-    let url = format!("{base_url}/user/network");
-    info!("Used url: {url:#?}");
     info!("Getting preferred network");
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token.as_str())
-        .header("X-APP-NAME", &config.auth_provider)
-        .send()
+    let response: GetPreferredNetworkResponse = BackendCall::new(config, access_token, Method::GET, "/user/network")
+        .execute_parse()
         .await?;
-    debug!("Response: {response:#?}");
-
-    match response.status() {
-        StatusCode::OK => Ok(response.json::<GetPreferredNetworkResponse>().await?.network_key),
-        StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-        _ => {
-            let status = response.status();
-            let text = response.text().await?;
-            error!(
-                "Failed to get preferred currency: Response status: {}, Response text: {}",
-                status, text
-            );
-            Err(ApiError::UnexpectedResponse {
-                code: status,
-                body: text,
-            })
-        }
-    }
+    Ok(response.network_key)
 }
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::error::ApiError;
     use crate::testing_utils::{AUTH_PROVIDER, HEADER_X_APP_NAME, IOTA_NETWORK_KEY, TOKEN, set_config};
 
     #[rstest::rstest]
@@ -6,7 +6,8 @@
 //! and getting payment details.
 //!
 
-use super::error::{ApiError, Result};
+use super::client::BackendCall;
+use super::error::Result;
 use crate::core::Config;
 use crate::types::newtypes::AccessToken;
 use api_types::api::viviswap::contract::{ContractRequestBody, ViviswapContractCreationResponse};
@@ -23,98 +24,8 @@ use api_types::api::viviswap::order::{GetOrderQuery, GetOrdersQuery, Order, Orde
 use api_types::api::viviswap::payment::ViviPaymentMethodsResponse;
 use api_types::api::viviswap::user::{UserDataRequest, UserDataResponse};
 use etopay_wallet::types::CryptoAmount;
-use log::{debug, error, info};
-use reqwest::{Method, StatusCode};
-use std::future::Future;
-
-/// helper object for calling the backend (reduces boiler-plate code)
-struct ViviswapBackendCall {
-    request_builder: reqwest::RequestBuilder,
-    method: reqwest::Method,
-    url: String,
-}
-
-impl ViviswapBackendCall {
-    pub fn new(config: &Config, access_token: &AccessToken, method: reqwest::Method, url_path: &str) -> Self {
-        let url = format!("{}{url_path}", config.backend_url);
-        info!("Used url: {url:#?}");
-
-        let client = reqwest::Client::new();
-        let request_builder = client
-            .request(method.clone(), &url)
-            .bearer_auth(access_token.as_str())
-            .header("X-APP-NAME", &config.auth_provider);
-
-        Self {
-            request_builder,
-            method,
-            url: url_path.to_string(),
-        }
-    }
-
-    pub fn with_query<T: serde::Serialize>(self, query: &T) -> Self {
-        Self {
-            request_builder: self.request_builder.query(query),
-            method: self.method,
-            url: self.url,
-        }
-    }
-
-    pub fn with_body<T: serde::Serialize>(self, body: &T) -> Self {
-        Self {
-            request_builder: self.request_builder.json(body),
-            method: self.method,
-            url: self.url,
-        }
-    }
-
-    async fn execute_inner<R, F, Fut>(self, ok_transform: F) -> Result<R>
-    where
-        F: FnOnce(reqwest::Response) -> Fut,
-        Fut: Future<Output = Result<R>>,
-    {
-        let response = self.request_builder.send().await?;
-        debug!("Response: {response:#?}");
-
-        match response.status() {
-            StatusCode::OK => ok_transform(response).await,
-            StatusCode::UNAUTHORIZED => Err(ApiError::MissingAccessToken),
-            _ => {
-                let status = response.status();
-                let text = response.text().await?;
-                let method = self.method.as_str();
-                let url = self.url;
-                error!(
-                    "Failed to {method} {}: Response status: {}, Response text: {}",
-                    url, status, text
-                );
-                Err(ApiError::UnexpectedResponse {
-                    code: status,
-                    body: text,
-                })
-            }
-        }
-    }
-    /// Execute this request and parse the response as JSON
-    pub async fn execute_parse<R: for<'de> serde::Deserialize<'de>>(self) -> Result<R> {
-        // since we cannot pass async closures, lets just define the function here
-        async fn parse<R: for<'de> serde::Deserialize<'de>>(response: reqwest::Response) -> Result<R> {
-            Ok(response.json::<R>().await?)
-        }
-
-        self.execute_inner(parse).await
-    }
-
-    /// Execute this request and ignore the return content
-    pub async fn execute(self) -> Result<()> {
-        // since we cannot pass async closures, lets just define the function here
-        async fn ok(_response: reqwest::Response) -> Result<()> {
-            Ok(())
-        }
-
-        self.execute_inner(ok).await
-    }
-}
+use log::info;
+use reqwest::Method;
 
 /// Creates a new viviswap user
 ///
@@ -146,7 +57,7 @@ pub async fn create_viviswap_user(
         terms_accepted,
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/users")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/users")
         .with_body(&request)
         .execute_parse()
         .await
@@ -170,7 +81,7 @@ pub async fn create_viviswap_user(
 pub async fn get_viviswap_kyc_status(config: &Config, access_token: &AccessToken) -> Result<KycDetailsResponse> {
     info!("Get viviswap kyc status");
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/kyc/status")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/kyc/status")
         .execute_parse()
         .await
 }
@@ -218,7 +129,7 @@ pub async fn set_viviswap_kyc_general_details(
         nationality: nationality.into(),
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/general")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/general")
         .with_body(&request)
         .execute()
         .await
@@ -254,7 +165,7 @@ pub async fn set_viviswap_kyc_personal_details(
         date_of_birth: date_of_birth.into(),
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/personal")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/personal")
         .with_body(&request)
         .execute()
         .await
@@ -290,7 +201,7 @@ pub async fn set_viviswap_kyc_identity_details(
         personal_document,
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/identity")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/identity")
         .with_body(&request)
         .execute()
         .await
@@ -320,7 +231,7 @@ pub async fn set_viviswap_kyc_residence_details(
 ) -> Result<()> {
     info!("Set viviswap kyc residence details ");
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/residence")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/residence")
         .with_body(&residence_details)
         .execute()
         .await
@@ -350,7 +261,7 @@ pub async fn get_viviswap_kyc_amla_open_questions(
 ) -> Result<GetKycAmlaQuestionsResponse> {
     info!("Set viviswap kyc open amla questions ");
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/kyc/questions")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/kyc/questions")
         .execute_parse()
         .await
 }
@@ -379,7 +290,7 @@ pub async fn set_viviswap_kyc_amla_answer(
 ) -> Result<()> {
     info!("Set viviswap kyc amla question answer ");
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/questions")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/questions")
         .with_body(&answer)
         .execute()
         .await
@@ -409,7 +320,7 @@ pub async fn get_viviswap_kyc_open_documents(
 ) -> Result<GetKycDocumentsResponse> {
     info!("Set viviswap kyc open documents ");
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/kyc/documents")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/kyc/documents")
         .execute_parse()
         .await
 }
@@ -438,7 +349,7 @@ pub async fn set_viviswap_kyc_document(
 ) -> Result<()> {
     info!("Set viviswap kyc document ");
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/documents")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/kyc/documents")
         .with_body(&document)
         .execute()
         .await
@@ -474,7 +385,7 @@ pub async fn delete_viviswap_detail(
         payment_detail_id: payment_detail_id.to_string(),
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::DELETE, "/viviswap/details")
+    BackendCall::new(config, access_token, Method::DELETE, "/viviswap/details")
         .with_query(&query)
         .execute()
         .await
@@ -510,7 +421,7 @@ pub async fn set_viviswap_detail(
     let request = SetDetailRequestBody {
         address: String::from(address),
     };
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/details")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/details")
         .with_query(&query)
         .with_body(&request)
         .execute_parse()
@@ -542,7 +453,7 @@ pub async fn get_viviswap_details(
 
     let query = GetPaymentDetailsRequestQueries { payment_method_key };
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/details")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/details")
         .with_query(&query)
         .execute_parse()
         .await
@@ -588,7 +499,7 @@ pub async fn set_viviswap_contract(
         outgoing_payment_detail_id,
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::POST, "/viviswap/contracts")
+    BackendCall::new(config, access_token, Method::POST, "/viviswap/contracts")
         .with_body(&request)
         .execute_parse()
         .await
@@ -615,7 +526,7 @@ pub async fn get_viviswap_payment_method(
 ) -> Result<ViviPaymentMethodsResponse> {
     info!("get_viviswap_payment_method");
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/methods")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/methods")
         .execute_parse()
         .await
 }
@@ -643,7 +554,7 @@ pub async fn get_viviswap_order(config: &Config, access_token: &AccessToken, ord
         id: order_id.to_string(),
     };
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/orders")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/orders")
         .with_query(&query)
         .execute_parse()
         .await
@@ -679,7 +590,7 @@ pub async fn get_viviswap_orders(
 
     let query = GetOrdersQuery { start, limit };
 
-    ViviswapBackendCall::new(config, access_token, Method::GET, "/viviswap/orders")
+    BackendCall::new(config, access_token, Method::GET, "/viviswap/orders")
         .with_query(&query)
         .execute_parse()
         .await
@@ -688,6 +599,7 @@ pub async fn get_viviswap_orders(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::error::ApiError;
     use crate::testing_utils::{
         ADDRESS, AUTH_PROVIDER, HEADER_X_APP_NAME, PAYMENT_DETAIL_ID, PAYMENT_METHOD_ID, PAYMENT_METHOD_KEY,
         PAYMENT_METHOD_KEY_SERIALIZED, TOKEN, USERNAME, example_bank_details, example_contract_response,
@@ -698,6 +610,7 @@ mod tests {
         kyc::{File, KycAmlaQuestion, KycOpenDocument, KycStep, KycVerificationStatus, OfficialDocumentType},
     };
     use mockito::Matcher;
+    use reqwest::StatusCode;
 
     fn example_kyc_status_response() -> KycDetailsResponse {
         KycDetailsResponse {
@@ -0,0 +1,274 @@
+use super::Sdk;
+use crate::types::newtypes::AccessToken;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Thread-safety required from an [`AccessTokenProvider`]. Not needed on wasm, where the provider
+/// is usually a JavaScript function.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// Thread-safety required from an [`AccessTokenProvider`]. Not needed on wasm, where the provider
+/// is usually a JavaScript function.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// Provides a new access token when the current one expired or was rejected by the backend
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait AccessTokenProvider: MaybeSendSync {
+    /// Get a new access token, or an error message if the user needs to log in again.
+    async fn get_access_token(&self) -> core::result::Result<AccessToken, String>;
+}
+
+/// Refreshes the access token with the [`AccessTokenProvider`] set on the SDK. It is shared with
+/// the [`super::Config`] so that every backend call can refresh the access token and retry.
+pub(crate) struct AccessTokenRefresher {
+    provider: Box<dyn AccessTokenProvider>,
+    latest: Mutex<Option<AccessToken>>,
+    /// Held while the provider is asked for a new access token, so that concurrent requests
+    /// rejected with the same access token refresh it only once
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+impl std::fmt::Debug for AccessTokenRefresher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessTokenRefresher").finish_non_exhaustive()
+    }
+}
+
+impl AccessTokenRefresher {
+    /// The access token returned by the last refresh, if any
+    pub(crate) fn latest(&self) -> Option<AccessToken> {
+        self.latest.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Get a new access token to replace the `expired` one, or [`None`] if the provider failed.
+    /// If the access token was already refreshed by a concurrent request, the new access token is
+    /// returned without asking the provider again.
+    pub(crate) async fn refresh(&self, expired: &AccessToken) -> Option<AccessToken> {
+        let _refreshing = self.refreshing.lock().await;
+        if let Some(latest) = self.latest() {
+            if latest.as_str() != expired.as_str() {
+                debug!("The access token was already refreshed");
+                return Some(latest);
+            }
+        }
+
+        info!("Refreshing the access token");
+        match self.provider.get_access_token().await {
+            Ok(access_token) => {
+                *self.latest.lock().unwrap_or_else(PoisonError::into_inner) = Some(access_token.clone());
+                Some(access_token)
+            }
+            Err(e) => {
+                warn!("Could not refresh the access token: {e}");
+                None
+            }
+        }
+    }
+
+    fn reset(&self) {
+        *self.latest.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+impl Sdk {
+    /// Set a provider for new access tokens
+    ///
+    /// The SDK asks the provider for a new access token when the current one is expired (according
+    /// to its `exp` claim) or rejected by the backend, and retries the request once with the new
+    /// token. Concurrent requests share a single refresh. The host app then no longer has to call
+    /// [`Sdk::refresh_access_token`] itself, except for setting the first access token.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider of new access tokens.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn set_access_token_provider(&mut self, provider: impl AccessTokenProvider + 'static) {
+        info!("Setting access token provider");
+        let refresher = Arc::new(AccessTokenRefresher {
+            provider: Box::new(provider),
+            latest: Mutex::new(None),
+            refreshing: tokio::sync::Mutex::new(()),
+        });
+        if let Some(config) = &mut self.config {
+            config.access_token_refresher = Some(refresher.clone());
+        }
+        self.access_token_refresher = Some(refresher);
+    }
+
+    /// Forget the access token of the last refresh, since the host app set a new one
+    pub(crate) fn reset_access_token_refresher(&self) {
+        if let Some(refresher) = &self.access_token_refresher {
+            refresher.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing_utils::{TOKEN, set_config};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+        token: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl AccessTokenProvider for CountingProvider {
+        async fn get_access_token(&self) -> core::result::Result<AccessToken, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.token {
+                Some(token) => AccessToken::try_from(token).map_err(|e| e.to_string()),
+                None => Err(String::from("login required")),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_call_is_retried_with_refreshed_token() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        sdk.refresh_access_token(Some(TOKEN.clone())).await.unwrap();
+        sdk.set_access_token_provider(CountingProvider {
+            calls: calls.clone(),
+            token: Some("refreshed token"),
+        });
+
+        let rejected = srv
+            .mock("GET", "/api/config/networks")
+            .match_header("authorization", format!("Bearer {}", TOKEN.as_str()).as_str())
+            .with_status(401)
+            .expect(1)
+            .create();
+        let accepted = srv
+            .mock("GET", "/api/config/networks")
+            .match_header("authorization", "Bearer refreshed token")
+            .with_status(200)
+            .with_body(r#"{"networks": []}"#)
+            .expect(2)
+            .create();
+
+        // Act
+        let first = sdk.get_networks().await;
+        let second = sdk.get_networks().await;
+
+        // Assert
+        assert!(first.unwrap().is_empty());
+        assert!(second.unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        rejected.assert();
+        accepted.assert();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_rejected_calls_refresh_the_token_once() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        sdk.set_access_token_provider(CountingProvider {
+            calls: calls.clone(),
+            token: Some("refreshed token"),
+        });
+
+        let rejected = srv
+            .mock("GET", "/api/config/networks")
+            .match_header("authorization", format!("Bearer {}", TOKEN.as_str()).as_str())
+            .with_status(401)
+            .expect(2)
+            .create();
+        let accepted = srv
+            .mock("GET", "/api/config/networks")
+            .match_header("authorization", "Bearer refreshed token")
+            .with_status(200)
+            .with_body(r#"{"networks": []}"#)
+            .expect(2)
+            .create();
+
+        // Act
+        let config = sdk.config.as_ref().unwrap();
+        let (first, second) = tokio::join!(
+            crate::backend::dlt::get_networks(config, &TOKEN),
+            crate::backend::dlt::get_networks(config, &TOKEN)
+        );
+
+        // Assert
+        assert!(first.unwrap().is_empty());
+        assert!(second.unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        rejected.assert();
+        accepted.assert();
+    }
+
+    #[tokio::test]
+    async fn test_backend_call_fails_if_provider_fails() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        sdk.set_access_token_provider(CountingProvider {
+            calls: calls.clone(),
+            token: None,
+        });
+        sdk.refresh_access_token(Some(TOKEN.clone())).await.unwrap();
+
+        let rejected = srv
+            .mock("GET", "/api/config/networks")
+            .with_status(401)
+            .expect(1)
+            .create();
+
+        // Act
+        let response = sdk.get_networks().await;
+
+        // Assert
+        assert_eq!(
+            response.unwrap_err().to_string(),
+            crate::Error::BackendApi(crate::backend::error::ApiError::MissingAccessToken).to_string()
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        rejected.assert();
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_refreshed_before_the_request() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        sdk.set_access_token_provider(CountingProvider {
+            calls: calls.clone(),
+            token: Some("refreshed token"),
+        });
+        // {"exp": 1} is long expired
+        let expired = AccessToken::try_from("eyJhbGciOiJub25lIn0.eyJleHAiOjF9.").unwrap();
+        sdk.refresh_access_token(Some(expired)).await.unwrap();
+
+        let accepted = srv
+            .mock("GET", "/api/config/networks")
+            .match_header("authorization", "Bearer refreshed token")
+            .with_status(200)
+            .with_body(r#"{"networks": []}"#)
+            .expect(1)
+            .create();
+
+        // Act
+        let response = sdk.get_networks().await;
+
+        // Assert
+        assert!(response.unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        accepted.assert();
+    }
+}
@@ -0,0 +1,300 @@
+//! This module provides methods for managing the labels of the user's own addresses and the
+//! address book of the user. Both are stored in the user repository and included in wallet backups.
+
+use super::Sdk;
+use crate::error::Result;
+use crate::types::users::{AddressBookEntry, AddressLabel};
+use log::info;
+
+/// Inserts the label, replacing an existing label for the same network and address.
+pub(crate) fn upsert_address_label(labels: &mut Vec<AddressLabel>, label: AddressLabel) {
+    match labels
+        .iter_mut()
+        .find(|l| l.network_key == label.network_key && l.address == label.address)
+    {
+        Some(existing) => *existing = label,
+        None => labels.push(label),
+    }
+}
+
+/// Inserts the entry, replacing an existing entry for the same network and address.
+pub(crate) fn upsert_address_book_entry(address_book: &mut Vec<AddressBookEntry>, entry: AddressBookEntry) {
+    match address_book
+        .iter_mut()
+        .find(|e| e.network_key == entry.network_key && e.address == entry.address)
+    {
+        Some(existing) => *existing = entry,
+        None => address_book.push(entry),
+    }
+}
+
+impl Sdk {
+    /// Set or remove the label of one of the user's own addresses
+    ///
+    /// # Arguments
+    ///
+    /// * `network_key` - The key of the network the address belongs to.
+    /// * `address` - The address to label.
+    /// * `label` - The label to set, or `None` to remove an existing label.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn set_address_label(
+        &mut self,
+        network_key: String,
+        address: String,
+        label: Option<String>,
+    ) -> Result<()> {
+        info!("Setting label for address {address} on network {network_key}");
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        let mut labels = repo.get(&active_user.username)?.address_labels;
+        match label {
+            Some(label) => upsert_address_label(
+                &mut labels,
+                AddressLabel {
+                    network_key,
+                    address,
+                    label,
+                },
+            ),
+            None => labels.retain(|l| l.network_key != network_key || l.address != address),
+        }
+        repo.set_address_labels(&active_user.username, labels)?;
+
+        Ok(())
+    }
+
+    /// Get the labels of the user's own addresses
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_address_labels(&self) -> Result<Vec<AddressLabel>> {
+        info!("Getting address labels");
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        Ok(repo.get(&active_user.username)?.address_labels)
+    }
+
+    /// Add an entry to the address book. An existing entry for the same network and address is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry to add.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn add_address_book_entry(&mut self, entry: AddressBookEntry) -> Result<()> {
+        info!("Adding address book entry for network {}", entry.network_key);
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        let mut address_book = repo.get(&active_user.username)?.address_book;
+        upsert_address_book_entry(&mut address_book, entry);
+        repo.set_address_book(&active_user.username, address_book)?;
+
+        Ok(())
+    }
+
+    /// Remove an entry from the address book. Does nothing if there is no such entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_key` - The key of the network the address belongs to.
+    /// * `address` - The address of the entry to remove.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn remove_address_book_entry(&mut self, network_key: &str, address: &str) -> Result<()> {
+        info!("Removing address book entry for network {network_key}");
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        let mut address_book = repo.get(&active_user.username)?.address_book;
+        address_book.retain(|e| e.network_key != network_key || e.address != address);
+        repo.set_address_book(&active_user.username, address_book)?;
+
+        Ok(())
+    }
+
+    /// Get the address book of the user
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_address_book(&self) -> Result<Vec<AddressBookEntry>> {
+        info!("Getting address book");
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        Ok(repo.get(&active_user.username)?.address_book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::core_testing_utils::handle_error_test_cases;
+    use crate::testing_utils::{ETH_NETWORK_KEY, IOTA_NETWORK_KEY, USERNAME, example_user_entity, set_config};
+    use crate::types::users::ActiveUser;
+    use crate::user::{UserRepo, memory_storage::MemoryUserStorage, repository::UserRepoImpl};
+    use crate::wallet_manager::MockWalletManager;
+    use rstest::rstest;
+
+    fn sdk_with_user(sdk: &mut Sdk) {
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&example_user_entity(USERNAME)).unwrap();
+        sdk.repo = Some(Box::new(repo));
+        sdk.active_user = Some(ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+    }
+
+    fn entry(network_key: &str, address: &str, name: &str) -> AddressBookEntry {
+        AddressBookEntry {
+            network_key: network_key.to_string(),
+            address: address.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_upsert_address_book_entry_replaces_same_network_and_address() {
+        // Arrange
+        let mut address_book = vec![entry(IOTA_NETWORK_KEY, "0x1", "alice")];
+
+        // Act
+        upsert_address_book_entry(&mut address_book, entry(ETH_NETWORK_KEY, "0x1", "bob"));
+        upsert_address_book_entry(&mut address_book, entry(IOTA_NETWORK_KEY, "0x1", "carol"));
+
+        // Assert
+        assert_eq!(
+            address_book,
+            vec![
+                entry(IOTA_NETWORK_KEY, "0x1", "carol"),
+                entry(ETH_NETWORK_KEY, "0x1", "bob")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_and_remove_address_label() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+
+        // Act
+        sdk.set_address_label(IOTA_NETWORK_KEY.to_string(), "0x1".to_string(), Some("old".to_string()))
+            .await
+            .unwrap();
+        sdk.set_address_label(
+            IOTA_NETWORK_KEY.to_string(),
+            "0x1".to_string(),
+            Some("savings".to_string()),
+        )
+        .await
+        .unwrap();
+        sdk.set_address_label(
+            ETH_NETWORK_KEY.to_string(),
+            "0x2".to_string(),
+            Some("daily".to_string()),
+        )
+        .await
+        .unwrap();
+        sdk.set_address_label(ETH_NETWORK_KEY.to_string(), "0x2".to_string(), None)
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            sdk.get_address_labels().await.unwrap(),
+            vec![AddressLabel {
+                network_key: IOTA_NETWORK_KEY.to_string(),
+                address: "0x1".to_string(),
+                label: "savings".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_address_book_entry() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+
+        // Act
+        sdk.add_address_book_entry(entry(IOTA_NETWORK_KEY, "0x1", "alice"))
+            .await
+            .unwrap();
+        sdk.add_address_book_entry(entry(ETH_NETWORK_KEY, "0x2", "bob"))
+            .await
+            .unwrap();
+        sdk.remove_address_book_entry(IOTA_NETWORK_KEY, "0x1").await.unwrap();
+
+        // Assert
+        assert_eq!(
+            sdk.get_address_book().await.unwrap(),
+            vec![entry(ETH_NETWORK_KEY, "0x2", "bob")]
+        );
+    }
+
+    #[rstest]
+    #[case::repo_init_error(crate::Error::UserRepoNotInitialized)]
+    #[case::user_init_error(crate::Error::UserNotInitialized)]
+    #[tokio::test]
+    async fn test_address_book_errors(#[case] expected: crate::Error) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        handle_error_test_cases(&expected, &mut sdk, 0, 0).await;
+
+        // Act
+        let response = sdk
+            .add_address_book_entry(entry(IOTA_NETWORK_KEY, "0x1", "alice"))
+            .await;
+
+        // Assert
+        assert_eq!(response.unwrap_err().to_string(), expected.to_string());
+    }
+}
@@ -1,17 +1,37 @@
 //! Configuration for SDK
 //!
-//!
+//! The [`Config`] is built by a [`ConfigBuilder`] from layered sources: the defaults, JSON or TOML
+//! documents, `ETOPAY_*` environment variables and programmatic overrides. Later sources take
+//! precedence over earlier ones.
 
 use super::Sdk;
+use super::access_token::AccessTokenRefresher;
 use crate::error::{Error, Result};
-use crate::user::UserRepo;
+use crate::secret_store::SecretStore;
+use crate::telemetry::{Telemetry, TelemetryHook};
 use crate::user::repository::UserRepoImpl;
+use crate::user::{UserKvStorage, UserRepo};
 use log::info;
-use std::path::Path;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+
+/// Prefix of the environment variables read by [`ConfigBuilder::env`]
+pub const ENV_PREFIX: &str = "ETOPAY_";
+
+/// Separator of the nested sections in the name of an environment variable, e.g.
+/// `ETOPAY_HTTP__TIMEOUT_MS` for `http.timeout_ms`
+pub const ENV_SEPARATOR: &str = "__";
 
 /// Struct to configure the SDK
-#[derive(Debug)]
+///
+/// New sections may be added in the future, so create it with [`Config::new`], [`Config::from_json`]
+/// or [`Config::builder`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Config {
     /// The root folder used to access the file system. It is assumed that we have full read and
     /// write permissions to this folder and that it already exists.
@@ -25,6 +45,206 @@ pub struct Config {
 
     /// Log level for filtering which log messages that end up in the log file.
     pub log_level: log::LevelFilter,
+
+    /// Selects the storage used for the user repository.
+    pub storage: StorageConfig,
+
+    /// Timeouts and retries of the requests to the backend.
+    pub http: HttpConfig,
+
+    /// Node URLs to use instead of the ones served by the backend, by network key.
+    pub rpc: HashMap<String, RpcOverride>,
+
+    /// Where log messages are written to.
+    pub log: LogConfig,
+
+    /// Optional functionality that can be switched off.
+    pub features: FeatureToggles,
+
+    /// Refreshes the access token of the backend calls with the provider set on the SDK
+    pub(crate) access_token_refresher: Option<Arc<AccessTokenRefresher>>,
+
+    /// Receives the telemetry events of the backend calls and the wallets, set on the SDK
+    pub(crate) telemetry: TelemetryHook,
+}
+
+/// Configuration of the requests to the backend
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Timeout of a whole request in milliseconds. Not supported on wasm.
+    pub timeout_ms: u64,
+    /// Timeout for establishing a connection in milliseconds. Not supported on wasm.
+    pub connect_timeout_ms: u64,
+    /// How often a `GET` request is retried after a timeout, a connection error or a `429`,
+    /// `502`, `503` or `504` response. Other requests are never retried.
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled with every further retry.
+    pub retry_base_delay_ms: u64,
+    /// Upper bound of the delay between two attempts in milliseconds. If the backend asks to wait
+    /// longer with `Retry-After`, the request is not retried.
+    pub max_retry_delay_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 30_000,
+            connect_timeout_ms: 10_000,
+            max_retries: 3,
+            retry_base_delay_ms: 250,
+            max_retry_delay_ms: 10_000,
+        }
+    }
+}
+
+/// The storage used for the user repository
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Select the storage from the enabled features: sqlite if `sqlite_repo` is enabled, jammdb if
+    /// `jammdb_repo` is enabled, the browser storage on wasm and an in-memory storage otherwise.
+    #[default]
+    Default,
+    /// A non-persistent in-memory storage.
+    Memory,
+    /// A jammdb file-based database, requires the `jammdb_repo` feature.
+    File,
+    /// A jammdb file-based database encrypted with the key from [`StorageConfig::key_source`],
+    /// requires the `jammdb_repo` feature.
+    EncryptedFile,
+    /// A SQLite database, requires the `sqlite_repo` feature.
+    Sqlite,
+    /// A SQLite database encrypted with the key from [`StorageConfig::key_source`], requires the
+    /// `sqlite_repo` feature.
+    EncryptedSqlite,
+}
+
+/// The source of the key used by [`StorageBackend::EncryptedFile`] and [`StorageBackend::EncryptedSqlite`]
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeySource {
+    /// A random key generated on first use and kept in the store set with [`Sdk::set_secret_store`].
+    #[default]
+    SecretStore,
+    /// A base64 encoded 256-bit key read from an environment variable.
+    Env {
+        /// The name of the environment variable
+        name: String,
+    },
+    /// A base64 encoded 256-bit key read from a file. The file is created with a random key if it
+    /// does not exist yet.
+    ///
+    /// The key is not bound to the device: it is stored unencrypted, so anyone who can read the
+    /// key file can decrypt the database. Only use it if the key file is kept apart from the
+    /// database, e.g. on a separately protected volume.
+    File {
+        /// The path of the key file
+        path: PathBuf,
+    },
+}
+
+/// Configuration of the storage used for the user repository
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// The storage to use.
+    pub backend: StorageBackend,
+    /// The folder of the database. Defaults to [`Config::path_prefix`].
+    pub path: Option<PathBuf>,
+    /// The source of the key for [`StorageBackend::EncryptedFile`] and [`StorageBackend::EncryptedSqlite`].
+    pub key_source: KeySource,
+}
+
+/// Override of the RPC endpoints of a network
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct RpcOverride {
+    /// The node URLs used instead of the ones served by the backend. Must not be empty.
+    pub node_urls: Vec<String>,
+}
+
+/// The destination of the log messages
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogOutput {
+    /// The file `etopay_sdk.log` in [`LogConfig::path`].
+    #[default]
+    File,
+    /// The standard output of the process.
+    Stdout,
+    /// Log messages are not written anywhere.
+    Disabled,
+}
+
+/// The format of the log messages
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// One line of text per message with the time, level, module and message.
+    #[default]
+    Text,
+    /// One JSON object per line with the fields `timestamp`, `level`, `target` and `message`.
+    Json,
+}
+
+/// Configuration of the log output. Not used on wasm.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Where log messages are written to.
+    pub output: LogOutput,
+    /// The folder of the log file. Defaults to [`Config::path_prefix`].
+    pub path: Option<PathBuf>,
+    /// The format of the log messages.
+    pub format: LogFormat,
+    /// The size in bytes after which the log file is rotated.
+    pub max_file_size_bytes: u64,
+    /// How many rotated log files are kept next to the log file, as `etopay_sdk.log.1` (the
+    /// newest) up to `etopay_sdk.log.<max_files>`.
+    pub max_files: u32,
+    /// Log levels of single modules overriding [`Config::log_level`], by module path, e.g.
+    /// `"etopay_sdk::backend": "DEBUG"`. A level applies to the module and all its submodules.
+    pub modules: HashMap<String, String>,
+    /// Scrub addresses, IBANs, email addresses and tokens from the log messages.
+    pub redact: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            output: LogOutput::default(),
+            path: None,
+            format: LogFormat::default(),
+            max_file_size_bytes: 5 * 1024 * 1024,
+            max_files: 3,
+            modules: HashMap::new(),
+            redact: true,
+        }
+    }
+}
+
+/// Optional functionality of the SDK, all enabled by default
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct FeatureToggles {
+    /// Keep the last fetched balances, transactions, networks and exchange rates in the user
+    /// database to return them while offline.
+    pub offline_cache: bool,
+    /// Check for changes in [`Sdk::poll_events`].
+    pub event_polling: bool,
+    /// Allow networks defined locally with [`Sdk::add_custom_network`].
+    pub custom_networks: bool,
+}
+
+impl Default for FeatureToggles {
+    fn default() -> Self {
+        Self {
+            offline_cache: true,
+            event_polling: true,
+            custom_networks: true,
+        }
+    }
 }
 
 /// Struct representing the  deserialized version of the config in JSON format.
@@ -40,6 +260,21 @@ struct DeserializedConfig {
     storage_path: String,
 
     auth_provider: String,
+
+    #[serde(default)]
+    storage: StorageConfig,
+
+    #[serde(default)]
+    http: HttpConfig,
+
+    #[serde(default)]
+    rpc: HashMap<String, RpcOverride>,
+
+    #[serde(default)]
+    log: LogConfig,
+
+    #[serde(default)]
+    features: FeatureToggles,
 }
 
 #[cfg(test)]
@@ -51,6 +286,11 @@ impl Default for DeserializedConfig {
             auth_provider: "standalone".to_string(),
             log_level: default_log_level(),
             storage_path: default_storage_path(),
+            storage: StorageConfig::default(),
+            http: HttpConfig::default(),
+            rpc: HashMap::new(),
+            log: LogConfig::default(),
+            features: FeatureToggles::default(),
         }
     }
 }
@@ -62,14 +302,45 @@ fn default_storage_path() -> String {
     ".".to_string()
 }
 
+/// The keys without a default value
+const REQUIRED_KEYS: [&str; 2] = ["backend_url", "auth_provider"];
+
+impl DeserializedConfig {
+    /// Deserialize the merged sources of a [`ConfigBuilder`]. Errors name the offending key.
+    #[allow(clippy::result_large_err)]
+    fn from_value(value: Map<String, Value>) -> Result<Self> {
+        if let Some(key) = REQUIRED_KEYS.iter().find(|key| !value.contains_key(**key)) {
+            return Err(invalid_config(*key, "is required"));
+        }
+
+        serde_path_to_error::deserialize(Value::Object(value))
+            .map_err(|e| invalid_config(e.path().to_string(), e.inner()))
+    }
+}
+
 /// To be used by bindings to deserialize JSON to the [`DeserializedConfig`] struct.
 impl FromStr for DeserializedConfig {
     type Err = crate::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        serde_json::from_str(s)
-            .map_err(|e| crate::Error::SetConfig(format!("Could not deserialize JSON config: {e:#?}")))
+        Self::from_value(parse_json(s)?)
+    }
+}
+
+/// Create an [`Error::SetConfig`] naming the offending key
+fn invalid_config(key: impl AsRef<str>, reason: impl ToString) -> Error {
+    Error::SetConfig(format!("{}: {}", key.as_ref(), reason.to_string()))
+}
+
+/// Check that a configured folder exists
+#[allow(clippy::result_large_err)]
+#[allow(unused_variables)] // there is no file system to check on wasm
+fn check_dir(key: &str, path: &Path) -> Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if !path.is_dir() {
+        return Err(invalid_config(key, "must be a valid existing directory"));
     }
+    Ok(())
 }
 
 impl TryFrom<DeserializedConfig> for Config {
@@ -77,54 +348,372 @@ impl TryFrom<DeserializedConfig> for Config {
 
     fn try_from(value: DeserializedConfig) -> Result<Self> {
         let path_prefix = Path::new(&value.storage_path);
-        #[cfg(not(target_arch = "wasm32"))]
-        if !path_prefix.is_dir() {
-            return Err(crate::Error::SetConfig(
-                "storage_path must be a valid existing directory".to_string(),
+        check_dir("storage_path", path_prefix)?;
+
+        if value.auth_provider.is_empty() {
+            return Err(invalid_config("auth_provider", "must not be empty"));
+        }
+
+        if let Some(path) = &value.storage.path {
+            check_dir("storage.path", path)?;
+        }
+        if let Some(path) = &value.log.path {
+            check_dir("log.path", path)?;
+        }
+
+        if value.log.max_file_size_bytes == 0 {
+            return Err(invalid_config("log.max_file_size_bytes", "must be greater than 0"));
+        }
+        for (module, level) in &value.log.modules {
+            log::LevelFilter::from_str(level).map_err(|e| {
+                invalid_config(
+                    format!("log.modules.{module}"),
+                    format!("Could not parse log level: {e}"),
+                )
+            })?;
+        }
+
+        if value.http.timeout_ms == 0 {
+            return Err(invalid_config("http.timeout_ms", "must be greater than 0"));
+        }
+        if value.http.connect_timeout_ms == 0 {
+            return Err(invalid_config("http.connect_timeout_ms", "must be greater than 0"));
+        }
+        if value.http.retry_base_delay_ms > value.http.max_retry_delay_ms {
+            return Err(invalid_config(
+                "http.retry_base_delay_ms",
+                "must not be greater than http.max_retry_delay_ms",
             ));
         }
 
-        if value.auth_provider.is_empty() {
-            return Err(crate::Error::SetConfig("auth_provider must not be empty".to_string()));
+        for (network_key, rpc) in &value.rpc {
+            let key = format!("rpc.{network_key}.node_urls");
+            if rpc.node_urls.is_empty() {
+                return Err(invalid_config(key, "must not be empty"));
+            }
+            for url in &rpc.node_urls {
+                reqwest::Url::parse(url).map_err(|e| invalid_config(&key, format!("{url}: {e}")))?;
+            }
         }
 
         Ok(Self {
-            backend_url: reqwest::Url::parse(&value.backend_url).map_err(|e| crate::Error::SetConfig(e.to_string()))?,
+            backend_url: reqwest::Url::parse(&value.backend_url).map_err(|e| invalid_config("backend_url", e))?,
             log_level: log::LevelFilter::from_str(&value.log_level)
-                .map_err(|e| crate::Error::SetConfig(format!("Could not parse log level: {e:#?}")))?,
+                .map_err(|e| invalid_config("log_level", format!("Could not parse log level: {e}")))?,
             auth_provider: value.auth_provider,
             path_prefix: path_prefix.into(),
+            storage: value.storage,
+            http: value.http,
+            rpc: value.rpc,
+            log: value.log,
+            features: value.features,
+            access_token_refresher: None,
+            telemetry: TelemetryHook::default(),
         })
     }
 }
 
 impl Config {
+    /// Create a [`Config`] with the default values for all other sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_prefix` - The root folder used to access the file system.
+    /// * `auth_provider` - The value of the X-APP-NAME header used to select the OAuth provider.
+    /// * `backend_url` - The URL to access the backend.
+    /// * `log_level` - The log level for filtering which log messages end up in the log file.
+    pub fn new(
+        path_prefix: impl Into<Box<Path>>,
+        auth_provider: impl Into<String>,
+        backend_url: reqwest::Url,
+        log_level: log::LevelFilter,
+    ) -> Self {
+        Self {
+            path_prefix: path_prefix.into(),
+            auth_provider: auth_provider.into(),
+            backend_url,
+            log_level,
+            storage: StorageConfig::default(),
+            http: HttpConfig::default(),
+            rpc: HashMap::new(),
+            log: LogConfig::default(),
+            features: FeatureToggles::default(),
+            access_token_refresher: None,
+            telemetry: TelemetryHook::default(),
+        }
+    }
+
     /// Load the [`Config`] directly from a JSON-formatted [`String`] or [`str`].
     #[allow(clippy::result_large_err)]
     pub fn from_json(json: impl AsRef<str>) -> Result<Self> {
-        let json = json.as_ref();
-        let deserialized_config: DeserializedConfig = json.parse()?;
-        Self::try_from(deserialized_config)
+        ConfigBuilder::new().json(json)?.build()
+    }
+
+    /// Load the [`Config`] directly from a TOML-formatted [`String`] or [`str`].
+    #[allow(clippy::result_large_err)]
+    pub fn from_toml(toml: impl AsRef<str>) -> Result<Self> {
+        ConfigBuilder::new().toml(toml)?.build()
+    }
+
+    /// Create a [`ConfigBuilder`] to build the [`Config`] from layered sources.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    /// The [`RpcOverride`] of a network.
+    pub fn rpc_override(&self, network_key: &str) -> Option<&RpcOverride> {
+        self.rpc.get(network_key)
+    }
+}
+
+/// Builds a [`Config`] from layered sources
+///
+/// Every source overrides the keys set by the sources added before it. Sections like `http` are
+/// merged key by key, so a source only needs to contain the keys it changes. Keys not set by any
+/// source get their default value.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> etopay_sdk::Result<()> {
+/// let config = etopay_sdk::core::Config::builder()
+///     .file("etopay.toml")?
+///     .env()?
+///     .set("log_level", "DEBUG")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ConfigBuilder {
+    value: Map<String, Value>,
+}
+
+impl ConfigBuilder {
+    /// Create a [`ConfigBuilder`] with only the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a JSON document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] if the document is not a JSON object.
+    #[allow(clippy::result_large_err)]
+    pub fn json(mut self, json: impl AsRef<str>) -> Result<Self> {
+        merge(&mut self.value, parse_json(json.as_ref())?);
+        Ok(self)
+    }
+
+    /// Add a TOML document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] if the document is not valid TOML.
+    #[allow(clippy::result_large_err)]
+    pub fn toml(mut self, toml: impl AsRef<str>) -> Result<Self> {
+        let value = toml::from_str(toml.as_ref())
+            .map_err(|e| crate::Error::SetConfig(format!("Could not deserialize TOML config: {e}")))?;
+        merge(&mut self.value, value);
+        Ok(self)
+    }
+
+    /// Add a JSON or TOML file. Files with the extension `toml` are read as TOML, all other files
+    /// as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] if the file cannot be read or parsed.
+    #[allow(clippy::result_large_err)]
+    pub fn file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| crate::Error::SetConfig(format!("Could not read config file {path:?}: {e}")))?;
+        if path.extension().is_some_and(|extension| extension == "toml") {
+            self.toml(content)
+        } else {
+            self.json(content)
+        }
+    }
+
+    /// Add the `ETOPAY_*` environment variables of the process, see [`ConfigBuilder::env_vars`].
+    /// Other variables are skipped, even if they are not valid unicode.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] naming the variable if the value of an `ETOPAY_*`
+    /// variable is not valid unicode.
+    #[allow(clippy::result_large_err)]
+    pub fn env(self) -> Result<Self> {
+        self.env_vars_os(std::env::vars_os())
+    }
+
+    /// Add the variables starting with [`ENV_PREFIX`] like [`ConfigBuilder::env_vars`], skipping
+    /// all other variables before they are converted to strings.
+    #[allow(clippy::result_large_err)]
+    fn env_vars_os(self, vars: impl IntoIterator<Item = (OsString, OsString)>) -> Result<Self> {
+        let mut prefixed = Vec::new();
+        for (name, value) in vars {
+            // names which are not valid unicode cannot name a key and are ignored like unknown keys
+            let Some(name) = name.to_str().filter(|name| name.starts_with(ENV_PREFIX)) else {
+                continue;
+            };
+            let value = value
+                .into_string()
+                .map_err(|_| invalid_config(name, "must be valid unicode"))?;
+            prefixed.push((name.to_string(), value));
+        }
+        Ok(self.env_vars(prefixed))
+    }
+
+    /// Add the variables starting with [`ENV_PREFIX`], followed by the key in upper case with
+    /// [`ENV_SEPARATOR`] separating nested sections, e.g. `ETOPAY_HTTP__MAX_RETRIES=5` sets
+    /// `http.max_retries`. The node URLs of a network are set with
+    /// `ETOPAY_RPC__<network key>__NODE_URLS` as a comma-separated list, where the network key is
+    /// used as is. Other variables starting with [`ENV_PREFIX`] are ignored.
+    ///
+    /// Values are strings. Numbers and booleans are only converted for keys of that type, so that
+    /// invalid values are reported for their key when building the [`Config`].
+    ///
+    /// # Arguments
+    ///
+    /// * `vars` - The names and values of the variables.
+    pub fn env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        for (name, value) in vars {
+            let Some(name) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            if let Some(network_key) = name
+                .strip_prefix("RPC")
+                .and_then(|name| name.strip_prefix(ENV_SEPARATOR))
+                .and_then(|name| name.strip_suffix("NODE_URLS"))
+                .and_then(|name| name.strip_suffix(ENV_SEPARATOR))
+            {
+                let node_urls = value.split(',').map(|url| Value::String(url.trim().to_string()));
+                let rpc = Value::Object(Map::from_iter([(
+                    network_key.to_string(),
+                    serde_json::json!({ "node_urls": node_urls.collect::<Vec<_>>() }),
+                )]));
+                merge(&mut self.value, Map::from_iter([("rpc".to_string(), rpc)]));
+                continue;
+            }
+
+            let Some((key, kind)) = ENV_KEYS.iter().find(|(key, _)| {
+                key.split('.')
+                    .map(str::to_uppercase)
+                    .collect::<Vec<_>>()
+                    .join(ENV_SEPARATOR)
+                    == name
+            }) else {
+                continue;
+            };
+            let value = match kind {
+                EnvValue::String => None,
+                EnvValue::Integer => value.parse::<u64>().ok().map(Value::from),
+                EnvValue::Bool => value.parse::<bool>().ok().map(Value::from),
+            }
+            .unwrap_or(Value::String(value));
+            self = self.set(key, value);
+        }
+        self
+    }
+
+    /// Set a single key. Keys of nested sections are separated by dots, e.g. `http.timeout_ms`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to set.
+    /// * `value` - The value of the key.
+    pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+        let value = key.rsplit('.').fold(value.into(), |value, section| {
+            Value::Object(Map::from_iter([(section.to_string(), value)]))
+        });
+        if let Value::Object(value) = value {
+            merge(&mut self.value, value);
+        }
+        self
+    }
+
+    /// Build and validate the [`Config`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] naming the key if a value is missing or invalid.
+    #[allow(clippy::result_large_err)]
+    pub fn build(self) -> Result<Config> {
+        Config::try_from(DeserializedConfig::from_value(self.value)?)
+    }
+}
+
+/// The type of the value of a key set by an environment variable
+enum EnvValue {
+    String,
+    Integer,
+    Bool,
+}
+
+/// The keys that can be set by environment variables, see [`ConfigBuilder::env_vars`]
+const ENV_KEYS: [(&str, EnvValue); 23] = [
+    ("backend_url", EnvValue::String),
+    ("auth_provider", EnvValue::String),
+    ("log_level", EnvValue::String),
+    ("storage_path", EnvValue::String),
+    ("storage.backend", EnvValue::String),
+    ("storage.path", EnvValue::String),
+    ("storage.key_source.type", EnvValue::String),
+    ("storage.key_source.name", EnvValue::String),
+    ("storage.key_source.path", EnvValue::String),
+    ("http.timeout_ms", EnvValue::Integer),
+    ("http.connect_timeout_ms", EnvValue::Integer),
+    ("http.max_retries", EnvValue::Integer),
+    ("http.retry_base_delay_ms", EnvValue::Integer),
+    ("http.max_retry_delay_ms", EnvValue::Integer),
+    ("log.output", EnvValue::String),
+    ("log.path", EnvValue::String),
+    ("log.format", EnvValue::String),
+    ("log.max_file_size_bytes", EnvValue::Integer),
+    ("log.max_files", EnvValue::Integer),
+    ("log.redact", EnvValue::Bool),
+    ("features.offline_cache", EnvValue::Bool),
+    ("features.event_polling", EnvValue::Bool),
+    ("features.custom_networks", EnvValue::Bool),
+];
+
+/// Parse a JSON object
+#[allow(clippy::result_large_err)]
+fn parse_json(json: &str) -> Result<Map<String, Value>> {
+    serde_json::from_str(json)
+        .map_err(|e| crate::Error::SetConfig(format!("Could not deserialize JSON config: {e:#?}")))
+}
+
+/// Merge `source` into `target`. Objects are merged recursively, all other values are replaced.
+fn merge(target: &mut Map<String, Value>, source: Map<String, Value>) {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Object(target)), Value::Object(source)) => merge(target, source),
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
     }
 }
 
 impl Sdk {
     /// Set the [`Config`] needed by the SDK
     #[allow(clippy::result_large_err)]
-    pub fn set_config(&mut self, config: Config) -> Result<()> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn set_config(&mut self, mut config: Config) -> Result<()> {
         info!("Setting config: {config:?}");
         // TODO: do any destructing things if config already exists
 
         // initialize the logger if we are not on wasm
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            let log_file = config.path_prefix.join("etopay_sdk.log");
-            let log_file_str = log_file
-                .to_str()
-                .ok_or_else(|| crate::Error::SetConfig(format!("config file path is not valid utf-8: {log_file:?}")))?;
-            crate::logger::init_logger(config.log_level, log_file_str)?;
-        }
+        crate::logger::init_logger(config.log_level, &config.log, &config.path_prefix)?;
 
+        config.access_token_refresher = self.access_token_refresher.clone();
+        config.telemetry = self.telemetry.clone();
         self.config = Some(config);
 
         // now do any necessary setup steps
@@ -133,42 +722,301 @@ impl Sdk {
         Ok(())
     }
 
-    /// Set path prefix
+    /// Export the last lines of the log file and its rotated files, oldest first, for example to
+    /// attach them to a support ticket. The lines are redacted as configured in [`LogConfig::redact`].
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The maximum number of lines to return.
+    ///
+    /// # Returns
+    ///
+    /// The lines, or none if the log messages are not written to a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::LogExport`] if a log file cannot be read.
+    #[cfg(not(target_arch = "wasm32"))]
     #[allow(clippy::result_large_err)]
-    fn initialize_user_repository(&mut self) -> Result<()> {
-        // initialize jammdb
-        #[cfg(feature = "jammdb_repo")]
-        let repo: Box<dyn UserRepo + Send + Sync> = {
-            let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
-            Box::new(UserRepoImpl::new(crate::user::file_storage::FileUserStorage::new(
-                &config.path_prefix,
-            )?))
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn export_logs(&self, lines: usize) -> Result<Vec<String>> {
+        crate::logger::last_lines(lines)
+    }
+
+    /// Set the [`Telemetry`] receiving the [`crate::telemetry::TelemetryEvent`]s of this SDK
+    /// instance, like the latency of backend and RPC calls. Replaces the previous telemetry, and
+    /// applies to wallets created from now on. Without a telemetry, no events are recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `telemetry` - The telemetry to use, or [`None`] to stop recording events.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn set_telemetry(&mut self, telemetry: Option<Arc<dyn Telemetry>>) {
+        self.telemetry = TelemetryHook::new(telemetry);
+        if let Some(config) = &mut self.config {
+            config.telemetry = self.telemetry.clone();
+        }
+    }
+
+    /// Set a [`SecretStore`] to keep the local share and the encrypted password of the users in,
+    /// for example a keystore provided by the platform. Secrets of existing users are moved into
+    /// the store the next time the user is written.
+    ///
+    /// The user database is then encrypted at rest with a key kept in the secret store. Existing
+    /// unencrypted entries are encrypted when the repository is opened. A secret store that does
+    /// not outlive the process, like [`crate::secret_store::MemorySecretStore`], can only be used
+    /// with [`StorageBackend::Memory`].
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_store` - The secret store to use.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] if a user is already initialized, or if the secret store
+    /// is not persistent but the user storage is.
+    #[allow(clippy::result_large_err)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn set_secret_store(&mut self, secret_store: Arc<dyn SecretStore>) -> Result<()> {
+        if self.active_user.is_some() {
+            return Err(crate::Error::SetConfig(
+                "the secret store must be set before initializing a user".to_string(),
+            ));
+        }
+
+        self.secret_store = Some(secret_store);
+
+        // re-create the repository if the config is already set. The old repository is dropped
+        // first to release any lock on the underlying storage.
+        if self.config.is_some() {
+            self.repo = None;
+            self.initialize_user_repository()?;
+        }
+        Ok(())
+    }
+
+    /// Switch the user repository to the IndexedDB of the browser if it is available. Users stored
+    /// in the local storage are moved to IndexedDB. If IndexedDB cannot be opened, the current
+    /// repository is kept.
+    ///
+    /// IndexedDB can only be opened asynchronously, so this is called after [`Sdk::set_config`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the users of the local storage cannot be moved to IndexedDB.
+    #[cfg(target_arch = "wasm32")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn initialize_indexed_db_repository(&mut self) -> Result<()> {
+        use crate::user::indexed_db_storage::IndexedDbUserStorage;
+        use crate::user::web_storage::BrowserLocalStorage;
+
+        if !IndexedDbUserStorage::is_available() {
+            log::warn!("IndexedDB is not available, keeping the current user storage!");
+            return Ok(());
+        }
+
+        let mut storage = match IndexedDbUserStorage::open().await {
+            Ok(storage) => storage,
+            Err(e) => {
+                log::warn!("Could not open IndexedDB, keeping the current user storage: {e}");
+                return Ok(());
+            }
         };
 
-        // for wasm: try browser, and fallback to in-memory if it fails!
+        let mut local_storage = BrowserLocalStorage::new();
+        if local_storage.is_available() {
+            storage.migrate_from(&mut local_storage).await?;
+        }
+
+        self.repo = Some(persistent_repo(storage, self.secret_store.clone())?);
+        Ok(())
+    }
+
+    /// Wait until all writes to the user storage completed. Only the IndexedDB storage of the
+    /// browser completes its writes asynchronously, for all other storages this returns right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a write to the user storage failed.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn flush_user_storage(&self) -> Result<()> {
         #[cfg(target_arch = "wasm32")]
-        let repo: Box<dyn UserRepo + Send + Sync> = {
-            // try to access to browser local storage
-            let browser_storage = crate::user::web_storage::BrowserLocalStorage::new();
-            if browser_storage.is_available() {
-                Box::new(UserRepoImpl::new(browser_storage))
-            } else {
-                log::warn!("Browser Local Storage is not available, falling back to in-memory user storage!");
-                Box::new(UserRepoImpl::new(crate::user::memory_storage::MemoryUserStorage::new()))
+        crate::user::indexed_db_storage::IndexedDbUserStorage::flush().await?;
+        Ok(())
+    }
+
+    /// Use a custom [`UserKvStorage`] for the user repository instead of the storage selected in
+    /// the [`Config`]. The storage is replaced again by the next call to [`Sdk::set_config`] or
+    /// [`Sdk::set_secret_store`], so this has to be called after them.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The storage to use.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SetConfig`] if a user is already initialized.
+    #[allow(clippy::result_large_err)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn set_user_storage<I>(&mut self, storage: I) -> Result<()>
+    where
+        I: UserKvStorage + Send + Sync + 'static,
+    {
+        if self.active_user.is_some() {
+            return Err(crate::Error::SetConfig(
+                "the user storage must be set before initializing a user".to_string(),
+            ));
+        }
+
+        self.repo = Some(Box::new(
+            UserRepoImpl::new(storage).with_secret_store(self.secret_store.clone()),
+        ));
+        Ok(())
+    }
+
+    /// Create the user repository selected by [`Config::storage`]
+    #[allow(clippy::result_large_err)]
+    fn initialize_user_repository(&mut self) -> Result<()> {
+        let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
+        let secret_store = self.secret_store.clone();
+        let path = config.storage.path.as_deref().unwrap_or(&config.path_prefix);
+
+        let repo: Box<dyn UserRepo + Send + Sync> = match &config.storage.backend {
+            StorageBackend::Default => default_repo(path, secret_store)?,
+            StorageBackend::Memory => Box::new(
+                UserRepoImpl::new(crate::user::memory_storage::MemoryUserStorage::new())
+                    .with_secret_store(secret_store),
+            ),
+            #[cfg(feature = "jammdb_repo")]
+            StorageBackend::File => {
+                persistent_repo(crate::user::file_storage::FileUserStorage::new(path)?, secret_store)?
+            }
+            #[cfg(feature = "jammdb_repo")]
+            StorageBackend::EncryptedFile => {
+                let key = config.storage.key_source.load_key(secret_store.as_deref())?;
+                let storage = crate::user::encrypted_storage::EncryptedUserStorage::open(
+                    crate::user::file_storage::FileUserStorage::new(path)?,
+                    &key,
+                )?;
+                Box::new(UserRepoImpl::new(storage).with_secret_store(secret_store))
+            }
+            #[cfg(feature = "sqlite_repo")]
+            StorageBackend::Sqlite => {
+                persistent_repo(crate::user::sqlite_storage::SqliteUserStorage::new(path)?, secret_store)?
+            }
+            #[cfg(feature = "sqlite_repo")]
+            StorageBackend::EncryptedSqlite => {
+                let key = config.storage.key_source.load_key(secret_store.as_deref())?;
+                let storage = crate::user::encrypted_storage::EncryptedUserStorage::open(
+                    crate::user::sqlite_storage::SqliteUserStorage::new(path)?,
+                    &key,
+                )?;
+                Box::new(UserRepoImpl::new(storage).with_secret_store(secret_store))
+            }
+            #[allow(unreachable_patterns)]
+            backend => {
+                return Err(crate::Error::SetConfig(format!(
+                    "storage backend {backend:?} is not available in this build"
+                )));
             }
         };
 
-        // if we are not compiling for wasm and the jammdb_repo feature is not active, use an
-        // in-memory storage.
-        #[cfg(all(not(target_arch = "wasm32"), not(feature = "jammdb_repo")))]
-        let repo: Box<dyn UserRepo + Send + Sync> =
-            Box::new(UserRepoImpl::new(crate::user::memory_storage::MemoryUserStorage::new()));
-
         self.repo = Some(repo);
         Ok(())
     }
 }
 
+/// Create the repository for [`StorageBackend::Default`] depending on the enabled features.
+#[allow(clippy::result_large_err)]
+#[allow(unused_variables)] // the path is not used by the in-memory and browser storages
+fn default_repo(path: &Path, secret_store: Option<Arc<dyn SecretStore>>) -> Result<Box<dyn UserRepo + Send + Sync>> {
+    // initialize sqlite
+    #[cfg(feature = "sqlite_repo")]
+    let repo = persistent_repo(crate::user::sqlite_storage::SqliteUserStorage::new(path)?, secret_store)?;
+
+    // initialize jammdb
+    #[cfg(all(feature = "jammdb_repo", not(feature = "sqlite_repo")))]
+    let repo = persistent_repo(crate::user::file_storage::FileUserStorage::new(path)?, secret_store)?;
+
+    // for wasm: try browser, and fallback to in-memory if it fails!
+    #[cfg(target_arch = "wasm32")]
+    let repo: Box<dyn UserRepo + Send + Sync> = {
+        // try to access to browser local storage
+        let browser_storage = crate::user::web_storage::BrowserLocalStorage::new();
+        if browser_storage.is_available() {
+            persistent_repo(browser_storage, secret_store)?
+        } else {
+            log::warn!("Browser Local Storage is not available, falling back to in-memory user storage!");
+            Box::new(
+                UserRepoImpl::new(crate::user::memory_storage::MemoryUserStorage::new())
+                    .with_secret_store(secret_store),
+            )
+        }
+    };
+
+    // if we are not compiling for wasm and no persistent storage feature is active, use an
+    // in-memory storage.
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        not(feature = "jammdb_repo"),
+        not(feature = "sqlite_repo")
+    ))]
+    let repo: Box<dyn UserRepo + Send + Sync> = Box::new(
+        UserRepoImpl::new(crate::user::memory_storage::MemoryUserStorage::new()).with_secret_store(secret_store),
+    );
+
+    Ok(repo)
+}
+
+impl KeySource {
+    /// Load the key used to encrypt the user database.
+    #[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo"))]
+    #[allow(clippy::result_large_err)]
+    fn load_key(&self, secret_store: Option<&dyn SecretStore>) -> Result<secrecy::SecretSlice<u8>> {
+        use crate::user::encrypted_storage::{decode_key, load_or_create_key, load_or_create_key_file};
+
+        match self {
+            KeySource::SecretStore => {
+                let store = secret_store.ok_or_else(|| {
+                    crate::Error::SetConfig("key_source secret_store requires a secret store to be set".to_string())
+                })?;
+                Ok(load_or_create_key(persistent_secret_store(store)?)?)
+            }
+            KeySource::Env { name } => {
+                let value = std::env::var(name).map_err(|e| {
+                    crate::Error::SetConfig(format!(
+                        "could not read storage key from environment variable {name}: {e}"
+                    ))
+                })?;
+                Ok(decode_key(&value)?)
+            }
+            KeySource::File { path } => {
+                log::warn!("The user database key is read from a key file, which does not bind it to the device");
+                Ok(load_or_create_key_file(path)?)
+            }
+        }
+    }
+}
+
+/// Create the repository for a persistent storage. If a secret store is set, the user database is
+/// encrypted with a key kept in the secret store.
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
+#[allow(clippy::result_large_err)]
+fn persistent_repo<I>(storage: I, secret_store: Option<Arc<dyn SecretStore>>) -> Result<Box<dyn UserRepo + Send + Sync>>
+where
+    I: crate::user::UserKvStorage + crate::user::UserBytesStorage + Send + Sync + 'static,
+{
+    let Some(store) = &secret_store else {
+        return Ok(Box::new(UserRepoImpl::new(storage)));
+    };
+
+    let key = crate::user::encrypted_storage::load_or_create_key(persistent_secret_store(store.as_ref())?)?;
+    let encrypted_storage = crate::user::encrypted_storage::EncryptedUserStorage::open(storage, &key)?;
+    Ok(Box::new(
+        UserRepoImpl::new(encrypted_storage).with_secret_store(secret_store),
+    ))
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)] // this is testing code so expect is fine
 impl Config {
@@ -181,6 +1029,13 @@ impl Config {
                 path_prefix: Path::new(&cleanup.path_prefix).into(),
                 auth_provider: "standalone".to_string(),
                 log_level: log::LevelFilter::Debug,
+                storage: StorageConfig::default(),
+                http: HttpConfig::default(),
+                rpc: HashMap::new(),
+                log: LogConfig::default(),
+                features: FeatureToggles::default(),
+                access_token_refresher: None,
+                telemetry: TelemetryHook::default(),
             },
             cleanup,
         )
@@ -195,15 +1050,37 @@ impl Config {
                 path_prefix: Path::new(&cleanup.path_prefix).into(),
                 auth_provider: "standalone".to_string(),
                 log_level: log::LevelFilter::Debug,
+                storage: StorageConfig::default(),
+                http: HttpConfig::default(),
+                rpc: HashMap::new(),
+                log: LogConfig::default(),
+                features: FeatureToggles::default(),
+                access_token_refresher: None,
+                telemetry: TelemetryHook::default(),
             },
             cleanup,
         )
     }
 }
 
+/// Check that the key of a persistent user database can be kept in the secret store, as it could
+/// not be decrypted after a restart otherwise.
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
+#[allow(clippy::result_large_err)]
+fn persistent_secret_store(store: &dyn SecretStore) -> Result<&dyn SecretStore> {
+    if !store.is_persistent() {
+        return Err(crate::Error::SetConfig(
+            "a persistent user storage cannot be encrypted with a key kept in a non-persistent secret store"
+                .to_string(),
+        ));
+    }
+    Ok(store)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing_utils::example_user_entity;
     use rstest::rstest;
 
     fn valid_deserialized_config() -> DeserializedConfig {
@@ -212,6 +1089,11 @@ mod tests {
             log_level: "INFO".to_string(),
             storage_path: ".".to_string(),
             auth_provider: "nonempty".to_string(),
+            storage: StorageConfig::default(),
+            http: HttpConfig::default(),
+            rpc: HashMap::new(),
+            log: LogConfig::default(),
+            features: FeatureToggles::default(),
         }
     }
 
@@ -283,4 +1165,467 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_set_secret_store() {
+        use crate::secret_store::{MemorySecretStore, SecretKind};
+        use crate::types::users::UserEntity;
+
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        config.storage.backend = StorageBackend::Memory;
+        let mut sdk = Sdk::new(config).unwrap();
+        let store = Arc::new(MemorySecretStore::new());
+
+        // Act
+        sdk.set_secret_store(store.clone()).unwrap();
+
+        // Assert
+        let user = UserEntity {
+            encrypted_password: Some(crate::testing_utils::ENCRYPTED_WALLET_PASSWORD.clone()),
+            ..example_user_entity("alice")
+        };
+        sdk.repo.as_mut().unwrap().create(&user).unwrap();
+        assert!(store.get("alice", SecretKind::EncryptedPassword).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_deserialize_storage_config() {
+        let config = DeserializedConfig::from_str(
+            r#"{
+                "backend_url": "http://example.com",
+                "auth_provider": "standalone",
+                "storage": {
+                    "backend": "encrypted_file",
+                    "path": "/tmp",
+                    "key_source": { "type": "file", "path": "/tmp/key" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.storage,
+            StorageConfig {
+                backend: StorageBackend::EncryptedFile,
+                path: Some(PathBuf::from("/tmp")),
+                key_source: KeySource::File {
+                    path: PathBuf::from("/tmp/key")
+                },
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "jammdb_repo")]
+    fn test_set_secret_store_rejects_memory_store_for_persistent_storage() {
+        use crate::secret_store::MemorySecretStore;
+
+        // Arrange
+        let (config, _cleanup) = Config::new_test_with_cleanup();
+        let mut sdk = Sdk::new(config).unwrap();
+
+        // Act
+        let result = sdk.set_secret_store(Arc::new(MemorySecretStore::new()));
+
+        // Assert
+        assert!(matches!(result, Err(crate::Error::SetConfig(_))));
+    }
+
+    #[test]
+    fn test_deserialize_http_config() {
+        let config = DeserializedConfig::from_str(
+            r#"{
+                "backend_url": "http://example.com",
+                "auth_provider": "standalone",
+                "http": {
+                    "timeout_ms": 5000,
+                    "max_retries": 0
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.http,
+            HttpConfig {
+                timeout_ms: 5000,
+                max_retries: 0,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_builder_merges_sources_in_order() {
+        // Arrange
+        let builder = Config::builder()
+            .json(r#"{ "backend_url": "http://json.example.com", "auth_provider": "standalone", "http": { "timeout_ms": 1000, "max_retries": 1 } }"#)
+            .unwrap()
+            .toml(
+                r#"
+                backend_url = "http://toml.example.com"
+
+                [http]
+                max_retries = 2
+                "#,
+            )
+            .unwrap()
+            .env_vars([
+                ("ETOPAY_HTTP__MAX_RETRIES".to_string(), "5".to_string()),
+                ("ETOPAY_FEATURES__EVENT_POLLING".to_string(), "false".to_string()),
+                ("OTHER_LOG_LEVEL".to_string(), "TRACE".to_string()),
+            ])
+            .set("log_level", "DEBUG");
+
+        // Act
+        let config = builder.build().unwrap();
+
+        // Assert
+        assert_eq!(config.backend_url.as_str(), "http://toml.example.com/");
+        assert_eq!(config.log_level, log::LevelFilter::Debug);
+        assert_eq!(
+            config.http,
+            HttpConfig {
+                timeout_ms: 1000,
+                max_retries: 5,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            config.features,
+            FeatureToggles {
+                event_polling: false,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_builder_reads_toml_file() {
+        // Arrange
+        let cleanup = testing::CleanUp::default();
+        let path = Path::new(&cleanup.path_prefix).join("etopay.toml");
+        std::fs::write(
+            &path,
+            r#"
+            backend_url = "http://example.com"
+            auth_provider = "standalone"
+
+            [rpc.IOTA]
+            node_urls = ["https://iota.example.com"]
+
+            [log]
+            output = "stdout"
+            "#,
+        )
+        .unwrap();
+
+        // Act
+        let config = Config::builder().file(&path).unwrap().build().unwrap();
+
+        // Assert
+        assert_eq!(config.log.output, LogOutput::Stdout);
+        assert_eq!(
+            config.rpc_override("IOTA"),
+            Some(&RpcOverride {
+                node_urls: vec!["https://iota.example.com".to_string()]
+            })
+        );
+        assert_eq!(config.rpc_override("ETH"), None);
+    }
+
+    #[test]
+    fn test_env_vars_parse_lists_and_nested_sections() {
+        let config = Config::builder()
+            .env_vars([
+                ("ETOPAY_BACKEND_URL".to_string(), "http://example.com".to_string()),
+                ("ETOPAY_AUTH_PROVIDER".to_string(), "standalone".to_string()),
+                ("ETOPAY_STORAGE__BACKEND".to_string(), "memory".to_string()),
+                ("ETOPAY_LOG__REDACT".to_string(), "false".to_string()),
+                (
+                    "ETOPAY_RPC__iota_rebased_testnet__NODE_URLS".to_string(),
+                    "https://a.example.com, https://b.example.com".to_string(),
+                ),
+            ])
+            .build()
+            .unwrap();
+
+        assert_eq!(config.storage.backend, StorageBackend::Memory);
+        assert!(!config.log.redact);
+        assert_eq!(
+            config
+                .rpc_override("iota_rebased_testnet")
+                .map(|rpc| rpc.node_urls.clone()),
+            Some(vec![
+                "https://a.example.com".to_string(),
+                "https://b.example.com".to_string()
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::number("123")]
+    #[case::null("null")]
+    #[case::boolean("true")]
+    fn test_env_vars_keep_string_values(#[case] auth_provider: &str) {
+        let config = Config::builder()
+            .env_vars([
+                ("ETOPAY_BACKEND_URL".to_string(), "http://example.com".to_string()),
+                ("ETOPAY_AUTH_PROVIDER".to_string(), auth_provider.to_string()),
+            ])
+            .build()
+            .unwrap();
+
+        assert_eq!(config.auth_provider, auth_provider);
+    }
+
+    #[test]
+    fn test_env_vars_invalid_number_names_key() {
+        let error = Config::builder()
+            .env_vars([
+                ("ETOPAY_BACKEND_URL".to_string(), "http://example.com".to_string()),
+                ("ETOPAY_AUTH_PROVIDER".to_string(), "standalone".to_string()),
+                ("ETOPAY_HTTP__TIMEOUT_MS".to_string(), "soon".to_string()),
+            ])
+            .build()
+            .unwrap_err();
+
+        match error {
+            crate::Error::SetConfig(message) => assert!(message.starts_with("http.timeout_ms: "), "{message}"),
+            other => panic!("expected SetConfig, got {other:?}"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_env_vars_os_skips_other_variables_that_are_not_unicode() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let config = Config::builder()
+            .env_vars_os([
+                (
+                    OsString::from("PATH_WITH_LATIN1"),
+                    OsString::from_vec(b"caf\xe9".to_vec()),
+                ),
+                (OsString::from_vec(b"caf\xe9".to_vec()), OsString::from("value")),
+                (
+                    OsString::from("ETOPAY_BACKEND_URL"),
+                    OsString::from("http://example.com"),
+                ),
+                (OsString::from("ETOPAY_AUTH_PROVIDER"), OsString::from("standalone")),
+            ])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(config.backend_url.as_str(), "http://example.com/");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_env_vars_os_value_not_unicode_names_key() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let error = Config::builder()
+            .env_vars_os([(
+                OsString::from("ETOPAY_AUTH_PROVIDER"),
+                OsString::from_vec(b"caf\xe9".to_vec()),
+            )])
+            .unwrap_err();
+
+        match error {
+            crate::Error::SetConfig(message) => assert!(message.starts_with("ETOPAY_AUTH_PROVIDER: "), "{message}"),
+            other => panic!("expected SetConfig, got {other:?}"),
+        }
+    }
+
+    #[rstest]
+    #[case::missing_key(r#"{ "auth_provider": "standalone" }"#, "backend_url")]
+    #[case::wrong_type(
+        r#"{ "backend_url": "http://example.com", "auth_provider": "standalone", "http": { "timeout_ms": "soon" } }"#,
+        "http.timeout_ms"
+    )]
+    #[case::zero_timeout(
+        r#"{ "backend_url": "http://example.com", "auth_provider": "standalone", "http": { "connect_timeout_ms": 0 } }"#,
+        "http.connect_timeout_ms"
+    )]
+    #[case::invalid_log_level(
+        r#"{ "backend_url": "http://example.com", "auth_provider": "standalone", "log_level": "LOUD" }"#,
+        "log_level"
+    )]
+    #[case::invalid_node_url(
+        r#"{ "backend_url": "http://example.com", "auth_provider": "standalone", "rpc": { "IOTA": { "node_urls": ["no url"] } } }"#,
+        "rpc.IOTA.node_urls"
+    )]
+    #[case::invalid_module_log_level(
+        r#"{ "backend_url": "http://example.com", "auth_provider": "standalone", "log": { "modules": { "etopay_sdk::backend": "LOUD" } } }"#,
+        "log.modules.etopay_sdk::backend"
+    )]
+    #[case::unknown_storage_backend(
+        r#"{ "backend_url": "http://example.com", "auth_provider": "standalone", "storage": { "backend": "floppy" } }"#,
+        "storage.backend"
+    )]
+    fn test_invalid_config_names_key(#[case] json: &str, #[case] expected_key: &str) {
+        let error = Config::from_json(json).unwrap_err();
+
+        match error {
+            crate::Error::SetConfig(message) => assert!(message.starts_with(&format!("{expected_key}: ")), "{message}"),
+            other => panic!("expected SetConfig, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_storage_path_error() {
+        let mut config = valid_deserialized_config();
+        config.storage.path = Some(PathBuf::from("nonexistent_folder"));
+
+        Config::try_from(config).unwrap_err();
+    }
+
+    #[rstest]
+    #[case::default(StorageBackend::Default, true)]
+    #[case::memory(StorageBackend::Memory, false)]
+    #[case::file(StorageBackend::File, true)]
+    fn test_storage_backend_persistence(#[case] backend: StorageBackend, #[case] persistent: bool) {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        config.storage.backend = backend.clone();
+        let path_prefix = config.path_prefix.clone();
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo
+            .as_mut()
+            .unwrap()
+            .create(&example_user_entity("alice"))
+            .unwrap();
+        drop(sdk);
+
+        // Act
+        let (mut config, _cleanup2) = Config::new_test_with_cleanup();
+        config.path_prefix = path_prefix;
+        config.storage.backend = backend;
+        let sdk = Sdk::new(config).unwrap();
+
+        // Assert
+        let users = sdk.list_local_users().unwrap();
+        assert_eq!(users.contains(&"alice".to_string()), persistent);
+    }
+
+    #[test]
+    fn test_encrypted_file_backend_with_key_file() {
+        // Arrange
+        let (mut config, cleanup) = Config::new_test_with_cleanup();
+        let key_path = Path::new(&cleanup.path_prefix).join("user-db.key");
+        config.storage = StorageConfig {
+            backend: StorageBackend::EncryptedFile,
+            path: None,
+            key_source: KeySource::File { path: key_path.clone() },
+        };
+        let storage = config.storage.clone();
+        let path_prefix = config.path_prefix.clone();
+
+        // Act
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo
+            .as_mut()
+            .unwrap()
+            .create(&example_user_entity("alice"))
+            .unwrap();
+        drop(sdk);
+
+        // Assert
+        assert!(key_path.exists());
+        let plain_storage = crate::user::file_storage::FileUserStorage::new(&path_prefix).unwrap();
+        plain_storage.get("alice").unwrap_err();
+        drop(plain_storage);
+
+        let (mut config, _cleanup2) = Config::new_test_with_cleanup();
+        config.path_prefix = path_prefix;
+        config.storage = storage;
+        let sdk = Sdk::new(config).unwrap();
+        assert_eq!(sdk.list_local_users().unwrap(), vec!["alice".to_string()]);
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite_repo")]
+    fn test_encrypted_sqlite_backend_with_key_file() {
+        use crate::user::UserBytesStorage;
+
+        // Arrange
+        let (mut config, cleanup) = Config::new_test_with_cleanup();
+        let key_path = Path::new(&cleanup.path_prefix).join("user-db.key");
+        config.storage = StorageConfig {
+            backend: StorageBackend::EncryptedSqlite,
+            path: None,
+            key_source: KeySource::File { path: key_path.clone() },
+        };
+        let storage = config.storage.clone();
+        let path_prefix = config.path_prefix.clone();
+
+        // Act
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo
+            .as_mut()
+            .unwrap()
+            .create(&example_user_entity("alice"))
+            .unwrap();
+        drop(sdk);
+
+        // Assert
+        assert!(key_path.exists());
+        let plain_storage = crate::user::sqlite_storage::SqliteUserStorage::new(&path_prefix).unwrap();
+        let bytes = plain_storage.get_bytes("alice").unwrap().unwrap();
+        assert!(rmp_serde::from_slice::<crate::types::users::UserEntity>(&bytes).is_err());
+        drop(plain_storage);
+
+        let (mut config, _cleanup2) = Config::new_test_with_cleanup();
+        config.path_prefix = path_prefix;
+        config.storage = storage;
+        let sdk = Sdk::new(config).unwrap();
+        assert_eq!(sdk.list_local_users().unwrap(), vec!["alice".to_string()]);
+    }
+
+    #[test]
+    fn test_encrypted_file_backend_requires_secret_store() {
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        config.storage.backend = StorageBackend::EncryptedFile;
+
+        let error = Sdk::new(config).err().unwrap();
+
+        assert!(matches!(error, crate::Error::SetConfig(_)));
+    }
+
+    #[test]
+    fn test_set_user_storage() {
+        // Arrange
+        let (config, _cleanup) = Config::new_test_with_cleanup();
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut storage = crate::user::memory_storage::MemoryUserStorage::new();
+        storage.set("bob", &example_user_entity("bob")).unwrap();
+
+        // Act
+        sdk.set_user_storage(storage).unwrap();
+
+        // Assert
+        assert_eq!(sdk.list_local_users().unwrap(), vec!["bob".to_string()]);
+    }
+
+    #[test]
+    fn test_set_user_storage_with_active_user_error() {
+        // Arrange
+        let (config, _cleanup) = Config::new_test_with_cleanup();
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: "alice".into(),
+            wallet_manager: Box::new(crate::wallet_manager::MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        let result = sdk.set_user_storage(crate::user::memory_storage::MemoryUserStorage::new());
+
+        // Assert
+        assert!(matches!(result, Err(crate::Error::SetConfig(_))));
+    }
 }
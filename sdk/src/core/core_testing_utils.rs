@@ -1,5 +1,5 @@
 use super::Sdk;
-use crate::testing_utils::{IOTA_NETWORK_KEY, SALT, TOKEN, example_api_networks};
+use crate::testing_utils::{IOTA_NETWORK_KEY, TOKEN, example_api_networks, example_user_entity};
 use crate::types::users::UserEntity;
 use crate::wallet_manager::MockWalletManager;
 use crate::{ErrorKind, WalletError};
@@ -38,6 +38,7 @@ pub async fn handle_error_test_cases(
                 username: USERNAME.into(),
                 wallet_manager: Box::new(MockWalletManager::new()),
                 mnemonic_derivation_options: Default::default(),
+                network_derivation_options: Default::default(),
             });
             sdk.access_token = Some(TOKEN.clone());
             sdk.config = None;
@@ -54,6 +55,7 @@ pub async fn handle_error_test_cases(
                 username: USERNAME.into(),
                 wallet_manager: Box::new(MockWalletManager::new()),
                 mnemonic_derivation_options: Default::default(),
+                network_derivation_options: Default::default(),
             });
             sdk.set_networks(example_api_networks());
             sdk.active_network = None;
@@ -72,6 +74,7 @@ pub async fn handle_error_test_cases(
                 username: USERNAME.into(),
                 wallet_manager: Box::new(MockWalletManager::new()),
                 mnemonic_derivation_options: Default::default(),
+                network_derivation_options: Default::default(),
             });
 
             sdk.access_token = None;
@@ -81,16 +84,8 @@ pub async fn handle_error_test_cases(
             mock_user_repo.expect_get().times(1).returning(move |r1| {
                 assert_eq!(r1, USERNAME);
                 Ok(UserEntity {
-                    user_id: None,
-                    username: USERNAME.to_string(),
-                    encrypted_password: None,
-                    salt: SALT.into(),
                     is_kyc_verified: true,
-                    kyc_type: KycType::Undefined,
-                    viviswap_state: None,
-                    local_share: None,
-                    wallet_transactions: Vec::new(),
-                    wallet_transactions_versioned: Vec::new(),
+                    ..example_user_entity(USERNAME)
                 })
             });
             sdk.repo = Some(Box::new(mock_user_repo));
@@ -98,6 +93,7 @@ pub async fn handle_error_test_cases(
                 username: USERNAME.into(),
                 wallet_manager: Box::new(MockWalletManager::new()),
                 mnemonic_derivation_options: Default::default(),
+                network_derivation_options: Default::default(),
             });
         }
         crate::Error::UserRepository(crate::user::error::UserKvStorageError::UserNotFound { .. }) => {
@@ -113,6 +109,7 @@ pub async fn handle_error_test_cases(
                 username: USERNAME.into(),
                 wallet_manager: Box::new(MockWalletManager::new()),
                 mnemonic_derivation_options: Default::default(),
+                network_derivation_options: Default::default(),
             });
         }
         other => panic!("Got unexpected or unhandled result: {:?}", other),
@@ -0,0 +1,415 @@
+//! This module provides methods for managing networks that are added locally by the user, e.g. a
+//! custom EVM RPC, an ERC20 token or an IOTA devnet. Custom networks are validated against the
+//! node they point to, stored in the user repository and available next to the networks served
+//! by the backend. Purchases are not supported on custom networks.
+
+use super::Sdk;
+use crate::error::Result;
+use api_types::api::networks::{ApiNetwork, ApiProtocol};
+use log::info;
+
+/// Checks that the network matches the chain of each of its nodes: the chain id for EVM networks
+/// and the decimals for ERC20 and IOTA Rebased tokens.
+async fn validate_custom_network(network: &ApiNetwork) -> Result<()> {
+    if network.node_urls.is_empty() {
+        return Err(crate::Error::InvalidCustomNetwork(String::from("no node url")));
+    }
+
+    for node_url in &network.node_urls {
+        let decimals = match &network.protocol {
+            ApiProtocol::Evm { chain_id } => {
+                check_chain_id(node_url, *chain_id).await?;
+                None
+            }
+            ApiProtocol::EvmERC20 {
+                chain_id,
+                contract_address,
+            } => {
+                check_chain_id(node_url, *chain_id).await?;
+                Some(etopay_wallet::erc20_decimals(node_url, contract_address).await?)
+            }
+            ApiProtocol::IotaRebased { coin_type } => {
+                Some(etopay_wallet::iota_rebased_coin_decimals(node_url, coin_type).await?)
+            }
+        };
+
+        if let Some(decimals) = decimals {
+            if decimals != network.decimals {
+                return Err(crate::Error::InvalidCustomNetwork(format!(
+                    "expected {} decimals but the node {node_url} reports {decimals}",
+                    network.decimals
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the EVM node at `node_url` serves the chain with `chain_id`.
+async fn check_chain_id(node_url: &str, chain_id: u64) -> Result<()> {
+    let actual = etopay_wallet::evm_chain_id(node_url).await?;
+    if actual != chain_id {
+        return Err(crate::Error::InvalidCustomNetwork(format!(
+            "expected chain id {chain_id} but the node {node_url} reports {actual}"
+        )));
+    }
+    Ok(())
+}
+
+impl Sdk {
+    /// Add a custom network for the active user
+    ///
+    /// The network is validated against each of its nodes before it is stored. A custom network with
+    /// the same key is replaced. Purchases are disabled for custom networks.
+    ///
+    /// # Arguments
+    ///
+    /// * `network` - The network to add.
+    ///
+    /// # Returns
+    ///
+    /// The stored network.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`crate::Error::InvalidCustomNetwork`] - If the key is used by a backend network, there is
+    ///   no node url, or the chain id or decimals do not match the node.
+    /// * [`crate::Error::WalletImplError`] - If the node cannot be queried.
+    /// * [`crate::Error::FeatureDisabled`] - If custom networks are disabled in the config.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn add_custom_network(&mut self, mut network: ApiNetwork) -> Result<ApiNetwork> {
+        info!("Adding custom network {}", network.key);
+        if !self.feature_enabled(|features| features.custom_networks) {
+            return Err(crate::Error::FeatureDisabled("custom_networks".to_string()));
+        }
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+        let username = active_user.username.clone();
+        let mut custom_networks = repo.get(&username)?.custom_networks;
+
+        if self.networks.iter().any(|n| n.key == network.key) {
+            return Err(crate::Error::InvalidCustomNetwork(format!(
+                "the key {} is used by a backend network",
+                network.key
+            )));
+        }
+        validate_custom_network(&network).await?;
+        network.can_do_purchases = false;
+
+        match custom_networks.iter_mut().find(|n| n.key == network.key) {
+            Some(existing) => *existing = network.clone(),
+            None => custom_networks.push(network.clone()),
+        }
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        repo.set_custom_networks(&username, custom_networks.clone())?;
+        self.custom_networks = custom_networks;
+
+        if self.active_network.as_ref().is_some_and(|n| n.key == network.key) {
+            self.active_network = Some(network.clone());
+        }
+
+        Ok(network)
+    }
+
+    /// Remove a custom network of the active user. Does nothing if there is no such network.
+    ///
+    /// If the network is selected, the selection is cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_key` - The key of the custom network.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn remove_custom_network(&mut self, network_key: &str) -> Result<()> {
+        info!("Removing custom network {network_key}");
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        let mut custom_networks = repo.get(&active_user.username)?.custom_networks;
+        custom_networks.retain(|n| n.key != network_key);
+        repo.set_custom_networks(&active_user.username, custom_networks.clone())?;
+
+        let is_active = self.active_network.as_ref().is_some_and(|n| n.key == network_key);
+        if is_active && !self.networks.iter().any(|n| n.key == network_key) {
+            self.active_network = None;
+            repo.set_selected_network(&active_user.username, None)?;
+        }
+        self.custom_networks = custom_networks;
+
+        Ok(())
+    }
+
+    /// Get the custom networks of the active user
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_custom_networks(&self) -> Result<Vec<ApiNetwork>> {
+        info!("Getting custom networks");
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        Ok(repo.get(&active_user.username)?.custom_networks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::core_testing_utils::handle_error_test_cases;
+    use crate::testing_utils::{
+        ETH_NETWORK_KEY, IOTA_NETWORK_KEY, USERNAME, example_api_networks, example_user_entity, set_config,
+    };
+    use crate::types::users::ActiveUser;
+    use crate::user::{UserRepo, memory_storage::MemoryUserStorage, repository::UserRepoImpl};
+    use crate::wallet_manager::MockWalletManager;
+    use mockito::Matcher;
+    use rstest::rstest;
+
+    const CUSTOM_NETWORK_KEY: &str = "LOCAL_ANVIL";
+
+    fn sdk_with_user(sdk: &mut Sdk) {
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&example_user_entity(USERNAME)).unwrap();
+        sdk.repo = Some(Box::new(repo));
+        sdk.active_user = Some(ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+    }
+
+    fn custom_network(key: &str, node_url: String, protocol: ApiProtocol, decimals: u32) -> ApiNetwork {
+        ApiNetwork {
+            key: key.to_string(),
+            is_testnet: true,
+            display_name: String::from("Custom"),
+            display_symbol: String::from("CST"),
+            coin_type: 60,
+            node_urls: vec![node_url],
+            decimals,
+            can_do_purchases: true,
+            protocol,
+            block_explorer_url: String::new(),
+        }
+    }
+
+    fn mock_rpc(srv: &mut mockito::Server, method: &str, result: serde_json::Value) -> mockito::Mock {
+        srv.mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({ "method": method })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::json!({ "jsonrpc": "2.0", "id": 0, "result": result }).to_string())
+            .expect(1)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_add_custom_evm_network() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        sdk.set_networks(example_api_networks());
+        let chain_id = mock_rpc(&mut srv, "eth_chainId", serde_json::json!("0x7a69"));
+        let network = custom_network(CUSTOM_NETWORK_KEY, srv.url(), ApiProtocol::Evm { chain_id: 31337 }, 18);
+
+        // Act
+        let added = sdk.add_custom_network(network.clone()).await.unwrap();
+
+        // Assert
+        chain_id.assert();
+        assert!(!added.can_do_purchases);
+        assert_eq!(sdk.get_custom_networks().await.unwrap(), vec![added.clone()]);
+        let networks = sdk.get_networks().await.unwrap();
+        assert_eq!(networks.len(), example_api_networks().len() + 1);
+        assert_eq!(networks.last(), Some(&added));
+        sdk.set_network(CUSTOM_NETWORK_KEY.to_string()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_add_custom_network_disabled_in_config() {
+        // Arrange
+        let (srv, mut config, _cleanup) = set_config().await;
+        config.features.custom_networks = false;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        let network = custom_network(CUSTOM_NETWORK_KEY, srv.url(), ApiProtocol::Evm { chain_id: 31337 }, 18);
+
+        // Act
+        let response = sdk.add_custom_network(network).await;
+
+        // Assert
+        assert!(matches!(response, Err(crate::Error::FeatureDisabled(_))));
+    }
+
+    #[tokio::test]
+    async fn test_add_custom_iota_rebased_network() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        let metadata = mock_rpc(
+            &mut srv,
+            "iotax_getCoinMetadata",
+            serde_json::json!({
+                "decimals": 9,
+                "name": "IOTA",
+                "symbol": "IOTA",
+                "description": "The main (gas)token of the IOTA Network.",
+                "iconUrl": null,
+                "id": null
+            }),
+        );
+        let network = custom_network(
+            CUSTOM_NETWORK_KEY,
+            srv.url(),
+            ApiProtocol::IotaRebased {
+                coin_type: String::from("0x2::iota::IOTA"),
+            },
+            9,
+        );
+
+        // Act
+        let response = sdk.add_custom_network(network).await;
+
+        // Assert
+        metadata.assert();
+        assert!(response.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_add_custom_network_rejects_wrong_chain_id() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        let chain_id = mock_rpc(&mut srv, "eth_chainId", serde_json::json!("0x1"));
+        let network = custom_network(CUSTOM_NETWORK_KEY, srv.url(), ApiProtocol::Evm { chain_id: 31337 }, 18);
+
+        // Act
+        let response = sdk.add_custom_network(network).await;
+
+        // Assert
+        chain_id.assert();
+        assert!(matches!(response, Err(crate::Error::InvalidCustomNetwork(_))));
+        assert!(sdk.get_custom_networks().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_custom_network_checks_every_node() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut other_srv = mockito::Server::new_async().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        let chain_id = mock_rpc(&mut srv, "eth_chainId", serde_json::json!("0x7a69"));
+        let other_chain_id = mock_rpc(&mut other_srv, "eth_chainId", serde_json::json!("0x1"));
+        let mut network = custom_network(CUSTOM_NETWORK_KEY, srv.url(), ApiProtocol::Evm { chain_id: 31337 }, 18);
+        network.node_urls.push(other_srv.url());
+
+        // Act
+        let response = sdk.add_custom_network(network).await;
+
+        // Assert
+        chain_id.assert();
+        other_chain_id.assert();
+        assert!(matches!(response, Err(crate::Error::InvalidCustomNetwork(_))));
+        assert!(sdk.get_custom_networks().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_custom_network_rejects_backend_key() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        sdk.set_networks(example_api_networks());
+        let network = custom_network(
+            ETH_NETWORK_KEY,
+            String::from("http://localhost:8545"),
+            ApiProtocol::Evm { chain_id: 31337 },
+            18,
+        );
+
+        // Act
+        let response = sdk.add_custom_network(network).await;
+
+        // Assert
+        assert!(matches!(response, Err(crate::Error::InvalidCustomNetwork(_))));
+    }
+
+    #[tokio::test]
+    async fn test_remove_custom_network_clears_selection() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk);
+        sdk.set_networks(example_api_networks());
+        let network = custom_network(
+            CUSTOM_NETWORK_KEY,
+            String::from("http://localhost:8545"),
+            ApiProtocol::Evm { chain_id: 31337 },
+            18,
+        );
+        sdk.repo
+            .as_mut()
+            .unwrap()
+            .set_custom_networks(USERNAME, vec![network.clone()])
+            .unwrap();
+        sdk.custom_networks = vec![network];
+        sdk.set_network(CUSTOM_NETWORK_KEY.to_string()).await.unwrap();
+
+        // Act
+        sdk.remove_custom_network(CUSTOM_NETWORK_KEY).await.unwrap();
+
+        // Assert
+        assert!(sdk.active_network.is_none());
+        assert!(sdk.get_custom_networks().await.unwrap().is_empty());
+        assert_eq!(sdk.get_networks().await.unwrap(), example_api_networks());
+        let user = sdk.repo.as_ref().unwrap().get(USERNAME).unwrap();
+        assert_eq!(user.selected_network, None);
+        assert!(sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.is_ok());
+    }
+
+    #[rstest]
+    #[case::repo_init_error(crate::Error::UserRepoNotInitialized)]
+    #[case::user_init_error(crate::Error::UserNotInitialized)]
+    #[tokio::test]
+    async fn test_custom_networks_errors(#[case] expected: crate::Error) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        handle_error_test_cases(&expected, &mut sdk, 0, 0).await;
+
+        // Act
+        let response = sdk.get_custom_networks().await;
+
+        // Assert
+        assert_eq!(response.unwrap_err().to_string(), expected.to_string());
+    }
+}
@@ -0,0 +1,737 @@
+use super::{Config, Sdk, fetch_networks};
+use crate::backend::client::sleep;
+use crate::backend::kyc::check_kyc_status;
+use crate::backend::transactions::get_transactions_list;
+use crate::error::Result;
+use crate::tx_version::VersionedWalletTransaction;
+use crate::types::newtypes::AccessToken;
+use crate::types::users::Cached;
+use api_types::api::networks::ApiNetwork;
+use api_types::api::transactions::ApiTxStatus;
+use chrono::{DateTime, Utc};
+use etopay_wallet::WalletUser;
+use etopay_wallet::types::{CryptoAmount, WalletTransaction, WalletTxStatus};
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{RwLock, broadcast};
+
+/// How often the background poller checks for changes, unless another interval is given
+pub const DEFAULT_EVENT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of events buffered for each subscriber before the oldest ones are dropped
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// Number of the latest purchases and swap orders that are checked for status changes
+const POLLED_ITEMS: u32 = 20;
+
+/// Event emitted by the SDK to its subscribers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum SdkEvent {
+    /// The balance of the wallet changed
+    BalanceChanged {
+        /// The network of the wallet
+        network_key: String,
+        /// The new balance
+        balance: CryptoAmount,
+    },
+    /// A pending wallet transaction was confirmed
+    TransactionConfirmed {
+        /// The network of the transaction
+        network_key: String,
+        /// The hash of the transaction
+        transaction_hash: String,
+    },
+    /// The status of a purchase changed
+    PurchaseStatusChanged {
+        /// The id of the purchase
+        purchase_id: String,
+        /// The new status
+        status: ApiTxStatus,
+    },
+    /// The KYC status of the user changed
+    KycStatusChanged {
+        /// Whether the user is verified now
+        is_verified: bool,
+    },
+    /// The status of a viviswap order changed
+    ViviswapOrderUpdated {
+        /// The id of the order
+        order_id: String,
+        /// The new status of the order
+        status: i32,
+    },
+    /// Another network was selected
+    NetworkChanged {
+        /// The key of the selected network
+        network_key: String,
+    },
+    /// A value could not be fetched and the last known value of the offline cache was returned
+    CachedValueReturned {
+        /// Which value was returned
+        value: CachedValue,
+        /// When the returned value was fetched
+        updated_at: DateTime<Utc>,
+    },
+}
+
+/// The values kept in the offline cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachedValue {
+    /// The balance returned by [`Sdk::get_balance`]
+    Balance,
+    /// The networks returned by [`Sdk::get_networks`]
+    Networks,
+    /// The exchange rate returned by [`Sdk::get_exchange_rate`]
+    ExchangeRate,
+}
+
+/// Sends the events of the SDK and remembers what the last poll observed
+pub(crate) struct Events {
+    sender: broadcast::Sender<SdkEvent>,
+    poller_running: Arc<AtomicBool>,
+    observed: Observed,
+}
+
+/// The state observed by the last poll, to detect changes
+#[derive(Default)]
+struct Observed {
+    balances: HashMap<String, CryptoAmount>,
+    purchase_statuses: HashMap<String, ApiTxStatus>,
+    kyc_verified: Option<bool>,
+    #[cfg(feature = "viviswap-swap")]
+    viviswap_order_statuses: HashMap<String, i32>,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            poller_running: Arc::new(AtomicBool::new(false)),
+            observed: Observed::default(),
+        }
+    }
+}
+
+impl Events {
+    /// Send the event to all subscribers
+    pub(crate) fn emit(&self, event: SdkEvent) {
+        debug!("Emitting event {event:?}");
+        // sending only fails if there are no subscribers
+        let _ = self.sender.send(event);
+    }
+
+    /// Forget the observed state, e.g. when the active user changes
+    pub(crate) fn reset(&mut self) {
+        self.observed = Observed::default();
+    }
+}
+
+/// Store the observed value and return whether it differs from the previous observation. The first
+/// observation is not a change.
+fn observe<K: Eq + Hash, V: PartialEq + Clone>(observed: &mut HashMap<K, V>, key: K, value: &V) -> bool {
+    observed
+        .insert(key, value.clone())
+        .is_some_and(|previous| previous != *value)
+}
+
+impl Sdk {
+    /// Subscribe to the events of the SDK
+    ///
+    /// [`SdkEvent::NetworkChanged`] is emitted by [`Sdk::set_network`], all other events are
+    /// detected by [`Sdk::poll_events`], which [`run_event_poller`] calls in the background while
+    /// the wallet is unlocked. A subscriber that falls behind misses the oldest events and receives
+    /// [`broadcast::error::RecvError::Lagged`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SdkEvent> {
+        self.events.sender.subscribe()
+    }
+
+    /// Check for changes and emit the corresponding events
+    ///
+    /// Does nothing unless the wallet is unlocked with [`Sdk::unlock_wallet`] and event polling is
+    /// enabled in [`crate::core::config::FeatureToggles`]. The networks are
+    /// refreshed first if they expired, see [`Sdk::refresh_networks`]. The balance and the
+    /// pending transactions are only checked for the wallet of the active network that is kept in
+    /// the unlocked session. The first poll only records the current state. A check that fails is
+    /// logged and does not stop the other checks.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn poll_events(&mut self) -> Result<()> {
+        if !self.is_wallet_unlocked()? {
+            debug!("Wallet is locked, not polling for events");
+            return Ok(());
+        }
+        let Some(snapshot) = self.poll_snapshot()? else {
+            return Ok(());
+        };
+        let polled = snapshot.poll().await;
+        self.apply_poll(polled)
+    }
+
+    /// Whether the wallet is unlocked and the session has not expired yet. Unlike
+    /// [`Sdk::is_wallet_unlocked`], this does not lock an expired session.
+    fn wallet_session_active(&self) -> bool {
+        self.active_user
+            .as_ref()
+            .and_then(|active_user| active_user.wallet_manager.unlocked_until())
+            .is_some_and(|expires_at| Utc::now() < expires_at)
+    }
+
+    /// Take everything a poll needs from the SDK, so that the backend and the wallet can be
+    /// queried without borrowing the SDK. Returns [`None`] if event polling is disabled.
+    #[allow(clippy::result_large_err)]
+    fn poll_snapshot(&self) -> Result<Option<PollSnapshot>> {
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        if !self.feature_enabled(|features| features.event_polling) {
+            debug!("Event polling is disabled, not polling for events");
+            return Ok(None);
+        }
+
+        let wallet = match &self.active_network {
+            Some(network) => match active_user
+                .wallet_manager
+                .try_get_unlocked(network, &active_user.mnemonic_derivation_options)
+            {
+                Some(wallet) => {
+                    let pending_transactions = repo
+                        .get_wallet_transactions(&active_user.username, &network.key, 0, usize::MAX)?
+                        .into_iter()
+                        .filter(|t| t.status() == WalletTxStatus::Pending)
+                        .map(|t| t.transaction_hash().to_string())
+                        .collect();
+                    Some(PolledWallet {
+                        network_key: network.key.clone(),
+                        wallet,
+                        pending_transactions,
+                    })
+                }
+                None => {
+                    debug!("No unlocked wallet for network {}", network.key);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Ok(Some(PollSnapshot {
+            config: self.config.clone(),
+            access_token: self.access_token.clone(),
+            username: active_user.username.clone(),
+            refresh_networks: self.networks_expired(),
+            wallet,
+            #[cfg(feature = "viviswap-swap")]
+            viviswap_user: repo.get(&active_user.username)?.viviswap_state.is_some(),
+        }))
+    }
+
+    /// Apply the results of a poll and emit the events for everything that changed. The results
+    /// are dropped if the active user changed in the meantime.
+    #[allow(clippy::result_large_err)]
+    fn apply_poll(&mut self, polled: PollResult) -> Result<()> {
+        if self
+            .active_user
+            .as_ref()
+            .is_none_or(|active_user| active_user.username != polled.username)
+        {
+            debug!("Active user changed, dropping the poll results");
+            return Ok(());
+        }
+
+        if let Some(networks) = polled.networks {
+            self.apply_networks(Cached::fresh(networks));
+        }
+
+        if let Some((network_key, balance)) = polled.balance {
+            if observe(&mut self.events.observed.balances, network_key.clone(), &balance) {
+                self.events.emit(SdkEvent::BalanceChanged { network_key, balance });
+            }
+        }
+        for (network_key, transaction) in polled.finished_transactions {
+            if transaction.status == WalletTxStatus::Confirmed {
+                self.events.emit(SdkEvent::TransactionConfirmed {
+                    network_key,
+                    transaction_hash: transaction.transaction_hash.clone(),
+                });
+            }
+            let Some(repo) = &mut self.repo else {
+                return Err(crate::Error::UserRepoNotInitialized);
+            };
+            repo.insert_wallet_transaction(&polled.username, VersionedWalletTransaction::from(transaction))?;
+        }
+
+        if let Some(is_verified) = polled.kyc_verified {
+            let previous = self.events.observed.kyc_verified.replace(is_verified);
+            if previous.is_some_and(|previous| previous != is_verified) {
+                self.events.emit(SdkEvent::KycStatusChanged { is_verified });
+            }
+        }
+
+        for (purchase_id, status) in polled.purchase_statuses {
+            if observe(
+                &mut self.events.observed.purchase_statuses,
+                purchase_id.clone(),
+                &status,
+            ) {
+                self.events
+                    .emit(SdkEvent::PurchaseStatusChanged { purchase_id, status });
+            }
+        }
+
+        #[cfg(feature = "viviswap-swap")]
+        for (order_id, status) in polled.viviswap_order_statuses {
+            if observe(
+                &mut self.events.observed.viviswap_order_statuses,
+                order_id.clone(),
+                &status,
+            ) {
+                self.events.emit(SdkEvent::ViviswapOrderUpdated { order_id, status });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The unlocked wallet of the active network and its pending transactions
+struct PolledWallet {
+    network_key: String,
+    wallet: Arc<dyn WalletUser + Send + Sync>,
+    pending_transactions: Vec<String>,
+}
+
+/// Everything a poll needs from the SDK, see [`Sdk::poll_snapshot`]
+struct PollSnapshot {
+    config: Option<Config>,
+    access_token: Option<AccessToken>,
+    username: String,
+    refresh_networks: bool,
+    wallet: Option<PolledWallet>,
+    #[cfg(feature = "viviswap-swap")]
+    viviswap_user: bool,
+}
+
+/// What a poll fetched, applied to the SDK by [`Sdk::apply_poll`]
+struct PollResult {
+    username: String,
+    networks: Option<Vec<ApiNetwork>>,
+    balance: Option<(String, CryptoAmount)>,
+    finished_transactions: Vec<(String, WalletTransaction)>,
+    kyc_verified: Option<bool>,
+    purchase_statuses: Vec<(String, ApiTxStatus)>,
+    #[cfg(feature = "viviswap-swap")]
+    viviswap_order_statuses: Vec<(String, i32)>,
+}
+
+impl PollSnapshot {
+    /// Query the backend and the wallet. A check that fails is logged and skipped.
+    async fn poll(self) -> PollResult {
+        let mut polled = PollResult {
+            username: self.username,
+            networks: None,
+            balance: None,
+            finished_transactions: Vec::new(),
+            kyc_verified: None,
+            purchase_statuses: Vec::new(),
+            #[cfg(feature = "viviswap-swap")]
+            viviswap_order_statuses: Vec::new(),
+        };
+
+        if let Some(PolledWallet {
+            network_key,
+            wallet,
+            pending_transactions,
+        }) = self.wallet
+        {
+            match wallet.get_balance().await {
+                Ok(balance) => polled.balance = Some((network_key.clone(), balance)),
+                Err(e) => warn!("Could not poll the balance: {e}"),
+            }
+            for hash in pending_transactions {
+                match wallet.get_wallet_tx(&hash).await {
+                    Ok(details) if details.status != WalletTxStatus::Pending => {
+                        polled.finished_transactions.push((network_key.clone(), details));
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Could not poll the status of transaction {hash}: {e}"),
+                }
+            }
+        }
+
+        let (Some(config), Some(access_token)) = (&self.config, &self.access_token) else {
+            debug!("Not logged in, not polling the backend");
+            return polled;
+        };
+
+        if self.refresh_networks {
+            match fetch_networks(config, access_token).await {
+                Ok(networks) => polled.networks = Some(networks),
+                Err(e) => warn!("Could not refresh the networks: {e}"),
+            }
+        }
+
+        match check_kyc_status(config, access_token, &polled.username).await {
+            Ok(status) => polled.kyc_verified = Some(status.is_verified),
+            Err(e) => warn!("Could not poll the KYC status: {e}"),
+        }
+
+        match get_transactions_list(config, access_token, 0, POLLED_ITEMS).await {
+            Ok(tx_list) => {
+                polled.purchase_statuses = tx_list.txs.into_iter().map(|tx| (tx.index, tx.status)).collect();
+            }
+            Err(e) => warn!("Could not poll the purchases: {e}"),
+        }
+
+        #[cfg(feature = "viviswap-swap")]
+        if self.viviswap_user {
+            match crate::backend::viviswap::get_viviswap_orders(config, access_token, 0, POLLED_ITEMS).await {
+                Ok(order_list) => {
+                    polled.viviswap_order_statuses = order_list
+                        .orders
+                        .into_iter()
+                        .map(|order| (order.id, order.status))
+                        .collect();
+                }
+                Err(e) => warn!("Could not poll the viviswap orders: {e}"),
+            }
+        }
+
+        polled
+    }
+}
+
+/// Resets the running flag of the event poller when it stops
+struct PollerGuard(Arc<AtomicBool>);
+
+impl Drop for PollerGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Poll for events in the background while the wallet is unlocked
+///
+/// Calls [`Sdk::poll_events`] every `interval` and returns as soon as the wallet is locked, the
+/// session expired or the SDK is dropped. Returns right away if a poller is already running for
+/// this SDK, so it can be started again whenever the wallet is unlocked.
+///
+/// # Arguments
+///
+/// * `sdk` - The shared SDK to poll.
+/// * `interval` - The time between two polls, e.g. [`DEFAULT_EVENT_POLL_INTERVAL`].
+pub async fn run_event_poller(sdk: Arc<RwLock<Sdk>>, interval: Duration) {
+    let poller_running = sdk.read().await.events.poller_running.clone();
+    if poller_running.swap(true, Ordering::SeqCst) {
+        debug!("Event poller is already running");
+        return;
+    }
+    let _guard = PollerGuard(poller_running);
+    let sdk = Arc::downgrade(&sdk);
+
+    info!("Starting event poller");
+    loop {
+        let Some(sdk) = sdk.upgrade() else {
+            break;
+        };
+
+        // the SDK is only locked to take the snapshot and to apply the results, not while the
+        // backend and the wallet are queried
+        let snapshot = {
+            let guard = sdk.read().await;
+            if !guard.wallet_session_active() {
+                drop(guard);
+                // drops the wallets of an expired session right away
+                let _ = sdk.write().await.is_wallet_unlocked();
+                break;
+            }
+            guard.poll_snapshot()
+        };
+        match snapshot {
+            Ok(Some(snapshot)) => {
+                let polled = snapshot.poll().await;
+                if let Err(e) = sdk.write().await.apply_poll(polled) {
+                    warn!("Could not apply the polled events: {e}");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Could not poll for events: {e}"),
+        }
+
+        drop(sdk);
+        sleep(interval).await;
+    }
+    info!("Stopping event poller");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing_utils::{
+        AUTH_PROVIDER, ENCRYPTED_WALLET_PASSWORD, HEADER_X_APP_NAME, IOTA_NETWORK_KEY, TOKEN, USERNAME,
+        example_api_networks, example_user_entity, example_versioned_wallet_transaction, set_config,
+    };
+    use crate::types::users::{ActiveUser, UserEntity};
+    use crate::user::{UserRepo, memory_storage::MemoryUserStorage, repository::UserRepoImpl};
+    use crate::wallet_manager::MockWalletManager;
+    use api_types::api::kyc::KycStatusResponse;
+    use etopay_wallet::MockWalletUser;
+    use etopay_wallet::types::WalletTransaction;
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicUsize;
+
+    fn sdk_with_user(sdk: &mut Sdk, wallet_manager: MockWalletManager, transactions: Vec<VersionedWalletTransaction>) {
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&UserEntity {
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            wallet_transactions_versioned: transactions,
+            ..example_user_entity(USERNAME)
+        })
+        .unwrap();
+        sdk.repo = Some(Box::new(repo));
+        sdk.active_user = Some(ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.access_token = Some(TOKEN.clone());
+    }
+
+    fn pending_transaction() -> VersionedWalletTransaction {
+        let mut transaction = WalletTransaction::from(example_versioned_wallet_transaction());
+        transaction.network_key = IOTA_NETWORK_KEY.to_string();
+        transaction.status = WalletTxStatus::Pending;
+        VersionedWalletTransaction::from(transaction)
+    }
+
+    /// A wallet manager with an unlocked wallet that returns the balances one after the other and
+    /// reports every transaction as confirmed
+    fn unlocked_wallet_manager(balances: Vec<CryptoAmount>) -> MockWalletManager {
+        let balances = Arc::new(Mutex::new(balances.into_iter()));
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_is_unlocked().return_const(true);
+        mock_wallet_manager.expect_try_get_unlocked().returning(move |_, _| {
+            let balance = balances.lock().unwrap().next().unwrap();
+            let mut mock_wallet_user = MockWalletUser::new();
+            mock_wallet_user.expect_get_balance().returning(move || Ok(balance));
+            mock_wallet_user.expect_get_wallet_tx().returning(|_| {
+                let mut transaction = WalletTransaction::from(pending_transaction());
+                transaction.status = WalletTxStatus::Confirmed;
+                Ok(transaction)
+            });
+            Some(Arc::new(mock_wallet_user))
+        });
+        mock_wallet_manager
+    }
+
+    #[tokio::test]
+    async fn test_set_network_emits_network_changed() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.set_networks(example_api_networks());
+        let mut events = sdk.subscribe_events();
+
+        // Act
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+
+        // Assert
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SdkEvent::NetworkChanged {
+                network_key: IOTA_NETWORK_KEY.to_string()
+            }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_poll_events_emits_wallet_events() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        // SAFETY: we know that these values are not negative
+        let (old_balance, new_balance) = unsafe {
+            (
+                CryptoAmount::new_unchecked(dec!(25.0)),
+                CryptoAmount::new_unchecked(dec!(30.0)),
+            )
+        };
+        sdk_with_user(
+            &mut sdk,
+            unlocked_wallet_manager(vec![old_balance, old_balance, new_balance]),
+            vec![pending_transaction()],
+        );
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        let mut events = sdk.subscribe_events();
+
+        // Act
+        sdk.poll_events().await.unwrap();
+        sdk.poll_events().await.unwrap();
+        sdk.poll_events().await.unwrap();
+
+        // Assert
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SdkEvent::TransactionConfirmed {
+                network_key: IOTA_NETWORK_KEY.to_string(),
+                transaction_hash: String::from("some tx id"),
+            }
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SdkEvent::BalanceChanged {
+                network_key: IOTA_NETWORK_KEY.to_string(),
+                balance: new_balance,
+            }
+        );
+        assert!(events.try_recv().is_err());
+        let user = sdk.repo.as_ref().unwrap().get(USERNAME).unwrap();
+        assert_eq!(
+            user.wallet_transactions_versioned[0].status(),
+            WalletTxStatus::Confirmed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_events_emits_kyc_status_changed() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, unlocked_wallet_manager(Vec::new()), Vec::new());
+        let mut events = sdk.subscribe_events();
+
+        let kyc_status = |is_verified| {
+            serde_json::to_string(&KycStatusResponse {
+                username: USERNAME.into(),
+                is_verified,
+            })
+            .unwrap()
+        };
+        let not_verified = srv
+            .mock("GET", "/api/kyc/check-status")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .with_status(200)
+            .with_body(kyc_status(false))
+            .expect(1)
+            .create();
+        let verified = srv
+            .mock("GET", "/api/kyc/check-status")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .with_status(200)
+            .with_body(kyc_status(true))
+            .expect(1)
+            .create();
+
+        // Act
+        sdk.poll_events().await.unwrap();
+        sdk.poll_events().await.unwrap();
+
+        // Assert
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SdkEvent::KycStatusChanged { is_verified: true }
+        );
+        assert!(events.try_recv().is_err());
+        not_verified.assert();
+        verified.assert();
+    }
+
+    #[tokio::test]
+    async fn test_poll_events_does_nothing_while_locked() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_is_unlocked().return_const(false);
+        mock_wallet_manager.expect_try_get_unlocked().never();
+        sdk_with_user(&mut sdk, mock_wallet_manager, Vec::new());
+        let mut events = sdk.subscribe_events();
+
+        // Act
+        let response = sdk.poll_events().await;
+
+        // Assert
+        response.unwrap();
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_event_poller_stops_when_locked() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_unlocked_until().return_const(None);
+        mock_wallet_manager.expect_is_unlocked().return_const(false);
+        sdk_with_user(&mut sdk, mock_wallet_manager, Vec::new());
+        let sdk = Arc::new(RwLock::new(sdk));
+
+        // Act
+        run_event_poller(sdk.clone(), Duration::from_millis(1)).await;
+
+        // Assert
+        assert!(!sdk.read().await.events.poller_running.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_run_event_poller_does_not_lock_the_sdk_during_requests() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        // unlocked for the first poll only
+        let polls = AtomicUsize::new(0);
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_unlocked_until().returning(move || {
+            (polls.fetch_add(1, Ordering::SeqCst) == 0).then(|| Utc::now() + Duration::from_secs(60))
+        });
+        mock_wallet_manager.expect_is_unlocked().return_const(false);
+        mock_wallet_manager.expect_try_get_unlocked().return_const(None);
+        sdk_with_user(&mut sdk, mock_wallet_manager, Vec::new());
+        let sdk = Arc::new(RwLock::new(sdk));
+
+        let sdk_unlocked = Arc::new(AtomicBool::new(false));
+        let kyc_status = srv
+            .mock("GET", "/api/kyc/check-status")
+            .with_status(200)
+            .with_body_from_request({
+                let sdk = sdk.clone();
+                let sdk_unlocked = sdk_unlocked.clone();
+                move |_| {
+                    sdk_unlocked.store(sdk.try_write().is_ok(), Ordering::SeqCst);
+                    serde_json::to_vec(&KycStatusResponse {
+                        username: USERNAME.into(),
+                        is_verified: true,
+                    })
+                    .unwrap()
+                }
+            })
+            .expect(1)
+            .create();
+
+        // Act
+        run_event_poller(sdk.clone(), Duration::from_millis(1)).await;
+
+        // Assert
+        kyc_status.assert();
+        assert!(sdk_unlocked.load(Ordering::SeqCst));
+    }
+}
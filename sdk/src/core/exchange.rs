@@ -1,27 +1,55 @@
 use super::Sdk;
-use crate::{backend::dlt::get_exchange_rate, error::Result};
-use log::info;
+use crate::{backend::dlt::get_exchange_rate, error::Result, types::users::Cached};
+use log::{info, warn};
 use rust_decimal::Decimal;
 
 impl Sdk {
     /// Return the current exchange rate.
     ///
+    /// If fetching fails, the last exchange rate fetched for the active network is returned from the
+    /// offline cache and [`super::events::SdkEvent::CachedValueReturned`] is emitted. Use
+    /// [`Sdk::get_exchange_rate_cached`] to tell the cached exchange rate apart from a fetched one.
+    ///
     /// # Returns
     ///
     /// Returns a `Result` containing the exchange rate as a `Decimal` type if successful, or a [`crate::Error`] if an error occurs.
     // MARK10:get_exchange_rate
-    pub async fn get_exchange_rate(&self) -> Result<Decimal> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_exchange_rate(&mut self) -> Result<Decimal> {
+        self.get_exchange_rate_cached()
+            .await
+            .map(|exchange_rate| exchange_rate.value)
+    }
+
+    /// Return the current exchange rate together with the time it was fetched.
+    ///
+    /// Like [`Sdk::get_exchange_rate`], but if fetching fails and the last known exchange rate is
+    /// returned from the offline cache, it is marked as [`Cached::stale`] and keeps the time it was
+    /// fetched.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_exchange_rate_cached(&mut self) -> Result<Cached<Decimal>> {
         info!("Fetching exchange rate from viviswap");
         let _user = self.get_user().await?;
 
         let access_token = self
             .access_token
-            .as_ref()
+            .clone()
             .ok_or(crate::error::Error::MissingAccessToken)?;
+        let network = self.active_network().await?;
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
-        let network = self.active_network.clone().ok_or(crate::Error::MissingNetwork)?;
-        let exchange_rate = get_exchange_rate(config, access_token, network.key).await?;
-        Ok(exchange_rate)
+        match get_exchange_rate(config, &access_token, network.key.clone()).await {
+            Ok(exchange_rate) => {
+                self.cache_exchange_rate(network.key, exchange_rate);
+                Ok(Cached::fresh(exchange_rate))
+            }
+            Err(e) => {
+                let Some(exchange_rate) = self.cached_exchange_rate(&network.key) else {
+                    return Err(e.into());
+                };
+                warn!("Could not fetch the exchange rate: {e}");
+                Ok(exchange_rate)
+            }
+        }
     }
 }
 
@@ -59,12 +87,17 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                let mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 2, KycType::Undefined);
+                mock_user_repo
+                    .expect_set_offline_cache()
+                    .once()
+                    .returning(|_, _| Ok(()));
                 sdk.repo = Some(Box::new(mock_user_repo));
                 sdk.active_user = Some(crate::types::users::ActiveUser {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
                 sdk.set_networks(example_api_networks());
@@ -112,12 +145,17 @@ mod tests {
         sdk.set_networks(example_api_networks());
         sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
 
-        sdk.repo = Some(Box::new(example_get_user(
+        let mut mock_user_repo = example_get_user(
             SwapPaymentDetailKey::Iota,
             false,
-            1,
+            2,
             KycType::Undefined, // changed to undefined because it was requiring the viviswap features
-        )));
+        );
+        mock_user_repo
+            .expect_set_offline_cache()
+            .once()
+            .returning(|_, _| Ok(()));
+        sdk.repo = Some(Box::new(mock_user_repo));
         sdk.active_user = Some(get_active_user());
         sdk.access_token = Some(TOKEN.clone());
 
@@ -148,6 +186,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(MockWalletManager::new()),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         }
     }
 }
@@ -0,0 +1,644 @@
+//! This module provides the export and import of all local data of a user, e.g. to move the
+//! transaction history, the KYC progress and the settings of a user to another device.
+//!
+//! The archive is protected by an export password, which has to pass the same strength check as
+//! the wallet password. Argon2id derives an encryption key and a MAC key from the password and a
+//! random salt. The content is encrypted with AES-256-GCM and the whole archive is authenticated
+//! with an HMAC-SHA256, which is verified on import before anything is decrypted. The local
+//! share and the encrypted password are never part of the archive; the wallet, including its
+//! passphrase, is restored from its backup or mnemonic instead.
+//!
+//! Anyone who knows the export password can create an archive, so the KYC state of the archive is
+//! never imported. It is fetched from the backend instead.
+
+use super::Sdk;
+use super::address_book::{upsert_address_book_entry, upsert_address_label};
+use crate::backend::kyc::check_kyc_status;
+use crate::error::Result;
+use crate::types::error::TypeError;
+use crate::types::newtypes::{EncryptionPin, EncryptionSalt, PlainPassword};
+use crate::types::users::{KycType, UserEntity};
+use crate::wallet_manager::check_pin;
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit, Payload},
+};
+use chrono::{DateTime, Utc};
+use etopay_wallet::MnemonicDerivationOption;
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// The magic bytes at the start of every user data archive
+const ARCHIVE_MAGIC: &[u8] = b"ETOPAYUD";
+
+/// The current version of the archive format
+pub const USER_DATA_FORMAT_VERSION: u8 = 2;
+
+/// Length of the key derivation salt stored after the version byte
+const SALT_LENGTH: usize = 16;
+
+/// Length of the AES-GCM nonce stored after the salt
+const NONCE_LENGTH: usize = 12;
+
+/// Length of the HMAC-SHA256 tag at the end of the archive
+const MAC_LENGTH: usize = 32;
+
+/// Length of each of the two keys derived from the export password
+const KEY_LENGTH: usize = 32;
+
+/// Length of the argon2id output, split into the encryption key and the MAC key
+const DERIVED_KEYS_LENGTH: u32 = 64;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The encrypted content of a user data archive
+#[derive(Debug, Serialize, Deserialize)]
+struct UserDataExport {
+    /// The version of the archive format, must match the unencrypted version byte
+    version: u8,
+    /// When the archive was created
+    exported_at: DateTime<Utc>,
+    /// The user entity without any secrets
+    user: UserEntity,
+    /// The derivation options of the active user
+    derivation_options: MnemonicDerivationOption,
+    /// The key of the network that was active when the archive was created
+    active_network: Option<String>,
+}
+
+/// Removes all secrets from the entity before it is written to an archive.
+fn strip_secrets(user: &mut UserEntity) {
+    user.local_share = None;
+    user.encrypted_password = None;
+    user.pin_attempts = Default::default();
+}
+
+/// Removes the KYC state from an imported entity, as it cannot be trusted.
+fn strip_kyc_state(user: &mut UserEntity) {
+    user.is_kyc_verified = false;
+    user.kyc_type = KycType::Undefined;
+    user.viviswap_state = None;
+}
+
+/// Derives the encryption key and the MAC key of an archive from the export password and salt
+/// with argon2id, using the parameters recommended by OWASP.
+#[allow(clippy::result_large_err)]
+fn derive_keys(password: &PlainPassword, salt: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    let config = argon2::Config {
+        hash_length: DERIVED_KEYS_LENGTH,
+        ..argon2::Config::owasp2()
+    };
+    let keys = argon2::hash_raw(password.as_str().as_bytes(), salt, &config)
+        .map_err(|e| crate::Error::InvalidUserDataArchive(format!("could not derive the archive keys: {e}")))?;
+    Ok(Zeroizing::new(keys))
+}
+
+/// Merges the imported entity into the local one. The secrets and the KYC state of the local
+/// entity are kept and address labels and address book entries of the archive replace local ones
+/// for the same address.
+fn merge_user(local: &mut UserEntity, imported: UserEntity) {
+    if imported.user_id.is_some() {
+        local.user_id = imported.user_id;
+    }
+    for label in imported.address_labels {
+        upsert_address_label(&mut local.address_labels, label);
+    }
+    for entry in imported.address_book {
+        upsert_address_book_entry(&mut local.address_book, entry);
+    }
+    if imported.selected_network.is_some() {
+        local.selected_network = imported.selected_network;
+    }
+    local
+        .network_derivation_options
+        .extend(imported.network_derivation_options);
+}
+
+impl Sdk {
+    /// Export all local data of the active user
+    ///
+    /// The archive contains the user entity without any secrets, the transaction history, the
+    /// viviswap and KYC state, the address book and the wallet settings. The KYC state is only
+    /// informational, it is not imported by [`Sdk::import_user_data`]. The archive is encrypted and
+    /// authenticated with keys derived from the export password and can be restored with
+    /// [`Sdk::import_user_data`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin of the wallet, to authorize the export.
+    /// * `password` - The export password, which is needed to import the archive.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted archive.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`crate::error::Error::Wallet`] - If the pin is incorrect or locked.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn export_user_data(&mut self, pin: &EncryptionPin, password: &PlainPassword) -> Result<Vec<u8>> {
+        info!("Exporting user data");
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+
+        let mut user = repo.get(&active_user.username)?;
+        check_pin(repo, &user, pin, &self.pin_policy)?;
+        strip_secrets(&mut user);
+        user.wallet_transactions_versioned = repo.get_all_wallet_transactions(&active_user.username)?;
+
+        let active_network = self
+            .active_network
+            .as_ref()
+            .map(|network| network.key.clone())
+            .or_else(|| user.selected_network.clone());
+        let export = UserDataExport {
+            version: USER_DATA_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            user,
+            derivation_options: active_user.mnemonic_derivation_options,
+            active_network,
+        };
+        let plaintext = Zeroizing::new(serde_json::to_vec(&export).map_err(|e| crate::Error::Parse(e.to_string()))?);
+
+        let salt: [u8; SALT_LENGTH] = rand::random();
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let keys = derive_keys(password, &salt)?;
+        let (encryption_key, mac_key) = keys.split_at(KEY_LENGTH);
+
+        let mut archive =
+            Vec::with_capacity(ARCHIVE_MAGIC.len() + 1 + SALT_LENGTH + NONCE_LENGTH + plaintext.len() + MAC_LENGTH);
+        archive.extend_from_slice(ARCHIVE_MAGIC);
+        archive.push(USER_DATA_FORMAT_VERSION);
+        archive.extend_from_slice(&salt);
+        archive.extend_from_slice(&nonce);
+
+        let cipher = Aes256Gcm::new_from_slice(encryption_key).map_err(|_| TypeError::PasswordEncryption)?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &archive,
+                },
+            )
+            .map_err(|_| TypeError::PasswordEncryption)?;
+        archive.extend_from_slice(&ciphertext);
+
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(mac_key).map_err(|_| TypeError::PasswordEncryption)?;
+        mac.update(&archive);
+        archive.extend_from_slice(&mac.finalize().into_bytes());
+        Ok(archive)
+    }
+
+    /// Import an archive created with [`Sdk::export_user_data`]
+    ///
+    /// If the user does not exist locally, it is created without a wallet, which has to be
+    /// restored from a backup or the mnemonic afterwards. Otherwise the archive is merged into the
+    /// local user and the local wallet is kept, which requires the pin of the local wallet. If the
+    /// imported user is the active user, the derivation options and the network of the archive are
+    /// selected as well.
+    ///
+    /// The KYC state is never taken from the archive. If the imported user is the active user and
+    /// an access token is set, it is fetched from the backend right away, otherwise the next time
+    /// the user is initialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin of the wallet of the local user, required if the user already exists
+    ///   locally and has a wallet.
+    /// * `password` - The export password used to create the archive.
+    /// * `archive` - The encrypted archive.
+    ///
+    /// # Returns
+    ///
+    /// The username of the imported user.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::InvalidUserDataArchive`] - If the archive is malformed or has an unsupported version.
+    /// * [`crate::error::Error::Type`] - If the password is incorrect or the archive was modified.
+    /// * [`crate::error::Error::Wallet`] - If the pin of the local wallet is missing, incorrect or locked.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn import_user_data(
+        &mut self,
+        pin: Option<&EncryptionPin>,
+        password: &PlainPassword,
+        archive: &[u8],
+    ) -> Result<String> {
+        info!("Importing user data");
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+
+        let Some((authenticated, tag)) = archive.split_last_chunk::<MAC_LENGTH>() else {
+            return Err(crate::Error::InvalidUserDataArchive(String::from(
+                "archive is too short",
+            )));
+        };
+        let Some(rest) = authenticated.strip_prefix(ARCHIVE_MAGIC) else {
+            return Err(crate::Error::InvalidUserDataArchive(String::from(
+                "not a user data archive",
+            )));
+        };
+        let Some((&version, rest)) = rest.split_first() else {
+            return Err(crate::Error::InvalidUserDataArchive(String::from("missing version")));
+        };
+        if version != USER_DATA_FORMAT_VERSION {
+            return Err(crate::Error::InvalidUserDataArchive(format!(
+                "unsupported version {version}"
+            )));
+        }
+        let Some((salt, rest)) = rest.split_first_chunk::<SALT_LENGTH>() else {
+            return Err(crate::Error::InvalidUserDataArchive(String::from("missing salt")));
+        };
+        let Some((nonce, ciphertext)) = rest.split_first_chunk::<NONCE_LENGTH>() else {
+            return Err(crate::Error::InvalidUserDataArchive(String::from("missing nonce")));
+        };
+
+        let keys = derive_keys(password, salt)?;
+        let (encryption_key, mac_key) = keys.split_at(KEY_LENGTH);
+
+        // verify the whole archive before anything is decrypted
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(mac_key).map_err(|_| TypeError::InvalidPinOrPassword)?;
+        mac.update(authenticated);
+        mac.verify_slice(tag).map_err(|_| TypeError::InvalidPinOrPassword)?;
+
+        let header = &authenticated[..authenticated.len() - ciphertext.len()];
+        let cipher = Aes256Gcm::new_from_slice(encryption_key).map_err(|_| TypeError::InvalidPinOrPassword)?;
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: header,
+                    },
+                )
+                .map_err(|_| TypeError::InvalidPinOrPassword)?,
+        );
+        let export: UserDataExport =
+            serde_json::from_slice(&plaintext).map_err(|e| crate::Error::InvalidUserDataArchive(e.to_string()))?;
+        if export.version != version {
+            return Err(crate::Error::InvalidUserDataArchive(String::from("version mismatch")));
+        }
+        info!("Importing user data exported at {}", export.exported_at);
+
+        let mut imported = export.user;
+        strip_secrets(&mut imported);
+        strip_kyc_state(&mut imported);
+        crate::user::migration::migrate(&mut imported)?;
+        let username = imported.username.clone();
+        if let Some(network_key) = &export.active_network {
+            imported.selected_network = Some(network_key.clone());
+            imported
+                .network_derivation_options
+                .insert(network_key.clone(), export.derivation_options);
+        }
+
+        // the transactions are inserted one by one, as the storage may keep them outside of the entity
+        let transactions = std::mem::take(&mut imported.wallet_transactions_versioned);
+        let network_derivation_options = match repo.get(&username) {
+            Ok(mut local) => {
+                // the archive changes an existing user, so the pin of its wallet is required
+                if local.encrypted_password.is_some() {
+                    let pin = pin.ok_or(crate::Error::Wallet(crate::WalletError::WrongPinOrPassword))?;
+                    check_pin(repo, &local, pin, &self.pin_policy)?;
+                    // reload the user, as checking the pin may reset the failed attempts
+                    local = repo.get(&username)?;
+                }
+                merge_user(&mut local, imported);
+                repo.update(&local)?;
+                local.network_derivation_options
+            }
+            Err(crate::user::error::UserKvStorageError::UserNotFound { .. }) => {
+                imported.salt = EncryptionSalt::generate();
+                repo.create(&imported)?;
+                imported.network_derivation_options
+            }
+            Err(e) => return Err(e.into()),
+        };
+        for transaction in transactions {
+            let known = repo
+                .get_wallet_transaction(&username, transaction.network_key(), transaction.transaction_hash())?
+                .is_some();
+            if !known {
+                repo.insert_wallet_transaction(&username, transaction)?;
+            }
+        }
+
+        // the access token belongs to the active user, so the KYC state of other users is fetched
+        // when they are initialized
+        let is_active_user = self.active_user.as_ref().is_some_and(|user| user.username == username);
+        if let (Some(config), Some(access_token)) = (&self.config, &self.access_token) {
+            if is_active_user {
+                match check_kyc_status(config, access_token, &username).await {
+                    Ok(status) => repo.set_kyc_state(&username, status.is_verified)?,
+                    Err(e) => warn!("Could not fetch the KYC state of the imported user: {e}"),
+                }
+            }
+        }
+
+        if let Some(active_user) = self.active_user.as_mut().filter(|user| user.username == username) {
+            active_user.mnemonic_derivation_options = export.derivation_options;
+            active_user.network_derivation_options = network_derivation_options;
+            if let Some(network_key) = export.active_network {
+                if let Err(e) = self.set_network(network_key).await {
+                    warn!("Could not select the network of the archive: {e}");
+                }
+            }
+        }
+
+        Ok(username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::core_testing_utils::handle_error_test_cases;
+    use crate::testing_utils::{
+        ENCRYPTED_WALLET_PASSWORD, IOTA_NETWORK_KEY, PIN, TOKEN, USERNAME, WALLET_PASSWORD, example_api_networks,
+        example_user_entity, example_versioned_wallet_transaction, set_config,
+    };
+    use crate::types::users::{ActiveUser, AddressBookEntry};
+    use crate::user::{UserRepo, memory_storage::MemoryUserStorage, repository::UserRepoImpl};
+    use crate::wallet_manager::MockWalletManager;
+    use api_types::api::kyc::KycStatusResponse;
+    use rstest::rstest;
+
+    fn example_user() -> UserEntity {
+        UserEntity {
+            user_id: Some(String::from("user-id")),
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            is_kyc_verified: true,
+            local_share: Some(String::from("local share")),
+            wallet_transactions_versioned: vec![example_versioned_wallet_transaction()],
+            address_book: vec![AddressBookEntry {
+                network_key: IOTA_NETWORK_KEY.to_string(),
+                address: String::from("address"),
+                name: String::from("Alice"),
+            }],
+            ..example_user_entity(USERNAME)
+        }
+    }
+
+    fn sdk_with_user(sdk: &mut Sdk, user: Option<UserEntity>) {
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        if let Some(user) = user {
+            repo.create(&user).unwrap();
+        }
+        sdk.repo = Some(Box::new(repo));
+        sdk.active_user = Some(ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: MnemonicDerivationOption { account: 1, index: 2 },
+            network_derivation_options: Default::default(),
+        });
+        sdk.set_networks(example_api_networks());
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_user_data_into_new_device() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        let archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut new_sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut new_sdk, None);
+        new_sdk.active_user.as_mut().unwrap().mnemonic_derivation_options = Default::default();
+
+        // Act
+        let username = new_sdk
+            .import_user_data(None, &WALLET_PASSWORD, &archive)
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(username, USERNAME);
+        let repo = new_sdk.repo.as_ref().unwrap();
+        let user = repo.get(USERNAME).unwrap();
+        let expected = example_user();
+        assert_eq!(user.user_id, expected.user_id);
+        assert!(!user.is_kyc_verified);
+        assert_eq!(user.kyc_type, KycType::Undefined);
+        assert_eq!(
+            repo.get_all_wallet_transactions(USERNAME).unwrap(),
+            expected.wallet_transactions_versioned
+        );
+        assert_eq!(user.address_book, expected.address_book);
+        assert!(user.local_share.is_none());
+        assert!(user.encrypted_password.is_none());
+
+        let active_user = new_sdk.active_user.as_ref().unwrap();
+        assert_eq!(
+            active_user.mnemonic_derivation_options,
+            MnemonicDerivationOption { account: 1, index: 2 }
+        );
+        assert_eq!(new_sdk.active_network.as_ref().unwrap().key, IOTA_NETWORK_KEY);
+    }
+
+    #[tokio::test]
+    async fn test_import_user_data_keeps_local_secrets() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        let archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+
+        let mut local = example_user();
+        local.wallet_transactions_versioned = Vec::new();
+        local.address_book = Vec::new();
+        local.is_kyc_verified = false;
+        sdk_with_user(&mut sdk, Some(local));
+
+        // Act
+        sdk.import_user_data(Some(&PIN), &WALLET_PASSWORD, &archive)
+            .await
+            .unwrap();
+
+        // Assert
+        let repo = sdk.repo.as_ref().unwrap();
+        let user = repo.get(USERNAME).unwrap();
+        assert_eq!(user.local_share.as_deref(), Some("local share"));
+        assert!(user.encrypted_password.is_some());
+        assert!(!user.is_kyc_verified);
+        assert_eq!(repo.get_all_wallet_transactions(USERNAME).unwrap().len(), 1);
+        assert_eq!(user.address_book.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_user_data_is_idempotent() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        let archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+
+        // Act
+        sdk.import_user_data(Some(&PIN), &WALLET_PASSWORD, &archive)
+            .await
+            .unwrap();
+        sdk.import_user_data(Some(&PIN), &WALLET_PASSWORD, &archive)
+            .await
+            .unwrap();
+
+        // Assert
+        let repo = sdk.repo.as_ref().unwrap();
+        let user = repo.get(USERNAME).unwrap();
+        assert_eq!(repo.get_all_wallet_transactions(USERNAME).unwrap().len(), 1);
+        assert_eq!(user.address_book.len(), 1);
+    }
+
+    #[rstest]
+    #[case::missing_pin(None)]
+    #[case::wrong_pin(Some(EncryptionPin::try_from_string("123457").unwrap()))]
+    #[tokio::test]
+    async fn test_import_user_data_requires_pin_of_existing_user(#[case] pin: Option<EncryptionPin>) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        let archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+
+        let mut local = example_user();
+        local.address_book = Vec::new();
+        sdk_with_user(&mut sdk, Some(local));
+
+        // Act
+        let result = sdk.import_user_data(pin.as_ref(), &WALLET_PASSWORD, &archive).await;
+
+        // Assert
+        assert!(matches!(result, Err(crate::Error::Wallet(_))));
+        let user = sdk.repo.as_ref().unwrap().get(USERNAME).unwrap();
+        assert!(user.address_book.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_user_data_fetches_kyc_state() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut user = example_user();
+        user.is_kyc_verified = false;
+        sdk_with_user(&mut sdk, Some(user));
+        let archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut new_sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut new_sdk, None);
+        new_sdk.access_token = Some(TOKEN.clone());
+        let body = serde_json::to_string(&KycStatusResponse {
+            username: USERNAME.into(),
+            is_verified: true,
+        })
+        .unwrap();
+        let kyc_mock = srv
+            .mock("GET", "/api/kyc/check-status")
+            .with_status(200)
+            .with_body(body)
+            .expect(1)
+            .create();
+
+        // Act
+        new_sdk
+            .import_user_data(None, &WALLET_PASSWORD, &archive)
+            .await
+            .unwrap();
+
+        // Assert
+        kyc_mock.assert();
+        let user = new_sdk.repo.as_ref().unwrap().get(USERNAME).unwrap();
+        assert!(user.is_kyc_verified);
+    }
+
+    #[rstest]
+    #[case::wrong_password(None, "not the export password")]
+    #[case::modified(Some(60), "correcthorsebatterystaple")]
+    #[case::wrong_magic(Some(0), "correcthorsebatterystaple")]
+    #[case::wrong_version(Some(ARCHIVE_MAGIC.len()), "correcthorsebatterystaple")]
+    #[case::modified_salt(Some(ARCHIVE_MAGIC.len() + 1), "correcthorsebatterystaple")]
+    #[case::modified_nonce(Some(ARCHIVE_MAGIC.len() + 1 + SALT_LENGTH), "correcthorsebatterystaple")]
+    #[tokio::test]
+    async fn test_import_user_data_rejects_invalid_archive(#[case] flip_byte: Option<usize>, #[case] password: &str) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        let mut archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+        if let Some(index) = flip_byte {
+            archive[index] ^= 0x01;
+        }
+        let password = PlainPassword::try_from_string(password).unwrap();
+
+        // Act
+        let result = sdk.import_user_data(Some(&PIN), &password, &archive).await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_user_data_rejects_modified_mac() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        let mut archive = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await.unwrap();
+        *archive.last_mut().unwrap() ^= 0x01;
+
+        // Act
+        let result = sdk.import_user_data(Some(&PIN), &WALLET_PASSWORD, &archive).await;
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(crate::Error::Type(TypeError::InvalidPinOrPassword))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_user_data_rejects_wrong_pin() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, Some(example_user()));
+        let wrong_pin = EncryptionPin::try_from_string("123457").unwrap();
+
+        // Act
+        let result = sdk.export_user_data(&wrong_pin, &WALLET_PASSWORD).await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::repo_init_error(crate::Error::UserRepoNotInitialized)]
+    #[case::user_init_error(crate::Error::UserNotInitialized)]
+    #[tokio::test]
+    async fn test_export_user_data_errors(#[case] expected: crate::Error) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        handle_error_test_cases(&expected, &mut sdk, 0, 0).await;
+
+        // Act
+        let response = sdk.export_user_data(&PIN, &WALLET_PASSWORD).await;
+
+        // Assert
+        assert_eq!(response.unwrap_err().to_string(), expected.to_string());
+    }
+}
@@ -1,5 +1,8 @@
 //! Main SDK module.
 
+/// Access token module.
+pub mod access_token;
+
 /// Config module.
 pub mod config;
 
@@ -23,6 +26,24 @@ pub mod exchange;
 /// Share module.
 pub mod share;
 
+/// Address book module.
+pub mod address_book;
+
+/// Export module.
+pub mod export;
+
+/// Events module.
+pub mod events;
+
+/// Offline cache module.
+pub mod offline_cache;
+
+/// Portfolio module.
+pub mod portfolio;
+
+/// Custom networks module.
+pub mod custom_networks;
+
 /// Testing utils in sdk core
 #[cfg(test)]
 pub(crate) mod core_testing_utils;
@@ -31,15 +52,20 @@ use crate::backend::dlt::get_networks;
 use crate::build;
 use crate::error::Result;
 use crate::types::newtypes::{AccessToken, EncryptionPin};
-use crate::types::users::ActiveUser;
+use crate::types::users::{ActiveUser, Cached, PinPolicy};
 use crate::user::UserRepo;
 use crate::wallet_manager::WalletBorrow;
 use api_types::api::networks::ApiNetwork;
+use chrono::{DateTime, TimeDelta, Utc};
 pub use config::Config;
-use log::debug;
+use etopay_wallet::MnemonicDerivationOption;
+use log::{debug, info, warn};
 
 pub(crate) type UserRepoT = Box<dyn UserRepo + Send + Sync + 'static>;
 
+/// How long the networks fetched from the backend are used before they are fetched again
+pub const NETWORKS_TTL: TimeDelta = TimeDelta::minutes(15);
+
 /// Struct representing the SDK and its core components including configuration, user management, and storage options.
 pub struct Sdk {
     /// Contains SDK configuration.
@@ -48,12 +74,33 @@ pub struct Sdk {
     active_user: Option<ActiveUser>,
     /// Contains the access token for various SDK operations.
     access_token: Option<AccessToken>,
+    /// Refreshes the access token with the provider set by the host app
+    access_token_refresher: Option<std::sync::Arc<access_token::AccessTokenRefresher>>,
+    /// Receives the telemetry events of this instance
+    telemetry: crate::telemetry::TelemetryHook,
     /// Contains the user repository for storing and loading different users.
     repo: Option<UserRepoT>,
     /// The currently active network
     active_network: Option<ApiNetwork>,
     /// Available networks
     networks: Vec<ApiNetwork>,
+    /// When the available networks were fetched from the backend, or [`None`] if they were set directly
+    networks_fetched_at: Option<DateTime<Utc>>,
+    /// Networks added locally by the active user
+    custom_networks: Vec<ApiNetwork>,
+    /// Optional store for the local share and the encrypted password, e.g. a platform keystore
+    secret_store: Option<std::sync::Arc<dyn crate::secret_store::SecretStore>>,
+    /// Policy to protect the pin against brute-force attacks
+    pin_policy: PinPolicy,
+    /// Sends events to the subscribers
+    events: events::Events,
+    /// Exchange rates fetched from the backend
+    exchange_rates: portfolio::ExchangeRateCache,
+    /// The offline cache of the active user, read from the user repository on first use
+    offline_cache: Option<offline_cache::OfflineCacheState>,
+    /// The network selection of the active user that [`Sdk::init_user`] could not restore without
+    /// the backend, restored when the active network is used
+    pending_network_restore: Option<user::PendingNetworkRestore>,
 }
 
 impl Drop for Sdk {
@@ -70,9 +117,19 @@ impl Default for Sdk {
             config: None,
             active_user: None,
             access_token: None,
+            access_token_refresher: None,
+            telemetry: Default::default(),
             repo: None,
             active_network: None,
             networks: vec![],
+            networks_fetched_at: None,
+            custom_networks: vec![],
+            secret_store: None,
+            pin_policy: PinPolicy::default(),
+            events: events::Events::default(),
+            exchange_rates: portfolio::ExchangeRateCache::default(),
+            offline_cache: None,
+            pending_network_restore: None,
         }
     }
 }
@@ -80,6 +137,7 @@ impl Default for Sdk {
 impl Sdk {
     /// Initialize an SDK instance from a config
     #[allow(clippy::result_large_err)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub fn new(config: Config) -> Result<Self> {
         debug!("Configuration: {:?}", config);
         let mut s = Self::default();
@@ -88,41 +146,259 @@ impl Sdk {
     }
 
     /// Set network
+    ///
+    /// If a user is initialized, the selection is stored for the user and restored by
+    /// [`Sdk::init_user`], and the derivation options set for this network are applied. If none are
+    /// set for this network and no network was selected before, the current derivation options are
+    /// kept and set for this network, unless they are the default ones. Emits [`events::SdkEvent::NetworkChanged`] if another network
+    /// was selected before.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_network(&mut self, network_key: String) -> Result<()> {
         debug!("Selected network_key: {:?}", network_key.clone());
 
-        let Some(network) = self.networks.iter().find(|network| network.key == network_key) else {
+        let Some(network) = self.find_network(&network_key).cloned() else {
             return Err(crate::Error::NetworkUnavailable(network_key));
         };
 
         debug!("Selected Network: {:?}", network);
-        self.active_network = Some(network.clone());
+        if let Some(active_user) = &mut self.active_user {
+            let Some(repo) = &mut self.repo else {
+                return Err(crate::Error::UserRepoNotInitialized);
+            };
+
+            let mut network_derivation_options = active_user.network_derivation_options.clone();
+            let options = match network_derivation_options.get(&network_key) {
+                Some(options) => *options,
+                None if self.active_network.is_none()
+                    && active_user.mnemonic_derivation_options != MnemonicDerivationOption::default() =>
+                {
+                    network_derivation_options.insert(network_key.clone(), active_user.mnemonic_derivation_options);
+                    repo.set_network_derivation_options(&active_user.username, network_derivation_options.clone())?;
+                    active_user.mnemonic_derivation_options
+                }
+                None => Default::default(),
+            };
+            repo.set_selected_network(&active_user.username, Some(network_key.clone()))?;
+
+            // only change the state in memory once the selection is stored
+            active_user.network_derivation_options = network_derivation_options;
+            active_user.mnemonic_derivation_options = options;
+        }
+
+        let changed = self
+            .active_network
+            .as_ref()
+            .is_none_or(|active| active.key != network_key);
+        self.active_network = Some(network);
+        self.pending_network_restore = None;
+        if changed {
+            self.events.emit(events::SdkEvent::NetworkChanged { network_key });
+        }
 
         Ok(())
     }
 
+    /// The active network. If [`Sdk::init_user`] could not restore the network of the active user
+    /// without the backend, it is restored first.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingNetwork`] - If no network is selected.
+    async fn active_network(&mut self) -> Result<ApiNetwork> {
+        self.restore_pending_network().await;
+        self.active_network.clone().ok_or(crate::Error::MissingNetwork)
+    }
+
     /// Set networks
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub fn set_networks(&mut self, networks: Vec<ApiNetwork>) {
         self.networks = networks;
     }
 
+    /// Set the [`PinPolicy`] used to protect the pin against brute-force attacks
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn set_pin_policy(&mut self, policy: PinPolicy) {
+        if let Some(active_user) = &mut self.active_user {
+            active_user.wallet_manager.set_pin_policy(policy.clone());
+        }
+        self.pin_policy = policy;
+    }
+
     /// Get networks
+    ///
+    /// Returns the networks served by the backend, followed by the custom networks of the active user.
+    /// The networks are fetched from the backend if none are available yet or if the fetched list is
+    /// older than [`NETWORKS_TTL`]. If fetching fails, the previous list or the list of the offline
+    /// cache is returned and [`events::SdkEvent::CachedValueReturned`] is emitted. Use
+    /// [`Sdk::get_networks_cached`] to tell such a list apart from a fetched one.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_networks(&mut self) -> Result<Vec<ApiNetwork>> {
+        self.get_networks_cached().await.map(|networks| networks.value)
+    }
+
+    /// Get networks together with the time they were fetched
+    ///
+    /// Like [`Sdk::get_networks`], but if fetching fails and the previous list or the list of the
+    /// offline cache is returned, it is marked as [`Cached::stale`] and keeps the time it was fetched.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_networks_cached(&mut self) -> Result<Cached<Vec<ApiNetwork>>> {
+        let mut stale = false;
         if self.networks.is_empty() {
             if self.access_token.is_none() {
                 return Err(crate::Error::MissingAccessToken);
             }
+            if let Err(e) = self.refresh_networks().await {
+                let Some(cached) = self.offline_cache().and_then(|cache| cache.networks.clone()) else {
+                    return Err(e);
+                };
+                warn!("Could not fetch the networks: {e}");
+                self.networks = cached.value;
+                self.networks_fetched_at = Some(cached.updated_at);
+                self.cached_value_returned(events::CachedValue::Networks, cached.updated_at);
+                stale = true;
+            }
+        } else if self.networks_expired() {
+            if let Err(e) = self.refresh_networks().await {
+                warn!("Could not refresh the networks, using the previous list: {e}");
+                if let Some(fetched_at) = self.networks_fetched_at {
+                    self.cached_value_returned(events::CachedValue::Networks, fetched_at);
+                }
+                stale = true;
+            }
+        }
 
-            let result = self.get_networks_backend().await;
-            match result {
-                Ok(n) => {
-                    self.networks = n.clone();
+        Ok(Cached {
+            value: self.available_networks().cloned().collect(),
+            updated_at: self.networks_fetched_at.unwrap_or_else(Utc::now),
+            stale,
+        })
+    }
+
+    /// Fetch the networks from the backend and replace the available networks
+    ///
+    /// The fetched list is stored in the offline cache of the active user. If the node urls or the
+    /// protocol of a network changed, its wallet kept in the unlocked session is dropped so that it is
+    /// created again with the new definition, and the selected network is updated. If a network was
+    /// removed, its wallet is dropped as well and it is no longer selected.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    /// * [`crate::Error::MissingAccessToken`] - If there is no access token.
+    /// * [`crate::Error::BackendApi`] - If the networks cannot be fetched.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn refresh_networks(&mut self) -> Result<()> {
+        info!("Refreshing networks");
+        let networks = self.get_networks_backend().await?;
+        self.apply_networks(Cached::fresh(networks));
+        Ok(())
+    }
+
+    /// Whether the networks were fetched from the backend more than [`NETWORKS_TTL`] ago
+    fn networks_expired(&self) -> bool {
+        self.networks_fetched_at
+            .is_some_and(|fetched_at| Utc::now() - fetched_at >= NETWORKS_TTL)
+    }
+
+    /// Replace the available networks with networks fetched from the backend
+    fn apply_networks(&mut self, networks: Cached<Vec<ApiNetwork>>) {
+        let changed = networks
+            .value
+            .iter()
+            .filter(|network| {
+                self.networks.iter().any(|previous| {
+                    previous.key == network.key
+                        && (previous.node_urls != network.node_urls || previous.protocol != network.protocol)
+                })
+            })
+            .collect::<Vec<_>>();
+        for network in &changed {
+            info!("Network {} changed, dropping its unlocked wallet", network.key);
+            if let Some(active_user) = &mut self.active_user {
+                active_user.wallet_manager.forget_unlocked_wallet(&network.key);
+            }
+            if self
+                .active_network
+                .as_ref()
+                .is_some_and(|active| active.key == network.key)
+            {
+                self.active_network = Some((*network).clone());
+            }
+        }
+
+        let removed = self
+            .networks
+            .iter()
+            .filter(|previous| !networks.value.iter().any(|network| network.key == previous.key))
+            .map(|previous| previous.key.clone())
+            .collect::<Vec<_>>();
+        for network_key in &removed {
+            info!("Network {network_key} was removed, dropping its unlocked wallet");
+            if let Some(active_user) = &mut self.active_user {
+                active_user.wallet_manager.forget_unlocked_wallet(network_key);
+            }
+        }
+
+        self.networks = networks.value.clone();
+        self.networks_fetched_at = Some(networks.updated_at);
+
+        // a custom network with the same key takes the place of a removed network
+        if let Some(active) = self
+            .active_network
+            .as_ref()
+            .filter(|active| removed.contains(&active.key))
+        {
+            let replacement = self.find_network(&active.key).cloned();
+            if replacement.is_none() {
+                warn!(
+                    "The selected network {} was removed, clearing the selection",
+                    active.key
+                );
+                if let (Some(repo), Some(active_user)) = (&mut self.repo, &self.active_user) {
+                    if let Err(e) = repo.set_selected_network(&active_user.username, None) {
+                        warn!("Could not clear the selected network: {e}");
+                    }
                 }
-                Err(e) => Err(e)?,
             }
+            self.active_network = replacement;
         }
+        self.update_offline_cache(|cache| {
+            let changed = cache
+                .networks
+                .as_ref()
+                .is_none_or(|previous| previous.value != networks.value);
+            cache.networks = Some(networks);
+            changed
+        });
+    }
 
-        Ok(self.networks.clone())
+    /// The networks served by the backend followed by the custom networks of the active user.
+    /// Custom networks with the key of a backend network are skipped, and all of them if they are
+    /// disabled in the config.
+    fn available_networks(&self) -> impl Iterator<Item = &ApiNetwork> {
+        let custom_networks_enabled = self.feature_enabled(|features| features.custom_networks);
+        self.networks.iter().chain(
+            self.custom_networks
+                .iter()
+                .filter(move |_| custom_networks_enabled)
+                .filter(|custom| !self.networks.iter().any(|network| network.key == custom.key)),
+        )
+    }
+
+    /// Whether the network is a custom network of the user, rather than a network of the backend
+    fn is_custom_network(&self, network_key: &str) -> bool {
+        !self.networks.iter().any(|network| network.key == network_key)
+            && self.custom_networks.iter().any(|network| network.key == network_key)
+    }
+
+    /// Whether an optional feature is enabled in the config. Features are enabled if no config is set.
+    fn feature_enabled(&self, feature: impl FnOnce(&config::FeatureToggles) -> bool) -> bool {
+        self.config.as_ref().is_none_or(|config| feature(&config.features))
+    }
+
+    /// Find an available network by its key
+    fn find_network(&self, network_key: &str) -> Option<&ApiNetwork> {
+        self.available_networks().find(|network| network.key == network_key)
     }
 
     /// Get supported networks from backend
@@ -132,9 +408,7 @@ impl Sdk {
             .access_token
             .as_ref()
             .ok_or(crate::error::Error::MissingAccessToken)?;
-        let backend_networks = get_networks(config, access_token).await?;
-
-        Ok(backend_networks)
+        fetch_networks(config, access_token).await
     }
 
     /// Tries to get the wallet of the currently active user. Or returns an error if no user is
@@ -144,13 +418,19 @@ impl Sdk {
     /// to call functions that take `&mut self` as receiver while holding on to the
     /// [`WalletBorrow`]
     async fn try_get_active_user_wallet(&mut self, pin: &EncryptionPin) -> Result<WalletBorrow<'_>> {
+        if self.repo.is_none() {
+            return Err(crate::Error::UserRepoNotInitialized);
+        }
+        if self.active_user.is_none() {
+            return Err(crate::Error::UserNotInitialized);
+        }
+        let network = self.active_network().await?;
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
         };
         let Some(active_user) = &mut self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
-        let network = self.active_network.as_ref().ok_or(crate::Error::MissingNetwork)?;
         let config = self.config.as_mut().ok_or(crate::Error::MissingConfig)?;
         let wallet = active_user
             .wallet_manager
@@ -158,7 +438,7 @@ impl Sdk {
                 config,
                 &self.access_token,
                 repo,
-                network,
+                &network,
                 pin,
                 &active_user.mnemonic_derivation_options,
             )
@@ -166,21 +446,96 @@ impl Sdk {
         Ok(wallet)
     }
 
+    /// Tries to get the wallets of the currently active user for several networks, with the
+    /// derivation options stored for each network. Returns one result per network, in the same
+    /// order and without repeated networks, or an error if a network is not available or the
+    /// mnemonic cannot be reconstructed.
+    async fn try_get_active_user_wallets(
+        &mut self,
+        pin: &EncryptionPin,
+        network_keys: &[String],
+    ) -> Result<Vec<(String, Result<WalletBorrow<'_>>)>> {
+        let mut unique_keys = Vec::with_capacity(network_keys.len());
+        for network_key in network_keys {
+            if !unique_keys.contains(network_key) {
+                unique_keys.push(network_key.clone());
+            }
+        }
+
+        // look up the networks before borrowing the user mutably
+        let mut networks = Vec::with_capacity(unique_keys.len());
+        for network_key in &unique_keys {
+            let network = self
+                .find_network(network_key)
+                .cloned()
+                .ok_or_else(|| crate::Error::NetworkUnavailable(network_key.clone()))?;
+            networks.push(network);
+        }
+        let Some(repo) = &mut self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        let Some(active_user) = &mut self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+        let mut networks_with_options = Vec::with_capacity(networks.len());
+        for network in networks {
+            let options = if self
+                .active_network
+                .as_ref()
+                .is_some_and(|active| active.key == network.key)
+            {
+                active_user.mnemonic_derivation_options
+            } else {
+                active_user
+                    .network_derivation_options
+                    .get(&network.key)
+                    .copied()
+                    .unwrap_or_default()
+            };
+            networks_with_options.push((network, options));
+        }
+        let config = self.config.as_mut().ok_or(crate::Error::MissingConfig)?;
+        let wallets = active_user
+            .wallet_manager
+            .try_get_many(config, &self.access_token, repo, &networks_with_options, pin)
+            .await?;
+        let mut results = Vec::with_capacity(wallets.len());
+        for (network_key, wallet) in unique_keys.into_iter().zip(wallets) {
+            results.push((network_key, wallet.map_err(crate::Error::from)));
+        }
+        Ok(results)
+    }
+
     /// A function that returns a multi-line String containing:
     /// * Branch name       (e.g. main)
     /// * Commit hash       (e.g. 92cedead),
     /// * Build time        (e.g. 2024-10-29 12:10:09 +00:00),
     /// * Rust version      (e.g. 1.80.1 (3f5fd8dd4 2024-08-06))
     /// * Toolchain channel (e.g. stable-x86_64-unknown-linux-gnu)
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub fn get_build_info() -> String {
         build::CLAP_LONG_VERSION.to_string()
     }
 }
 
+/// Fetch the networks from the backend, with the node urls configured in [`Config::rpc`]
+async fn fetch_networks(config: &Config, access_token: &AccessToken) -> Result<Vec<ApiNetwork>> {
+    let mut backend_networks = get_networks(config, access_token).await?;
+
+    for network in &mut backend_networks {
+        if let Some(rpc) = config.rpc_override(&network.key) {
+            debug!("Using the configured node urls for network {}", network.key);
+            network.node_urls = rpc.node_urls.clone();
+        }
+    }
+
+    Ok(backend_networks)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::core::core_testing_utils::handle_error_test_cases;
-    use crate::testing_utils::IOTA_NETWORK_KEY;
+    use crate::testing_utils::{ETH_NETWORK_KEY, IOTA_NETWORK_KEY};
     use crate::{
         core::Sdk,
         error::Result,
@@ -190,7 +545,7 @@ mod tests {
     };
     use api_types::api::dlt::ApiGetNetworksResponse;
     use api_types::api::networks::ApiNetwork;
-    use etopay_wallet::MockWalletUser;
+    use etopay_wallet::{MnemonicDerivationOption, MockWalletUser};
     use rstest::rstest;
 
     use crate::{
@@ -212,12 +567,18 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                sdk.repo = Some(Box::new(MockUserRepo::new()));
+                let mut mock_user_repo = MockUserRepo::new();
+                mock_user_repo
+                    .expect_set_selected_network()
+                    .once()
+                    .returning(|_, _| Ok(()));
+                sdk.repo = Some(Box::new(mock_user_repo));
                 let mock_wallet_manager = example_wallet_borrow();
                 sdk.active_user = Some(crate::types::users::ActiveUser {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.set_networks(example_api_networks());
                 sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
@@ -265,6 +626,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -305,4 +667,210 @@ mod tests {
             m.assert();
         }
     }
+
+    #[tokio::test]
+    async fn test_get_networks_backend_applies_rpc_override() {
+        // Arrange
+        let (mut srv, mut config, _cleanup) = set_config().await;
+        config.rpc.insert(
+            IOTA_NETWORK_KEY.to_string(),
+            super::config::RpcOverride {
+                node_urls: vec![String::from("https://node.example.com")],
+            },
+        );
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.access_token = Some(TOKEN.clone());
+        let body = serde_json::to_string(&ApiGetNetworksResponse {
+            networks: example_api_networks(),
+        })
+        .unwrap();
+        let mock = srv
+            .mock("GET", "/api/config/networks")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let networks = sdk.get_networks_backend().await.unwrap();
+
+        // Assert
+        mock.assert();
+        for network in networks {
+            if network.key == IOTA_NETWORK_KEY {
+                assert_eq!(network.node_urls, vec![String::from("https://node.example.com")]);
+            } else {
+                assert_ne!(network.node_urls, vec![String::from("https://node.example.com")]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_networks_refreshes_expired_networks() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.access_token = Some(TOKEN.clone());
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_forget_unlocked_wallet()
+            .withf(|network_key| network_key == IOTA_NETWORK_KEY)
+            .once()
+            .return_const(());
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.networks = example_api_networks();
+        sdk.networks_fetched_at = Some(chrono::Utc::now() - super::NETWORKS_TTL - chrono::TimeDelta::minutes(1));
+        sdk.active_network = sdk.find_network(IOTA_NETWORK_KEY).cloned();
+
+        let mut updated = example_api_networks();
+        let iota = updated.iter_mut().find(|n| n.key == IOTA_NETWORK_KEY).unwrap();
+        iota.node_urls = vec![String::from("https://api.devnet.iota.cafe")];
+        let body = serde_json::to_string(&ApiGetNetworksResponse {
+            networks: updated.clone(),
+        })
+        .unwrap();
+        let mock_server = srv
+            .mock("GET", "/api/config/networks")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let networks = sdk.get_networks().await.unwrap();
+        let cached = sdk.get_networks().await.unwrap();
+
+        // Assert
+        mock_server.assert();
+        assert_eq!(networks, updated);
+        assert_eq!(cached, updated);
+        assert_eq!(
+            sdk.active_network.as_ref().unwrap().node_urls,
+            vec![String::from("https://api.devnet.iota.cafe")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_networks_clears_removed_active_network() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.access_token = Some(TOKEN.clone());
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_set_selected_network()
+            .withf(|username, network_key| username == USERNAME && network_key.is_none())
+            .once()
+            .returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_get()
+            .returning(|_| Ok(crate::testing_utils::example_user_entity(USERNAME)));
+        mock_user_repo.expect_set_offline_cache().returning(|_, _| Ok(()));
+        sdk.repo = Some(Box::new(mock_user_repo));
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_forget_unlocked_wallet()
+            .withf(|network_key| network_key == IOTA_NETWORK_KEY)
+            .once()
+            .return_const(());
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.networks = example_api_networks();
+        sdk.active_network = sdk.find_network(IOTA_NETWORK_KEY).cloned();
+
+        let mut updated = example_api_networks();
+        updated.retain(|n| n.key != IOTA_NETWORK_KEY);
+        let body = serde_json::to_string(&ApiGetNetworksResponse {
+            networks: updated.clone(),
+        })
+        .unwrap();
+        let mock_server = srv
+            .mock("GET", "/api/config/networks")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        sdk.refresh_networks().await.unwrap();
+
+        // Assert
+        mock_server.assert();
+        assert_eq!(sdk.networks, updated);
+        assert!(sdk.active_network.is_none());
+        assert!(matches!(sdk.active_network().await, Err(crate::Error::MissingNetwork)));
+    }
+
+    #[tokio::test]
+    async fn test_get_networks_keeps_expired_networks_if_refresh_fails() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.access_token = Some(TOKEN.clone());
+        sdk.networks = example_api_networks();
+        sdk.networks_fetched_at = Some(chrono::Utc::now() - super::NETWORKS_TTL);
+
+        let mock_server = srv
+            .mock("GET", "/api/config/networks")
+            .with_status(500)
+            .expect(1)
+            .create();
+
+        // Act
+        let networks = sdk.get_networks().await;
+
+        // Assert
+        mock_server.assert();
+        assert_eq!(networks.unwrap(), example_api_networks());
+    }
+
+    #[tokio::test]
+    async fn test_set_network_keeps_state_if_selection_is_not_stored() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo.expect_set_selected_network().times(1).returning(|_, _| {
+            Err(crate::user::error::UserKvStorageError::Storage(String::from(
+                "disk full",
+            )))
+        });
+        sdk.repo = Some(Box::new(mock_user_repo));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: MnemonicDerivationOption { account: 1, index: 2 },
+            network_derivation_options: [(
+                IOTA_NETWORK_KEY.to_string(),
+                MnemonicDerivationOption { account: 1, index: 2 },
+            )]
+            .into(),
+        });
+        sdk.set_networks(example_api_networks());
+        sdk.active_network = sdk.find_network(IOTA_NETWORK_KEY).cloned();
+
+        // Act
+        let response = sdk.set_network(ETH_NETWORK_KEY.to_string()).await;
+
+        // Assert
+        assert!(response.is_err());
+        assert_eq!(sdk.active_network.as_ref().unwrap().key, IOTA_NETWORK_KEY);
+        assert_eq!(
+            sdk.active_user.as_ref().unwrap().mnemonic_derivation_options,
+            MnemonicDerivationOption { account: 1, index: 2 }
+        );
+    }
 }
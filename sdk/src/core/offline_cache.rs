@@ -0,0 +1,429 @@
+//! This module provides the offline cache of the active user. Every balance, list of networks and
+//! exchange rate fetched by [`Sdk::get_balance`], [`Sdk::get_networks`] and
+//! [`Sdk::get_exchange_rate`] is remembered together with the time it was fetched. If fetching
+//! fails, e.g. because the device is offline, these methods return the last known value instead
+//! and emit [`SdkEvent::CachedValueReturned`]. [`Sdk::get_balance_cached`],
+//! [`Sdk::get_networks_cached`] and [`Sdk::get_exchange_rate_cached`] return the value as a
+//! [`Cached`], so that callers can tell the last known value apart from a fresh one.
+//!
+//! The cache is kept in memory and only written to the user repository if a value changed or it
+//! was last written more than [`PERSIST_INTERVAL`] ago, so that fetching the same values again
+//! does not rewrite the user entity every time.
+
+use super::Sdk;
+use super::events::{CachedValue, SdkEvent};
+use crate::types::users::{Cached, CachedBalance, OfflineCache};
+use crate::user::UserRepo;
+use chrono::{DateTime, TimeDelta, Utc};
+use etopay_wallet::MnemonicDerivationOption;
+use etopay_wallet::types::CryptoAmount;
+use log::warn;
+use rust_decimal::Decimal;
+
+/// How long unchanged values are only updated in memory before they are written to the user
+/// repository again
+pub const PERSIST_INTERVAL: TimeDelta = TimeDelta::minutes(15);
+
+/// The offline cache of the active user, kept in memory
+#[derive(Debug)]
+pub(crate) struct OfflineCacheState {
+    /// The user the cache belongs to
+    username: String,
+    /// The last known values
+    cache: OfflineCache,
+    /// When the cache was last written to or read from the user repository
+    persisted_at: DateTime<Utc>,
+}
+
+/// Inserts the balance, replacing the cached balance of the same network and derivation path.
+/// Returns whether the balance changed.
+fn upsert_balance(balances: &mut Vec<CachedBalance>, balance: CachedBalance) -> bool {
+    match balances
+        .iter_mut()
+        .find(|b| b.network_key == balance.network_key && b.derivation_options == balance.derivation_options)
+    {
+        Some(existing) => {
+            let changed = existing.balance.value != balance.balance.value;
+            *existing = balance;
+            changed
+        }
+        None => {
+            balances.push(balance);
+            true
+        }
+    }
+}
+
+/// Get the offline cache of the user, reading it from the repository if the cache in memory
+/// belongs to another user.
+fn load<'a>(
+    state: &'a mut Option<OfflineCacheState>,
+    repo: &(dyn UserRepo + Send + Sync),
+    username: &str,
+) -> Option<&'a mut OfflineCacheState> {
+    if state.as_ref().is_some_and(|state| state.username == username) {
+        return state.as_mut();
+    }
+    match repo.get(username) {
+        Ok(user) => Some(state.insert(OfflineCacheState {
+            username: username.to_string(),
+            cache: user.offline_cache,
+            persisted_at: Utc::now(),
+        })),
+        Err(e) => {
+            warn!("Could not read the offline cache: {e}");
+            None
+        }
+    }
+}
+
+impl Sdk {
+    /// Update the offline cache of the active user. The update returns whether a value changed,
+    /// and the cache is only written to the repository if it did or the last write is older than
+    /// [`PERSIST_INTERVAL`]. Failures are only logged, since updating the cache must not fail the
+    /// call that fetched the value.
+    pub(super) fn update_offline_cache(&mut self, update: impl FnOnce(&mut OfflineCache) -> bool) {
+        if !self.feature_enabled(|features| features.offline_cache) {
+            return;
+        }
+        let (Some(repo), Some(active_user)) = (&mut self.repo, &self.active_user) else {
+            return;
+        };
+        let Some(state) = load(&mut self.offline_cache, repo.as_ref(), &active_user.username) else {
+            return;
+        };
+
+        let changed = update(&mut state.cache);
+        let now = Utc::now();
+        if !changed && now - state.persisted_at < PERSIST_INTERVAL {
+            return;
+        }
+        match repo.set_offline_cache(&active_user.username, state.cache.clone()) {
+            Ok(()) => state.persisted_at = now,
+            Err(e) => warn!("Could not update the offline cache: {e}"),
+        }
+    }
+
+    /// Get the offline cache of the active user, if it is enabled and there is one.
+    pub(super) fn offline_cache(&mut self) -> Option<&OfflineCache> {
+        if !self.feature_enabled(|features| features.offline_cache) {
+            return None;
+        }
+        let (Some(repo), Some(active_user)) = (&self.repo, &self.active_user) else {
+            return None;
+        };
+        load(&mut self.offline_cache, repo.as_ref(), &active_user.username).map(|state| &state.cache)
+    }
+
+    /// Remember the balance fetched for a network and derivation path.
+    pub(super) fn cache_balance(
+        &mut self,
+        network_key: String,
+        derivation_options: MnemonicDerivationOption,
+        balance: CryptoAmount,
+    ) {
+        let entry = CachedBalance {
+            network_key,
+            derivation_options,
+            balance: Cached::fresh(balance),
+        };
+        self.update_offline_cache(|cache| upsert_balance(&mut cache.balances, entry));
+    }
+
+    /// Get the last known balance of a network and derivation path, marked as stale, and emit
+    /// [`SdkEvent::CachedValueReturned`] if there is one.
+    pub(super) fn cached_balance(
+        &mut self,
+        network_key: &str,
+        derivation_options: MnemonicDerivationOption,
+    ) -> Option<Cached<CryptoAmount>> {
+        let cached = self.offline_cache()?.balances.iter().find_map(|b| {
+            (b.network_key == network_key && b.derivation_options == derivation_options).then(|| b.balance.clone())
+        })?;
+        self.cached_value_returned(CachedValue::Balance, cached.updated_at);
+        Some(cached.into_stale())
+    }
+
+    /// Remember the exchange rate fetched for a network.
+    pub(super) fn cache_exchange_rate(&mut self, network_key: String, rate: Decimal) {
+        self.update_offline_cache(|cache| {
+            let previous = cache.exchange_rates.insert(network_key, Cached::fresh(rate));
+            previous.is_none_or(|previous| previous.value != rate)
+        });
+    }
+
+    /// Get the last known exchange rate of a network, marked as stale, and emit
+    /// [`SdkEvent::CachedValueReturned`] if there is one.
+    pub(super) fn cached_exchange_rate(&mut self, network_key: &str) -> Option<Cached<Decimal>> {
+        let cached = self.offline_cache()?.exchange_rates.get(network_key).cloned()?;
+        self.cached_value_returned(CachedValue::ExchangeRate, cached.updated_at);
+        Some(cached.into_stale())
+    }
+
+    /// Forget the cached balances of the active user, e.g. when its wallet is deleted.
+    pub(super) fn clear_cached_balances(&mut self) {
+        self.update_offline_cache(|cache| {
+            let changed = !cache.balances.is_empty();
+            cache.balances.clear();
+            changed
+        });
+    }
+
+    /// Log and emit that a value was returned from the offline cache.
+    pub(super) fn cached_value_returned(&self, value: CachedValue, updated_at: DateTime<Utc>) {
+        warn!("Returning the cached {value:?} fetched at {updated_at}");
+        self.events.emit(SdkEvent::CachedValueReturned { value, updated_at });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing_utils::{
+        AUTH_PROVIDER, ENCRYPTED_WALLET_PASSWORD, HEADER_X_APP_NAME, IOTA_NETWORK_KEY, PIN, TOKEN, USERNAME,
+        example_api_networks, example_exchange_rate_response, example_user_entity, set_config,
+    };
+    use crate::types::users::{ActiveUser, UserEntity};
+    use crate::user::{memory_storage::MemoryUserStorage, repository::UserRepoImpl};
+    use crate::wallet_manager::{MockWalletManager, WalletBorrow};
+    use api_types::api::dlt::ApiGetNetworksResponse;
+    use etopay_wallet::MockWalletUser;
+    use rust_decimal_macros::dec;
+
+    fn sdk_with_user(sdk: &mut Sdk, wallet_manager: MockWalletManager) {
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&UserEntity {
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            ..example_user_entity(USERNAME)
+        })
+        .unwrap();
+        sdk.repo = Some(Box::new(repo));
+        sdk.active_user = Some(ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.access_token = Some(TOKEN.clone());
+    }
+
+    /// A wallet manager that returns a wallet with the balance, or fails if there is none
+    #[allow(clippy::result_large_err)]
+    fn wallet_manager(balance: Option<CryptoAmount>) -> MockWalletManager {
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_try_get().returning(move |_, _, _, _, _, _| {
+            let Some(balance) = balance else {
+                return Err(crate::WalletError::MissingAccessToken);
+            };
+            let mut mock_wallet_user = MockWalletUser::new();
+            mock_wallet_user.expect_get_balance().returning(move || Ok(balance));
+            Ok(WalletBorrow::from(mock_wallet_user))
+        });
+        mock_wallet_manager
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_returns_cached_balance() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        // SAFETY: we know that this value is not negative
+        let balance = unsafe { CryptoAmount::new_unchecked(dec!(25.0)) };
+        sdk_with_user(&mut sdk, wallet_manager(Some(balance)));
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        let mut events = sdk.subscribe_events();
+
+        // Act
+        let fresh = sdk.get_balance_cached(&PIN).await.unwrap();
+        sdk.active_user.as_mut().unwrap().wallet_manager = Box::new(wallet_manager(None));
+        let cached = sdk.get_balance_cached(&PIN).await.unwrap();
+
+        // Assert
+        assert_eq!(fresh.value, balance);
+        assert!(!fresh.stale);
+        assert_eq!(cached.value, balance);
+        assert!(cached.stale);
+        assert!(cached.updated_at <= fresh.updated_at);
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            SdkEvent::CachedValueReturned {
+                value: CachedValue::Balance,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_does_not_return_balance_of_other_derivation_path() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        // SAFETY: we know that this value is not negative
+        let balance = unsafe { CryptoAmount::new_unchecked(dec!(25.0)) };
+        sdk_with_user(&mut sdk, wallet_manager(Some(balance)));
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        sdk.get_balance(&PIN).await.unwrap();
+
+        let active_user = sdk.active_user.as_mut().unwrap();
+        active_user.wallet_manager = Box::new(wallet_manager(None));
+        active_user.mnemonic_derivation_options = MnemonicDerivationOption { account: 0, index: 1 };
+
+        // Act
+        let response = sdk.get_balance(&PIN).await;
+
+        // Assert
+        assert_eq!(
+            response.unwrap_err().to_string(),
+            crate::Error::Wallet(crate::WalletError::MissingAccessToken).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_without_cache_error() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, wallet_manager(None));
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+
+        // Act
+        let response = sdk.get_balance(&PIN).await;
+
+        // Assert
+        assert_eq!(
+            response.unwrap_err().to_string(),
+            crate::Error::Wallet(crate::WalletError::MissingAccessToken).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_with_cache_wrong_pin_error() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        // SAFETY: we know that this value is not negative
+        let balance = unsafe { CryptoAmount::new_unchecked(dec!(25.0)) };
+        sdk_with_user(&mut sdk, wallet_manager(Some(balance)));
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        sdk.get_balance(&PIN).await.unwrap();
+
+        // Act
+        let wrong_pin = crate::types::newtypes::EncryptionPin::try_from_string("654321").unwrap();
+        let response = sdk.get_balance(&wrong_pin).await;
+
+        // Assert
+        response.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_values_are_not_written_again() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        // SAFETY: we know that this value is not negative
+        let balance = unsafe { CryptoAmount::new_unchecked(dec!(25.0)) };
+        sdk_with_user(&mut sdk, wallet_manager(Some(balance)));
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        sdk.get_balance(&PIN).await.unwrap();
+        let stored = sdk.repo.as_ref().unwrap().get(USERNAME).unwrap().offline_cache;
+
+        // Act
+        sdk.get_balance(&PIN).await.unwrap();
+
+        // Assert
+        let in_memory = sdk.offline_cache().unwrap().balances[0].balance.updated_at;
+        assert!(in_memory > stored.balances[0].balance.updated_at);
+        let stored_again = sdk.repo.as_ref().unwrap().get(USERNAME).unwrap().offline_cache;
+        assert_eq!(stored_again, stored);
+    }
+
+    #[tokio::test]
+    async fn test_get_networks_returns_cached_networks() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, MockWalletManager::new());
+
+        let body = serde_json::to_string(&ApiGetNetworksResponse {
+            networks: example_api_networks(),
+        })
+        .unwrap();
+        let networks_mock = srv
+            .mock("GET", "/api/config/networks")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let fresh = sdk.get_networks_cached().await.unwrap();
+        networks_mock.assert_async().await;
+        drop(networks_mock);
+        drop(srv);
+        sdk.set_networks(Vec::new());
+        let mut events = sdk.subscribe_events();
+        let cached = sdk.get_networks_cached().await.unwrap();
+
+        // Assert
+        assert_eq!(fresh.value, example_api_networks());
+        assert!(!fresh.stale);
+        assert_eq!(cached.value, example_api_networks());
+        assert!(cached.stale);
+        assert_eq!(cached.updated_at, fresh.updated_at);
+        assert_eq!(sdk.networks, example_api_networks());
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            SdkEvent::CachedValueReturned {
+                value: CachedValue::Networks,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_exchange_rate_returns_cached_exchange_rate() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk_with_user(&mut sdk, MockWalletManager::new());
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+
+        let body = serde_json::to_string(&example_exchange_rate_response()).unwrap();
+        let exchange_rate_mock = srv
+            .mock("GET", "/api/courses?network_key=IOTA")
+            .match_header(HEADER_X_APP_NAME, AUTH_PROVIDER)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let fresh = sdk.get_exchange_rate_cached().await.unwrap();
+        exchange_rate_mock.assert_async().await;
+        drop(exchange_rate_mock);
+        drop(srv);
+        let mut events = sdk.subscribe_events();
+        let cached = sdk.get_exchange_rate_cached().await.unwrap();
+
+        // Assert
+        assert_eq!(cached.value, fresh.value);
+        assert!(!fresh.stale);
+        assert!(cached.stale);
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            SdkEvent::CachedValueReturned {
+                value: CachedValue::ExchangeRate,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,506 @@
+//! This module values crypto amounts in a fiat currency: the balances of the user, the total
+//! portfolio and past transactions at the exchange rate of the day they happened. Exchange rates
+//! are cached, current ones for [`EXCHANGE_RATE_TTL`] and historic ones for the lifetime of the SDK.
+
+use super::Sdk;
+use crate::backend::dlt::{get_exchange_rate_history, get_exchange_rate_in};
+use crate::error::Result;
+use crate::types::currencies::FiatCurrency;
+use crate::types::networks::PerNetwork;
+use crate::types::newtypes::EncryptionPin;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use etopay_wallet::types::{CryptoAmount, WalletTransaction};
+use log::{debug, info};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How long a current exchange rate is reused before it is fetched again
+pub const EXCHANGE_RATE_TTL: TimeDelta = TimeDelta::seconds(60);
+
+/// A crypto amount valued in a fiat currency
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FiatValue {
+    /// The valued crypto amount
+    pub amount: CryptoAmount,
+    /// The fiat currency of the value
+    pub currency: FiatCurrency,
+    /// The price of one unit of the crypto currency used for the valuation
+    pub exchange_rate: Decimal,
+    /// The value of the amount in the fiat currency
+    pub value: Decimal,
+}
+
+impl FiatValue {
+    fn new(amount: CryptoAmount, currency: FiatCurrency, exchange_rate: Decimal) -> Self {
+        Self {
+            amount,
+            currency,
+            exchange_rate,
+            value: amount.inner() * exchange_rate,
+        }
+    }
+}
+
+/// The value of the balances of a user on all networks
+#[derive(Debug)]
+pub struct PortfolioValue {
+    /// The fiat currency of the values
+    pub currency: FiatCurrency,
+    /// The sum of the values of all networks that could be valued
+    pub total: Decimal,
+    /// The value per network. Networks where the balance or the exchange rate could not be
+    /// fetched are reported as failures and are not part of the total.
+    pub networks: PerNetwork<FiatValue>,
+}
+
+/// Exchange rates fetched from the backend
+#[derive(Debug, Default)]
+pub(crate) struct ExchangeRateCache {
+    /// The current exchange rate per network key and currency, with the time it was fetched
+    current: HashMap<(String, FiatCurrency), (Decimal, DateTime<Utc>)>,
+    /// The exchange rate per network key, currency and day. Only completed days are cached.
+    historic: HashMap<(String, FiatCurrency, NaiveDate), Decimal>,
+}
+
+/// Parses the date of a course, which is either a timestamp or a day.
+fn parse_course_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc())
+        })
+}
+
+impl Sdk {
+    /// Get the current exchange rate of a network in a fiat currency
+    ///
+    /// The exchange rate is cached for [`EXCHANGE_RATE_TTL`].
+    ///
+    /// # Arguments
+    ///
+    /// * `network_key` - The key of the network.
+    /// * `currency` - The fiat currency.
+    ///
+    /// # Returns
+    ///
+    /// The price of one unit of the network's crypto currency in the fiat currency.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingAccessToken`] - If there is no access token.
+    /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    /// * [`crate::Error::BackendApi`] - If the exchange rate cannot be fetched.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_exchange_rate_in(&mut self, network_key: &str, currency: FiatCurrency) -> Result<Decimal> {
+        let key = (network_key.to_string(), currency);
+        if let Some((rate, fetched_at)) = self.exchange_rates.current.get(&key) {
+            if Utc::now() - *fetched_at < EXCHANGE_RATE_TTL {
+                debug!("Using cached exchange rate of {network_key} in {currency}");
+                return Ok(*rate);
+            }
+        }
+
+        info!("Fetching exchange rate of {network_key} in {currency}");
+        let access_token = self
+            .access_token
+            .as_ref()
+            .ok_or(crate::error::Error::MissingAccessToken)?;
+        let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
+        let rate = get_exchange_rate_in(config, access_token, network_key.to_string(), currency).await?;
+        self.exchange_rates.current.insert(key, (rate, Utc::now()));
+        Ok(rate)
+    }
+
+    /// Get the exchange rate of a network in a fiat currency at a point in time
+    ///
+    /// Uses the latest exchange rate of the day that is not later than `date`, or the first one
+    /// of the day if all are later. Exchange rates of completed days are cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_key` - The key of the network.
+    /// * `currency` - The fiat currency.
+    /// * `date` - The point in time.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingAccessToken`] - If there is no access token.
+    /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    /// * [`crate::Error::BackendApi`] - If the exchange rates cannot be fetched.
+    /// * [`crate::Error::ExchangeRateUnavailable`] - If the backend has no exchange rate for the day.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_historic_exchange_rate(
+        &mut self,
+        network_key: &str,
+        currency: FiatCurrency,
+        date: DateTime<Utc>,
+    ) -> Result<Decimal> {
+        let day = date.date_naive();
+        let key = (network_key.to_string(), currency, day);
+        if let Some(rate) = self.exchange_rates.historic.get(&key) {
+            debug!("Using cached exchange rate of {network_key} in {currency} on {day}");
+            return Ok(*rate);
+        }
+
+        info!("Fetching exchange rate of {network_key} in {currency} on {day}");
+        let access_token = self
+            .access_token
+            .as_ref()
+            .ok_or(crate::error::Error::MissingAccessToken)?;
+        let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
+        let courses =
+            get_exchange_rate_history(config, access_token, network_key.to_string(), currency, day, day).await?;
+
+        let mut courses = courses
+            .into_iter()
+            .filter_map(|course| parse_course_date(&course.date).map(|date| (date, course.course.0)))
+            .collect::<Vec<_>>();
+        courses.sort_by_key(|(date, _)| *date);
+        let rate = courses
+            .iter()
+            .rev()
+            .find(|(course_date, _)| *course_date <= date)
+            .or(courses.first())
+            .map(|(_, rate)| *rate)
+            .ok_or_else(|| crate::Error::ExchangeRateUnavailable(format!("{network_key} in {currency} on {day}")))?;
+
+        if day < Utc::now().date_naive() {
+            self.exchange_rates.historic.insert(key, rate);
+        }
+        Ok(rate)
+    }
+
+    /// Value a wallet transaction at the exchange rate of the time it happened
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction` - The wallet transaction.
+    /// * `currency` - The fiat currency.
+    ///
+    /// # Errors
+    ///
+    /// * Any error of [`Sdk::get_historic_exchange_rate`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_transaction_fiat_value(
+        &mut self,
+        transaction: &WalletTransaction,
+        currency: FiatCurrency,
+    ) -> Result<FiatValue> {
+        let rate = self
+            .get_historic_exchange_rate(&transaction.network_key, currency, transaction.date)
+            .await?;
+        Ok(FiatValue::new(transaction.amount, currency, rate))
+    }
+
+    /// Get the value of the balance on the selected network in a fiat currency
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN of the user.
+    /// * `currency` - The fiat currency.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingNetwork`] - If no network is selected.
+    /// * Any error of [`Sdk::get_balance`] or [`Sdk::get_exchange_rate_in`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_fiat_balance(&mut self, pin: &EncryptionPin, currency: FiatCurrency) -> Result<FiatValue> {
+        let network_key = self.active_network().await?.key;
+        let balance = self.get_balance(pin).await?;
+        let rate = self.get_exchange_rate_in(&network_key, currency).await?;
+        Ok(FiatValue::new(balance, currency, rate))
+    }
+
+    /// Get the value of the balances on all networks in a fiat currency
+    ///
+    /// The balances are fetched with [`Sdk::get_all_balances`]. A network where the balance or
+    /// the exchange rate cannot be fetched is reported as failure and not added to the total.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN of the user.
+    /// * `currency` - The fiat currency.
+    ///
+    /// # Errors
+    ///
+    /// * Any error of [`Sdk::get_all_balances`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_portfolio_value(&mut self, pin: &EncryptionPin, currency: FiatCurrency) -> Result<PortfolioValue> {
+        info!("Valuing the portfolio in {currency}");
+        let balances = self.get_all_balances(pin).await?;
+
+        let mut networks = PerNetwork {
+            values: Default::default(),
+            failures: balances.failures,
+        };
+        for (network_key, balance) in balances.values {
+            match self.get_exchange_rate_in(&network_key, currency).await {
+                Ok(rate) => {
+                    networks
+                        .values
+                        .insert(network_key, FiatValue::new(balance, currency, rate));
+                }
+                Err(e) => {
+                    networks.failures.insert(network_key, e);
+                }
+            }
+        }
+        let total = networks.values.values().map(|value| value.value).sum();
+
+        Ok(PortfolioValue {
+            currency,
+            total,
+            networks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::core_testing_utils::handle_error_test_cases;
+    use crate::testing_utils::{
+        ETH_NETWORK_KEY, IOTA_NETWORK_KEY, PIN, TOKEN, USERNAME, example_api_networks, example_exchange_rate_response,
+        example_get_user, set_config,
+    };
+    use crate::types::users::{ActiveUser, KycType};
+    use crate::wallet::wallet_manager::{MockWalletManager, WalletBorrow};
+    use api_types::api::dlt::{Course, GetCourseHistoryResponse};
+    use api_types::api::viviswap::detail::SwapPaymentDetailKey;
+    use chrono::TimeZone;
+    use etopay_wallet::MockWalletUser;
+    use mockito::Matcher;
+    use rstest::rstest;
+    use rust_decimal_macros::dec;
+
+    fn course(course: Decimal, date: &str) -> Course {
+        Course {
+            course: course.into(),
+            date: date.to_string(),
+            currency: None,
+        }
+    }
+
+    #[rstest]
+    #[case::success(Ok(example_exchange_rate_response().course.course.0))]
+    #[case::missing_access_token(Err(crate::Error::MissingAccessToken))]
+    #[case::missing_config(Err(crate::Error::MissingConfig))]
+    #[tokio::test]
+    async fn test_get_exchange_rate_in(#[case] expected: Result<Decimal>) {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut mock_server = None;
+
+        match &expected {
+            Ok(_) => {
+                sdk.refresh_access_token(Some(TOKEN.clone())).await.unwrap();
+                let mut response = example_exchange_rate_response();
+                response.course.currency = Some(String::from("USD"));
+                let body = serde_json::to_string(&response).unwrap();
+                mock_server = Some(
+                    srv.mock("GET", "/api/courses")
+                        .match_query(Matcher::Exact("network_key=IOTA&currency=USD".to_string()))
+                        .with_status(200)
+                        .with_body(&body)
+                        .expect(1)
+                        .create(),
+                );
+            }
+            Err(error) => {
+                handle_error_test_cases(error, &mut sdk, 0, 0).await;
+            }
+        }
+
+        // Act
+        let first = sdk.get_exchange_rate_in(IOTA_NETWORK_KEY, FiatCurrency::Usd).await;
+
+        // Assert
+        match expected {
+            Ok(rate) => {
+                assert_eq!(first.unwrap(), rate);
+                // the second call is answered from the cache
+                let second = sdk.get_exchange_rate_in(IOTA_NETWORK_KEY, FiatCurrency::Usd).await;
+                assert_eq!(second.unwrap(), rate);
+            }
+            Err(ref err) => {
+                assert_eq!(first.unwrap_err().to_string(), err.to_string());
+            }
+        }
+        if let Some(m) = mock_server {
+            m.assert();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_fiat_value_uses_rate_of_the_transaction_time() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.refresh_access_token(Some(TOKEN.clone())).await.unwrap();
+
+        let body = serde_json::to_string(&GetCourseHistoryResponse {
+            courses: vec![
+                course(dec!(0.30), "2024-05-01T18:00:00Z"),
+                course(dec!(0.10), "2024-05-01T06:00:00Z"),
+                course(dec!(0.20), "2024-05-01T12:00:00Z"),
+            ],
+        })
+        .unwrap();
+        let mock_server = srv
+            .mock("GET", "/api/courses/history")
+            .match_query(Matcher::Exact(
+                "network_key=IOTA&from_date=2024-05-01&to_date=2024-05-01&currency=EUR".to_string(),
+            ))
+            .with_status(200)
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        let transaction = WalletTransaction {
+            date: Utc.with_ymd_and_hms(2024, 5, 1, 13, 30, 0).unwrap(),
+            block_number_hash: None,
+            transaction_hash: String::from("hash"),
+            sender: String::new(),
+            receiver: String::new(),
+            // SAFETY: we know that this value is not negative
+            amount: unsafe { CryptoAmount::new_unchecked(dec!(10)) },
+            network_key: IOTA_NETWORK_KEY.to_string(),
+            status: etopay_wallet::types::WalletTxStatus::Confirmed,
+            explorer_url: None,
+            gas_fee: None,
+            is_sender: false,
+        };
+
+        // Act
+        let first = sdk
+            .get_transaction_fiat_value(&transaction, FiatCurrency::Eur)
+            .await
+            .unwrap();
+        let second = sdk
+            .get_transaction_fiat_value(&transaction, FiatCurrency::Eur)
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(first.exchange_rate, dec!(0.20));
+        assert_eq!(first.value, dec!(2.00));
+        assert_eq!(first, second);
+        mock_server.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_historic_exchange_rate_unavailable() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.refresh_access_token(Some(TOKEN.clone())).await.unwrap();
+
+        let body = serde_json::to_string(&GetCourseHistoryResponse { courses: vec![] }).unwrap();
+        let mock_server = srv
+            .mock("GET", "/api/courses/history")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        let response = sdk
+            .get_historic_exchange_rate(
+                IOTA_NETWORK_KEY,
+                FiatCurrency::Chf,
+                Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap(),
+            )
+            .await;
+
+        // Assert
+        assert!(matches!(response, Err(crate::Error::ExchangeRateUnavailable(_))));
+        mock_server.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_portfolio_value_reports_failures_separately() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.refresh_access_token(Some(TOKEN.clone())).await.unwrap();
+        sdk.repo = Some(Box::new(example_get_user(
+            SwapPaymentDetailKey::Iota,
+            false,
+            1,
+            KycType::Undefined,
+        )));
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_try_get_many()
+            .once()
+            .returning(|_, _, _, networks, _| {
+                Ok(networks
+                    .iter()
+                    .map(|_| {
+                        let mut mock_wallet_user = MockWalletUser::new();
+                        mock_wallet_user
+                            .expect_get_balance()
+                            .once()
+                            // SAFETY: we know that this value is not negative
+                            .returning(|| Ok(unsafe { CryptoAmount::new_unchecked(dec!(4)) }));
+                        Ok(WalletBorrow::from(mock_wallet_user))
+                    })
+                    .collect())
+            });
+        sdk.active_user = Some(ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.set_networks(
+            example_api_networks()
+                .into_iter()
+                .filter(|network| network.key == IOTA_NETWORK_KEY || network.key == ETH_NETWORK_KEY)
+                .collect(),
+        );
+
+        let body = serde_json::to_string(&example_exchange_rate_response()).unwrap();
+        let iota_rate = srv
+            .mock("GET", "/api/courses")
+            .match_query(Matcher::Exact("network_key=IOTA&currency=EUR".to_string()))
+            .with_status(200)
+            .with_body(&body)
+            .expect(1)
+            .create();
+        let eth_rate = srv
+            .mock("GET", "/api/courses")
+            .match_query(Matcher::Exact("network_key=ETH&currency=EUR".to_string()))
+            .with_status(500)
+            .expect(1)
+            .create();
+
+        // Act
+        let response = sdk.get_portfolio_value(&PIN, FiatCurrency::Eur).await.unwrap();
+
+        // Assert
+        assert_eq!(response.currency, FiatCurrency::Eur);
+        assert_eq!(response.total, dec!(4.0));
+        assert_eq!(response.networks.values.len(), 1);
+        assert!(response.networks.values.contains_key(IOTA_NETWORK_KEY));
+        assert!(response.networks.failures.contains_key(ETH_NETWORK_KEY));
+        iota_rate.assert();
+        eth_rate.assert();
+    }
+
+    #[rstest]
+    #[case("2024-05-01T12:00:00Z", Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()))]
+    #[case("2024-05-01", Some(Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()))]
+    #[case("2222-22-22", None)]
+    fn test_parse_course_date(#[case] date: &str, #[case] expected: Option<DateTime<Utc>>) {
+        assert_eq!(parse_course_date(date), expected);
+    }
+}
@@ -19,6 +19,7 @@ impl Sdk {
     /// - [`crate::Error::UserRepoNotInitialized`] if the repository fails to initialize.
     /// - [`crate::Error::UserNotInitialized)`] if the user fails to initialize.
     /// - [`crate::Error::UserAlreadyKycVerified`] if the user is already KYC verified.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn start_kyc_verification_for_postident(&mut self) -> Result<NewCaseIdResponse> {
         info!("Starting PostIdent Verification for user");
         let Some(repo) = &mut self.repo else {
@@ -42,7 +43,9 @@ impl Sdk {
             .as_ref()
             .ok_or(crate::error::Error::MissingAccessToken)?;
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
-        let response = get_new_case_id(config, access_token).await?;
+        let response = self
+            .telemetry
+            .kyc_step("postident", "start", get_new_case_id(config, access_token).await)?;
 
         Ok(response)
     }
@@ -56,6 +59,7 @@ impl Sdk {
     /// # Errors
     ///
     /// - [`crate::Error::UserNotInitialized)`] if the user fails to initialize.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_kyc_details_for_postident(&self) -> Result<CaseDetailsResponse> {
         info!("Fetching KYC details for postident");
         let Some(_user) = &self.active_user else {
@@ -83,6 +87,7 @@ impl Sdk {
     /// # Returns
     ///
     /// Returns `Ok(())` if the case status is updated successfully.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn update_kyc_status_for_postident(&self, case_id: &str) -> Result<()> {
         info!("updating KYC details for postident");
         let Some(_user) = &self.active_user else {
@@ -95,7 +100,11 @@ impl Sdk {
             .ok_or(crate::error::Error::MissingAccessToken)?;
 
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
-        update_case_status(config, access_token, case_id).await?;
+        self.telemetry.kyc_step(
+            "postident",
+            "update_status",
+            update_case_status(config, access_token, case_id).await,
+        )?;
 
         Ok(())
     }
@@ -146,6 +155,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -178,6 +188,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = None;
             }
@@ -196,6 +207,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
                 sdk.config = None;
@@ -207,6 +219,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -250,6 +263,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -308,6 +322,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -15,6 +15,7 @@ impl Sdk {
     /// # Error
     ///
     /// Returns error if the user is not initialized.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_recovery_share(&self) -> Result<Option<Share>> {
         info!("Getting recovery share");
         let Some(active_user) = &self.active_user else {
@@ -32,6 +33,7 @@ impl Sdk {
     /// # Error
     ///
     /// Returns error if the user is not initialized.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_recovery_share(&mut self, share: Share) -> Result<()> {
         info!("Setting recovery share");
         let Some(active_user) = &mut self.active_user else {
@@ -80,6 +82,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -122,6 +125,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
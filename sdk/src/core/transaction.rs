@@ -34,9 +34,11 @@ impl Sdk {
     ///
     /// # Errors
     ///
-    /// Returns an error if the user or wallet is not initialized, or if there is an error creating the transaction.
+    /// Returns an error if the user or wallet is not initialized, if the selected network is a custom network,
+    /// or if there is an error creating the transaction.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_purchase_request(
-        &self,
+        &mut self,
         receiver: &str,
         amount: CryptoAmount,
         product_hash: &str,
@@ -47,13 +49,16 @@ impl Sdk {
         let Some(_active_user) = &self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
+        let network = self.active_network().await?;
 
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
         let access_token = self
             .access_token
             .as_ref()
             .ok_or(crate::error::Error::MissingAccessToken)?;
-        let network = self.active_network.clone().ok_or(crate::Error::MissingNetwork)?;
+        if self.is_custom_network(&network.key) {
+            return Err(crate::Error::PurchaseNotSupported(network.key));
+        }
 
         let purchase_model = PurchaseModel::try_from(purchase_type.to_string()).map_err(crate::error::Error::Parse)?;
 
@@ -87,6 +92,7 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an error if the user or wallet is not initialized, or if there is an error getting the transaction details.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_purchase_details(&self, purchase_id: &str) -> Result<PurchaseDetails> {
         info!("Getting purchase details with id {purchase_id}");
         let Some(_active_user) = &self.active_user else {
@@ -125,9 +131,11 @@ impl Sdk {
     ///
     /// Returns an error if the user or wallet is not initialized, if there is an error verifying the PIN,
     /// if there is an error getting the transaction details, or if there is an error committing the transaction.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn confirm_purchase_request(&mut self, pin: &EncryptionPin, purchase_id: &str) -> Result<()> {
         info!("Confirming purchase request with id {purchase_id}");
         self.verify_pin(pin).await?;
+        let current_network = self.active_network().await?;
 
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -152,8 +160,6 @@ impl Sdk {
             )))?;
         }
 
-        let current_network = self.active_network.as_ref().ok_or(crate::Error::MissingNetwork)?;
-
         // for now we check that the correct network_key is configured, in the future we might just
         // instantiate the correct wallet instead of throwing an error
         let network = &tx_details.network;
@@ -188,10 +194,10 @@ impl Sdk {
 
         // Store tx details for the new transaction
         let newly_created_transaction = wallet.get_wallet_tx(&tx_id).await?;
-        let mut user = repo.get(&active_user.username)?;
-        user.wallet_transactions_versioned
-            .push(VersionedWalletTransaction::V2(newly_created_transaction));
-        let _ = repo.set_wallet_transactions(&active_user.username, user.wallet_transactions_versioned);
+        let _ = repo.insert_wallet_transaction(
+            &active_user.username,
+            VersionedWalletTransaction::V2(newly_created_transaction),
+        );
 
         debug!("Transaction id on network: {tx_id}");
 
@@ -217,6 +223,7 @@ impl Sdk {
     ///
     /// Returns an error if the user or wallet is not initialized, if there is an error verifying the PIN,
     /// or if there is an error sending the amount.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn send_amount(
         &mut self,
         pin: &EncryptionPin,
@@ -226,6 +233,7 @@ impl Sdk {
     ) -> Result<String> {
         info!("Sending amount {amount:?} to receiver {address}");
         self.verify_pin(pin).await?;
+        let network = self.active_network().await?;
 
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -235,7 +243,6 @@ impl Sdk {
         };
 
         let config = self.config.as_mut().ok_or(crate::Error::MissingConfig)?;
-        let network = self.active_network.as_ref().ok_or(crate::Error::MissingNetwork)?;
 
         let wallet = active_user
             .wallet_manager
@@ -243,7 +250,7 @@ impl Sdk {
                 config,
                 &self.access_token,
                 repo,
-                network,
+                &network,
                 pin,
                 &active_user.mnemonic_derivation_options,
             )
@@ -267,13 +274,10 @@ impl Sdk {
                 // store the created transaction in the repo
                 let newly_created_transaction = wallet.get_wallet_tx(&tx_id).await?;
 
-                let user = repo.get(&active_user.username)?;
-
-                let mut wallet_transactions = user.wallet_transactions_versioned;
-
-                wallet_transactions.push(VersionedWalletTransaction::V2(newly_created_transaction));
-
-                let _ = repo.set_wallet_transactions(&active_user.username, wallet_transactions);
+                let _ = repo.insert_wallet_transaction(
+                    &active_user.username,
+                    VersionedWalletTransaction::V2(newly_created_transaction),
+                );
                 tx_id
             }
         };
@@ -298,6 +302,7 @@ impl Sdk {
     ///
     /// Returns an error if the user or wallet is not initialized, if there is an error verifying the PIN,
     /// or if there is an error estimating the gas.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn estimate_gas(
         &mut self,
         pin: &EncryptionPin,
@@ -307,6 +312,7 @@ impl Sdk {
     ) -> Result<GasCostEstimation> {
         info!("Estimating gas for sending amount {amount:?} to receiver {address}");
         self.verify_pin(pin).await?;
+        let network = self.active_network().await?;
 
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -316,7 +322,6 @@ impl Sdk {
         };
 
         let config = self.config.as_mut().ok_or(crate::Error::MissingConfig)?;
-        let network = self.active_network.as_ref().ok_or(crate::Error::MissingNetwork)?;
 
         let wallet = active_user
             .wallet_manager
@@ -324,7 +329,7 @@ impl Sdk {
                 config,
                 &self.access_token,
                 repo,
-                network,
+                &network,
                 pin,
                 &active_user.mnemonic_derivation_options,
             )
@@ -356,6 +361,7 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an error if there is a problem getting the list of transactions.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_tx_list(&self, start: u32, limit: u32) -> Result<TxList> {
         info!("Getting list of transactions");
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
@@ -477,6 +483,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -519,6 +526,36 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_create_purchase_request_on_custom_network() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.set_networks(example_api_networks());
+        let mut custom_network = example_api_network(ETH_NETWORK_KEY.to_string());
+        custom_network.key = String::from("LOCAL_ANVIL");
+        sdk.custom_networks = vec![custom_network];
+        sdk.set_network(String::from("LOCAL_ANVIL")).await.unwrap();
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.access_token = Some(TOKEN.clone());
+        let mock_server = srv.mock("POST", "/api/transactions/create").expect(0).create();
+
+        // Act
+        let amount = CryptoAmount::try_from(dec!(10.0)).unwrap();
+        let response = sdk
+            .create_purchase_request("receiver", amount, "hash", "app_data", "CLIK")
+            .await;
+
+        // Assert
+        assert!(matches!(response, Err(crate::Error::PurchaseNotSupported(key)) if key == "LOCAL_ANVIL"));
+        mock_server.assert();
+    }
+
     #[rstest]
     #[case::success(Ok(()))]
     #[case::repo_init_error(Err(crate::Error::UserRepoNotInitialized))]
@@ -541,9 +578,9 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 2, KycType::Undefined);
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
                 mock_user_repo
-                    .expect_set_wallet_transactions()
+                    .expect_insert_wallet_transaction()
                     .once()
                     .returning(|_, _| Ok(()));
 
@@ -579,6 +616,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
 
                 sdk.access_token = Some(TOKEN.clone());
@@ -620,6 +658,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
 
                 sdk.access_token = Some(TOKEN.clone());
@@ -683,6 +722,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -745,9 +785,9 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 2, KycType::Undefined);
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
                 mock_user_repo
-                    .expect_set_wallet_transactions()
+                    .expect_insert_wallet_transaction()
                     .once()
                     .returning(|_, _| Ok(()));
                 sdk.repo = Some(Box::new(mock_user_repo));
@@ -770,6 +810,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -800,7 +841,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_send_amount_with_eth_should_trigger_a_call_to_set_wallet_transaction() {
+    async fn test_send_amount_with_eth_should_trigger_a_call_to_insert_wallet_transaction() {
         // Arrange
         let (_srv, config, _cleanup) = set_config().await;
         let mut sdk = Sdk::new(config).unwrap();
@@ -822,14 +863,14 @@ mod tests {
             is_sender: false,
         });
 
-        let wallet_transactions = vec![wallet_transaction.clone()].to_owned();
+        let expected_transaction = wallet_transaction.clone();
 
-        let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Eth, false, 2, KycType::Undefined);
+        let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Eth, false, 1, KycType::Undefined);
         mock_user_repo
-            .expect_set_wallet_transactions()
+            .expect_insert_wallet_transaction()
             .times(1)
-            .returning(move |_, expected_wallet_transactions| {
-                assert_eq!(wallet_transactions, expected_wallet_transactions);
+            .returning(move |_, transaction| {
+                assert_eq!(expected_transaction, transaction);
                 Ok(())
             });
         sdk.repo = Some(Box::new(mock_user_repo));
@@ -855,6 +896,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(mock_wallet_manager),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         });
 
         // Act
@@ -894,6 +936,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -9,8 +9,16 @@ use crate::types::newtypes::AccessToken;
 use crate::types::newtypes::EncryptionPin;
 use crate::types::newtypes::EncryptionSalt;
 use crate::types::users::{ActiveUser, KycType, UserEntity};
+use api_types::api::networks::ApiNetwork;
 use log::{debug, info, warn};
 
+/// The network selection of the active user that still has to be restored with the backend
+#[derive(Debug)]
+pub(crate) struct PendingNetworkRestore {
+    /// The network stored for the user, or [`None`] to use the preferred network of the backend
+    selected_network: Option<String>,
+}
+
 impl Sdk {
     /// Get user entity
     ///
@@ -21,6 +29,7 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an `Error` if there is an issue initializing the user or accessing the repository.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_user(&self) -> Result<UserEntity> {
         debug!("Getting the user");
         let Some(repo) = &self.repo else {
@@ -49,6 +58,7 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an `Error` if there is an issue validating the configuration, initializing the repository, or creating the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_new_user(&mut self, username: &str) -> Result<()> {
         info!("Creating a new user");
         let Some(repo) = &mut self.repo else {
@@ -67,6 +77,14 @@ impl Sdk {
             local_share: None,
             wallet_transactions: Vec::new(),
             wallet_transactions_versioned: Vec::new(),
+            address_labels: Vec::new(),
+            address_book: Vec::new(),
+            pin_attempts: Default::default(),
+            schema_version: crate::user::migration::CURRENT_SCHEMA_VERSION,
+            selected_network: None,
+            network_derivation_options: Default::default(),
+            custom_networks: Vec::new(),
+            offline_cache: Default::default(),
         };
 
         repo.create(&user)?;
@@ -87,8 +105,11 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an `Error` if there is an issue verifying the PIN, initializing the repository, initiliazing the user, deleting the user, or deleting the wallet.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn delete_user(&mut self, pin: Option<&EncryptionPin>) -> Result<()> {
-        let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
+        if self.config.is_none() {
+            return Err(crate::Error::MissingConfig);
+        }
 
         let user_entity = self.get_user().await?;
 
@@ -99,6 +120,8 @@ impl Sdk {
             info!("Pin verified");
         }
 
+        let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
+
         let Some(active_user) = &mut self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
@@ -120,6 +143,7 @@ impl Sdk {
 
         // Delete the user in the repo
         repo.delete(username)?;
+        self.offline_cache = None;
 
         // take the wallet out of the Option and try to delete it, it will then be dropped
         if let Err(e) = active_user
@@ -146,13 +170,17 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an `Error` if there is an issue validating the configuration, initializing the repository, or checking the KYC status.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn init_user(&mut self, username: &str) -> Result<()> {
         info!("Initializing user {username}");
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
         };
         let user = repo.get(username)?;
-        let active_user = ActiveUser::from(user);
+        let selected_network = user.selected_network.clone();
+        let custom_networks = user.custom_networks.clone();
+        let mut active_user = ActiveUser::from(user);
+        active_user.wallet_manager.set_pin_policy(self.pin_policy.clone());
 
         if let Some(access_token) = &self.access_token {
             let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
@@ -161,10 +189,157 @@ impl Sdk {
         }
 
         self.active_user = Some(active_user);
+        self.offline_cache = None;
+        self.custom_networks = custom_networks;
+
+        // the network is restored from the local data if possible, anything that needs the
+        // backend is deferred until the active network is used
+        self.pending_network_restore = None;
+        if !self.restore_network_locally(selected_network.as_deref()) {
+            self.pending_network_restore = Some(PendingNetworkRestore { selected_network });
+        }
 
         Ok(())
     }
 
+    /// Select the network stored for the active user from the available networks, or from the
+    /// networks of the offline cache if none are available. A cached list older than
+    /// [`super::NETWORKS_TTL`] is fetched again by the next call to [`Sdk::get_networks`]. Returns
+    /// whether the network was selected.
+    fn restore_network_locally(&mut self, selected_network: Option<&str>) -> bool {
+        let Some(network_key) = selected_network else {
+            return false;
+        };
+        if self.networks.is_empty() {
+            if let Some(cached) = self.offline_cache().and_then(|cache| cache.networks.clone()) {
+                debug!("Using the cached networks");
+                self.networks = cached.value;
+                self.networks_fetched_at = Some(cached.updated_at);
+            }
+        }
+        let Some(network) = self.find_network(network_key).cloned() else {
+            return false;
+        };
+        self.select_restored_network(network);
+        true
+    }
+
+    /// Restore the network selection deferred by [`Sdk::init_user`]: the network stored for the
+    /// active user, or the preferred network of the user in the backend if none is stored. The
+    /// networks are fetched if none are available. Failures are only logged, since the network can
+    /// still be selected manually.
+    pub(super) async fn restore_pending_network(&mut self) {
+        let Some(PendingNetworkRestore { selected_network }) = self.pending_network_restore.take() else {
+            return;
+        };
+        if self.active_user.is_none() || self.active_network.is_some() {
+            return;
+        }
+
+        let network_key = match selected_network {
+            Some(network_key) => Some(network_key),
+            None if self.access_token.is_some() => self.get_preferred_network().await.unwrap_or_else(|e| {
+                warn!("Could not get the preferred network: {e}");
+                None
+            }),
+            None => None,
+        };
+        let Some(network_key) = network_key else {
+            return;
+        };
+
+        if self.networks.is_empty() && self.access_token.is_some() {
+            if let Err(e) = self.get_networks().await {
+                warn!("Could not fetch the networks: {e}");
+            }
+        }
+        let Some(network) = self.find_network(&network_key).cloned() else {
+            warn!("Network {network_key} is not available, no network selected");
+            return;
+        };
+        self.select_restored_network(network);
+    }
+
+    /// Select a restored network and apply the derivation options set for it.
+    fn select_restored_network(&mut self, network: ApiNetwork) {
+        debug!("Restoring network {}", network.key);
+        if let Some(active_user) = &mut self.active_user {
+            active_user.mnemonic_derivation_options = active_user
+                .network_derivation_options
+                .get(&network.key)
+                .copied()
+                .unwrap_or_default();
+        }
+        self.events.emit(super::events::SdkEvent::NetworkChanged {
+            network_key: network.key.clone(),
+        });
+        self.active_network = Some(network);
+    }
+
+    /// List the usernames of all users stored on this device
+    ///
+    /// # Returns
+    ///
+    /// Returns the usernames sorted alphabetically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the repository is not initialized or the users cannot be read.
+    #[allow(clippy::result_large_err)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn list_local_users(&self) -> Result<Vec<String>> {
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+        Ok(repo.list_users()?)
+    }
+
+    /// Switch to another user stored on this device
+    ///
+    /// The currently active user is torn down first: the wallet is locked, a recovery share that
+    /// has not been downloaded yet is zeroized and the access token is cleared, since it belongs
+    /// to the previous user. Set the access token of the new user with [`Sdk::refresh_access_token`].
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user to switch to.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the user is switched successfully, or an `Error` if an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the repository is not initialized or the user does not exist. The
+    /// current user stays active in this case.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn switch_user(&mut self, username: &str) -> Result<()> {
+        info!("Switching to user {username}");
+        let Some(repo) = &self.repo else {
+            return Err(crate::Error::UserRepoNotInitialized);
+        };
+
+        // make sure the user exists before tearing down the current one
+        repo.get(username)?;
+
+        self.teardown_active_user();
+        self.init_user(username).await
+    }
+
+    /// Tear down the active user, if any, and clear the secrets held in memory
+    fn teardown_active_user(&mut self) {
+        if let Some(mut active_user) = self.active_user.take() {
+            debug!("Tearing down user {}", active_user.username);
+            active_user.wallet_manager.lock();
+            // the share data is zeroized when it is dropped
+            active_user.wallet_manager.set_recovery_share(None);
+        }
+        self.access_token = None;
+        self.active_network = None;
+        self.custom_networks.clear();
+        self.events.reset();
+    }
+
     /// Refresh access token
     ///
     /// # Arguments
@@ -178,7 +353,9 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an `Error` if there is an issue validating the configuration.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn refresh_access_token(&mut self, access_token: Option<AccessToken>) -> Result<()> {
+        self.reset_access_token_refresher();
         self.access_token = access_token;
 
         Ok(())
@@ -197,6 +374,7 @@ impl Sdk {
     /// # Errors
     ///
     /// Returns an `Error` if there is an issue validating the configuration, initializing the repository, or checking the KYC status.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn is_kyc_status_verified(&mut self, username: &str) -> Result<bool> {
         info!("Checking KYC status of user {username}");
         let Some(repo) = &mut self.repo else {
@@ -220,6 +398,7 @@ impl Sdk {
     }
 
     /// Set the user preferred network
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_preferred_network(&mut self, network_key: Option<String>) -> Result<()> {
         let Some(_user) = &self.active_user else {
             return Err(crate::Error::UserNotInitialized);
@@ -234,6 +413,7 @@ impl Sdk {
     }
 
     /// Get the user preferred network
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_preferred_network(&self) -> Result<Option<String>> {
         let Some(_user) = &self.active_user else {
             return Err(crate::Error::UserNotInitialized);
@@ -253,11 +433,16 @@ mod tests {
     use super::*;
     use crate::core::core_testing_utils::handle_error_test_cases;
     use crate::testing_utils::{
-        AUTH_PROVIDER, HEADER_X_APP_NAME, IOTA_NETWORK_KEY, TOKEN, USERNAME, example_get_user, set_config,
+        AUTH_PROVIDER, ETH_NETWORK_KEY, HEADER_X_APP_NAME, IOTA_NETWORK_KEY, TOKEN, USERNAME, example_api_networks,
+        example_get_user, set_config,
     };
+    use crate::types::users::Cached;
+    use crate::user::{memory_storage::MemoryUserStorage, repository::UserRepoImpl};
     use crate::{core::Sdk, user::MockUserRepo, wallet_manager::MockWalletManager};
+    use api_types::api::dlt::ApiGetNetworksResponse;
     use api_types::api::kyc::KycStatusResponse;
     use api_types::api::viviswap::detail::SwapPaymentDetailKey;
+    use etopay_wallet::MnemonicDerivationOption;
     use rstest::rstest;
 
     #[rstest]
@@ -280,6 +465,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -321,6 +507,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -367,6 +554,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_user),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
 
                 sdk.access_token = Some(TOKEN.clone());
@@ -411,6 +599,215 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case::success(Ok(vec![String::from("alice"), String::from(USERNAME)]))]
+    #[case::repo_init_error(Err(crate::Error::UserRepoNotInitialized))]
+    #[tokio::test]
+    async fn test_list_local_users(#[case] expected: Result<Vec<String>>) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+
+        match &expected {
+            Ok(users) => {
+                let mut mock_user_repo = MockUserRepo::new();
+                let users = users.clone();
+                mock_user_repo
+                    .expect_list_users()
+                    .times(1)
+                    .returning(move || Ok(users.clone()));
+                sdk.repo = Some(Box::new(mock_user_repo));
+            }
+            Err(error) => {
+                handle_error_test_cases(error, &mut sdk, 0, 0).await;
+            }
+        }
+
+        // Act
+        let response = sdk.list_local_users();
+
+        // Assert
+        match expected {
+            Ok(users) => assert_eq!(response.unwrap(), users),
+            Err(ref expected_err) => {
+                assert_eq!(response.err().unwrap().to_string(), expected_err.to_string());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_switch_user() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo = Some(Box::new(example_get_user(
+            SwapPaymentDetailKey::Iota,
+            false,
+            2,
+            KycType::Undefined,
+        )));
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_lock().times(1).return_const(());
+        mock_wallet_manager
+            .expect_set_recovery_share()
+            .withf(|share| share.is_none())
+            .times(1)
+            .return_const(());
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: String::from("previous_user"),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.access_token = Some(TOKEN.clone());
+
+        // Act
+        let response = sdk.switch_user(USERNAME).await;
+
+        // Assert
+        response.unwrap();
+        assert_eq!(sdk.active_user.as_ref().unwrap().username, USERNAME);
+        assert!(sdk.access_token.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_network_selection_and_derivation_options_are_restored() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo = Some(Box::new(UserRepoImpl::new(MemoryUserStorage::new())));
+        sdk.create_new_user(USERNAME).await.unwrap();
+        sdk.init_user(USERNAME).await.unwrap();
+        sdk.set_networks(example_api_networks());
+
+        // Act
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        sdk.set_wallet_derivation_options(1, 2).await.unwrap();
+        sdk.set_network(ETH_NETWORK_KEY.to_string()).await.unwrap();
+        let eth_options = sdk.active_user.as_ref().unwrap().mnemonic_derivation_options;
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+        sdk.switch_user(USERNAME).await.unwrap();
+
+        // Assert
+        assert_eq!(eth_options, MnemonicDerivationOption::default());
+        assert_eq!(sdk.active_network.as_ref().unwrap().key, IOTA_NETWORK_KEY);
+        assert_eq!(
+            sdk.active_user.as_ref().unwrap().mnemonic_derivation_options,
+            MnemonicDerivationOption { account: 1, index: 2 }
+        );
+        let user = sdk.repo.as_ref().unwrap().get(USERNAME).unwrap();
+        assert_eq!(user.selected_network, Some(IOTA_NETWORK_KEY.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_init_user_restores_network_from_cached_networks() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo = Some(Box::new(UserRepoImpl::new(MemoryUserStorage::new())));
+        sdk.create_new_user(USERNAME).await.unwrap();
+        let repo = sdk.repo.as_mut().unwrap();
+        let mut user = repo.get(USERNAME).unwrap();
+        user.selected_network = Some(ETH_NETWORK_KEY.to_string());
+        user.offline_cache.networks = Some(Cached::fresh(example_api_networks()));
+        repo.update(&user).unwrap();
+
+        // Act
+        sdk.init_user(USERNAME).await.unwrap();
+
+        // Assert
+        assert_eq!(sdk.active_network.as_ref().unwrap().key, ETH_NETWORK_KEY);
+        assert_eq!(sdk.networks, example_api_networks());
+    }
+
+    #[tokio::test]
+    async fn test_init_user_restores_preferred_network_on_first_use() {
+        // Arrange
+        let (mut srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo = Some(Box::new(UserRepoImpl::new(MemoryUserStorage::new())));
+        sdk.create_new_user(USERNAME).await.unwrap();
+        sdk.access_token = Some(TOKEN.clone());
+
+        let body = serde_json::to_string(&KycStatusResponse {
+            username: USERNAME.into(),
+            is_verified: false,
+        })
+        .unwrap();
+        let kyc_mock = srv
+            .mock("GET", "/api/kyc/check-status")
+            .with_status(200)
+            .with_body(body)
+            .expect(1)
+            .create();
+        let preferred_network_mock = srv
+            .mock("GET", "/api/user/network")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"network_key\":\"ETH\"}")
+            .expect(1)
+            .create();
+        let body = serde_json::to_string(&ApiGetNetworksResponse {
+            networks: example_api_networks(),
+        })
+        .unwrap();
+        let networks_mock = srv
+            .mock("GET", "/api/config/networks")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        // Act
+        sdk.init_user(USERNAME).await.unwrap();
+
+        // Assert
+        kyc_mock.assert();
+        assert!(!preferred_network_mock.matched());
+        assert!(!networks_mock.matched());
+        assert!(sdk.active_network.is_none());
+
+        // Act
+        let network = sdk.active_network().await.unwrap();
+
+        // Assert
+        preferred_network_mock.assert();
+        networks_mock.assert();
+        assert_eq!(network.key, ETH_NETWORK_KEY);
+    }
+
+    #[tokio::test]
+    async fn test_switch_user_not_found_keeps_active_user() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo.expect_get().times(1).returning(|username| {
+            Err(crate::user::error::UserKvStorageError::UserNotFound {
+                username: username.to_string(),
+            })
+        });
+        sdk.repo = Some(Box::new(mock_user_repo));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.access_token = Some(TOKEN.clone());
+
+        // Act
+        let response = sdk.switch_user("unknown").await;
+
+        // Assert
+        assert!(response.is_err());
+        assert_eq!(sdk.active_user.as_ref().unwrap().username, USERNAME);
+        assert!(sdk.access_token.is_some());
+    }
+
     #[rstest]
     #[case::success(true, Ok(()))]
     #[case::repo_init_error(false, Err(crate::Error::UserRepoNotInitialized))]
@@ -432,6 +829,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
 
                 sdk.access_token = Some(TOKEN.clone());
@@ -493,6 +891,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
 
                 sdk.access_token = Some(TOKEN.clone());
@@ -550,6 +949,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
@@ -600,6 +1000,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
 
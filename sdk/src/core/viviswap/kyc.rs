@@ -39,6 +39,7 @@ impl Sdk {
     /// * User already exists.
     /// * Viviswap API error.
     /// * User status update error.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn start_kyc_verification_for_viviswap(
         &mut self,
         mail: &str,
@@ -64,7 +65,11 @@ impl Sdk {
             return Err(crate::Error::Viviswap(ViviswapError::UserStateExisting));
         };
 
-        let new_viviswap_user = create_viviswap_user(config, access_token, mail, terms_accepted).await?;
+        let new_viviswap_user = self.telemetry.kyc_step(
+            "viviswap",
+            "start",
+            create_viviswap_user(config, access_token, mail, terms_accepted).await,
+        )?;
 
         user.viviswap_state = Some(ViviswapState::new());
 
@@ -87,6 +92,7 @@ impl Sdk {
     ///
     /// * Repository initialization error.
     /// * Viviswap API error.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_kyc_details_for_viviswap(&mut self) -> Result<ViviswapKycStatus> {
         info!("Getting KYC details for viviswap user");
         // load user entity
@@ -99,7 +105,7 @@ impl Sdk {
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
 
         let kyc_status = get_viviswap_kyc_status(config, access_token).await?;
-        debug!("KYC Status response: {kyc_status:#?}");
+        debug!("KYC verification status: {:?}", kyc_status.verification_status);
 
         let username = user.username;
         let verification_status: ViviswapVerificationStatus = kyc_status.verification_status.into();
@@ -152,6 +158,7 @@ impl Sdk {
     /// # Returns
     ///
     /// Returns `Ok(())` if the submission is successful.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn submit_kyc_partially_status_for_viviswap(&mut self) -> Result<()> {
         info!("Submitting partial KYC status for viviswap");
 
@@ -250,21 +257,29 @@ impl Sdk {
 
         // submit viviswap general kyc details
         if viviswap_state.next_verification_step == ViviswapVerificationStep::General {
-            set_viviswap_kyc_general_details(
-                config,
-                access_token,
-                *is_individual,
-                *is_pep,
-                *is_us_citizen,
-                *is_regulatory_disclosure,
-                country_of_residence,
-                nationality,
-            )
-            .await?;
+            self.telemetry.kyc_step(
+                "viviswap",
+                "general_details",
+                set_viviswap_kyc_general_details(
+                    config,
+                    access_token,
+                    *is_individual,
+                    *is_pep,
+                    *is_us_citizen,
+                    *is_regulatory_disclosure,
+                    country_of_residence,
+                    nationality,
+                )
+                .await,
+            )?;
         }
 
         // submit viviswap personal kyc details
-        set_viviswap_kyc_personal_details(config, access_token, full_name, date_of_birth).await?;
+        self.telemetry.kyc_step(
+            "viviswap",
+            "personal_details",
+            set_viviswap_kyc_personal_details(config, access_token, full_name, date_of_birth).await,
+        )?;
 
         // get new verification status of viviswap user
         self.get_kyc_details_for_viviswap().await?;
@@ -301,6 +316,7 @@ impl Sdk {
     ///
     /// Returns a vector of errors if any validation errors occur during the update process.
     #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn update_kyc_partially_status_for_viviswap(
         &mut self,
         is_individual: Option<bool>,
@@ -482,6 +498,7 @@ impl Sdk {
     ///
     /// - [[`crate::Error::UserNotInitialized)`]] - If the user is not initialized.
     /// - [[`crate::Error::ViviswapApiError`]] - If there is an error in the viviswap API.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_viviswap_kyc_identity_details(
         &self,
         official_document: IdentityOfficialDocumentData,
@@ -496,13 +513,17 @@ impl Sdk {
             .access_token
             .as_ref()
             .ok_or(crate::error::Error::MissingAccessToken)?;
-        backend::viviswap::set_viviswap_kyc_identity_details(
-            config,
-            access_token,
-            official_document,
-            personal_document,
-        )
-        .await?;
+        self.telemetry.kyc_step(
+            "viviswap",
+            "identity_details",
+            backend::viviswap::set_viviswap_kyc_identity_details(
+                config,
+                access_token,
+                official_document,
+                personal_document,
+            )
+            .await,
+        )?;
         Ok(())
     }
 
@@ -524,6 +545,7 @@ impl Sdk {
     /// - [[`crate::Error::ViviswapValidation`]] - If the input values are not valid.
     /// - [[`crate::Error::ViviswapApiError`]] - If there is an error in the viviswap API.
     #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_viviswap_kyc_residence_details(
         &self,
         country_code: String,
@@ -569,23 +591,27 @@ impl Sdk {
             .access_token
             .as_ref()
             .ok_or_else(|| crate::error::Error::MissingAccessToken)?;
-        backend::viviswap::set_viviswap_kyc_residence_details(
-            config,
-            access_token,
-            api_types::api::viviswap::kyc::SetResidenceDataRequest {
-                country_code,
-                region,
-                zip_code,
-                city,
-                address_line_1,
-                address_line_2,
-                is_public_entry,
-                public_entry_reference,
-                has_no_official_document,
-                document_residence_proof,
-            },
-        )
-        .await?;
+        self.telemetry.kyc_step(
+            "viviswap",
+            "residence_details",
+            backend::viviswap::set_viviswap_kyc_residence_details(
+                config,
+                access_token,
+                api_types::api::viviswap::kyc::SetResidenceDataRequest {
+                    country_code,
+                    region,
+                    zip_code,
+                    city,
+                    address_line_1,
+                    address_line_2,
+                    is_public_entry,
+                    public_entry_reference,
+                    has_no_official_document,
+                    document_residence_proof,
+                },
+            )
+            .await,
+        )?;
         Ok(())
     }
 
@@ -603,6 +629,7 @@ impl Sdk {
     ///
     /// - [[`crate::Error::UserNotInitialized)`]] - If the user is not initialized.
     /// - [[`crate::Error::ViviswapApiError`]] - If there is an error in the viviswap API.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_viviswap_kyc_amla_open_questions(&self) -> Result<Vec<KycAmlaQuestion>> {
         let Some(_user) = &self.active_user else {
             return Err(crate::Error::UserNotInitialized);
@@ -631,6 +658,7 @@ impl Sdk {
     ///
     /// - [[`crate::Error::UserNotInitialized)`]] - If the user is not initialized.
     /// - [[`crate::Error::ViviswapApiError`]] - If there is an error in the viviswap API.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_viviswap_kyc_amla_answer(
         &self,
         question_id: String,
@@ -646,16 +674,20 @@ impl Sdk {
             .access_token
             .as_ref()
             .ok_or(crate::error::Error::MissingAccessToken)?;
-        backend::viviswap::set_viviswap_kyc_amla_answer(
-            config,
-            access_token,
-            api_types::api::viviswap::kyc::AnswerData {
-                question_id,
-                answers,
-                freetext_answer,
-            },
-        )
-        .await?;
+        self.telemetry.kyc_step(
+            "viviswap",
+            "amla_answer",
+            backend::viviswap::set_viviswap_kyc_amla_answer(
+                config,
+                access_token,
+                api_types::api::viviswap::kyc::AnswerData {
+                    question_id,
+                    answers,
+                    freetext_answer,
+                },
+            )
+            .await,
+        )?;
         Ok(())
     }
 
@@ -673,6 +705,7 @@ impl Sdk {
     ///
     /// - [`crate::Error::UserNotInitialized)`] - If the user is not initialized.
     /// - [`crate::Error::ViviswapApiError`] - If there is an error in the viviswap API.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_viviswap_kyc_open_documents(&self) -> Result<Vec<KycOpenDocument>> {
         let Some(_user) = &self.active_user else {
             return Err(crate::Error::UserNotInitialized);
@@ -703,6 +736,7 @@ impl Sdk {
     ///
     /// - [`crate::Error::ViviswapApiError`] - If there is an error in the viviswap API.
     /// - [`crate::Error::UserNotInitialized)`] - If the user is not initialized.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_viviswap_kyc_document(
         &self,
         document_id: String,
@@ -720,18 +754,22 @@ impl Sdk {
             .as_ref()
             .ok_or(crate::error::Error::MissingAccessToken)?;
 
-        backend::viviswap::set_viviswap_kyc_document(
-            config,
-            access_token,
-            api_types::api::viviswap::kyc::SetDocumentDataRequest {
-                document_id,
-                expiration_date,
-                document_number,
-                front_image,
-                back_image,
-            },
-        )
-        .await?;
+        self.telemetry.kyc_step(
+            "viviswap",
+            "document",
+            backend::viviswap::set_viviswap_kyc_document(
+                config,
+                access_token,
+                api_types::api::viviswap::kyc::SetDocumentDataRequest {
+                    document_id,
+                    expiration_date,
+                    document_number,
+                    front_image,
+                    back_image,
+                },
+            )
+            .await,
+        )?;
         Ok(())
     }
 }
@@ -36,6 +36,7 @@ impl Sdk {
     /// - [`crate::Error::ViviswapApi`] - If there is an error in the viviswap API.
     /// - [`crate::Error::UserStatusUpdateError`] - If there is an error updating the user status.
     // MARK1:get_iban_for_viviswap
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_iban_for_viviswap(&mut self) -> Result<ViviswapAddressDetail> {
         info!("Getting IBAN for viviswap");
         // load user entity
@@ -114,13 +115,9 @@ impl Sdk {
 
         // get all viviswap details for specific payment-method
         let details = get_viviswap_details(config, access_token, payment_method_key).await?;
-        debug!("{details:#?}");
+        debug!("Found {} payment details", details.payment_detail.len());
         // search for address in existing details
         for detail in &details.payment_detail {
-            debug!("{detail:#?}");
-            let add = detail.address.clone();
-            debug!("{add:#?}");
-            debug!("{address:#?}");
             if detail.address == address {
                 return Ok(ViviswapAddressDetail {
                     id: detail.id.to_string(),
@@ -172,6 +169,7 @@ impl Sdk {
     /// - [`crate::Error::ViviswapMissingUserError`] - If the viviswap user is missing.
     /// - [`crate::Error::UserStatusUpdateError`] - If there is an error updating the user status.
     // MARK3:update_iban_for_viviswap
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn update_iban_for_viviswap(
         &mut self,
         pin: &EncryptionPin,
@@ -238,6 +236,7 @@ impl Sdk {
     /// - [`crate::Error::ViviswapInvalidState`] - If the viviswap state is invalid.
     /// - [`crate::Error::ViviswapApi`] - If there is an error with the Viviswap API.
     // MARK4:create_deposit_with_viviswap
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_deposit_with_viviswap(&mut self, pin: &EncryptionPin) -> Result<ViviswapDeposit> {
         info!("Creating deposit for viviswap");
         // load user entity
@@ -255,7 +254,7 @@ impl Sdk {
         };
 
         let iban_method_id = self.get_payment_method_id_viviswap(SwapPaymentDetailKey::Sepa).await?;
-        let network = self.active_network.clone().ok_or(crate::Error::MissingNetwork)?;
+        let network = self.active_network().await?;
         let currency = Currency::try_from(network.display_symbol)?;
 
         let payment_method_key = currency.to_vivi_payment_method_key();
@@ -323,8 +322,9 @@ impl Sdk {
     /// - [`crate::Error::ConfigInitError`] - If there is an error initializing the configuration.
     /// - [`crate::Error::ViviswapMissingUserError`] - If the viviswap user is missing.
     // MARK5:create_detail_for_viviswap
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_detail_for_viviswap(&mut self, pin: &EncryptionPin) -> Result<ViviswapAddressDetail> {
-        let network = self.active_network.clone().ok_or(crate::Error::MissingNetwork)?;
+        let network = self.active_network().await?;
         let currency = Currency::try_from(network.display_symbol)?;
         let payment_method_key = currency.to_vivi_payment_method_key();
 
@@ -424,6 +424,7 @@ impl Sdk {
     /// - [`crate::Error::ViviswapInvalidState`] - If the viviswap state is invalid.
     /// - [`crate::Error::ViviswapApi`] - If there is an error with the Viviswap API.
     // MARK7:create_withdrawal_with_viviswap
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_withdrawal_with_viviswap(
         &mut self,
         amount: CryptoAmount,
@@ -442,7 +443,7 @@ impl Sdk {
             return Err(crate::Error::Viviswap(ViviswapError::MissingUser));
         };
 
-        let network = self.active_network.clone().ok_or(crate::Error::MissingNetwork)?;
+        let network = self.active_network().await?;
         let currency = Currency::try_from(network.display_symbol)?;
 
         // check if iban exists, otherwise error
@@ -520,6 +521,7 @@ impl Sdk {
     /// * Repository initialization error.
     /// * Viviswap API error.
     // MARK8:get_swap_list
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_swap_list(&self, start: u32, limit: u32) -> Result<OrderList> {
         let Some(_user) = &self.active_user else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -545,6 +547,7 @@ impl Sdk {
     ///
     /// Returns a `Result` containing the swap order details or an error.
     // MARK9:get_swap_details
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_swap_details(&self, order_id: String) -> Result<Order> {
         let Some(_user) = &self.active_user else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -593,6 +596,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(MockWalletManager::new()),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         }
     }
 
@@ -805,6 +809,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(mock_wallet_manager),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         });
 
         // create viviswap contract
@@ -3,19 +3,29 @@
 //!
 //! It also includes various helper functions and imports required for the wallet functionality.
 use super::Sdk;
+use super::address_book::{upsert_address_book_entry, upsert_address_label};
 use crate::{
     backend::dlt::put_user_address,
     error::Result,
+    kdbx::BackupMetadata,
+    mnemonic::Language,
     tx_version::VersionedWalletTransaction,
-    types::newtypes::{EncryptionPin, EncryptionSalt, PlainPassword},
+    types::{
+        networks::PerNetwork,
+        newtypes::{Bip39Passphrase, EncryptionPin, EncryptionSalt, PlainPassword},
+        users::{Cached, PinAttempts},
+    },
     wallet::error::{ErrorKind, WalletError},
+    wallet_manager::check_pin,
 };
 use etopay_wallet::{
     MnemonicDerivationOption,
     types::{CryptoAmount, WalletTransaction, WalletTxInfoList, WalletTxStatus},
 };
 
+use futures_util::future::join_all;
 use log::{debug, info, warn};
+use std::time::Duration;
 
 impl Sdk {
     /// Create and store a wallet from a new random mnemonic
@@ -33,8 +43,67 @@ impl Sdk {
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_wallet_from_new_mnemonic(&mut self, pin: &EncryptionPin) -> Result<String> {
-        info!("Creating a new wallet from random mnemonic");
+        self.create_wallet_from_new_mnemonic_with_language(pin, Language::English, &None)
+            .await
+    }
+
+    /// Create and store a wallet from a new random mnemonic, protected with a BIP39 passphrase
+    ///
+    /// The passphrase (sometimes called the "25th word") is stored in the shares together with the
+    /// mnemonic, such that recovering the wallet from the shares restores it as well, and is used
+    /// for deriving the keys on all networks.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN for the wallet.
+    /// * `passphrase` - The BIP39 passphrase combined with the mnemonic.
+    ///
+    /// # Returns
+    ///
+    /// The new random mnemonic.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn create_wallet_from_new_mnemonic_with_passphrase(
+        &mut self,
+        pin: &EncryptionPin,
+        passphrase: &Bip39Passphrase,
+    ) -> Result<String> {
+        self.create_wallet_from_new_mnemonic_with_language(pin, Language::English, &Some(passphrase.clone()))
+            .await
+    }
+
+    /// Create and store a wallet from a new random mnemonic using the wordlist of the given language
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN for the wallet.
+    /// * `language` - The BIP39 wordlist language of the new mnemonic.
+    /// * `passphrase` - The optional BIP39 passphrase combined with the mnemonic.
+    ///
+    /// # Returns
+    ///
+    /// The new random mnemonic.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn create_wallet_from_new_mnemonic_with_language(
+        &mut self,
+        pin: &EncryptionPin,
+        language: Language,
+        passphrase: &Option<Bip39Passphrase>,
+    ) -> Result<String> {
+        info!("Creating a new wallet from random mnemonic in {language:?}");
 
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -48,13 +117,16 @@ impl Sdk {
 
         let mnemonic = active_user
             .wallet_manager
-            .create_wallet_from_new_mnemonic(config, &self.access_token, repo, pin)
+            .create_wallet_from_new_mnemonic(config, &self.access_token, repo, pin, language, passphrase)
             .await?;
         Ok(mnemonic)
     }
 
     /// Create and store a wallet from an existing mnemonic
     ///
+    /// The mnemonic can use the wordlist of any of the [`crate::mnemonic::SUPPORTED_LANGUAGES`], the
+    /// language is detected automatically.
+    ///
     /// # Arguments
     ///
     /// * `pin` - The PIN for the wallet.
@@ -65,7 +137,46 @@ impl Sdk {
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_wallet_from_existing_mnemonic(&mut self, pin: &EncryptionPin, mnemonic: &str) -> Result<()> {
+        self.create_wallet_from_existing_mnemonic_with_optional_passphrase(pin, mnemonic, &None)
+            .await
+    }
+
+    /// Create and store a wallet from an existing mnemonic protected with a BIP39 passphrase
+    ///
+    /// Use this when migrating a wallet that was created with a passphrase (sometimes called the
+    /// "25th word"), otherwise the derived addresses differ from the ones of the original wallet.
+    /// The passphrase is stored in the shares together with the mnemonic.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN for the wallet.
+    /// * `mnemonic` - The mnemonic to use for the wallet.
+    /// * `passphrase` - The BIP39 passphrase combined with the mnemonic.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn create_wallet_from_existing_mnemonic_with_passphrase(
+        &mut self,
+        pin: &EncryptionPin,
+        mnemonic: &str,
+        passphrase: &Bip39Passphrase,
+    ) -> Result<()> {
+        self.create_wallet_from_existing_mnemonic_with_optional_passphrase(pin, mnemonic, &Some(passphrase.clone()))
+            .await
+    }
+
+    async fn create_wallet_from_existing_mnemonic_with_optional_passphrase(
+        &mut self,
+        pin: &EncryptionPin,
+        mnemonic: &str,
+        passphrase: &Option<Bip39Passphrase>,
+    ) -> Result<()> {
         info!("Creating a new wallet from existing mnemonic");
 
         let Some(repo) = &mut self.repo else {
@@ -80,13 +191,17 @@ impl Sdk {
 
         active_user
             .wallet_manager
-            .create_wallet_from_existing_mnemonic(config, &self.access_token, repo, pin, mnemonic)
+            .create_wallet_from_existing_mnemonic(config, &self.access_token, repo, pin, mnemonic, passphrase)
             .await?;
         Ok(())
     }
 
     /// Create and store a wallet from an existing kdbx backup file
     ///
+    /// Besides the mnemonic, the BIP39 passphrase, the derivation options, the active network, the
+    /// address labels and the address book stored in the backup are restored. Backups that only contain the mnemonic
+    /// are supported as well.
+    ///
     /// # Arguments
     ///
     /// * `pin` - The PIN for the wallet.
@@ -98,6 +213,7 @@ impl Sdk {
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_wallet_from_backup(
         &mut self,
         pin: &EncryptionPin,
@@ -116,15 +232,45 @@ impl Sdk {
 
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
 
-        active_user
+        let metadata = active_user
             .wallet_manager
             .create_wallet_from_backup(config, &self.access_token, repo, pin, backup, backup_password)
             .await?;
+
+        // restore the wallet data stored next to the mnemonic (empty for backups of version 1)
+        active_user.mnemonic_derivation_options = metadata.derivation_options;
+
+        if !metadata.address_labels.is_empty() || !metadata.address_book.is_empty() {
+            let user = repo.get(&active_user.username)?;
+
+            let mut address_labels = user.address_labels;
+            for label in metadata.address_labels {
+                upsert_address_label(&mut address_labels, label);
+            }
+            repo.set_address_labels(&active_user.username, address_labels)?;
+
+            let mut address_book = user.address_book;
+            for entry in metadata.address_book {
+                upsert_address_book_entry(&mut address_book, entry);
+            }
+            repo.set_address_book(&active_user.username, address_book)?;
+        }
+
+        if let Some(network_key) = metadata.active_network {
+            match self.find_network(&network_key).cloned() {
+                Some(network) => self.active_network = Some(network),
+                None => warn!("Network {network_key} from backup is not available, keeping the current network"),
+            }
+        }
+
         Ok(())
     }
 
     /// Create a kdbx wallet backup from an existing wallet.
     ///
+    /// The backup contains the mnemonic, the BIP39 passphrase (if any), the current derivation options, the networks in use, the
+    /// address labels and the address book, together with the backup format version and the time of creation.
+    ///
     /// # Arguments
     ///
     /// * `pin` - The PIN for the wallet.
@@ -139,6 +285,7 @@ impl Sdk {
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn create_wallet_backup(
         &mut self,
         pin: &EncryptionPin,
@@ -156,9 +303,37 @@ impl Sdk {
 
         let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
 
+        let user = repo.get(&active_user.username)?;
+        let transactions = repo.get_all_wallet_transactions(&active_user.username)?;
+
+        // collect all networks the wallet is used on, starting with the currently active one
+        let active_network = self
+            .active_network
+            .as_ref()
+            .map(|network| network.key.clone())
+            .or_else(|| user.selected_network.clone());
+        let mut networks: Vec<String> = Vec::new();
+        for network_key in active_network
+            .iter()
+            .map(String::as_str)
+            .chain(transactions.iter().map(VersionedWalletTransaction::network_key))
+        {
+            if !networks.iter().any(|n| n == network_key) {
+                networks.push(network_key.to_string());
+            }
+        }
+
+        let metadata = BackupMetadata {
+            derivation_options: active_user.mnemonic_derivation_options,
+            active_network,
+            networks,
+            address_labels: user.address_labels,
+            address_book: user.address_book,
+        };
+
         let backup = active_user
             .wallet_manager
-            .create_wallet_backup(config, &self.access_token, repo, pin, backup_password)
+            .create_wallet_backup(config, &self.access_token, repo, pin, backup_password, &metadata)
             .await?;
         Ok(backup)
     }
@@ -180,6 +355,7 @@ impl Sdk {
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn verify_mnemonic(&mut self, pin: &EncryptionPin, mnemonic: &str) -> Result<bool> {
         info!("Verifying mnemonic");
 
@@ -213,6 +389,7 @@ impl Sdk {
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn delete_wallet(&mut self, pin: &EncryptionPin) -> Result<()> {
         warn!("Deleting wallet for user. Potential loss of funds if mnemonic/wallet is not backed up!");
 
@@ -231,13 +408,15 @@ impl Sdk {
             .wallet_manager
             .delete_wallet(config, &self.access_token, repo)
             .await?;
+        self.clear_cached_balances();
 
         Ok(())
     }
 
     /// Verify pin
     ///
-    /// Verifies the pin for the wallet.
+    /// Verifies the pin for the wallet. Wrong attempts are counted and limited according to the
+    /// [`crate::types::users::PinPolicy`] set with [`Sdk::set_pin_policy`].
     ///
     /// # Arguments
     ///
@@ -252,31 +431,94 @@ impl Sdk {
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
     /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet.
-    /// * [`WalletError::WrongPinOrPassword`] - If the pin or password is incorrect.
-    pub async fn verify_pin(&self, pin: &EncryptionPin) -> Result<()> {
+    /// * [`WalletError::WrongPin`] - If the pin is incorrect, with the number of remaining attempts.
+    /// * [`WalletError::PinBackoff`] - If the pin was entered wrong too often and the next attempt is delayed.
+    /// * [`WalletError::PinLocked`] - If the wallet is locked after too many wrong attempts.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn verify_pin(&mut self, pin: &EncryptionPin) -> Result<()> {
         info!("Verifying wallet pin");
-        let Some(repo) = &self.repo else {
+        let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
         };
         let Some(active_user) = &self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
 
-        let username = &active_user.username;
-        let user = repo.get(username)?;
+        let user = repo.get(&active_user.username)?;
+        Ok(check_pin(repo, &user, pin, &self.pin_policy)?)
+    }
 
-        // Ensure encrypted password exists in user
-        let Some(encrypted_password) = user.encrypted_password else {
-            return Err(WalletError::WalletNotInitialized(ErrorKind::MissingPassword))?;
+    /// Unlock the wallet
+    ///
+    /// Verifies the pin and keeps the wallets in memory until the `ttl` expires or
+    /// [`Sdk::lock_wallet`] is called. While unlocked, wallet operations reuse the wallet instead
+    /// of downloading the shares and deriving the keys again on every call.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The pin of the wallet.
+    /// * `ttl` - How long the wallet stays unlocked. Calling this again while unlocked extends the session.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the wallet is unlocked, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`WalletError::WrongPin`] - If the pin is incorrect, with the number of remaining attempts.
+    /// * [`WalletError::PinBackoff`] - If the pin was entered wrong too often and the next attempt is delayed.
+    /// * [`WalletError::PinLocked`] - If the wallet is locked after too many wrong attempts.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn unlock_wallet(&mut self, pin: &EncryptionPin, ttl: Duration) -> Result<()> {
+        info!("Unlocking wallet for {ttl:?}");
+        self.verify_pin(pin).await?;
+
+        let Some(active_user) = &mut self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
         };
+        active_user.wallet_manager.unlock(ttl);
+        Ok(())
+    }
 
-        // Decrypt the password using the provided PIN
-        if encrypted_password.decrypt(pin, &user.salt).is_err() {
-            return Err(WalletError::WrongPinOrPassword)?;
-        }
+    /// Lock the wallet
+    ///
+    /// Ends the session started with [`Sdk::unlock_wallet`] and drops the wallets kept in memory.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[allow(clippy::result_large_err)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn lock_wallet(&mut self) -> Result<()> {
+        info!("Locking wallet");
+        let Some(active_user) = &mut self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+        active_user.wallet_manager.lock();
         Ok(())
     }
 
+    /// Check if the wallet is unlocked
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the wallet was unlocked with [`Sdk::unlock_wallet`] and the session has not expired yet.
+    /// An expired session is locked right away.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[allow(clippy::result_large_err)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn is_wallet_unlocked(&mut self) -> Result<bool> {
+        let Some(active_user) = &mut self.active_user else {
+            return Err(crate::Error::UserNotInitialized);
+        };
+        Ok(active_user.wallet_manager.is_unlocked())
+    }
+
     /// Reset pin
     ///
     /// Resets the pin for the wallet using the provided password and new pin.
@@ -295,7 +537,10 @@ impl Sdk {
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
     /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet.
-    /// * [`WalletError::WrongPinOrPassword`] - If the pin or password is incorrect.
+    /// * [`WalletError::WrongPin`] - If the old pin is incorrect, with the number of remaining attempts.
+    /// * [`WalletError::PinBackoff`] - If the pin was entered wrong too often and the next attempt is delayed.
+    /// * [`WalletError::PinLocked`] - If the wallet is locked after too many wrong attempts.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn change_pin(&mut self, old_pin: &EncryptionPin, new_pin: &EncryptionPin) -> Result<()> {
         info!("Resetting pin with password");
         let Some(repo) = &mut self.repo else {
@@ -307,6 +552,7 @@ impl Sdk {
 
         let username = &active_user.username;
         let mut user = repo.get(username)?;
+        check_pin(repo, &user, old_pin, &self.pin_policy)?;
 
         let Some(encrypted_password) = user.encrypted_password else {
             return Err(WalletError::WalletNotInitialized(ErrorKind::MissingPassword))?;
@@ -322,6 +568,7 @@ impl Sdk {
         // Update user
         user.salt = salt;
         user.encrypted_password = Some(encrypted_password);
+        user.pin_attempts = PinAttempts::default();
         repo.update(&user)?;
 
         Ok(())
@@ -342,6 +589,7 @@ impl Sdk {
     ///
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_wallet_password(&mut self, pin: &EncryptionPin, new_password: &PlainPassword) -> Result<()> {
         info!("Setting password");
 
@@ -355,13 +603,11 @@ impl Sdk {
         let mut user = repo.get(&active_user.username)?;
 
         // if password already exists, return an error!
-        if let Some(encrypted_password) = user.encrypted_password {
+        if user.encrypted_password.is_some() {
             info!("Password exists, changing password");
 
             // verify that the pin is correct by decrypting the password using the provided PIN
-            if encrypted_password.decrypt(pin, &user.salt).is_err() {
-                return Err(WalletError::WrongPinOrPassword)?;
-            }
+            check_pin(repo, &user, pin, &self.pin_policy)?;
 
             let config = self.config.as_ref().ok_or(crate::Error::MissingConfig)?;
 
@@ -395,6 +641,7 @@ impl Sdk {
     ///
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn is_wallet_password_set(&self) -> Result<bool> {
         info!("Checking if password is set");
 
@@ -421,16 +668,17 @@ impl Sdk {
     /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
     /// * [`crate::Error::MissingConfig`] - If the sdk config is missing.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn generate_new_address(&mut self, pin: &EncryptionPin) -> Result<String> {
         info!("Generating new wallet address");
         self.verify_pin(pin).await?;
+        let network = self.active_network().await?;
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
         };
         let Some(active_user) = &mut self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
-        let network = self.active_network.as_ref().ok_or(crate::Error::MissingNetwork)?;
         let config = self.config.as_mut().ok_or(crate::Error::MissingConfig)?;
         let wallet = active_user
             .wallet_manager
@@ -438,7 +686,7 @@ impl Sdk {
                 config,
                 &self.access_token,
                 repo,
-                network,
+                &network,
                 pin,
                 &active_user.mnemonic_derivation_options,
             )
@@ -458,7 +706,10 @@ impl Sdk {
 
     /// Get the balance of the user
     ///
-    /// Fetches the balance of the user from the wallet.
+    /// Fetches the balance of the user from the wallet. The pin is always verified. If fetching
+    /// fails, the last balance fetched for the active network and derivation path is returned
+    /// from the offline cache and [`super::events::SdkEvent::CachedValueReturned`] is emitted.
+    /// Use [`Sdk::get_balance_cached`] to tell the cached balance apart from a fetched one.
     ///
     /// # Returns
     ///
@@ -467,16 +718,117 @@ impl Sdk {
     /// # Errors
     ///
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
-    /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet.
+    /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet and no balance is cached.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_balance(&mut self, pin: &EncryptionPin) -> Result<CryptoAmount> {
+        self.get_balance_cached(pin).await.map(|balance| balance.value)
+    }
+
+    /// Get the balance of the user together with the time it was fetched
+    ///
+    /// Like [`Sdk::get_balance`], but if fetching fails and the last known balance is returned
+    /// from the offline cache, it is marked as [`Cached::stale`] and keeps the time it was fetched.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet and no balance is cached.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_balance_cached(&mut self, pin: &EncryptionPin) -> Result<Cached<CryptoAmount>> {
         info!("Fetching balance");
         self.verify_pin(pin).await?;
-        let wallet = self.try_get_active_user_wallet(pin).await?;
-        let balance = wallet.get_balance().await?;
-        debug!("Balance: {balance:?}");
+        let result = async {
+            let wallet = self.try_get_active_user_wallet(pin).await?;
+            Ok::<_, crate::Error>(wallet.get_balance().await?)
+        }
+        .await;
+
+        let (Some(network), Some(active_user)) = (&self.active_network, &self.active_user) else {
+            return result.map(Cached::fresh);
+        };
+        let network_key = network.key.clone();
+        let derivation_options = active_user.mnemonic_derivation_options;
+        match result {
+            Ok(balance) => {
+                debug!("Balance: {balance:?}");
+                self.cache_balance(network_key, derivation_options, balance);
+                Ok(Cached::fresh(balance))
+            }
+            Err(e) => {
+                let Some(balance) = self.cached_balance(&network_key, derivation_options) else {
+                    return Err(e);
+                };
+                warn!("Could not fetch the balance: {e}");
+                Ok(balance)
+            }
+        }
+    }
+
+    /// Get the balance on a network
+    ///
+    /// Unlike [`Sdk::get_balance`], the network does not need to be selected with
+    /// [`Sdk::set_network`]. The derivation options stored for the network are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN of the user.
+    /// * `network_key` - The key of the network, as returned by [`Sdk::get_networks`].
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`crate::Error::NetworkUnavailable`] - If the network is not available.
+    /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_balance_for(&mut self, pin: &EncryptionPin, network_key: &str) -> Result<CryptoAmount> {
+        info!("Fetching balance on network {network_key}");
+        self.verify_pin(pin).await?;
+        let mut wallets = self
+            .try_get_active_user_wallets(pin, &[network_key.to_string()])
+            .await?;
+        let (_, wallet) = wallets
+            .pop()
+            .ok_or_else(|| crate::Error::NetworkUnavailable(network_key.to_string()))?;
+        let balance = wallet?.get_balance().await?;
+        debug!("Balance on network {network_key}: {balance:?}");
         Ok(balance)
     }
 
+    /// Get the balances on all networks
+    ///
+    /// Fetches the balance on every network returned by [`Sdk::get_networks`], with the derivation
+    /// options stored for each network. The mnemonic is reconstructed only once and the balances
+    /// are fetched concurrently. A network where this fails does not fail the others, its error is
+    /// reported in [`PerNetwork::failures`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The PIN of the user.
+    ///
+    /// # Errors
+    ///
+    /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`WalletError::WalletNotInitialized`] - If the mnemonic cannot be reconstructed.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn get_all_balances(&mut self, pin: &EncryptionPin) -> Result<PerNetwork<CryptoAmount>> {
+        info!("Fetching balances on all networks");
+        self.verify_pin(pin).await?;
+        let network_keys = self
+            .available_networks()
+            .map(|network| network.key.clone())
+            .collect::<Vec<_>>();
+        let wallets = self.try_get_active_user_wallets(pin, &network_keys).await?;
+        let balances = join_all(wallets.into_iter().map(|(network_key, wallet)| async move {
+            let balance = match wallet {
+                Ok(wallet) => wallet.get_balance().await.map_err(Into::into),
+                Err(e) => Err(e),
+            };
+            (network_key, balance)
+        }))
+        .await;
+        Ok(balances.into_iter().collect())
+    }
+
     /// wallet transaction list
     ///
     /// Returns paginated list of wallet transaction list.
@@ -494,6 +846,7 @@ impl Sdk {
     ///
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
     /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_wallet_tx_list(
         &mut self,
         pin: &EncryptionPin,
@@ -502,6 +855,7 @@ impl Sdk {
     ) -> Result<WalletTxInfoList> {
         info!("Wallet getting list of transactions");
         self.verify_pin(pin).await?;
+        let network = self.active_network().await?;
 
         let Some(repo) = &mut self.repo else {
             return Err(crate::Error::UserRepoNotInitialized);
@@ -509,7 +863,6 @@ impl Sdk {
         let Some(active_user) = &mut self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
-        let network = self.active_network.as_ref().ok_or(crate::Error::MissingNetwork)?;
         let config = self.config.as_mut().ok_or(crate::Error::MissingConfig)?;
         let wallet = active_user
             .wallet_manager
@@ -517,33 +870,28 @@ impl Sdk {
                 config,
                 &self.access_token,
                 repo,
-                network,
+                &network,
                 pin,
                 &active_user.mnemonic_derivation_options,
             )
             .await?;
 
-        let user = repo.get(active_user.username.as_str())?;
+        let username = active_user.username.as_str();
 
         // Retrieve the transaction list from the wallet,
-        // 1) fetch and add new (untracked) transactions from the network,
-        // 2) migrate transactions to the latest version if necessary,
+        // 1) fetch and store new (untracked) transactions from the network,
+        // 2) migrate transactions of the requested page to the latest version if necessary,
         // 3) confirm pending transactions,
-        // 4) and save the updated list back to the wallet.
-        let mut wallet_transactions = user.wallet_transactions_versioned;
+        // 4) and store the transactions that changed.
 
-        // 1) fetch and add new (untracked) transactions from the network
+        // 1) fetch and store new (untracked) transactions from the network
         match wallet.get_wallet_tx_list(start, limit).await {
             Ok(transaction_hashes) => {
                 // go through and get the details for any new hashes
                 log::debug!("Digests: {:#?}", transaction_hashes);
                 for hash in transaction_hashes {
-                    // check if transaction is already in the list (not very efficient to do a linear search, but good enough for now)
                     // check both the transaction hash and the network key, as hash collisions can occur across different blockchain networks
-                    if wallet_transactions
-                        .iter()
-                        .any(|t| t.transaction_hash() == hash && t.network_key() == network.key)
-                    {
+                    if repo.get_wallet_transaction(username, &network.key, &hash)?.is_some() {
                         continue;
                     }
 
@@ -552,7 +900,9 @@ impl Sdk {
                     // not included, we should add it!
                     match wallet.get_wallet_tx(&hash).await {
                         Err(e) => log::warn!("Could not get transaction details for {hash}: {e}"),
-                        Ok(details) => wallet_transactions.push(VersionedWalletTransaction::from(details)),
+                        Ok(details) => {
+                            repo.insert_wallet_transaction(username, VersionedWalletTransaction::from(details))?
+                        }
                     }
                 }
             }
@@ -561,39 +911,29 @@ impl Sdk {
             Err(e) => return Err(e.into()),
         };
 
-        wallet_transactions.sort_by_key(|b| std::cmp::Reverse(b.date()));
         let mut wallet_tx_list = Vec::new();
-
-        for t in wallet_transactions
-            .iter_mut()
-            .filter(|tx| tx.network_key() == network.key)
-            .skip(start)
-            .take(limit)
-        {
-            // 2) migrate transactions to the latest version if necessary
-            if let VersionedWalletTransaction::V1(v1) = t {
-                if let Ok(details) = wallet.get_wallet_tx(&v1.transaction_hash).await {
-                    *t = VersionedWalletTransaction::from(details);
-                    wallet_tx_list.push(WalletTransaction::from(t.clone()));
-                    continue;
+        for transaction in repo.get_wallet_transactions(username, &network.key, start, limit)? {
+            // 2) migrate transactions to the latest version if necessary, 3) confirm pending transactions
+            let outdated = matches!(transaction, VersionedWalletTransaction::V1(_))
+                || transaction.status() == WalletTxStatus::Pending;
+            let updated = if outdated {
+                wallet.get_wallet_tx(transaction.transaction_hash()).await.ok()
+            } else {
+                None
+            };
+
+            // 4) and store the transactions that changed
+            let transaction = match updated {
+                Some(details) => {
+                    let transaction = VersionedWalletTransaction::from(details);
+                    repo.insert_wallet_transaction(username, transaction.clone())?;
+                    transaction
                 }
-            }
-
-            // 3) confirm pending transactions
-            if t.status() == WalletTxStatus::Pending {
-                if let Ok(details) = wallet.get_wallet_tx(t.transaction_hash()).await {
-                    *t = VersionedWalletTransaction::V2(details);
-                    wallet_tx_list.push(WalletTransaction::from(t.clone()));
-                    continue;
-                }
-            }
-
-            wallet_tx_list.push(WalletTransaction::from(t.clone()));
+                None => transaction,
+            };
+            wallet_tx_list.push(WalletTransaction::from(transaction));
         }
 
-        // 4) and save the updated list back to the wallet.
-        let _ = repo.set_wallet_transactions(&user.username, wallet_transactions);
-
         Ok(WalletTxInfoList {
             transactions: wallet_tx_list,
         })
@@ -615,6 +955,7 @@ impl Sdk {
     ///
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
     /// * [`WalletError::WalletNotInitialized`] - If there is an error initializing the wallet.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn get_wallet_tx(&mut self, pin: &EncryptionPin, tx_id: &str) -> Result<WalletTransaction> {
         info!("Wallet getting details of particular transactions");
         self.verify_pin(pin).await?;
@@ -625,6 +966,9 @@ impl Sdk {
 
     /// Set wallet mnemonic derivation options
     ///
+    /// If a network is selected, the options are stored for this network and applied again
+    /// whenever the network is selected.
+    ///
     /// # Arguments
     ///
     /// * `account` - The account to use.
@@ -633,15 +977,30 @@ impl Sdk {
     /// # Errors
     ///
     /// * [`crate::Error::UserNotInitialized`] - If there is an error initializing the user.
+    /// * [`crate::Error::UserRepoNotInitialized`] - If there is an error initializing the repository.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub async fn set_wallet_derivation_options(&mut self, account: u32, index: u32) -> Result<()> {
         let options = MnemonicDerivationOption { account, index };
 
         info!("Setting wallet mnemonic derivation options: {options:?}");
 
+        // the options are stored for the restored network, not the network selected later
+        self.restore_pending_network().await;
+
         let Some(active_user) = &mut self.active_user else {
             return Err(crate::Error::UserNotInitialized);
         };
 
+        if let Some(network) = &self.active_network {
+            let Some(repo) = &mut self.repo else {
+                return Err(crate::Error::UserRepoNotInitialized);
+            };
+            let mut network_derivation_options = active_user.network_derivation_options.clone();
+            network_derivation_options.insert(network.key.clone(), options);
+            repo.set_network_derivation_options(&active_user.username, network_derivation_options.clone())?;
+            active_user.network_derivation_options = network_derivation_options;
+        }
+
         active_user.mnemonic_derivation_options = options;
 
         Ok(())
@@ -654,10 +1013,11 @@ mod tests {
     use crate::core::core_testing_utils::handle_error_test_cases;
     use crate::testing_utils::{
         ADDRESS, AUTH_PROVIDER, ENCRYPTED_WALLET_PASSWORD, ETH_NETWORK_KEY, HEADER_X_APP_NAME, IOTA_NETWORK_KEY,
-        MNEMONIC, PIN, SALT, TOKEN, TX_INDEX, USERNAME, WALLET_PASSWORD, example_api_networks, example_get_user,
-        example_versioned_wallet_transaction, set_config,
+        MNEMONIC, PIN, TOKEN, TX_INDEX, USERNAME, WALLET_PASSWORD, example_api_networks, example_get_user,
+        example_user_entity, example_versioned_wallet_transaction, set_config,
     };
-    use crate::types::users::UserEntity;
+    use crate::types::users::{AddressBookEntry, AddressLabel, PinPolicy, UserEntity};
+    use crate::user::{UserRepo, memory_storage::MemoryUserStorage, repository::UserRepoImpl};
     use crate::{
         core::Sdk,
         types::users::KycType,
@@ -695,11 +1055,12 @@ mod tests {
                 mock_wallet_manager
                     .expect_create_wallet_from_new_mnemonic()
                     .once()
-                    .returning(|_, _, _, _| Ok(MNEMONIC.to_string()));
+                    .returning(|_, _, _, _, _, _| Ok(MNEMONIC.to_string()));
                 sdk.active_user = Some(crate::types::users::ActiveUser {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -739,11 +1100,12 @@ mod tests {
                 mock_wallet_manager
                     .expect_create_wallet_from_existing_mnemonic()
                     .once()
-                    .returning(|_, _, _, _, _| Ok(()));
+                    .returning(|_, _, _, _, _, _| Ok(()));
                 sdk.active_user = Some(crate::types::users::ActiveUser {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -763,6 +1125,67 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_create_wallet_from_new_mnemonic_with_language() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo = Some(Box::new(MockUserRepo::new()));
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_create_wallet_from_new_mnemonic()
+            .once()
+            .withf(|_, _, _, _, language, passphrase| *language == Language::Korean && passphrase.is_none())
+            .returning(|_, _, _, _, _, _| Ok(MNEMONIC.to_string()));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        let response = sdk
+            .create_wallet_from_new_mnemonic_with_language(&PIN, Language::Korean, &None)
+            .await;
+
+        // Assert
+        assert_eq!(response.unwrap(), MNEMONIC);
+    }
+
+    #[tokio::test]
+    async fn test_create_wallet_from_existing_mnemonic_with_passphrase() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.repo = Some(Box::new(MockUserRepo::new()));
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_create_wallet_from_existing_mnemonic()
+            .once()
+            .withf(|_, _, _, _, mnemonic, passphrase| {
+                mnemonic == MNEMONIC && passphrase.as_ref().map(Bip39Passphrase::as_str) == Some("25th word")
+            })
+            .returning(|_, _, _, _, _, _| Ok(()));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        let passphrase = Bip39Passphrase::try_from_string("25th word").unwrap();
+        let response = sdk
+            .create_wallet_from_existing_mnemonic_with_passphrase(&PIN, MNEMONIC, &passphrase)
+            .await;
+
+        // Assert
+        response.unwrap();
+    }
+
     #[rstest]
     #[case::success(Ok(BACKUP.to_vec()))]
     #[case::missing_config(Err(crate::Error::MissingConfig))]
@@ -776,16 +1199,30 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                sdk.repo = Some(Box::new(MockUserRepo::new()));
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
+                mock_user_repo
+                    .expect_get_all_wallet_transactions()
+                    .once()
+                    .returning(|_| Ok(Vec::new()));
+                sdk.repo = Some(Box::new(mock_user_repo));
+                sdk.set_networks(example_api_networks());
+                sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+
                 let mut mock_wallet_manager = MockWalletManager::new();
                 mock_wallet_manager
                     .expect_create_wallet_backup()
                     .once()
-                    .returning(|_, _, _, _, _| Ok(BACKUP.to_vec()));
+                    .withf(|_, _, _, _, _, metadata| {
+                        metadata.derivation_options == MnemonicDerivationOption { account: 1, index: 2 }
+                            && metadata.active_network.as_deref() == Some(IOTA_NETWORK_KEY)
+                            && metadata.networks == vec![IOTA_NETWORK_KEY.to_string()]
+                    })
+                    .returning(|_, _, _, _, _, _| Ok(BACKUP.to_vec()));
                 sdk.active_user = Some(crate::types::users::ActiveUser {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
-                    mnemonic_derivation_options: Default::default(),
+                    mnemonic_derivation_options: MnemonicDerivationOption { account: 1, index: 2 },
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -825,11 +1262,12 @@ mod tests {
                 mock_wallet_manager
                     .expect_create_wallet_from_backup()
                     .once()
-                    .returning(|_, _, _, _, _, _| Ok(()));
+                    .returning(|_, _, _, _, _, _| Ok(BackupMetadata::default()));
                 sdk.active_user = Some(crate::types::users::ActiveUser {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -849,6 +1287,68 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_create_wallet_from_backup_restores_metadata() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.set_networks(example_api_networks());
+        sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
+
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&UserEntity {
+            address_book: vec![AddressBookEntry {
+                network_key: ETH_NETWORK_KEY.to_string(),
+                address: "0x1".to_string(),
+                name: "alice".to_string(),
+            }],
+            ..example_user_entity(USERNAME)
+        })
+        .unwrap();
+        sdk.repo = Some(Box::new(repo));
+
+        let metadata = BackupMetadata {
+            derivation_options: MnemonicDerivationOption { account: 3, index: 4 },
+            active_network: Some(ETH_NETWORK_KEY.to_string()),
+            networks: vec![ETH_NETWORK_KEY.to_string()],
+            address_labels: vec![AddressLabel {
+                network_key: ETH_NETWORK_KEY.to_string(),
+                address: "0x2".to_string(),
+                label: "savings".to_string(),
+            }],
+            address_book: vec![AddressBookEntry {
+                network_key: ETH_NETWORK_KEY.to_string(),
+                address: "0x3".to_string(),
+                name: "bob".to_string(),
+            }],
+        };
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        let returned_metadata = metadata.clone();
+        mock_wallet_manager
+            .expect_create_wallet_from_backup()
+            .once()
+            .returning(move |_, _, _, _, _, _| Ok(returned_metadata.clone()));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        sdk.create_wallet_from_backup(&PIN, BACKUP, &WALLET_PASSWORD)
+            .await
+            .unwrap();
+
+        // Assert
+        let active_user = sdk.active_user.as_ref().unwrap();
+        assert_eq!(active_user.mnemonic_derivation_options, metadata.derivation_options);
+        assert_eq!(sdk.active_network.as_ref().unwrap().key, ETH_NETWORK_KEY);
+        assert_eq!(sdk.get_address_labels().await.unwrap(), metadata.address_labels);
+        assert_eq!(sdk.get_address_book().await.unwrap().len(), 2);
+    }
+
     #[rstest]
     #[case::success(Ok(true))]
     #[case::missing_config(Err(crate::Error::MissingConfig))]
@@ -872,6 +1372,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -908,7 +1409,7 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 2, KycType::Undefined);
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 3, KycType::Undefined);
                 mock_user_repo.expect_update().once().returning(|_| Ok(()));
 
                 sdk.repo = Some(Box::new(mock_user_repo));
@@ -922,6 +1423,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
 
                 let new_pin = EncryptionPin::try_from_string("123456").unwrap();
@@ -966,6 +1468,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -985,6 +1488,267 @@ mod tests {
         }
     }
 
+    fn pin_test_user(pin_attempts: PinAttempts) -> UserEntity {
+        UserEntity {
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            local_share: Some(String::from("local share")),
+            pin_attempts,
+            ..example_user_entity(USERNAME)
+        }
+    }
+
+    #[rstest]
+    #[case::wrong_pin(
+        "654321",
+        PinAttempts::default(),
+        Some(PinAttempts { failed_attempts: 1, last_failed_at: None, locked: false }),
+        Err(WalletError::WrongPin { remaining_attempts: 9 })
+    )]
+    #[case::last_attempt_locks(
+        "654321",
+        PinAttempts { failed_attempts: 9, last_failed_at: Some(0), locked: false },
+        Some(PinAttempts { failed_attempts: 10, last_failed_at: None, locked: true }),
+        Err(WalletError::PinLocked)
+    )]
+    #[case::locked("123456", PinAttempts { failed_attempts: 10, last_failed_at: Some(0), locked: true }, None, Err(WalletError::PinLocked))]
+    #[case::backoff(
+        "123456",
+        PinAttempts { failed_attempts: 3, last_failed_at: Some(Utc::now().timestamp()), locked: false },
+        None,
+        Err(WalletError::PinBackoff { retry_after_secs: 30 })
+    )]
+    #[case::backoff_expired(
+        "123456",
+        PinAttempts { failed_attempts: 3, last_failed_at: Some(Utc::now().timestamp() - 30), locked: false },
+        Some(PinAttempts::default()),
+        Ok(())
+    )]
+    #[case::correct_pin_resets_attempts(
+        "123456",
+        PinAttempts { failed_attempts: 2, last_failed_at: Some(0), locked: false },
+        Some(PinAttempts::default()),
+        Ok(())
+    )]
+    #[tokio::test]
+    async fn test_verify_pin_attempts(
+        #[case] pin: &str,
+        #[case] pin_attempts: PinAttempts,
+        #[case] stored_attempts: Option<PinAttempts>,
+        #[case] expected: core::result::Result<(), WalletError>,
+    ) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_get()
+            .once()
+            .return_once(move |_| Ok(pin_test_user(pin_attempts)));
+        mock_user_repo
+            .expect_set_pin_attempts()
+            .times(usize::from(stored_attempts.is_some()))
+            .returning(move |username, attempts| {
+                assert_eq!(username, USERNAME);
+                let expected_attempts = stored_attempts.clone().unwrap();
+                assert_eq!(attempts.failed_attempts, expected_attempts.failed_attempts);
+                assert_eq!(attempts.locked, expected_attempts.locked);
+                assert_eq!(attempts.last_failed_at.is_some(), expected_attempts.failed_attempts > 0);
+                Ok(())
+            });
+        sdk.repo = Some(Box::new(mock_user_repo));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        let response = sdk.verify_pin(&EncryptionPin::try_from_string(pin).unwrap()).await;
+
+        // Assert
+        match expected {
+            Ok(()) => response.unwrap(),
+            Err(ref expected_err) => {
+                let crate::Error::Wallet(err) = response.unwrap_err() else {
+                    panic!("expected a wallet error");
+                };
+                assert_eq!(std::mem::discriminant(&err), std::mem::discriminant(expected_err));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_pin_wipes_local_share() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        sdk.set_pin_policy(PinPolicy {
+            wipe_local_share_after: Some(1),
+            ..Default::default()
+        });
+
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_get()
+            .once()
+            .returning(|_| Ok(pin_test_user(PinAttempts::default())));
+        mock_user_repo.expect_set_pin_attempts().once().returning(|_, _| Ok(()));
+        mock_user_repo
+            .expect_set_local_share()
+            .once()
+            .withf(|username, share| username == USERNAME && share.is_none())
+            .returning(|_, _| Ok(()));
+        sdk.repo = Some(Box::new(mock_user_repo));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(MockWalletManager::new()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        let response = sdk.verify_pin(&EncryptionPin::try_from_string("654321").unwrap()).await;
+
+        // Assert
+        assert!(matches!(
+            response,
+            Err(crate::Error::Wallet(WalletError::WrongPin { remaining_attempts: 9 }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unlock_and_lock_wallet() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_get()
+            .once()
+            .returning(|_| Ok(pin_test_user(PinAttempts::default())));
+        sdk.repo = Some(Box::new(mock_user_repo));
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_unlock()
+            .once()
+            .with(eq(std::time::Duration::from_secs(300)))
+            .return_const(());
+        mock_wallet_manager.expect_lock().once().return_const(());
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        sdk.unlock_wallet(&PIN, std::time::Duration::from_secs(300))
+            .await
+            .unwrap();
+        sdk.lock_wallet().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_wallet_backup_locks_after_wrong_pins() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&UserEntity {
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            ..example_user_entity(USERNAME)
+        })
+        .unwrap();
+        sdk.repo = Some(Box::new(repo));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(crate::wallet_manager::WalletManagerImpl::new(USERNAME)),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.set_pin_policy(PinPolicy {
+            attempts_before_backoff: 5,
+            max_attempts: 3,
+            ..Default::default()
+        });
+        sdk.create_wallet_from_existing_mnemonic(&PIN, MNEMONIC).await.unwrap();
+        let wrong_pin = EncryptionPin::try_from_string("654321").unwrap();
+
+        // Act
+        let mut results = Vec::new();
+        for _ in 0..3 {
+            results.push(sdk.create_wallet_backup(&wrong_pin, &WALLET_PASSWORD).await);
+        }
+        let with_correct_pin = sdk.create_wallet_backup(&PIN, &WALLET_PASSWORD).await;
+
+        // Assert
+        assert!(matches!(
+            results[0],
+            Err(crate::Error::Wallet(WalletError::WrongPin { remaining_attempts: 2 }))
+        ));
+        assert!(matches!(
+            results[1],
+            Err(crate::Error::Wallet(WalletError::WrongPin { remaining_attempts: 1 }))
+        ));
+        assert!(matches!(results[2], Err(crate::Error::Wallet(WalletError::PinLocked))));
+        assert!(matches!(
+            with_correct_pin,
+            Err(crate::Error::Wallet(WalletError::PinLocked))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unlock_wallet_wrong_pin() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_get()
+            .once()
+            .returning(|_| Ok(pin_test_user(PinAttempts::default())));
+        mock_user_repo.expect_set_pin_attempts().once().returning(|_, _| Ok(()));
+        sdk.repo = Some(Box::new(mock_user_repo));
+
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager.expect_unlock().never();
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(mock_wallet_manager),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+
+        // Act
+        let response = sdk
+            .unlock_wallet(
+                &EncryptionPin::try_from_string("654321").unwrap(),
+                std::time::Duration::from_secs(300),
+            )
+            .await;
+
+        // Assert
+        assert!(matches!(
+            response,
+            Err(crate::Error::Wallet(WalletError::WrongPin { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_pin_policy_backoff() {
+        let policy = PinPolicy::default();
+        assert_eq!(policy.backoff(2), None);
+        assert_eq!(policy.backoff(3), Some(std::time::Duration::from_secs(30)));
+        assert_eq!(policy.backoff(5), Some(std::time::Duration::from_secs(120)));
+        assert_eq!(policy.backoff(20), Some(std::time::Duration::from_secs(60 * 60)));
+    }
+
     #[rstest]
     #[case::success(Ok(()))]
     #[case::repo_init_error(Err(crate::Error::UserRepoNotInitialized))]
@@ -1008,6 +1772,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -1043,18 +1808,7 @@ mod tests {
                 let mut mock_user_repo = MockUserRepo::new();
                 mock_user_repo.expect_get().times(1).returning(move |r1| {
                     assert_eq!(r1, USERNAME);
-                    Ok(UserEntity {
-                        user_id: None,
-                        username: USERNAME.into(),
-                        encrypted_password: None,
-                        salt: SALT.into(),
-                        is_kyc_verified: false,
-                        kyc_type: KycType::Undefined,
-                        viviswap_state: Option::None,
-                        local_share: None,
-                        wallet_transactions: Vec::new(),
-                        wallet_transactions_versioned: Vec::new(),
-                    })
+                    Ok(example_user_entity(USERNAME))
                 });
                 mock_user_repo.expect_update().once().returning(|_| Ok(()));
                 sdk.repo = Some(Box::new(mock_user_repo));
@@ -1063,6 +1817,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(MockWalletManager::new()),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
             }
             Err(error) => {
@@ -1112,6 +1867,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.access_token = Some(TOKEN.clone());
                 sdk.set_networks(example_api_networks());
@@ -1171,7 +1927,11 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                let mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 2, KycType::Undefined);
+                mock_user_repo
+                    .expect_set_offline_cache()
+                    .once()
+                    .returning(|_, _| Ok(()));
                 sdk.repo = Some(Box::new(mock_user_repo));
 
                 let mut mock_wallet_manager = MockWalletManager::new();
@@ -1188,12 +1948,13 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.set_networks(example_api_networks());
                 sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
             }
             Err(error) => {
-                handle_error_test_cases(error, &mut sdk, 1, 0).await;
+                handle_error_test_cases(error, &mut sdk, 2, 0).await;
             }
         }
 
@@ -1211,6 +1972,116 @@ mod tests {
         }
     }
 
+    /// A wallet manager that creates a wallet with a balance of 25 for the IOTA network and fails
+    /// for all other networks
+    fn wallet_manager_with_iota_balance() -> MockWalletManager {
+        let mut mock_wallet_manager = MockWalletManager::new();
+        mock_wallet_manager
+            .expect_try_get_many()
+            .once()
+            .returning(|_, _, _, networks, _| {
+                Ok(networks
+                    .iter()
+                    .map(|(network, _)| {
+                        if network.key != IOTA_NETWORK_KEY {
+                            return Err(WalletError::WalletNotInitialized(ErrorKind::UseMnemonic));
+                        }
+                        let mut mock_wallet_user = MockWalletUser::new();
+                        mock_wallet_user
+                            .expect_get_balance()
+                            .once()
+                            // SAFETY: we know that this value is not negative
+                            .returning(|| Ok(unsafe { CryptoAmount::new_unchecked(dec!(25.0)) }));
+                        Ok(WalletBorrow::from(mock_wallet_user))
+                    })
+                    .collect())
+            });
+        mock_wallet_manager
+    }
+
+    #[rstest]
+    // SAFETY: we know that this value is not negative
+    #[case::success(Ok(unsafe { CryptoAmount::new_unchecked(dec!(25.0)) }))]
+    #[case::network_unavailable(Err(crate::Error::NetworkUnavailable(String::from("unknown"))))]
+    #[case::repo_init_error(Err(crate::Error::UserRepoNotInitialized))]
+    #[case::user_init_error(Err(crate::Error::UserNotInitialized))]
+    #[case::missing_config(Err(crate::Error::MissingConfig))]
+    #[tokio::test]
+    async fn test_get_balance_for(#[case] expected: Result<CryptoAmount>) {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mut network_key = IOTA_NETWORK_KEY;
+
+        match &expected {
+            Ok(_) | Err(crate::Error::NetworkUnavailable(_)) => {
+                let mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
+                sdk.repo = Some(Box::new(mock_user_repo));
+                let mock_wallet_manager = if expected.is_ok() {
+                    wallet_manager_with_iota_balance()
+                } else {
+                    network_key = "unknown";
+                    MockWalletManager::new()
+                };
+                sdk.active_user = Some(crate::types::users::ActiveUser {
+                    username: USERNAME.into(),
+                    wallet_manager: Box::new(mock_wallet_manager),
+                    mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
+                });
+                // no network is selected
+                sdk.set_networks(example_api_networks());
+            }
+            Err(error) => {
+                handle_error_test_cases(error, &mut sdk, 1, 0).await;
+            }
+        }
+
+        // Act
+        let response = sdk.get_balance_for(&PIN, network_key).await;
+
+        // Assert
+        match expected {
+            Ok(resp) => {
+                assert_eq!(response.unwrap(), resp);
+            }
+            Err(ref expected_err) => {
+                assert_eq!(response.err().unwrap().to_string(), expected_err.to_string());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_all_balances_reports_failures_separately() {
+        // Arrange
+        let (_srv, config, _cleanup) = set_config().await;
+        let mut sdk = Sdk::new(config).unwrap();
+        let mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
+        sdk.repo = Some(Box::new(mock_user_repo));
+        sdk.active_user = Some(crate::types::users::ActiveUser {
+            username: USERNAME.into(),
+            wallet_manager: Box::new(wallet_manager_with_iota_balance()),
+            mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
+        });
+        sdk.set_networks(example_api_networks());
+
+        // Act
+        let response = sdk.get_all_balances(&PIN).await.unwrap();
+
+        // Assert
+        assert_eq!(
+            response.values,
+            // SAFETY: we know that this value is not negative
+            [(IOTA_NETWORK_KEY.to_string(), unsafe {
+                CryptoAmount::new_unchecked(dec!(25.0))
+            })]
+            .into()
+        );
+        assert_eq!(response.failures.len(), example_api_networks().len() - 1);
+        assert!(!response.failures.contains_key(IOTA_NETWORK_KEY));
+    }
+
     #[rstest]
     #[case::success(Ok(WalletTransaction::from(example_versioned_wallet_transaction())))]
     #[case::repo_init_error(Err(crate::Error::UserRepoNotInitialized))]
@@ -1240,6 +2111,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.set_networks(example_api_networks());
                 sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
@@ -1276,11 +2148,12 @@ mod tests {
 
         match &expected {
             Ok(_) => {
-                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 2, KycType::Undefined);
+                let mut mock_user_repo = example_get_user(SwapPaymentDetailKey::Iota, false, 1, KycType::Undefined);
                 mock_user_repo
-                    .expect_set_wallet_transactions()
+                    .expect_get_wallet_transactions()
                     .once()
-                    .returning(|_, _| Ok(()));
+                    .returning(|_, _, _, _| Ok(vec![]));
+                mock_user_repo.expect_insert_wallet_transaction().never();
                 sdk.repo = Some(Box::new(mock_user_repo));
 
                 let mut mock_wallet_manager = MockWalletManager::new();
@@ -1296,6 +2169,7 @@ mod tests {
                     username: USERNAME.into(),
                     wallet_manager: Box::new(mock_wallet_manager),
                     mnemonic_derivation_options: Default::default(),
+                    network_derivation_options: Default::default(),
                 });
                 sdk.set_networks(example_api_networks());
                 sdk.set_network(IOTA_NETWORK_KEY.to_string()).await.unwrap();
@@ -1378,52 +2252,44 @@ mod tests {
         ];
 
         let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_set_selected_network()
+            .once()
+            .returning(|_, _| Ok(()));
         mock_user_repo.expect_get().returning(move |_| {
             Ok(UserEntity {
-                user_id: None,
-                username: USERNAME.to_string(),
                 encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
-                salt: SALT.into(),
-                is_kyc_verified: false,
-                kyc_type: KycType::Undefined,
-                viviswap_state: None,
-                local_share: None,
-                wallet_transactions: Vec::new(),
-                wallet_transactions_versioned: wallet_transactions_versioned.clone(),
+                ..example_user_entity(USERNAME)
             })
         });
 
-        let expected = vec![
-            VersionedWalletTransaction::from(mock_wallet_transaction(
-                String::from("some tx id"),
-                WalletTxStatus::Confirmed,
-                String::from("IOTA"),
-                mock_date,
-            )),
-            VersionedWalletTransaction::from(mock_wallet_transaction(
-                String::from("1"),
-                WalletTxStatus::Pending,
-                String::from("ETH"),
-                mock_date,
-            )),
-            VersionedWalletTransaction::from(mock_wallet_transaction(
-                String::from("2"),
-                WalletTxStatus::Confirmed, // this one
-                String::from("ETH"),
-                mock_date,
-            )),
-            VersionedWalletTransaction::from(mock_wallet_transaction(
-                String::from("3"),
-                WalletTxStatus::Pending,
-                String::from("ETH"),
-                mock_date,
-            )),
-        ];
+        // the storage returns the requested page of the transactions on the network
+        mock_user_repo
+            .expect_get_wallet_transactions()
+            .once()
+            .with(eq(USERNAME.to_string()), eq(String::from("ETH")), eq(1), eq(1))
+            .returning(move |_, network_key, start, limit| {
+                Ok(wallet_transactions_versioned
+                    .iter()
+                    .filter(|t| t.network_key() == network_key)
+                    .skip(start)
+                    .take(limit)
+                    .cloned()
+                    .collect())
+            });
 
         mock_user_repo
-            .expect_set_wallet_transactions()
+            .expect_insert_wallet_transaction()
             .once()
-            .with(eq(USERNAME.to_string()), eq(expected.clone()))
+            .with(
+                eq(USERNAME.to_string()),
+                eq(VersionedWalletTransaction::from(mock_wallet_transaction(
+                    String::from("2"),
+                    WalletTxStatus::Confirmed, // this one
+                    String::from("ETH"),
+                    mock_date,
+                ))),
+            )
             .returning(|_, _| Ok(()));
 
         sdk.repo = Some(Box::new(mock_user_repo));
@@ -1454,6 +2320,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(mock_wallet_manager),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         });
 
         sdk.set_networks(example_api_networks());
@@ -1496,25 +2363,22 @@ mod tests {
         ))];
 
         let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_set_selected_network()
+            .once()
+            .returning(|_, _| Ok(()));
         mock_user_repo.expect_get().returning(move |_| {
             Ok(UserEntity {
-                user_id: None,
-                username: USERNAME.to_string(),
                 encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
-                salt: SALT.into(),
-                is_kyc_verified: false,
-                kyc_type: KycType::Undefined,
-                viviswap_state: None,
-                local_share: None,
-                wallet_transactions: Vec::new(),
-                wallet_transactions_versioned: wallet_transactions.clone(),
+                ..example_user_entity(USERNAME)
             })
         });
 
         mock_user_repo
-            .expect_set_wallet_transactions()
+            .expect_get_wallet_transactions()
             .once()
-            .returning(|_, _| Ok(()));
+            .returning(move |_, _, _, _| Ok(wallet_transactions.clone()));
+        mock_user_repo.expect_insert_wallet_transaction().never();
 
         sdk.repo = Some(Box::new(mock_user_repo));
 
@@ -1533,6 +2397,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(mock_wallet_manager),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         });
 
         sdk.set_networks(example_api_networks());
@@ -1579,28 +2444,16 @@ mod tests {
             WalletTransaction::from(tx_1),
         ];
 
-        let mut mock_user_repo = MockUserRepo::new();
-        mock_user_repo.expect_get().returning(move |_| {
-            Ok(UserEntity {
-                user_id: None,
-                username: USERNAME.to_string(),
-                encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
-                salt: SALT.into(),
-                is_kyc_verified: false,
-                kyc_type: KycType::Undefined,
-                viviswap_state: None,
-                local_share: None,
-                wallet_transactions: Vec::new(),
-                wallet_transactions_versioned: wallet_transactions.clone(),
-            })
-        });
-
-        mock_user_repo
-            .expect_set_wallet_transactions()
-            .once()
-            .returning(|_, _| Ok(()));
-
-        sdk.repo = Some(Box::new(mock_user_repo));
+        let mut repo = UserRepoImpl::new(MemoryUserStorage::new());
+        repo.create(&UserEntity {
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            ..example_user_entity(USERNAME)
+        })
+        .unwrap();
+        for transaction in wallet_transactions {
+            repo.insert_wallet_transaction(USERNAME, transaction).unwrap();
+        }
+        sdk.repo = Some(Box::new(repo));
 
         let mut mock_wallet_manager = MockWalletManager::new();
         mock_wallet_manager.expect_try_get().returning(move |_, _, _, _, _, _| {
@@ -1617,6 +2470,7 @@ mod tests {
             username: USERNAME.into(),
             wallet_manager: Box::new(mock_wallet_manager),
             mnemonic_derivation_options: Default::default(),
+            network_derivation_options: Default::default(),
         });
 
         sdk.set_networks(example_api_networks());
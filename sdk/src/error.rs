@@ -39,6 +39,10 @@ pub enum Error {
     #[error("Error while setting the configuration: {0}")]
     SetConfig(String),
 
+    /// Error occurs when a function needs a feature that is switched off in the config
+    #[error("The feature {0} is disabled in the config")]
+    FeatureDisabled(String),
+
     /// Error occurs when user is not initialized
     #[error("User is not initialized")]
     UserNotInitialized,
@@ -73,6 +77,22 @@ pub enum Error {
     #[error("ParseChainIdError: {0}")]
     ParseChainIdError(String),
 
+    /// Error raises if a user data archive cannot be read or does not match the user
+    #[error("Invalid user data archive: {0}")]
+    InvalidUserDataArchive(String),
+
+    /// Error raises if no exchange rate is available for a network, currency and date
+    #[error("No exchange rate available: {0}")]
+    ExchangeRateUnavailable(String),
+
+    /// Error raises if a custom network is invalid or does not match the chain it points to
+    #[error("Invalid custom network: {0}")]
+    InvalidCustomNetwork(String),
+
+    /// Error raises if a purchase is requested on a custom network
+    #[error("Network with id {0} does not support purchases.")]
+    PurchaseNotSupported(String),
+
     /// Error raises if something failed to parse
     #[error("Parse error: {0}")]
     Parse(String),
@@ -82,11 +102,17 @@ pub enum Error {
     #[serde(serialize_with = "debug_string")]
     CryptoAmount(#[from] etopay_wallet::types::CryptoAmountError),
 
-    /// Error from fern logger
+    /// Error occurs if the log file cannot be opened
     #[cfg(not(target_arch = "wasm32"))]
     #[serde(serialize_with = "display_string")]
     #[error("LoggerInit error: {0}")]
-    LoggerInit(fern_logger::Error),
+    LoggerInit(std::io::Error),
+
+    /// Error occurs if the log files cannot be read for exporting
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(serialize_with = "display_string")]
+    #[error("Could not export the logs: {0}")]
+    LogExport(std::io::Error),
 
     /// Error occurs in sdk backend (api)
     #[error("BackendApi error: {0}")]
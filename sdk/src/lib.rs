@@ -15,9 +15,18 @@ mod user;
 mod wallet;
 pub use wallet::error::{ErrorKind, WalletError};
 
+/// Pluggable storage for the secrets of a user
+pub use user::secret_store;
+
+/// Pluggable storage for the users, see [`core::Sdk::set_user_storage`]
+pub use user::{UserKvStorage, error::UserKvStorageError};
+
 #[cfg(not(target_arch = "wasm32"))]
 mod logger;
 
+/// Pluggable telemetry hooks, see [`core::Sdk::set_telemetry`]
+pub mod telemetry;
+
 pub use wallet::*;
 
 /// mod used for sdk unit-test utilities
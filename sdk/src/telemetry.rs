@@ -0,0 +1,300 @@
+//! Telemetry hooks
+//!
+//! The SDK reports [`TelemetryEvent`]s, like the latency of backend and RPC calls, failed share
+//! downloads and the steps of the KYC process, to the [`Telemetry`] set with
+//! [`crate::core::Sdk::set_telemetry`]. Each SDK instance reports to its own telemetry, and nothing
+//! is recorded until an implementation is set.
+//!
+//! With the `tracing` feature, the public methods of the [`crate::core::Sdk`], the backend calls
+//! and the RPC calls are additionally wrapped in [`tracing`](https://docs.rs/tracing) spans. With
+//! the `opentelemetry` feature, [`OpenTelemetryMetrics`] records the events as OpenTelemetry
+//! metrics.
+
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Receives the [`TelemetryEvent`]s of the SDK
+pub trait Telemetry: Send + Sync {
+    /// Record an event. This is called on the task that produced the event, so it should return
+    /// quickly and hand the event off if it needs to do more work.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to record.
+    fn record(&self, event: &TelemetryEvent);
+}
+
+/// An event reported to the [`Telemetry`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum TelemetryEvent {
+    /// A request to the backend finished, including all retries
+    BackendCall {
+        /// The HTTP method of the request
+        method: String,
+        /// The path of the request relative to the backend url, without query parameters
+        path: String,
+        /// The status of the response, or none if no response was received
+        status: Option<u16>,
+        /// How often the request was sent
+        attempts: u32,
+        /// The time until the last response or error in milliseconds
+        duration_ms: u64,
+    },
+    /// A call to the wallet of a network finished, which usually queries the node of the network.
+    /// The success rate of sending transactions is reported with the operation `send_amount`.
+    RpcCall {
+        /// The key of the network
+        network_key: String,
+        /// The called operation, e.g. `get_balance` or `send_amount`
+        operation: &'static str,
+        /// Whether the call succeeded
+        success: bool,
+        /// The duration of the call in milliseconds
+        duration_ms: u64,
+    },
+    /// A share of the wallet was requested from the backend
+    ShareDownload {
+        /// The requested share, `recovery` or `backup`
+        share: &'static str,
+        /// The result of the download
+        outcome: ShareDownloadOutcome,
+    },
+    /// A step of the KYC process was submitted
+    KycStep {
+        /// The KYC provider, `viviswap` or `postident`
+        provider: &'static str,
+        /// The submitted step, e.g. `personal_details`
+        step: &'static str,
+        /// Whether the step was accepted
+        success: bool,
+    },
+}
+
+/// The result of a share download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareDownloadOutcome {
+    /// The share was downloaded
+    Downloaded,
+    /// No share is stored in the backend
+    NotAvailable,
+    /// The download failed
+    Failed,
+}
+
+/// The telemetry set with [`crate::core::Sdk::set_telemetry`], passed on to the backend calls and
+/// the wallets of the SDK instance
+#[derive(Clone, Default)]
+pub(crate) struct TelemetryHook(Option<Arc<dyn Telemetry>>);
+
+impl TelemetryHook {
+    pub(crate) fn new(telemetry: Option<Arc<dyn Telemetry>>) -> Self {
+        Self(telemetry)
+    }
+
+    /// Record an event if a telemetry is set. The event is only created in that case.
+    pub(crate) fn record(&self, event: impl FnOnce() -> TelemetryEvent) {
+        if let Some(telemetry) = &self.0 {
+            telemetry.record(&event());
+        }
+    }
+
+    /// Record the result of a submitted KYC step and pass it on
+    #[cfg(any(feature = "postident", feature = "viviswap-kyc"))]
+    pub(crate) fn kyc_step<T, E>(
+        &self,
+        provider: &'static str,
+        step: &'static str,
+        result: std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        self.record(|| TelemetryEvent::KycStep {
+            provider,
+            step,
+            success: result.is_ok(),
+        });
+        result
+    }
+}
+
+impl std::fmt::Debug for TelemetryHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TelemetryHook").field(&self.0.is_some()).finish()
+    }
+}
+
+/// Records the [`TelemetryEvent`]s as OpenTelemetry metrics
+///
+/// | Event          | Metric                          | Attributes                   |
+/// |----------------|---------------------------------|------------------------------|
+/// | BackendCall    | `etopay.backend.duration` (ms)  | `method`, `path`, `status`   |
+/// | RpcCall        | `etopay.rpc.duration` (ms)      | `network`, `operation`, `success` |
+/// | ShareDownload  | `etopay.share.downloads`        | `share`, `outcome`           |
+/// | KycStep        | `etopay.kyc.steps`              | `provider`, `step`, `success` |
+///
+/// The exporter is configured by the application with the meter provider of the meter.
+#[cfg(feature = "opentelemetry")]
+#[derive(Debug)]
+pub struct OpenTelemetryMetrics {
+    backend_duration: opentelemetry::metrics::Histogram<f64>,
+    rpc_duration: opentelemetry::metrics::Histogram<f64>,
+    share_downloads: opentelemetry::metrics::Counter<u64>,
+    kyc_steps: opentelemetry::metrics::Counter<u64>,
+}
+
+#[cfg(feature = "opentelemetry")]
+impl OpenTelemetryMetrics {
+    /// Create the instruments with a meter.
+    ///
+    /// # Arguments
+    ///
+    /// * `meter` - The meter to create the instruments with.
+    pub fn new(meter: &opentelemetry::metrics::Meter) -> Self {
+        Self {
+            backend_duration: meter
+                .f64_histogram("etopay.backend.duration")
+                .with_unit("ms")
+                .with_description("Duration of the requests to the backend")
+                .build(),
+            rpc_duration: meter
+                .f64_histogram("etopay.rpc.duration")
+                .with_unit("ms")
+                .with_description("Duration of the calls to the wallets of the networks")
+                .build(),
+            share_downloads: meter
+                .u64_counter("etopay.share.downloads")
+                .with_description("Downloads of wallet shares from the backend")
+                .build(),
+            kyc_steps: meter
+                .u64_counter("etopay.kyc.steps")
+                .with_description("Submitted steps of the KYC process")
+                .build(),
+        }
+    }
+
+    /// Create the instruments with the meter `etopay_sdk` of the global meter provider.
+    pub fn global() -> Self {
+        Self::new(&opentelemetry::global::meter("etopay_sdk"))
+    }
+}
+
+/// Converts a duration in milliseconds to the value of a histogram. Durations above `u32::MAX`
+/// milliseconds (49 days) are capped, so that the conversion is lossless.
+#[cfg(feature = "opentelemetry")]
+fn histogram_ms(duration_ms: u64) -> f64 {
+    f64::from(u32::try_from(duration_ms).unwrap_or(u32::MAX))
+}
+
+#[cfg(feature = "opentelemetry")]
+impl Telemetry for OpenTelemetryMetrics {
+    fn record(&self, event: &TelemetryEvent) {
+        use opentelemetry::KeyValue;
+
+        match event {
+            TelemetryEvent::BackendCall {
+                method,
+                path,
+                status,
+                duration_ms,
+                ..
+            } => self.backend_duration.record(
+                histogram_ms(*duration_ms),
+                &[
+                    KeyValue::new("method", method.clone()),
+                    KeyValue::new("path", path.clone()),
+                    KeyValue::new("status", status.map_or(0, i64::from)),
+                ],
+            ),
+            TelemetryEvent::RpcCall {
+                network_key,
+                operation,
+                success,
+                duration_ms,
+            } => self.rpc_duration.record(
+                histogram_ms(*duration_ms),
+                &[
+                    KeyValue::new("network", network_key.clone()),
+                    KeyValue::new("operation", *operation),
+                    KeyValue::new("success", *success),
+                ],
+            ),
+            TelemetryEvent::ShareDownload { share, outcome } => self.share_downloads.add(
+                1,
+                &[
+                    KeyValue::new("share", *share),
+                    KeyValue::new("outcome", format!("{outcome:?}").to_lowercase()),
+                ],
+            ),
+            TelemetryEvent::KycStep {
+                provider,
+                step,
+                success,
+            } => self.kyc_steps.add(
+                1,
+                &[
+                    KeyValue::new("provider", *provider),
+                    KeyValue::new("step", *step),
+                    KeyValue::new("success", *success),
+                ],
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use std::sync::{Mutex, PoisonError};
+
+    /// A [`Telemetry`] keeping the recorded events
+    #[derive(Debug, Default)]
+    pub(crate) struct RecordingTelemetry {
+        pub(crate) events: Mutex<Vec<TelemetryEvent>>,
+    }
+
+    impl Telemetry for RecordingTelemetry {
+        fn record(&self, event: &TelemetryEvent) {
+            self.events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(event.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_without_telemetry_does_not_create_event() {
+        let telemetry = TelemetryHook::default();
+
+        telemetry.record(|| unreachable!("the event must not be created without a telemetry"));
+    }
+
+    #[cfg(any(feature = "postident", feature = "viviswap-kyc"))]
+    #[test]
+    fn test_kyc_step_records_result() {
+        use super::testing::RecordingTelemetry;
+
+        // Arrange
+        let recording = Arc::new(RecordingTelemetry::default());
+        let telemetry = TelemetryHook::new(Some(recording.clone()));
+
+        // Act
+        let result = telemetry.kyc_step("viviswap", "personal_details", Err::<(), _>("rejected"));
+
+        // Assert
+        assert_eq!(result, Err("rejected"));
+        assert_eq!(
+            *recording.events.lock().unwrap(),
+            vec![TelemetryEvent::KycStep {
+                provider: "viviswap",
+                step: "personal_details",
+                success: false,
+            }]
+        );
+    }
+}
@@ -99,15 +99,37 @@ pub async fn set_config() -> (Server, Config, CleanUp) {
     (srv, config, cleanup)
 }
 
+/// A fresh [`UserEntity`] with every optional field empty, for use with struct update syntax in tests.
+pub fn example_user_entity(username: &str) -> UserEntity {
+    UserEntity {
+        user_id: None,
+        username: username.to_string(),
+        encrypted_password: None,
+        salt: SALT.into(),
+        is_kyc_verified: false,
+        kyc_type: KycType::Undefined,
+        viviswap_state: None,
+        local_share: None,
+        wallet_transactions: Vec::new(),
+        wallet_transactions_versioned: Vec::new(),
+        address_labels: Vec::new(),
+        address_book: Vec::new(),
+        pin_attempts: Default::default(),
+        schema_version: crate::user::migration::CURRENT_SCHEMA_VERSION,
+        selected_network: None,
+        network_derivation_options: Default::default(),
+        custom_networks: Vec::new(),
+        offline_cache: Default::default(),
+    }
+}
+
 pub fn example_get_user(key: SwapPaymentDetailKey, verified: bool, times: usize, kyc_type: KycType) -> MockUserRepo {
     let mut mock_user_repo = MockUserRepo::new();
+    mock_user_repo.expect_set_selected_network().returning(|_, _| Ok(()));
     mock_user_repo.expect_get().times(times).returning(move |r1| {
         assert_eq!(r1, USERNAME);
         Ok(UserEntity {
-            user_id: None,
-            username: USERNAME.to_string(),
             encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
-            salt: SALT.into(),
             is_kyc_verified: verified,
             kyc_type: kyc_type.to_owned(),
             viviswap_state: Some(ViviswapState {
@@ -149,9 +171,7 @@ pub fn example_get_user(key: SwapPaymentDetailKey, verified: bool, times: usize,
                     ]),
                 }),
             }),
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            ..example_user_entity(USERNAME)
         })
     });
     mock_user_repo
@@ -309,6 +329,7 @@ pub fn example_exchange_rate_response() -> GetCourseResponse {
         course: Course {
             course: dec!(1.0).into(),
             date: "2222-22-22".into(),
+            currency: None,
         },
     }
 }
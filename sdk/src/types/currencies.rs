@@ -63,12 +63,54 @@ impl std::fmt::Display for Currency {
     }
 }
 
+/// Fiat currencies that crypto amounts can be valued in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum FiatCurrency {
+    /// Euro
+    #[default]
+    Eur,
+    /// US Dollar
+    Usd,
+    /// Swiss Franc
+    Chf,
+}
+
+impl TryFrom<String> for FiatCurrency {
+    type Error = TypeError;
+    /// Convert from String to FiatCurrency, used at the API boundary to interface with the bindings.
+    fn try_from(currency: String) -> Result<Self> {
+        match currency.to_lowercase().as_str() {
+            "eur" => Ok(Self::Eur),
+            "usd" => Ok(Self::Usd),
+            "chf" => Ok(Self::Chf),
+            _ => Err(TypeError::InvalidCurrency(currency)),
+        }
+    }
+}
+
+// the display implementation must be compatible with TryFrom<String> since it is part of the
+// public binding interface, and is also the ISO 4217 code sent to the backend.
+impl std::fmt::Display for FiatCurrency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FiatCurrency::Eur => write!(f, "EUR"),
+            FiatCurrency::Usd => write!(f, "USD"),
+            FiatCurrency::Chf => write!(f, "CHF"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::types::currencies::Currency;
+    use crate::types::currencies::{Currency, FiatCurrency};
 
     #[rstest::rstest]
     fn test_display_roundtrip(#[values(Currency::Iota, Currency::Eth)] c: Currency) {
         assert_eq!(c, Currency::try_from(c.to_string()).unwrap());
     }
+
+    #[rstest::rstest]
+    fn test_fiat_display_roundtrip(#[values(FiatCurrency::Eur, FiatCurrency::Usd, FiatCurrency::Chf)] c: FiatCurrency) {
+        assert_eq!(c, FiatCurrency::try_from(c.to_string()).unwrap());
+    }
 }
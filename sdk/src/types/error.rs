@@ -12,6 +12,10 @@ pub enum TypeError {
     #[error("Password should not be empty")]
     EmptyPassword,
 
+    /// Error raises if the BIP39 passphrase is empty
+    #[error("Passphrase should not be empty")]
+    EmptyPassphrase,
+
     /// Error occurs when the user password is weak
     #[error("Weak password")]
     WeakPassword,
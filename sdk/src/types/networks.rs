@@ -1 +1,38 @@
 pub use api_types::api::networks::{ApiNetwork, ApiProtocol};
+use std::collections::BTreeMap;
+
+/// Results of an operation on several networks, by network key. The networks where the operation
+/// failed are reported separately, so that one unavailable network does not fail the others.
+#[derive(Debug)]
+pub struct PerNetwork<T> {
+    /// The results of the networks where the operation succeeded
+    pub values: BTreeMap<String, T>,
+    /// The errors of the networks where the operation failed
+    pub failures: BTreeMap<String, crate::Error>,
+}
+
+impl<T> Default for PerNetwork<T> {
+    fn default() -> Self {
+        Self {
+            values: BTreeMap::new(),
+            failures: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> FromIterator<(String, crate::Result<T>)> for PerNetwork<T> {
+    fn from_iter<I: IntoIterator<Item = (String, crate::Result<T>)>>(iter: I) -> Self {
+        let mut results = Self::default();
+        for (network_key, result) in iter {
+            match result {
+                Ok(value) => {
+                    results.values.insert(network_key, value);
+                }
+                Err(e) => {
+                    results.failures.insert(network_key, e);
+                }
+            }
+        }
+        results
+    }
+}
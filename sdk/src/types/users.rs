@@ -4,8 +4,16 @@ use crate::{
     types::viviswap::ViviswapState,
     wallet_manager::{WalletManager, WalletManagerImpl},
 };
-use etopay_wallet::{MnemonicDerivationOption, types::WalletTxInfo};
+use api_types::api::networks::ApiNetwork;
+use chrono::{DateTime, Utc};
+use etopay_wallet::{
+    MnemonicDerivationOption,
+    types::{CryptoAmount, WalletTxInfo},
+};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 /// Struct for storing a user in the database
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -29,12 +37,170 @@ pub struct UserEntity {
     /// The local share from the SSS scheme, stored as a string (same as in the backend)
     pub local_share: Option<String>,
 
-    /// User wallet transactions (deprecated)
+    /// User wallet transactions (deprecated, moved to `wallet_transactions_versioned` by the
+    /// schema migration and only kept to read the stored layout)
     pub wallet_transactions: Vec<WalletTxInfo>,
 
     /// User wallet transactions (versioned)
     #[serde(default)]
     pub wallet_transactions_versioned: Vec<VersionedWalletTransaction>,
+
+    /// Labels the user attached to their own wallet addresses
+    #[serde(default)]
+    pub address_labels: Vec<AddressLabel>,
+
+    /// Addresses of other parties saved by the user
+    #[serde(default)]
+    pub address_book: Vec<AddressBookEntry>,
+
+    /// Wrong pin attempts, used to protect the pin against brute-force attacks
+    #[serde(default)]
+    pub pin_attempts: PinAttempts,
+
+    /// Schema version of the stored entity, used to migrate entities stored by older versions
+    #[serde(default)]
+    pub schema_version: u32,
+
+    /// The last values fetched from the network, returned while offline
+    #[serde(default)]
+    pub offline_cache: OfflineCache,
+
+    /// The key of the network selected by the user
+    #[serde(default)]
+    pub selected_network: Option<String>,
+
+    /// The derivation options selected by the user per network key
+    #[serde(default)]
+    pub network_derivation_options: HashMap<String, MnemonicDerivationOption>,
+
+    /// Networks added locally by the user, in addition to the networks served by the backend
+    #[serde(default)]
+    pub custom_networks: Vec<ApiNetwork>,
+}
+
+/// Wrong pin attempts of a user since the last correct pin
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PinAttempts {
+    /// Number of consecutive wrong pin attempts
+    pub failed_attempts: u32,
+    /// Unix timestamp (in seconds) of the last wrong pin attempt
+    pub last_failed_at: Option<i64>,
+    /// Whether the wallet is locked because of too many wrong pin attempts
+    pub locked: bool,
+}
+
+/// Policy to protect the pin against brute-force attacks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinPolicy {
+    /// Number of wrong pin attempts that are allowed without delay
+    pub attempts_before_backoff: u32,
+    /// Delay enforced after the first attempt exceeding `attempts_before_backoff`. The delay is
+    /// doubled with every further wrong attempt.
+    pub backoff_base: Duration,
+    /// Upper bound of the delay between two pin attempts
+    pub max_backoff: Duration,
+    /// Number of wrong pin attempts after which the wallet is locked. A locked wallet can only be
+    /// unlocked by restoring it from the mnemonic or a backup.
+    pub max_attempts: u32,
+    /// If set, the local share is deleted after this many wrong pin attempts. The wallet can then
+    /// only be restored with the recovery and backup shares, the mnemonic or a backup.
+    pub wipe_local_share_after: Option<u32>,
+}
+
+impl Default for PinPolicy {
+    fn default() -> Self {
+        Self {
+            attempts_before_backoff: 3,
+            backoff_base: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(60 * 60),
+            max_attempts: 10,
+            wipe_local_share_after: None,
+        }
+    }
+}
+
+impl PinPolicy {
+    /// The delay to enforce before the next pin attempt after `failed_attempts` wrong attempts,
+    /// or [`None`] if no delay is needed.
+    pub fn backoff(&self, failed_attempts: u32) -> Option<Duration> {
+        let exponent = failed_attempts.checked_sub(self.attempts_before_backoff)?;
+        let factor = 2u32.saturating_pow(exponent);
+        Some(self.backoff_base.saturating_mul(factor).min(self.max_backoff))
+    }
+}
+
+/// A label attached to one of the user's own wallet addresses
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct AddressLabel {
+    /// The key of the network the address belongs to
+    pub network_key: String,
+    /// The labelled address
+    pub address: String,
+    /// The label given by the user
+    pub label: String,
+}
+
+/// An entry in the user's address book
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct AddressBookEntry {
+    /// The key of the network the address belongs to
+    pub network_key: String,
+    /// The address of the contact
+    pub address: String,
+    /// The name of the contact
+    pub name: String,
+}
+
+/// A value together with the time it was fetched
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Cached<T> {
+    /// The value
+    pub value: T,
+    /// When the value was fetched
+    pub updated_at: DateTime<Utc>,
+    /// Whether fetching failed and the value is the last known value instead
+    #[serde(default)]
+    pub stale: bool,
+}
+
+impl<T> Cached<T> {
+    /// Wrap a value that was just fetched
+    pub fn fresh(value: T) -> Self {
+        Self {
+            value,
+            updated_at: Utc::now(),
+            stale: false,
+        }
+    }
+
+    /// Mark the value as returned because fetching failed
+    pub fn into_stale(self) -> Self {
+        Self { stale: true, ..self }
+    }
+}
+
+/// The last known balance of the wallet on a network and derivation path
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct CachedBalance {
+    /// The key of the network
+    pub network_key: String,
+    /// The account and index the address was derived with
+    pub derivation_options: MnemonicDerivationOption,
+    /// The balance of the address
+    pub balance: Cached<CryptoAmount>,
+}
+
+/// The last values fetched from the network, kept in the user repository to be returned while
+/// the device is offline
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct OfflineCache {
+    /// The last known balance per network and derivation path
+    pub balances: Vec<CachedBalance>,
+    /// The last fetched list of networks
+    pub networks: Option<Cached<Vec<ApiNetwork>>>,
+    /// The last known exchange rate per network key
+    pub exchange_rates: HashMap<String, Cached<Decimal>>,
 }
 
 /// Struct to manage the state of the currently active (initialized) user
@@ -48,14 +214,24 @@ pub struct ActiveUser {
 
     /// The currently active [`MnemonicDerivationOption`]
     pub mnemonic_derivation_options: MnemonicDerivationOption,
+
+    /// The [`MnemonicDerivationOption`] selected per network key, applied when the network is selected
+    pub network_derivation_options: HashMap<String, MnemonicDerivationOption>,
 }
 
 impl From<UserEntity> for ActiveUser {
     fn from(entity: UserEntity) -> Self {
+        let mnemonic_derivation_options = entity
+            .selected_network
+            .as_ref()
+            .and_then(|network_key| entity.network_derivation_options.get(network_key))
+            .copied()
+            .unwrap_or_default();
         ActiveUser {
             wallet_manager: Box::new(WalletManagerImpl::new(&entity.username)),
             username: entity.username,
-            mnemonic_derivation_options: Default::default(),
+            mnemonic_derivation_options,
+            network_derivation_options: entity.network_derivation_options,
         }
     }
 }
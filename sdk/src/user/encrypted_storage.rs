@@ -0,0 +1,533 @@
+//! This module provides an implementation of [`UserKvStorage`] that encrypts the serialized
+//! [`UserEntity`] with AES-256-GCM before it is written to the underlying [`UserBytesStorage`].
+//! The key is bound to the device by storing it in a [`SecretStore`], see [`load_or_create_key`].
+//!
+//! If the underlying storage has a [`super::TransactionIndex`], every transaction is encrypted on its
+//! own and indexed by keyed digests of its network key and hash, so the index stays usable
+//! without revealing the transactions. Only the dates are stored unencrypted to sort the
+//! transactions.
+//!
+//! Entities and transactions that were stored unencrypted by previous versions are encrypted when
+//! the storage is opened. Afterwards, unencrypted entries are rejected.
+
+use super::error::{Result, UserKvStorageError};
+use super::secret_store::{SecretKind, SecretStore};
+use super::{TransactionRow, UserBytesStorage, UserKvStorage};
+use crate::tx_version::VersionedWalletTransaction;
+use crate::types::users::UserEntity;
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit, Payload},
+};
+use base64::prelude::*;
+use hmac::{Hmac, Mac};
+use log::info;
+use rand::RngCore;
+use secrecy::{ExposeSecret, SecretSlice};
+use sha2::Sha256;
+
+/// Prefix of every encrypted entry, used to tell encrypted and plaintext entries apart
+const ENCRYPTED_PREFIX: &[u8] = b"ETOPAY-ENC-V1";
+/// Length of the AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+/// Length of the database key in bytes
+const KEY_LEN: usize = 32;
+/// Context of the key of the transaction index digests, derived from the database key
+const INDEX_KEY_CONTEXT: &[u8] = b"ETOPAY-TX-INDEX-V1";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Load the key used to encrypt the user database from the [`SecretStore`], or generate and
+/// store a new random key if there is none yet.
+pub fn load_or_create_key(store: &dyn SecretStore) -> Result<SecretSlice<u8>> {
+    // the key is shared by all users and thus stored without a username
+    if let Some(key) = store.get("", SecretKind::DatabaseKey)? {
+        return Ok(key);
+    }
+
+    info!("Generating new user database key");
+    let mut bytes = vec![0u8; KEY_LEN];
+    rand::rng().fill_bytes(&mut bytes);
+    let key = SecretSlice::from(bytes);
+    store.set("", SecretKind::DatabaseKey, &key)?;
+    Ok(key)
+}
+
+/// Decode a base64 encoded 256-bit key, e.g. provided through an environment variable.
+pub fn decode_key(encoded: &str) -> Result<SecretSlice<u8>> {
+    let bytes = BASE64_STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| UserKvStorageError::Storage(format!("user database key is not valid base64: {e}")))?;
+    if bytes.len() != KEY_LEN {
+        return Err(UserKvStorageError::Storage(format!(
+            "user database key must be {KEY_LEN} bytes"
+        )));
+    }
+    Ok(SecretSlice::from(bytes))
+}
+
+/// Load the base64 encoded key used to encrypt the user database from a file, or generate a new
+/// random key and write it to the file if it does not exist yet. On unix the file is only readable
+/// by the current user.
+///
+/// The key is stored in plaintext and thus not bound to the device. It only protects the database
+/// if the key file is stored apart from it, prefer [`load_or_create_key`] with a platform keystore.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_or_create_key_file(path: &std::path::Path) -> Result<SecretSlice<u8>> {
+    use std::io::Write;
+
+    if path.exists() {
+        let encoded = std::fs::read_to_string(path)
+            .map_err(|e| UserKvStorageError::Storage(format!("could not read user database key file: {e}")))?;
+        return decode_key(&encoded);
+    }
+
+    info!("Generating new user database key file");
+    let mut bytes = vec![0u8; KEY_LEN];
+    rand::rng().fill_bytes(&mut bytes);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(BASE64_STANDARD.encode(&bytes).as_bytes()))
+        .map_err(|e| UserKvStorageError::Storage(format!("could not write user database key file: {e}")))?;
+    Ok(SecretSlice::from(bytes))
+}
+
+/// Implementation of [`UserKvStorage`] that encrypts the entities before storing them in the
+/// inner [`UserBytesStorage`].
+pub struct EncryptedUserStorage<I: UserBytesStorage> {
+    inner: I,
+    keys: StorageKeys,
+}
+
+/// The keys of an [`EncryptedUserStorage`], kept apart from the inner storage so both can be
+/// borrowed at the same time
+struct StorageKeys {
+    /// Encrypts the entities and transactions
+    cipher: Aes256Gcm,
+    /// Computes the lookup keys of the transaction index
+    index_mac: HmacSha256,
+}
+
+impl<I: UserBytesStorage> EncryptedUserStorage<I> {
+    /// Open the encrypted storage with the provided 256-bit key, encrypting all entries that are
+    /// still stored as plaintext.
+    pub fn open(inner: I, key: &SecretSlice<u8>) -> Result<Self> {
+        let cipher = Aes256Gcm::new_from_slice(key.expose_secret())
+            .map_err(|_| UserKvStorageError::Storage(format!("user database key must be {KEY_LEN} bytes")))?;
+
+        // use a separate key for the digests, derived from the database key
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(key.expose_secret())
+            .map_err(|_| UserKvStorageError::Storage(format!("user database key must be {KEY_LEN} bytes")))?;
+        mac.update(INDEX_KEY_CONTEXT);
+        let index_mac = <HmacSha256 as Mac>::new_from_slice(&mac.finalize().into_bytes())
+            .map_err(|_| UserKvStorageError::Storage(String::from("could not derive the index key")))?;
+
+        let mut storage = Self {
+            inner,
+            keys: StorageKeys { cipher, index_mac },
+        };
+        storage.migrate_plaintext()?;
+        Ok(storage)
+    }
+
+    /// Get the inner storage back
+    #[cfg(all(test, feature = "sqlite_repo"))]
+    pub(crate) fn into_inner(self) -> I {
+        self.inner
+    }
+
+    /// Encrypt all entries and transactions that are stored as plaintext.
+    fn migrate_plaintext(&mut self) -> Result<()> {
+        for username in self.inner.usernames()? {
+            if let Some(bytes) = self.inner.get_bytes(&username)? {
+                if !bytes.starts_with(ENCRYPTED_PREFIX) {
+                    info!("Encrypting plaintext user database entry");
+                    let encrypted = self.keys.encrypt(&username, &bytes)?;
+                    self.inner.set_bytes(&username, &encrypted)?;
+                }
+            }
+
+            let Some(index) = self.inner.transaction_index_mut() else {
+                continue;
+            };
+            let rows = index.get_all_rows(&username)?;
+            if rows.iter().all(|data| data.starts_with(ENCRYPTED_PREFIX)) {
+                continue;
+            }
+            info!("Encrypting plaintext transactions");
+            let rows = rows
+                .iter()
+                .map(|data| {
+                    let transaction = if data.starts_with(ENCRYPTED_PREFIX) {
+                        self.keys.decrypt_transaction(&username, data)?
+                    } else {
+                        rmp_serde::from_slice(data)?
+                    };
+                    self.keys.row(&username, &transaction)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            index.replace_rows(&username, &rows)?;
+        }
+        Ok(())
+    }
+
+    /// Return [`UserKvStorageError::UserNotFound`] if the user does not exist
+    fn check_exists(&self, username: &str) -> Result<()> {
+        if !self.exists(username)? {
+            return Err(UserKvStorageError::UserNotFound {
+                username: username.to_owned(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl StorageKeys {
+    /// Encrypt the plaintext, binding it to the username so entries cannot be swapped.
+    fn encrypt(&self, username: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: username.as_bytes(),
+                },
+            )
+            .map_err(|e| UserKvStorageError::Storage(format!("could not encrypt user entry: {e}")))?;
+
+        Ok([ENCRYPTED_PREFIX, &nonce, &ciphertext].concat())
+    }
+
+    /// Decrypt an entry created by [`Self::encrypt`]. Plaintext entries are only accepted by the
+    /// migration when the storage is opened, so entries without the prefix are rejected.
+    fn decrypt(&self, username: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+        let Some(encrypted) = bytes.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Err(UserKvStorageError::Storage(String::from("user entry is not encrypted")));
+        };
+        if encrypted.len() < NONCE_LEN {
+            return Err(UserKvStorageError::Storage(String::from(
+                "encrypted user entry is too short",
+            )));
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: username.as_bytes(),
+                },
+            )
+            .map_err(|e| UserKvStorageError::Storage(format!("could not decrypt user entry: {e}")))
+    }
+
+    /// Compute the lookup key of the index from the parts, each prefixed with its length so that
+    /// different parts cannot produce the same input.
+    fn lookup_key(&self, parts: &[&str]) -> String {
+        let mut mac = self.index_mac.clone();
+        for part in parts {
+            mac.update(&u64::try_from(part.len()).unwrap_or(u64::MAX).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        BASE64_URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    }
+
+    /// The lookup key of a network
+    fn network_lookup_key(&self, username: &str, network_key: &str) -> String {
+        self.lookup_key(&["network", username, network_key])
+    }
+
+    /// The lookup key of a transaction on a network
+    fn hash_lookup_key(&self, username: &str, network_key: &str, transaction_hash: &str) -> String {
+        self.lookup_key(&["transaction", username, network_key, transaction_hash])
+    }
+
+    /// The encrypted row of a transaction
+    fn row(&self, username: &str, transaction: &VersionedWalletTransaction) -> Result<TransactionRow> {
+        Ok(TransactionRow {
+            network_key: self.network_lookup_key(username, transaction.network_key()),
+            hash: self.hash_lookup_key(username, transaction.network_key(), transaction.transaction_hash()),
+            date: transaction.date().timestamp_millis(),
+            data: self.encrypt(username, &rmp_serde::to_vec(transaction)?)?,
+        })
+    }
+
+    /// Decrypt the data of a row created by [`Self::row`]
+    fn decrypt_transaction(&self, username: &str, data: &[u8]) -> Result<VersionedWalletTransaction> {
+        Ok(rmp_serde::from_slice(&self.decrypt(username, data)?)?)
+    }
+
+    /// Decrypt the data of a list of rows
+    fn decrypt_transactions(&self, username: &str, rows: Vec<Vec<u8>>) -> Result<Vec<VersionedWalletTransaction>> {
+        rows.iter()
+            .map(|data| self.decrypt_transaction(username, data))
+            .collect()
+    }
+}
+
+impl<I: UserBytesStorage> UserKvStorage for EncryptedUserStorage<I> {
+    fn get(&self, username: &str) -> Result<UserEntity> {
+        let bytes = self
+            .inner
+            .get_bytes(username)?
+            .ok_or_else(|| UserKvStorageError::UserNotFound {
+                username: username.to_owned(),
+            })?;
+
+        let plaintext = self.keys.decrypt(username, &bytes)?;
+        Ok(rmp_serde::from_slice(&plaintext)?)
+    }
+
+    fn delete(&mut self, username: &str) -> Result<()> {
+        self.inner.delete_bytes(username)
+    }
+
+    fn exists(&self, username: &str) -> Result<bool> {
+        Ok(self.inner.get_bytes(username)?.is_some())
+    }
+
+    fn set(&mut self, username: &str, value: &UserEntity) -> Result<()> {
+        let Some(index) = self.inner.transaction_index_mut() else {
+            let plaintext = rmp_serde::to_vec(value)?;
+            let encrypted = self.keys.encrypt(username, &plaintext)?;
+            return self.inner.set_bytes(username, &encrypted);
+        };
+
+        // the transactions of the entity, if any, are inserted into the index
+        let mut user = value.clone();
+        for transaction in std::mem::take(&mut user.wallet_transactions_versioned) {
+            index.upsert_row(username, &self.keys.row(username, &transaction)?)?;
+        }
+        let plaintext = rmp_serde::to_vec(&user)?;
+        let encrypted = self.keys.encrypt(username, &plaintext)?;
+        self.inner.set_bytes(username, &encrypted)
+    }
+
+    fn list_users(&self) -> Result<Vec<String>> {
+        self.inner.usernames()
+    }
+
+    fn insert_transaction(&mut self, username: &str, transaction: &VersionedWalletTransaction) -> Result<()> {
+        self.check_exists(username)?;
+        let Some(index) = self.inner.transaction_index_mut() else {
+            return super::insert_entity_transaction(self, username, transaction);
+        };
+        index.upsert_row(username, &self.keys.row(username, transaction)?)
+    }
+
+    fn get_transaction(
+        &self,
+        username: &str,
+        network_key: &str,
+        transaction_hash: &str,
+    ) -> Result<Option<VersionedWalletTransaction>> {
+        let Some(index) = self.inner.transaction_index() else {
+            return super::get_entity_transaction(self, username, network_key, transaction_hash);
+        };
+        let data = index.get_row(
+            username,
+            &self.keys.network_lookup_key(username, network_key),
+            &self.keys.hash_lookup_key(username, network_key, transaction_hash),
+        )?;
+        data.map(|data| self.keys.decrypt_transaction(username, &data))
+            .transpose()
+    }
+
+    fn get_transactions(
+        &self,
+        username: &str,
+        network_key: &str,
+        start: usize,
+        limit: usize,
+    ) -> Result<Vec<VersionedWalletTransaction>> {
+        let Some(index) = self.inner.transaction_index() else {
+            return super::get_entity_transactions(self, username, network_key, start, limit);
+        };
+        let rows = index.get_rows(
+            username,
+            &self.keys.network_lookup_key(username, network_key),
+            start,
+            limit,
+        )?;
+        self.keys.decrypt_transactions(username, rows)
+    }
+
+    fn get_all_transactions(&self, username: &str) -> Result<Vec<VersionedWalletTransaction>> {
+        let Some(index) = self.inner.transaction_index() else {
+            return Ok(self.get(username)?.wallet_transactions_versioned);
+        };
+        self.check_exists(username)?;
+        self.keys.decrypt_transactions(username, index.get_all_rows(username)?)
+    }
+
+    fn set_transactions(&mut self, username: &str, transactions: &[VersionedWalletTransaction]) -> Result<()> {
+        self.check_exists(username)?;
+        let Some(index) = self.inner.transaction_index_mut() else {
+            return super::set_entity_transactions(self, username, transactions);
+        };
+        let rows = transactions
+            .iter()
+            .map(|transaction| self.keys.row(username, transaction))
+            .collect::<Result<Vec<_>>>()?;
+        index.replace_rows(username, &rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing_utils::{ENCRYPTED_WALLET_PASSWORD, example_user_entity};
+
+    use crate::user::secret_store::MemorySecretStore;
+    use std::collections::HashMap;
+
+    /// Simple [`UserBytesStorage`] to inspect the stored bytes
+    #[derive(Default)]
+    struct BytesStorage(HashMap<String, Vec<u8>>);
+
+    impl UserBytesStorage for BytesStorage {
+        fn get_bytes(&self, username: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.0.get(username).cloned())
+        }
+
+        fn set_bytes(&mut self, username: &str, bytes: &[u8]) -> Result<()> {
+            self.0.insert(username.to_string(), bytes.to_vec());
+            Ok(())
+        }
+
+        fn delete_bytes(&mut self, username: &str) -> Result<()> {
+            self.0.remove(username);
+            Ok(())
+        }
+
+        fn usernames(&self) -> Result<Vec<String>> {
+            Ok(self.0.keys().cloned().collect())
+        }
+    }
+
+    fn user_entity(username: &str) -> UserEntity {
+        UserEntity {
+            encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
+            local_share: Some(String::from("local share")),
+            ..example_user_entity(username)
+        }
+    }
+
+    fn key() -> SecretSlice<u8> {
+        SecretSlice::from(vec![7u8; KEY_LEN])
+    }
+
+    #[test]
+    fn it_should_encrypt_entries() {
+        // Arrange
+        let mut storage = EncryptedUserStorage::open(BytesStorage::default(), &key()).unwrap();
+        let user = user_entity("alice");
+
+        // Act
+        storage.set("alice", &user).unwrap();
+
+        // Assert
+        let stored = storage.inner.get_bytes("alice").unwrap().unwrap();
+        assert!(stored.starts_with(ENCRYPTED_PREFIX));
+        assert!(!stored.windows(b"local share".len()).any(|w| w == b"local share"));
+        assert_eq!(storage.get("alice").unwrap(), user);
+        assert!(storage.exists("alice").unwrap());
+
+        storage.delete("alice").unwrap();
+        assert!(!storage.exists("alice").unwrap());
+    }
+
+    #[test]
+    fn it_should_migrate_plaintext_entries() {
+        // Arrange
+        let user = user_entity("alice");
+        let mut inner = BytesStorage::default();
+        inner.set_bytes("alice", &rmp_serde::to_vec(&user).unwrap()).unwrap();
+
+        // Act
+        let storage = EncryptedUserStorage::open(inner, &key()).unwrap();
+
+        // Assert
+        let stored = storage.inner.get_bytes("alice").unwrap().unwrap();
+        assert!(stored.starts_with(ENCRYPTED_PREFIX));
+        assert_eq!(storage.get("alice").unwrap(), user);
+    }
+
+    #[test]
+    fn it_should_reject_plaintext_entries_after_migration() {
+        // Arrange
+        let user = user_entity("alice");
+        let mut storage = EncryptedUserStorage::open(BytesStorage::default(), &key()).unwrap();
+
+        // Act
+        storage
+            .inner
+            .set_bytes("alice", &rmp_serde::to_vec(&user).unwrap())
+            .unwrap();
+
+        // Assert
+        assert!(storage.get("alice").is_err());
+    }
+
+    #[test]
+    fn it_should_fail_with_wrong_key() {
+        // Arrange
+        let mut storage = EncryptedUserStorage::open(BytesStorage::default(), &key()).unwrap();
+        storage.set("alice", &user_entity("alice")).unwrap();
+
+        // Act
+        let storage = EncryptedUserStorage::open(storage.inner, &SecretSlice::from(vec![8u8; KEY_LEN])).unwrap();
+
+        // Assert
+        assert!(storage.get("alice").is_err());
+    }
+
+    #[test]
+    fn it_should_not_decrypt_swapped_entries() {
+        // Arrange
+        let mut storage = EncryptedUserStorage::open(BytesStorage::default(), &key()).unwrap();
+        storage.set("alice", &user_entity("alice")).unwrap();
+        let alice = storage.inner.get_bytes("alice").unwrap().unwrap();
+
+        // Act
+        storage.inner.set_bytes("bob", &alice).unwrap();
+
+        // Assert
+        assert!(storage.get("bob").is_err());
+    }
+
+    #[test]
+    fn it_should_load_the_same_key() {
+        // Arrange
+        let store = MemorySecretStore::new();
+
+        // Act
+        let key = load_or_create_key(&store).unwrap();
+        let loaded_key = load_or_create_key(&store).unwrap();
+
+        // Assert
+        assert_eq!(key.expose_secret().len(), KEY_LEN);
+        assert_eq!(key.expose_secret(), loaded_key.expose_secret());
+    }
+
+    #[test]
+    fn it_should_decode_key() {
+        use base64::prelude::*;
+
+        let key = decode_key(&BASE64_STANDARD.encode([7u8; KEY_LEN])).unwrap();
+        assert_eq!(key.expose_secret(), &[7u8; KEY_LEN]);
+
+        decode_key(&BASE64_STANDARD.encode([7u8; 16])).unwrap_err();
+        decode_key("not base64!").unwrap_err();
+    }
+}
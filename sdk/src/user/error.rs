@@ -1,17 +1,28 @@
 /// A [`core::result::Result`] with [`UserKvStorageError`] as its error variant.
 pub type Result<T> = core::result::Result<T, UserKvStorageError>;
 
+/// Errors of a [`super::UserKvStorage`]
 #[derive(thiserror::Error, Debug)]
 pub enum UserKvStorageError {
     /// The user already exists in the KV storage.
     #[error("User already exists: {username}")]
-    UserAlreadyExists { username: String },
+    UserAlreadyExists {
+        /// The username of the user
+        username: String,
+    },
 
     /// The user is not found in the KV storage,
     #[error("User not found: {username}")]
-    UserNotFound { username: String },
+    UserNotFound {
+        /// The username of the user
+        username: String,
+    },
 
     /// An internal storage error happened (backend specific)
     #[error("Internal storage error: {0}")]
     Storage(String),
+
+    /// An error happened in the secret store (backend specific)
+    #[error("Secret store error: {0}")]
+    SecretStore(String),
 }
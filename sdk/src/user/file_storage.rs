@@ -2,6 +2,7 @@
 //! This is mainly used on systems that have access to the file system as it provides a persistent
 //! storage mechanism.
 
+use super::UserBytesStorage;
 use super::error::{Result, UserKvStorageError};
 use crate::types::users::UserEntity;
 use jammdb::DB;
@@ -34,25 +35,28 @@ impl From<jammdb::Error> for UserKvStorageError {
     }
 }
 
-impl super::UserKvStorage for FileUserStorage {
-    fn get(&self, username: &str) -> Result<UserEntity> {
+impl super::UserBytesStorage for FileUserStorage {
+    fn get_bytes(&self, username: &str) -> Result<Option<Vec<u8>>> {
         let tx = self.db.tx(true)?;
 
         // get the bucket we created in the last transaction
         let users_bucket = tx.get_or_create_bucket(DB_BUCKET)?;
 
-        let kv = users_bucket
-            .get_kv(username)
-            .ok_or_else(|| UserKvStorageError::UserNotFound {
-                username: username.to_owned(),
-            })?;
+        Ok(users_bucket.get_kv(username).map(|kv| kv.value().to_vec()))
+    }
 
-        let user: UserEntity = rmp_serde::from_slice(kv.value())?;
+    fn set_bytes(&mut self, username: &str, bytes: &[u8]) -> Result<()> {
+        let tx = self.db.tx(true)?;
+        let users_bucket = tx.get_or_create_bucket(DB_BUCKET)?;
+        users_bucket.put(username, bytes.to_vec())?;
 
-        Ok(user)
+        // commit the changes so they are saved to disk
+        tx.commit()?;
+
+        Ok(())
     }
 
-    fn delete(&mut self, username: &str) -> Result<()> {
+    fn delete_bytes(&mut self, username: &str) -> Result<()> {
         let tx = self.db.tx(true)?;
         let users_bucket = tx.get_or_create_bucket(DB_BUCKET)?;
 
@@ -68,23 +72,46 @@ impl super::UserKvStorage for FileUserStorage {
         Ok(())
     }
 
-    fn exists(&self, username: &str) -> Result<bool> {
+    fn usernames(&self) -> Result<Vec<String>> {
         let tx = self.db.tx(true)?;
         let users_bucket = tx.get_or_create_bucket(DB_BUCKET)?;
-        Ok(users_bucket.get_kv(username).is_some())
+        Ok(users_bucket
+            .kv_pairs()
+            .map(|kv| String::from_utf8_lossy(kv.key()).into_owned())
+            .collect())
     }
+}
 
-    fn set(&mut self, username: &str, value: &UserEntity) -> Result<()> {
+impl super::UserKvStorage for FileUserStorage {
+    fn get(&self, username: &str) -> Result<UserEntity> {
+        let bytes = self
+            .get_bytes(username)?
+            .ok_or_else(|| UserKvStorageError::UserNotFound {
+                username: username.to_owned(),
+            })?;
+
+        let user: UserEntity = rmp_serde::from_slice(&bytes)?;
+
+        Ok(user)
+    }
+
+    fn delete(&mut self, username: &str) -> Result<()> {
+        self.delete_bytes(username)
+    }
+
+    fn exists(&self, username: &str) -> Result<bool> {
         let tx = self.db.tx(true)?;
         let users_bucket = tx.get_or_create_bucket(DB_BUCKET)?;
+        Ok(users_bucket.get_kv(username).is_some())
+    }
 
+    fn set(&mut self, username: &str, value: &UserEntity) -> Result<()> {
         // serialize struct to bytes and store in bucket
         let user_bytes = rmp_serde::to_vec(&value)?;
-        users_bucket.put(username, user_bytes)?;
-
-        // commit the changes so they are saved to disk
-        tx.commit()?;
+        self.set_bytes(username, &user_bytes)
+    }
 
-        Ok(())
+    fn list_users(&self) -> Result<Vec<String>> {
+        self.usernames()
     }
 }
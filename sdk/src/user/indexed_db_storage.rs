@@ -0,0 +1,290 @@
+//! This module provides an implementation of [`super::UserKvStorage`] that uses the IndexedDB of
+//! the browser. In contrast to the local storage, IndexedDB is also available in web workers and
+//! service workers and is not limited to a few megabytes.
+//!
+//! IndexedDB only offers an asynchronous API, while [`super::UserKvStorage`] is synchronous. All
+//! entries are therefore loaded into memory when the storage is opened, reads are served from
+//! memory and writes are issued to IndexedDB immediately. The write transactions are executed in
+//! the order they are issued. A write that fails asynchronously is returned as error by the next
+//! call to the storage, and [`IndexedDbUserStorage::flush`] waits until all writes completed.
+
+use super::error::{Result, UserKvStorageError};
+use super::{UserBytesStorage, web_storage::BrowserLocalStorage};
+use crate::types::users::UserEntity;
+use log::{error, info, warn};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen_futures::{JsFuture, spawn_local};
+use web_sys::{
+    IdbDatabase, IdbFactory, IdbOpenDbRequest, IdbRequest, IdbTransaction, IdbTransactionMode, js_sys,
+    wasm_bindgen::{JsCast, JsValue, closure::Closure},
+};
+
+#[doc = r"The name of the IndexedDB database"]
+const DB_NAME: &str = "etopay";
+#[doc = r"The version of the IndexedDB database, increase when changing the object stores"]
+const DB_VERSION: u32 = 1;
+#[doc = r"The name of the object store holding the serialized users"]
+const STORE_NAME: &str = "users";
+
+thread_local! {
+    /// The open database connection. IndexedDB objects cannot be sent across threads, so the
+    /// connection is kept per thread instead of in the storage.
+    static DATABASE: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+
+    /// Completes with the last write transaction. Transactions on the same object store are
+    /// executed in order, so all previous writes completed once it completes.
+    static LAST_WRITE: RefCell<Option<js_sys::Promise>> = const { RefCell::new(None) };
+
+    /// The first write that failed asynchronously and was not yet reported
+    static WRITE_ERROR: RefCell<Option<UserKvStorageError>> = const { RefCell::new(None) };
+}
+
+fn storage_error(context: &str, e: JsValue) -> UserKvStorageError {
+    UserKvStorageError::Storage(format!("{context}: {e:?}"))
+}
+
+/// Get the IndexedDB factory of the current global scope, which is either a window or a worker.
+fn indexed_db_factory() -> Result<IdbFactory> {
+    let factory = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))
+        .map_err(|e| storage_error("no indexedDB object found", e))?;
+    factory
+        .dyn_into::<IdbFactory>()
+        .map_err(|e| storage_error("indexedDB should be web_sys::IdbFactory", e))
+}
+
+/// Wait for an [`IdbRequest`] to complete and return its result.
+async fn request_result(request: &IdbRequest) -> Result<JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    let result = JsFuture::from(promise).await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+
+    result.map_err(|e| storage_error("IndexedDB request failed", e))?;
+    request
+        .result()
+        .map_err(|e| storage_error("could not get IndexedDB request result", e))
+}
+
+/// Open the database, creating the object store on first use.
+async fn open_database() -> Result<IdbDatabase> {
+    let request: IdbOpenDbRequest = indexed_db_factory()?
+        .open_with_u32(DB_NAME, DB_VERSION)
+        .map_err(|e| storage_error("could not open IndexedDB", e))?;
+
+    let upgrade_request = request.clone();
+    let on_upgrade_needed = Closure::<dyn FnMut(JsValue)>::new(move |_event: JsValue| {
+        let Ok(db) = upgrade_request.result().and_then(|db| db.dyn_into::<IdbDatabase>()) else {
+            error!("Could not get IndexedDB database during upgrade");
+            return;
+        };
+        if !db.object_store_names().contains(STORE_NAME) {
+            if let Err(e) = db.create_object_store(STORE_NAME) {
+                error!("Could not create IndexedDB object store: {e:?}");
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade_needed.as_ref().unchecked_ref()));
+
+    let result = request_result(&request).await;
+    request.set_onupgradeneeded(None);
+
+    result?
+        .dyn_into::<IdbDatabase>()
+        .map_err(|e| storage_error("IndexedDB result should be web_sys::IdbDatabase", e))
+}
+
+/// Return the error of a write that failed asynchronously since the last call, if any.
+fn take_write_error() -> Result<()> {
+    match WRITE_ERROR.with_borrow_mut(Option::take) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// A promise that resolves when the transaction completes and rejects if it fails or is aborted.
+fn transaction_completion(tx: &IdbTransaction) -> js_sys::Promise {
+    js_sys::Promise::new(&mut |resolve, reject| {
+        tx.set_oncomplete(Some(&resolve));
+        tx.set_onerror(Some(&reject));
+        tx.set_onabort(Some(&reject));
+    })
+}
+
+/// Issue a write request in its own transaction on the open database. Fails if a previous write
+/// failed. The write completes asynchronously, a failure is recorded and returned by the next call
+/// to the storage. The returned promise completes with the transaction.
+fn issue_write(
+    username: &str,
+    request: impl FnOnce(&web_sys::IdbObjectStore) -> core::result::Result<IdbRequest, JsValue>,
+) -> Result<js_sys::Promise> {
+    take_write_error()?;
+
+    let completion = DATABASE.with_borrow(|db| {
+        let db = db
+            .as_ref()
+            .ok_or_else(|| UserKvStorageError::Storage(String::from("IndexedDB is not open")))?;
+        let tx = db
+            .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)
+            .map_err(|e| storage_error("could not start IndexedDB transaction", e))?;
+        let store = tx
+            .object_store(STORE_NAME)
+            .map_err(|e| storage_error("could not start IndexedDB transaction", e))?;
+        request(&store).map_err(|e| storage_error(&format!("could not write user {username}"), e))?;
+        Ok::<_, UserKvStorageError>(transaction_completion(&tx))
+    })?;
+
+    let username = username.to_string();
+    let completed = completion.clone();
+    spawn_local(async move {
+        if let Err(e) = JsFuture::from(completed).await {
+            error!("Could not write user {username} to IndexedDB: {e:?}");
+            WRITE_ERROR.with_borrow_mut(|error| {
+                error.get_or_insert_with(|| storage_error(&format!("could not write user {username}"), e));
+            });
+        }
+    });
+    LAST_WRITE.with_borrow_mut(|last| *last = Some(completion.clone()));
+    Ok(completion)
+}
+
+/// An implementation of [`super::UserKvStorage`] that uses the IndexedDB of the browser and
+/// rmp_serde to store user entities.
+pub struct IndexedDbUserStorage {
+    cache: HashMap<String, Vec<u8>>,
+}
+
+impl IndexedDbUserStorage {
+    /// Checks if IndexedDB is available in the current global scope
+    pub fn is_available() -> bool {
+        indexed_db_factory().is_ok()
+    }
+
+    /// Open the database and load all stored users into memory
+    pub async fn open() -> Result<Self> {
+        let db = open_database().await?;
+
+        let store = db
+            .transaction_with_str(STORE_NAME)
+            .and_then(|tx| tx.object_store(STORE_NAME))
+            .map_err(|e| storage_error("could not start IndexedDB transaction", e))?;
+        let keys_request = store
+            .get_all_keys()
+            .map_err(|e| storage_error("could not get IndexedDB keys", e))?;
+        let values_request = store
+            .get_all()
+            .map_err(|e| storage_error("could not get IndexedDB values", e))?;
+        let keys = js_sys::Array::from(&request_result(&keys_request).await?);
+        let values = js_sys::Array::from(&request_result(&values_request).await?);
+
+        // both requests return the entries sorted by key
+        let cache = keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(key, value)| Some((key.as_string()?, js_sys::Uint8Array::new(&value).to_vec())))
+            .collect();
+
+        DATABASE.with_borrow_mut(|database| *database = Some(db));
+        Ok(Self { cache })
+    }
+
+    /// Wait until all writes issued so far completed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first write that failed since the last call.
+    pub async fn flush() -> Result<()> {
+        if let Some(last) = LAST_WRITE.with_borrow_mut(Option::take) {
+            // a failure is recorded by the write itself
+            let _ = JsFuture::from(last).await;
+        }
+        take_write_error()
+    }
+
+    /// Move all users from the local storage that do not exist in IndexedDB yet. The entries are
+    /// copied as bytes, so encrypted entries stay encrypted. An entry is only removed from the
+    /// local storage once it has been written to IndexedDB. Entries that already exist in
+    /// IndexedDB are kept in the local storage untouched.
+    pub async fn migrate_from(&mut self, local_storage: &mut BrowserLocalStorage) -> Result<()> {
+        for username in local_storage.usernames()? {
+            if self.cache.contains_key(&username) {
+                warn!("User already exists in IndexedDB, not migrating it from local storage");
+                continue;
+            }
+            let Some(bytes) = local_storage.get_bytes(&username)? else {
+                continue;
+            };
+
+            info!("Migrating user from local storage to IndexedDB");
+            let completion = self.put(&username, &bytes)?;
+            JsFuture::from(completion)
+                .await
+                .map_err(|e| storage_error(&format!("could not migrate user {username}"), e))?;
+            local_storage.delete_bytes(&username)?;
+        }
+        Ok(())
+    }
+
+    fn put(&mut self, username: &str, bytes: &[u8]) -> Result<js_sys::Promise> {
+        let value = js_sys::Uint8Array::from(bytes);
+        let completion = issue_write(username, |store| {
+            store.put_with_key(&value, &JsValue::from_str(username))
+        })?;
+        self.cache.insert(username.to_string(), bytes.to_vec());
+        Ok(completion)
+    }
+}
+
+impl super::UserBytesStorage for IndexedDbUserStorage {
+    fn get_bytes(&self, username: &str) -> Result<Option<Vec<u8>>> {
+        take_write_error()?;
+        Ok(self.cache.get(username).cloned())
+    }
+
+    fn set_bytes(&mut self, username: &str, bytes: &[u8]) -> Result<()> {
+        self.put(username, bytes)?;
+        Ok(())
+    }
+
+    fn delete_bytes(&mut self, username: &str) -> Result<()> {
+        issue_write(username, |store| store.delete(&JsValue::from_str(username)))?;
+        self.cache.remove(username);
+        Ok(())
+    }
+
+    fn usernames(&self) -> Result<Vec<String>> {
+        Ok(self.cache.keys().cloned().collect())
+    }
+}
+
+impl super::UserKvStorage for IndexedDbUserStorage {
+    fn get(&self, username: &str) -> Result<UserEntity> {
+        let bytes = self
+            .get_bytes(username)?
+            .ok_or_else(|| UserKvStorageError::UserNotFound {
+                username: username.to_owned(),
+            })?;
+        Ok(rmp_serde::from_slice(&bytes)?)
+    }
+
+    fn delete(&mut self, username: &str) -> Result<()> {
+        self.delete_bytes(username)
+    }
+
+    fn exists(&self, username: &str) -> Result<bool> {
+        take_write_error()?;
+        Ok(self.cache.contains_key(username))
+    }
+
+    fn set(&mut self, username: &str, value: &UserEntity) -> Result<()> {
+        let bytes = rmp_serde::to_vec(value)?;
+        self.set_bytes(username, &bytes)
+    }
+
+    fn list_users(&self) -> Result<Vec<String>> {
+        self.usernames()
+    }
+}
@@ -46,29 +46,21 @@ impl super::UserKvStorage for MemoryUserStorage {
         self.db.insert(username.to_owned(), value.clone());
         Ok(())
     }
+
+    /// List all stored keys.
+    fn list_users(&self) -> Result<Vec<String>> {
+        Ok(self.db.keys().cloned().collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{
-        types::{newtypes::EncryptionSalt, users::KycType},
-        user::UserKvStorage,
-    };
+    use crate::testing_utils::example_user_entity;
+    use crate::user::UserKvStorage;
 
     fn create_user_entity() -> UserEntity {
-        UserEntity {
-            user_id: None,
-            username: String::from("username"),
-            encrypted_password: None,
-            salt: EncryptionSalt::generate(),
-            is_kyc_verified: false,
-            kyc_type: KycType::Undefined,
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
-        }
+        example_user_entity("username")
     }
     use testing::CleanUp;
 
@@ -0,0 +1,158 @@
+//! Schema versioning of the stored [`UserEntity`].
+//!
+//! Every entity carries the schema version it was written with. Entities stored by older versions
+//! of the SDK are upgraded by running the missing migration steps in order when they are loaded
+//! by the [`super::repository::UserRepoImpl`], which writes the upgraded entity back right away.
+//!
+//! To change the stored layout, add a step to [`MIGRATIONS`] and a fixture of the previous layout
+//! to the tests.
+
+use super::error::Result;
+use crate::tx_version::{VersionedWalletTransaction, WalletTxInfoV1};
+use crate::types::users::UserEntity;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+
+/// A migration step, upgrading an entity from schema version `n` to `n + 1`
+type Migration = fn(&mut UserEntity) -> Result<()>;
+
+/// The migration steps, the step at index `n` upgrades an entity from schema version `n`
+const MIGRATIONS: &[Migration] = &[migrate_legacy_transactions];
+
+/// The schema version of entities written by this version of the SDK, one step per entry of
+/// [`MIGRATIONS`]
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Run all migration steps that have not been applied to the entity yet.
+///
+/// Returns `true` if the entity was changed.
+pub fn migrate(user: &mut UserEntity) -> Result<bool> {
+    if user.schema_version > CURRENT_SCHEMA_VERSION {
+        warn!(
+            "User entity has schema version {}, which is newer than the supported version {CURRENT_SCHEMA_VERSION}",
+            user.schema_version
+        );
+        return Ok(false);
+    }
+
+    let from = user.schema_version;
+    while let Some(migration) = usize::try_from(user.schema_version)
+        .ok()
+        .and_then(|version| MIGRATIONS.get(version))
+    {
+        info!("Migrating user entity from schema version {}", user.schema_version);
+        migration(user)?;
+        user.schema_version += 1;
+    }
+    Ok(user.schema_version != from)
+}
+
+/// Version 0 -> 1: move the deprecated `wallet_transactions` to `wallet_transactions_versioned`.
+/// They are converted to [`VersionedWalletTransaction::V1`] and upgraded to the latest transaction
+/// format the next time the transaction list is fetched.
+fn migrate_legacy_transactions(user: &mut UserEntity) -> Result<()> {
+    for tx in std::mem::take(&mut user.wallet_transactions) {
+        let exists = user
+            .wallet_transactions_versioned
+            .iter()
+            .any(|t| t.network_key() == tx.network_key && t.transaction_hash() == tx.transaction_hash);
+        if exists {
+            continue;
+        }
+
+        let date = DateTime::parse_from_rfc3339(&tx.date)
+            .map(|date| date.with_timezone(&Utc))
+            .unwrap_or_else(|e| {
+                warn!("Invalid date of legacy transaction {}: {e}", tx.transaction_hash);
+                DateTime::UNIX_EPOCH
+            });
+
+        user.wallet_transactions_versioned
+            .push(VersionedWalletTransaction::V1(WalletTxInfoV1 {
+                date,
+                block_number_hash: tx.block_number_hash,
+                transaction_hash: tx.transaction_hash,
+                sender: tx.sender,
+                receiver: tx.receiver,
+                amount: tx.amount,
+                network_key: tx.network_key,
+                status: tx.status,
+                explorer_url: tx.explorer_url,
+            }));
+    }
+    Ok(())
+}
+
+#[cfg(all(test, any(feature = "jammdb_repo", feature = "sqlite_repo")))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use etopay_wallet::types::WalletTxStatus;
+
+    /// An entity in the layout of schema version 0, with one deprecated and one versioned transaction
+    const USER_ENTITY_V0: &[u8] = include_bytes!("fixtures/user_entity_v0.msgpack");
+
+    #[test]
+    fn it_should_have_a_migration_step_per_schema_version() {
+        assert_eq!(u32::try_from(MIGRATIONS.len()), Ok(CURRENT_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn it_should_migrate_schema_version_0() {
+        // Arrange
+        let mut user: UserEntity = rmp_serde::from_slice(USER_ENTITY_V0).unwrap();
+        assert_eq!(user.schema_version, 0);
+
+        // Act
+        let changed = migrate(&mut user).unwrap();
+
+        // Assert
+        assert!(changed);
+        assert_eq!(user.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(user.username, "alice");
+        assert_eq!(user.local_share.as_deref(), Some("local share"));
+        assert!(user.wallet_transactions.is_empty());
+
+        let hashes: Vec<_> = user
+            .wallet_transactions_versioned
+            .iter()
+            .map(VersionedWalletTransaction::transaction_hash)
+            .collect();
+        assert_eq!(hashes, vec!["v1_tx", "legacy_tx"]);
+
+        let legacy = &user.wallet_transactions_versioned[1];
+        assert_eq!(legacy.date(), Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        assert_eq!(legacy.status(), WalletTxStatus::Confirmed);
+        assert_eq!(legacy.network_key(), "IOTA");
+    }
+
+    #[test]
+    fn it_should_not_migrate_twice() {
+        // Arrange
+        let mut user: UserEntity = rmp_serde::from_slice(USER_ENTITY_V0).unwrap();
+        migrate(&mut user).unwrap();
+        let migrated = user.clone();
+
+        // Act
+        let changed = migrate(&mut user).unwrap();
+
+        // Assert
+        assert!(!changed);
+        assert_eq!(user, migrated);
+    }
+
+    #[test]
+    fn it_should_keep_newer_schema_versions() {
+        // Arrange
+        let mut user: UserEntity = rmp_serde::from_slice(USER_ENTITY_V0).unwrap();
+        user.schema_version = CURRENT_SCHEMA_VERSION + 1;
+
+        // Act
+        let changed = migrate(&mut user).unwrap();
+
+        // Assert
+        assert!(!changed);
+        assert_eq!(user.schema_version, CURRENT_SCHEMA_VERSION + 1);
+        assert_eq!(user.wallet_transactions.len(), 1);
+    }
+}
@@ -7,20 +7,29 @@
 //!
 
 pub mod error;
+pub mod migration;
 pub mod repository;
+pub mod secret_store;
 
 use crate::{
     share::Share,
     tx_version::VersionedWalletTransaction,
     types::{
         newtypes::EncryptedPassword,
-        users::{KycType, UserEntity},
+        users::{AddressBookEntry, AddressLabel, KycType, OfflineCache, PinAttempts, UserEntity},
         viviswap::{ViviswapVerificationStatus, ViviswapVerificationStep},
     },
 };
+use api_types::api::networks::ApiNetwork;
 use error::{Result, UserKvStorageError};
+use etopay_wallet::MnemonicDerivationOption;
+use std::collections::HashMap;
 
 /// Storage abstraction of [`UserEntity`] objects as a simple Key-Value storage
+///
+/// Storages with an index of the wallet transactions, like [`sqlite_storage::SqliteUserStorage`],
+/// keep the transactions outside of the entity. Their [`UserKvStorage::get`] returns the entity
+/// without transactions, which are only accessible through the transaction methods.
 #[cfg_attr(test, mockall::automock)]
 pub trait UserKvStorage {
     /// Get a value by key. Returns Error if key does not exist.
@@ -32,8 +41,181 @@ pub trait UserKvStorage {
     /// Check if a key exists.
     fn exists(&self, username: &str) -> Result<bool>;
 
-    /// Associate a key with a value. This will overwrite any previous value.
+    /// Associate a key with a value. This will overwrite any previous value. Storages with an
+    /// index of the transactions insert the transactions of the entity and keep all other stored
+    /// transactions.
     fn set(&mut self, username: &str, value: &UserEntity) -> Result<()>;
+
+    /// List all stored keys.
+    fn list_users(&self) -> Result<Vec<String>>;
+
+    /// Insert a wallet transaction of a user, replacing any transaction with the same network key
+    /// and hash. The default implementation rewrites the whole [`UserEntity`], storages with an
+    /// index should override it.
+    fn insert_transaction(&mut self, username: &str, transaction: &VersionedWalletTransaction) -> Result<()> {
+        insert_entity_transaction(self, username, transaction)
+    }
+
+    /// Get a wallet transaction of a user by network key and hash. Returns [`None`] if the
+    /// transaction does not exist.
+    fn get_transaction(
+        &self,
+        username: &str,
+        network_key: &str,
+        transaction_hash: &str,
+    ) -> Result<Option<VersionedWalletTransaction>> {
+        get_entity_transaction(self, username, network_key, transaction_hash)
+    }
+
+    /// Get the wallet transactions of a user on a network, newest first, skipping the first
+    /// `start` transactions and returning at most `limit` transactions.
+    fn get_transactions(
+        &self,
+        username: &str,
+        network_key: &str,
+        start: usize,
+        limit: usize,
+    ) -> Result<Vec<VersionedWalletTransaction>> {
+        get_entity_transactions(self, username, network_key, start, limit)
+    }
+
+    /// Get all wallet transactions of a user on all networks, in insertion order.
+    fn get_all_transactions(&self, username: &str) -> Result<Vec<VersionedWalletTransaction>> {
+        Ok(self.get(username)?.wallet_transactions_versioned)
+    }
+
+    /// Replace all wallet transactions of a user.
+    fn set_transactions(&mut self, username: &str, transactions: &[VersionedWalletTransaction]) -> Result<()> {
+        set_entity_transactions(self, username, transactions)
+    }
+}
+
+// implementations of the transaction methods for storages that keep the transactions in the entity
+
+/// Insert a transaction into the entity, see [`UserKvStorage::insert_transaction`]
+fn insert_entity_transaction<S: UserKvStorage + ?Sized>(
+    storage: &mut S,
+    username: &str,
+    transaction: &VersionedWalletTransaction,
+) -> Result<()> {
+    let mut user = storage.get(username)?;
+    let existing = user.wallet_transactions_versioned.iter_mut().find(|t| {
+        t.network_key() == transaction.network_key() && t.transaction_hash() == transaction.transaction_hash()
+    });
+    match existing {
+        Some(t) => *t = transaction.clone(),
+        None => user.wallet_transactions_versioned.push(transaction.clone()),
+    }
+    storage.set(username, &user)
+}
+
+/// Get a transaction from the entity, see [`UserKvStorage::get_transaction`]
+fn get_entity_transaction<S: UserKvStorage + ?Sized>(
+    storage: &S,
+    username: &str,
+    network_key: &str,
+    transaction_hash: &str,
+) -> Result<Option<VersionedWalletTransaction>> {
+    let user = storage.get(username)?;
+    Ok(user
+        .wallet_transactions_versioned
+        .into_iter()
+        .find(|t| t.network_key() == network_key && t.transaction_hash() == transaction_hash))
+}
+
+/// Get a page of the transactions from the entity, see [`UserKvStorage::get_transactions`]
+fn get_entity_transactions<S: UserKvStorage + ?Sized>(
+    storage: &S,
+    username: &str,
+    network_key: &str,
+    start: usize,
+    limit: usize,
+) -> Result<Vec<VersionedWalletTransaction>> {
+    let user = storage.get(username)?;
+    let mut transactions: Vec<_> = user
+        .wallet_transactions_versioned
+        .into_iter()
+        .filter(|t| t.network_key() == network_key)
+        .collect();
+    transactions.sort_by_key(|t| std::cmp::Reverse(t.date()));
+    Ok(transactions.into_iter().skip(start).take(limit).collect())
+}
+
+/// Replace the transactions of the entity, see [`UserKvStorage::set_transactions`]
+fn set_entity_transactions<S: UserKvStorage + ?Sized>(
+    storage: &mut S,
+    username: &str,
+    transactions: &[VersionedWalletTransaction],
+) -> Result<()> {
+    let mut user = storage.get(username)?;
+    user.wallet_transactions_versioned = transactions.to_vec();
+    storage.set(username, &user)
+}
+
+/// Storage of serialized [`UserEntity`] objects as raw bytes. Implemented by the persistent
+/// storages so that the bytes can be encrypted before they are stored, see
+/// [`encrypted_storage::EncryptedUserStorage`].
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
+pub trait UserBytesStorage {
+    /// Get the bytes stored for a key. Returns [`None`] if the key does not exist.
+    fn get_bytes(&self, username: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Associate a key with bytes. This will overwrite any previous value.
+    fn set_bytes(&mut self, username: &str, bytes: &[u8]) -> Result<()>;
+
+    /// Remove a key and the associated bytes, including the transactions in the
+    /// [`TransactionIndex`]. No error if the key does not exist.
+    fn delete_bytes(&mut self, username: &str) -> Result<()>;
+
+    /// List all stored keys.
+    fn usernames(&self) -> Result<Vec<String>>;
+
+    /// The index the storage keeps the wallet transactions in, or [`None`] if the transactions are
+    /// stored as part of the entity.
+    fn transaction_index(&self) -> Option<&dyn TransactionIndex> {
+        None
+    }
+
+    /// Mutable access to the [`UserBytesStorage::transaction_index`].
+    fn transaction_index_mut(&mut self) -> Option<&mut dyn TransactionIndex> {
+        None
+    }
+}
+
+/// A wallet transaction as stored in a [`TransactionIndex`]
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRow {
+    /// The lookup key of the network of the transaction
+    pub network_key: String,
+    /// The lookup key of the transaction on its network
+    pub hash: String,
+    /// The date of the transaction in milliseconds since the unix epoch, used for sorting
+    pub date: i64,
+    /// The serialized transaction
+    pub data: Vec<u8>,
+}
+
+/// Storage of serialized wallet transactions, indexed by user, network key, hash and date. The
+/// network key and hash are only used as lookup keys, so a wrapping storage may store digests of
+/// them instead, as [`encrypted_storage::EncryptedUserStorage`] does.
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
+pub trait TransactionIndex {
+    /// Insert a row, replacing any row with the same network key and hash.
+    fn upsert_row(&mut self, username: &str, row: &TransactionRow) -> Result<()>;
+
+    /// Get the data of the row with the network key and hash.
+    fn get_row(&self, username: &str, network_key: &str, hash: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Get the data of the rows of a network, newest first, skipping the first `start` rows and
+    /// returning at most `limit` rows.
+    fn get_rows(&self, username: &str, network_key: &str, start: usize, limit: usize) -> Result<Vec<Vec<u8>>>;
+
+    /// Get the data of all rows of a user, in insertion order.
+    fn get_all_rows(&self, username: &str) -> Result<Vec<Vec<u8>>>;
+
+    /// Replace all rows of a user.
+    fn replace_rows(&mut self, username: &str, rows: &[TransactionRow]) -> Result<()>;
 }
 
 /// Represents the storage and loading of different users in a repository. This could be
@@ -93,13 +275,26 @@ pub trait UserRepo {
     ///
     /// # Returns
     ///
-    /// Returns the `UserEntity` if the user is found, otherwise returns an `Error`.
+    /// Returns the `UserEntity` if the user is found, otherwise returns an `Error`. The wallet
+    /// transactions are not included if the storage keeps them in an index, use
+    /// [`UserRepo::get_wallet_transactions`] and [`UserRepo::get_all_wallet_transactions`] instead.
     ///
     /// # Errors
     ///
     /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
     fn get(&self, username: &str) -> Result<UserEntity>;
 
+    /// List the usernames of all stored users.
+    ///
+    /// # Returns
+    ///
+    /// Returns the usernames sorted alphabetically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error reading the users from the database.
+    fn list_users(&self) -> Result<Vec<String>>;
+
     /// Set the wallet password for a user.
     ///
     /// # Arguments
@@ -116,6 +311,22 @@ pub trait UserRepo {
     /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
     fn set_wallet_password(&mut self, username: &str, password: EncryptedPassword) -> Result<()>;
 
+    /// Set the wrong pin attempts of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `pin_attempts` - The wrong pin attempts since the last correct pin.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the pin attempts are set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_pin_attempts(&mut self, username: &str, pin_attempts: PinAttempts) -> Result<()>;
+
     /// Set user kyc state.
     ///
     /// # Arguments
@@ -188,6 +399,185 @@ pub trait UserRepo {
     /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
     fn set_wallet_transactions(&mut self, username: &str, transaction: Vec<VersionedWalletTransaction>) -> Result<()>;
 
+    /// Insert a single wallet transaction, replacing any transaction with the same network key
+    /// and hash, without rewriting the other transactions if the storage supports it.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `transaction` - The transaction to insert
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the wallet transaction is inserted successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error storing the transaction in the database.
+    fn insert_wallet_transaction(&mut self, username: &str, transaction: VersionedWalletTransaction) -> Result<()>;
+
+    /// Get a single wallet transaction by network key and hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `network_key` - The key of the network the transaction was made on.
+    /// * `transaction_hash` - The hash of the transaction.
+    ///
+    /// # Returns
+    ///
+    /// Returns the transaction, or [`None`] if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the transaction from the database.
+    fn get_wallet_transaction(
+        &self,
+        username: &str,
+        network_key: &str,
+        transaction_hash: &str,
+    ) -> Result<Option<VersionedWalletTransaction>>;
+
+    /// Get a page of the wallet transactions on a network, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `network_key` - The key of the network to get the transactions for.
+    /// * `start` - The number of transactions to skip.
+    /// * `limit` - The maximum number of transactions to return.
+    ///
+    /// # Returns
+    ///
+    /// Returns the transactions sorted by date, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the transactions from the database.
+    fn get_wallet_transactions(
+        &self,
+        username: &str,
+        network_key: &str,
+        start: usize,
+        limit: usize,
+    ) -> Result<Vec<VersionedWalletTransaction>>;
+
+    /// Get all wallet transactions on all networks, e.g. to export them. Prefer
+    /// [`UserRepo::get_wallet_transactions`] to show the transactions of a network.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    ///
+    /// # Returns
+    ///
+    /// Returns the transactions in the order they were inserted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the transactions from the database.
+    fn get_all_wallet_transactions(&self, username: &str) -> Result<Vec<VersionedWalletTransaction>>;
+
+    /// Set the labels of the user's own wallet addresses.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `labels` - List of address labels
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the address labels are set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_address_labels(&mut self, username: &str, labels: Vec<AddressLabel>) -> Result<()>;
+
+    /// Set the address book of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `address_book` - List of address book entries
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the address book is set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_address_book(&mut self, username: &str, address_book: Vec<AddressBookEntry>) -> Result<()>;
+
+    /// Set the offline cache of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `cache` - The values to return while offline
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the cache is set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_offline_cache(&mut self, username: &str, cache: OfflineCache) -> Result<()>;
+
+    /// Set the network selected by a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `network_key` - The key of the selected network, or [`None`] to clear the selection.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the network is set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_selected_network(&mut self, username: &str, network_key: Option<String>) -> Result<()>;
+
+    /// Set the derivation options selected by a user per network.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `options` - The derivation options per network key
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the derivation options are set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_network_derivation_options(
+        &mut self,
+        username: &str,
+        options: HashMap<String, MnemonicDerivationOption>,
+    ) -> Result<()>;
+
+    /// Set the networks added locally by a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The username of the user.
+    /// * `networks` - The custom networks
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the networks are set successfully, otherwise returns an `Error`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error::KVError` if there is an error retrieving the user from the database.
+    fn set_custom_networks(&mut self, username: &str, networks: Vec<ApiNetwork>) -> Result<()>;
+
     /// Set the local share for a user.
     ///
     /// # Arguments
@@ -210,23 +600,35 @@ pub trait UserRepo {
 #[cfg(feature = "jammdb_repo")]
 pub mod file_storage;
 
+/// An implementation of [`UserKvStorage`] using a SQLite database with an indexed transactions table.
+#[cfg(feature = "sqlite_repo")]
+pub mod sqlite_storage;
+
 /// An implementation of [`UserKvStorage`] that uses the browsers local storage.
 #[cfg(target_arch = "wasm32")]
 pub mod web_storage;
 
+/// An implementation of [`UserKvStorage`] that uses the browsers IndexedDB.
+#[cfg(target_arch = "wasm32")]
+pub mod indexed_db_storage;
+
 /// An implementation of [`UserKvStorage`] that uses a non-persistent in-memory storage.
 pub mod memory_storage;
 
+/// An implementation of [`UserKvStorage`] that encrypts the entities before storing them in a [`UserBytesStorage`].
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
+pub mod encrypted_storage;
+
 // implementations shared by file and web storage
 
-#[cfg(any(feature = "jammdb_repo", target_arch = "wasm32"))]
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
 impl From<rmp_serde::decode::Error> for UserKvStorageError {
     fn from(value: rmp_serde::decode::Error) -> Self {
         UserKvStorageError::Storage(format!("rmp_serde::decode::Error: {:#?}", value))
     }
 }
 
-#[cfg(any(feature = "jammdb_repo", target_arch = "wasm32"))]
+#[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo", target_arch = "wasm32"))]
 impl From<rmp_serde::encode::Error> for UserKvStorageError {
     fn from(value: rmp_serde::encode::Error) -> Self {
         UserKvStorageError::Storage(format!("rmp_serde::encode::Error: {:#?}", value))
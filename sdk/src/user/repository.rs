@@ -2,85 +2,180 @@
 
 use super::UserRepo;
 use super::error::Result;
+use super::secret_store::{SecretKind, SecretStore};
 use crate::{
     share::Share,
     tx_version::VersionedWalletTransaction,
     types::{
         newtypes::EncryptedPassword,
-        users::{KycType, UserEntity},
+        users::{AddressBookEntry, AddressLabel, KycType, OfflineCache, PinAttempts, UserEntity},
         viviswap::{ViviswapPartiallyKycDetails, ViviswapState, ViviswapVerificationStatus, ViviswapVerificationStep},
     },
     user::error::UserKvStorageError,
 };
+use api_types::api::networks::ApiNetwork;
+use etopay_wallet::MnemonicDerivationOption;
 use log::debug;
+use secrecy::{ExposeSecret, SecretSlice};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
 
 pub struct UserRepoImpl<I: super::UserKvStorage> {
-    inner: I,
+    /// The storage of the entities, behind a lock so that entities can be migrated on read
+    inner: RwLock<I>,
+    /// If set, the local share and the encrypted password are kept in this store instead of the [`UserEntity`].
+    secret_store: Option<Arc<dyn SecretStore>>,
 }
 
 impl<I: super::UserKvStorage> UserRepoImpl<I> {
     pub fn new(inner: I) -> Self {
-        Self { inner }
+        Self {
+            inner: RwLock::new(inner),
+            secret_store: None,
+        }
+    }
+
+    /// Read access to the inner storage
+    fn inner(&self) -> RwLockReadGuard<'_, I> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Write access to the inner storage
+    fn inner_mut(&mut self) -> &mut I {
+        self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keep the local share and the encrypted password in the provided [`SecretStore`].
+    pub fn with_secret_store(mut self, secret_store: Option<Arc<dyn SecretStore>>) -> Self {
+        self.secret_store = secret_store;
+        self
+    }
+
+    /// Move the secrets of the user into the secret store, if one is configured, and write the
+    /// remaining entity to the inner storage. Secrets still stored in the entity by a previous
+    /// version (without secret store) are migrated this way on the next write.
+    /// If `delete_missing` is set, secrets which are [`None`] in the entity are removed from the store.
+    fn store(&mut self, username: &str, mut user: UserEntity, delete_missing: bool) -> Result<()> {
+        if let Some(store) = &self.secret_store {
+            match user.local_share.take() {
+                Some(share) => store.set(username, SecretKind::LocalShare, &SecretSlice::from(share.into_bytes()))?,
+                None if delete_missing => store.delete(username, SecretKind::LocalShare)?,
+                None => {}
+            }
+            match user.encrypted_password.take() {
+                Some(password) => store.set(
+                    username,
+                    SecretKind::EncryptedPassword,
+                    &SecretSlice::from(password.as_bytes().to_vec()),
+                )?,
+                None if delete_missing => store.delete(username, SecretKind::EncryptedPassword)?,
+                None => {}
+            }
+        }
+        self.inner_mut().set(username, &user)
+    }
+
+    /// Get the entity from the inner storage, migrated to the current schema version. The
+    /// migrated entity is written back right away.
+    fn load(&self, username: &str) -> Result<UserEntity> {
+        let mut user = self.inner().get(username)?;
+        if super::migration::migrate(&mut user)? {
+            debug!("Migrated user entity to schema version {}", user.schema_version);
+            self.inner
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .set(username, &user)?;
+        }
+        Ok(user)
     }
 }
 
 impl<I: super::UserKvStorage> UserRepo for UserRepoImpl<I> {
     fn create(&mut self, user: &UserEntity) -> Result<()> {
         debug!("Creating entry in user DB");
-        if self.inner.exists(&user.username)? {
+        if self.inner().exists(&user.username)? {
             return Err(UserKvStorageError::UserAlreadyExists {
                 username: user.username.clone(),
             })?;
         }
 
-        self.inner.set(&user.username, user)?;
-        Ok(())
+        self.store(&user.username, user.clone(), true)
     }
 
     fn update(&mut self, user: &UserEntity) -> Result<()> {
         debug!("Updating entry in user DB");
 
-        if !self.inner.exists(&user.username)? {
+        if !self.inner().exists(&user.username)? {
             return Err(UserKvStorageError::UserNotFound {
                 username: user.username.clone(),
             })?;
         }
-        self.inner.set(&user.username, user)
+        self.store(&user.username, user.clone(), true)
     }
 
     fn delete(&mut self, username: &str) -> Result<()> {
         debug!("Deleting entry in user DB");
-        self.inner.delete(username)
+        if let Some(store) = &self.secret_store {
+            store.delete(username, SecretKind::LocalShare)?;
+            store.delete(username, SecretKind::EncryptedPassword)?;
+        }
+        self.inner_mut().delete(username)
     }
 
     fn get(&self, username: &str) -> Result<UserEntity> {
         debug!("Fetching entry in user DB");
-        let u = self.inner.get(username)?;
+        let mut u = self.load(username)?;
+
+        if let Some(store) = &self.secret_store {
+            if let Some(share) = store.get(username, SecretKind::LocalShare)? {
+                let share = String::from_utf8(share.expose_secret().to_vec())
+                    .map_err(|e| UserKvStorageError::SecretStore(format!("Invalid local share: {e}")))?;
+                u.local_share = Some(share);
+            }
+            if let Some(password) = store.get(username, SecretKind::EncryptedPassword)? {
+                // SAFETY: the bytes were stored from an `EncryptedPassword` by `Self::store`
+                u.encrypted_password = Some(unsafe { EncryptedPassword::new_unchecked(password.expose_secret()) });
+            }
+        }
         Ok(u)
     }
 
+    fn list_users(&self) -> Result<Vec<String>> {
+        let mut usernames = self.inner().list_users()?;
+        usernames.sort();
+        Ok(usernames)
+    }
+
     fn set_wallet_password(&mut self, username: &str, password: EncryptedPassword) -> Result<()> {
         debug!("Setting password in user DB");
 
-        let mut user = self.inner.get(username)?;
+        let mut user = self.load(username)?;
         user.encrypted_password = Some(password.to_owned());
-        self.inner.set(username, &user)
+        self.store(username, user, false)
+    }
+
+    fn set_pin_attempts(&mut self, username: &str, pin_attempts: PinAttempts) -> Result<()> {
+        debug!("Setting pin attempts in user DB: {pin_attempts:?}");
+
+        let mut user = self.load(username)?;
+        user.pin_attempts = pin_attempts;
+        self.store(username, user, false)
     }
 
     fn set_kyc_state(&mut self, username: &str, is_verified: bool) -> Result<()> {
         debug!("Setting KYC state in user DB: {is_verified}");
 
-        let mut user = self.inner.get(username)?;
+        let mut user = self.load(username)?;
         user.is_kyc_verified = is_verified;
-        self.inner.set(username, &user)
+        self.store(username, user, false)
     }
 
     fn set_kyc_type(&mut self, username: &str, kyc_type: KycType) -> Result<()> {
         debug!("Setting KYC type in user DB: {kyc_type:#?}");
 
-        let mut user = self.inner.get(username)?;
+        let mut user = self.load(username)?;
         user.kyc_type = kyc_type;
-        self.inner.set(username, &user)
+        self.store(username, user, false)
     }
 
     fn set_viviswap_kyc_state(
@@ -94,7 +189,7 @@ impl<I: super::UserKvStorage> UserRepo for UserRepoImpl<I> {
             "Setting viviswap KYC state in user DB: {verification_status:?}, {monthly_limit_eur}, {next_verification_step:?}"
         );
 
-        let mut user = self.inner.get(username)?;
+        let mut user = self.load(username)?;
 
         match user.viviswap_state {
             None => {
@@ -118,22 +213,98 @@ impl<I: super::UserKvStorage> UserRepo for UserRepoImpl<I> {
                 });
             }
         };
-        self.inner.set(username, &user)
+        self.store(username, user, false)
     }
 
     fn set_local_share(&mut self, username: &str, share: Option<&Share>) -> Result<()> {
-        use secrecy::ExposeSecret;
         debug!("Setting local share in user DB for: {username}");
-        let mut user = self.inner.get(username)?;
+        let mut user = self.load(username)?;
         user.local_share = share.map(|s| s.to_string().expose_secret().to_string());
-        self.inner.set(username, &user)
+        if let (Some(store), None) = (&self.secret_store, share) {
+            store.delete(username, SecretKind::LocalShare)?;
+        }
+        self.store(username, user, false)
     }
 
     fn set_wallet_transactions(&mut self, username: &str, transaction: Vec<VersionedWalletTransaction>) -> Result<()> {
         debug!("Setting wallet transactions in user DB: {transaction:#?}");
-        let mut user = self.inner.get(username)?;
-        user.wallet_transactions_versioned = transaction;
-        self.inner.set(username, &user)
+        // load the user to migrate it before its transactions are replaced
+        self.load(username)?;
+        self.inner_mut().set_transactions(username, &transaction)
+    }
+
+    fn insert_wallet_transaction(&mut self, username: &str, transaction: VersionedWalletTransaction) -> Result<()> {
+        debug!("Inserting wallet transaction {}", transaction.transaction_hash());
+        self.inner_mut().insert_transaction(username, &transaction)
+    }
+
+    fn get_wallet_transaction(
+        &self,
+        username: &str,
+        network_key: &str,
+        transaction_hash: &str,
+    ) -> Result<Option<VersionedWalletTransaction>> {
+        self.inner().get_transaction(username, network_key, transaction_hash)
+    }
+
+    fn get_wallet_transactions(
+        &self,
+        username: &str,
+        network_key: &str,
+        start: usize,
+        limit: usize,
+    ) -> Result<Vec<VersionedWalletTransaction>> {
+        self.inner().get_transactions(username, network_key, start, limit)
+    }
+
+    fn get_all_wallet_transactions(&self, username: &str) -> Result<Vec<VersionedWalletTransaction>> {
+        self.inner().get_all_transactions(username)
+    }
+
+    fn set_address_labels(&mut self, username: &str, labels: Vec<AddressLabel>) -> Result<()> {
+        debug!("Setting address labels in user DB: {labels:#?}");
+        let mut user = self.load(username)?;
+        user.address_labels = labels;
+        self.store(username, user, false)
+    }
+
+    fn set_address_book(&mut self, username: &str, address_book: Vec<AddressBookEntry>) -> Result<()> {
+        debug!("Setting address book in user DB: {address_book:#?}");
+        let mut user = self.load(username)?;
+        user.address_book = address_book;
+        self.store(username, user, false)
+    }
+
+    fn set_offline_cache(&mut self, username: &str, cache: OfflineCache) -> Result<()> {
+        debug!("Setting offline cache in user DB");
+        let mut user = self.load(username)?;
+        user.offline_cache = cache;
+        self.store(username, user, false)
+    }
+
+    fn set_selected_network(&mut self, username: &str, network_key: Option<String>) -> Result<()> {
+        debug!("Setting selected network in user DB: {network_key:?}");
+        let mut user = self.load(username)?;
+        user.selected_network = network_key;
+        self.store(username, user, false)
+    }
+
+    fn set_network_derivation_options(
+        &mut self,
+        username: &str,
+        options: HashMap<String, MnemonicDerivationOption>,
+    ) -> Result<()> {
+        debug!("Setting network derivation options in user DB: {options:?}");
+        let mut user = self.load(username)?;
+        user.network_derivation_options = options;
+        self.store(username, user, false)
+    }
+
+    fn set_custom_networks(&mut self, username: &str, networks: Vec<ApiNetwork>) -> Result<()> {
+        debug!("Setting custom networks in user DB: {networks:?}");
+        let mut user = self.load(username)?;
+        user.custom_networks = networks;
+        self.store(username, user, false)
     }
 }
 
@@ -147,27 +318,19 @@ mod tests {
 
     use super::*;
     use crate::{
-        testing_utils::{ENCRYPTED_WALLET_PASSWORD, ETH_NETWORK_KEY},
+        testing_utils::{ENCRYPTED_WALLET_PASSWORD, ETH_NETWORK_KEY, example_api_network, example_user_entity},
         tx_version::VersionedWalletTransaction,
         types::{
             newtypes::{EncryptedPassword, EncryptionPin, EncryptionSalt, PlainPassword},
             users::KycType,
         },
-        user::memory_storage::MemoryUserStorage,
+        user::{UserKvStorage, memory_storage::MemoryUserStorage, secret_store::MemorySecretStore},
     };
 
     fn create_user_entity(username: &str, password: Option<EncryptedPassword>) -> UserEntity {
         UserEntity {
-            user_id: None,
-            username: username.to_owned(),
             encrypted_password: password,
-            salt: EncryptionSalt::generate(),
-            is_kyc_verified: false,
-            kyc_type: KycType::Undefined,
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            ..example_user_entity(username)
         }
     }
 
@@ -286,16 +449,9 @@ mod tests {
 
         // Act
         let updated_user = UserEntity {
-            user_id: None,
-            username: username.clone(),
-            encrypted_password: None,
-            salt: new_salt.clone(),       // New salt value for update
-            is_kyc_verified: true,        // New KYC verification status
-            kyc_type: KycType::Undefined, // New KYC type
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            salt: new_salt.clone(), // New salt value for update
+            is_kyc_verified: true,  // New KYC verification status
+            ..example_user_entity(&username)
         };
         let result = user_repo.update(&updated_user);
 
@@ -318,16 +474,9 @@ mod tests {
 
         // Act
         let updated_user = UserEntity {
-            user_id: None,
-            username: username.clone(),
-            encrypted_password: None,
-            salt: new_salt.clone(),       // New salt value for update
-            is_kyc_verified: true,        // New KYC verification status
-            kyc_type: KycType::Undefined, // New KYC type
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            salt: new_salt.clone(), // New salt value for update
+            is_kyc_verified: true,  // New KYC verification status
+            ..example_user_entity(&username)
         };
         let result = user_repo.update(&updated_user);
 
@@ -353,22 +502,30 @@ mod tests {
         assert!(result.is_ok());
         assert!(user_repo.get(&username).is_err());
     }
+    #[test]
+    fn it_should_list_users() {
+        // Arrange
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        for username in ["satoshi", "alice", "hauju"] {
+            user_repo.create(&create_user_entity(username, None)).unwrap();
+        }
+        user_repo.delete("hauju").unwrap();
+
+        // Act
+        let usernames = user_repo.list_users().unwrap();
+
+        // Assert
+        assert_eq!(usernames, vec!["alice", "satoshi"]);
+    }
+
     #[test]
     fn it_should_set_user_kyc_type() {
         // Arrange
         let username = String::from("hauju");
         let user = UserEntity {
-            user_id: None,
-            username: username.clone(),
-            encrypted_password: None,
-            salt: EncryptionSalt::generate(),
-            is_kyc_verified: false,
             // Add the actual field for storing KYC type
             kyc_type: KycType::Undefined,
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            ..example_user_entity(&username)
         };
         let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
         user_repo.create(&user).unwrap();
@@ -423,6 +580,119 @@ mod tests {
         assert_eq!(&retrieved_user.local_share.unwrap(), share.to_string().expose_secret());
     }
 
+    #[test]
+    fn it_should_set_pin_attempts() {
+        // Arrange
+        let username = "hauju";
+        let user = create_user_entity(username, None);
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&user).unwrap();
+
+        let pin_attempts = PinAttempts {
+            failed_attempts: 3,
+            last_failed_at: Some(1_700_000_000),
+            locked: false,
+        };
+
+        // Act
+        user_repo.set_pin_attempts(username, pin_attempts.clone()).unwrap();
+
+        // Assert
+        assert_eq!(user_repo.get(username).unwrap().pin_attempts, pin_attempts);
+    }
+
+    #[test]
+    fn it_should_set_offline_cache() {
+        // Arrange
+        let username = "hauju";
+        let user = create_user_entity(username, None);
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&user).unwrap();
+
+        let mut cache = OfflineCache::default();
+        cache.exchange_rates.insert(
+            ETH_NETWORK_KEY.to_string(),
+            crate::types::users::Cached::fresh(dec!(1.5)),
+        );
+        cache.balances.push(crate::types::users::CachedBalance {
+            network_key: ETH_NETWORK_KEY.to_string(),
+            derivation_options: Default::default(),
+            // SAFETY: we know that this value is not negative
+            balance: crate::types::users::Cached::fresh(unsafe { CryptoAmount::new_unchecked(dec!(2.0)) }),
+        });
+
+        // Act
+        user_repo.set_offline_cache(username, cache.clone()).unwrap();
+
+        // Assert
+        let retrieved_user = user_repo.get(username).unwrap();
+        assert_eq!(retrieved_user.offline_cache, cache);
+
+        #[cfg(feature = "jammdb_repo")]
+        {
+            let bytes = rmp_serde::to_vec(&retrieved_user).unwrap();
+            let deserialized: UserEntity = rmp_serde::from_slice(&bytes).unwrap();
+            assert_eq!(deserialized.offline_cache, cache);
+        }
+    }
+
+    #[test]
+    fn it_should_set_selected_network() {
+        // Arrange
+        let username = "hauju";
+        let user = create_user_entity(username, None);
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&user).unwrap();
+
+        // Act
+        user_repo
+            .set_selected_network(username, Some(ETH_NETWORK_KEY.to_string()))
+            .unwrap();
+
+        // Assert
+        let retrieved_user = user_repo.get(username).unwrap();
+        assert_eq!(retrieved_user.selected_network, Some(ETH_NETWORK_KEY.to_string()));
+    }
+
+    #[test]
+    fn it_should_set_network_derivation_options() {
+        // Arrange
+        let username = "hauju";
+        let user = create_user_entity(username, None);
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&user).unwrap();
+        let options = HashMap::from([(
+            ETH_NETWORK_KEY.to_string(),
+            MnemonicDerivationOption { account: 1, index: 2 },
+        )]);
+
+        // Act
+        user_repo
+            .set_network_derivation_options(username, options.clone())
+            .unwrap();
+
+        // Assert
+        let retrieved_user = user_repo.get(username).unwrap();
+        assert_eq!(retrieved_user.network_derivation_options, options);
+    }
+
+    #[test]
+    fn it_should_set_custom_networks() {
+        // Arrange
+        let username = "hauju";
+        let user = create_user_entity(username, None);
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&user).unwrap();
+        let networks = vec![example_api_network(ETH_NETWORK_KEY.to_string())];
+
+        // Act
+        user_repo.set_custom_networks(username, networks.clone()).unwrap();
+
+        // Assert
+        let retrieved_user = user_repo.get(username).unwrap();
+        assert_eq!(retrieved_user.custom_networks, networks);
+    }
+
     #[test]
     fn it_should_update_wallet_transactions() {
         // Arrange
@@ -482,6 +752,124 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_should_insert_and_query_wallet_transactions() {
+        // Arrange
+        let username = String::from("hauju");
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&create_user_entity(&username, None)).unwrap();
+
+        let transaction = |hash: &str, seconds_ago: i64, status: WalletTxStatus| {
+            VersionedWalletTransaction::V2(WalletTransaction {
+                date: Utc::now() - chrono::Duration::seconds(seconds_ago),
+                block_number_hash: None,
+                transaction_hash: hash.to_string(),
+                receiver: String::new(),
+                sender: String::new(),
+                // SAFETY: the value is non-negative
+                amount: unsafe { CryptoAmount::new_unchecked(dec!(1.0)) },
+                network_key: ETH_NETWORK_KEY.to_string(),
+                status,
+                explorer_url: None,
+                gas_fee: None,
+                is_sender: true,
+            })
+        };
+
+        // Act
+        user_repo
+            .insert_wallet_transaction(&username, transaction("tx1", 30, WalletTxStatus::Pending))
+            .unwrap();
+        user_repo
+            .insert_wallet_transaction(&username, transaction("tx2", 10, WalletTxStatus::Pending))
+            .unwrap();
+        let confirmed = transaction("tx1", 30, WalletTxStatus::Confirmed);
+        user_repo
+            .insert_wallet_transaction(&username, confirmed.clone())
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            user_repo
+                .get_wallet_transaction(&username, ETH_NETWORK_KEY, "tx1")
+                .unwrap(),
+            Some(confirmed)
+        );
+        assert!(
+            user_repo
+                .get_wallet_transaction(&username, "other", "tx1")
+                .unwrap()
+                .is_none()
+        );
+
+        let transactions = user_repo
+            .get_wallet_transactions(&username, ETH_NETWORK_KEY, 0, 10)
+            .unwrap();
+        let hashes: Vec<_> = transactions.iter().map(|t| t.transaction_hash()).collect();
+        assert_eq!(hashes, vec!["tx2", "tx1"]);
+        assert_eq!(
+            user_repo
+                .get_wallet_transactions(&username, ETH_NETWORK_KEY, 1, 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        let all = user_repo.get_all_wallet_transactions(&username).unwrap();
+        let hashes: Vec<_> = all.iter().map(|t| t.transaction_hash()).collect();
+        assert_eq!(hashes, vec!["tx1", "tx2"]);
+    }
+
+    #[cfg(any(feature = "jammdb_repo", feature = "sqlite_repo"))]
+    #[test]
+    fn it_should_migrate_on_get_and_persist_right_away() {
+        // Arrange
+        let user: UserEntity = rmp_serde::from_slice(include_bytes!("fixtures/user_entity_v0.msgpack")).unwrap();
+        let mut storage = MemoryUserStorage::new();
+        storage.set("alice", &user).unwrap();
+        let user_repo = UserRepoImpl::new(storage);
+
+        // Act
+        let loaded = user_repo.get("alice").unwrap();
+
+        // Assert
+        assert_eq!(loaded.schema_version, crate::user::migration::CURRENT_SCHEMA_VERSION);
+        assert_eq!(loaded.wallet_transactions_versioned.len(), 2);
+        let stored = user_repo.inner().get("alice").unwrap();
+        assert_eq!(stored.schema_version, crate::user::migration::CURRENT_SCHEMA_VERSION);
+        assert!(stored.wallet_transactions.is_empty());
+        assert_eq!(stored, loaded);
+    }
+
+    #[test]
+    fn it_should_set_address_labels_and_address_book() {
+        // Arrange
+        let username = String::from("hauju");
+
+        let user = create_user_entity(&username, None);
+        let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
+        user_repo.create(&user).unwrap();
+
+        let labels = vec![AddressLabel {
+            network_key: ETH_NETWORK_KEY.to_string(),
+            address: String::from("0x1234"),
+            label: String::from("savings"),
+        }];
+        let address_book = vec![AddressBookEntry {
+            network_key: ETH_NETWORK_KEY.to_string(),
+            address: String::from("0x5678"),
+            name: String::from("alice"),
+        }];
+
+        // Act
+        user_repo.set_address_labels(&username, labels.clone()).unwrap();
+        user_repo.set_address_book(&username, address_book.clone()).unwrap();
+
+        // Assert
+        let retrieved_user = user_repo.get(&username).unwrap();
+        assert_eq!(retrieved_user.address_labels, labels);
+        assert_eq!(retrieved_user.address_book, address_book);
+    }
+
     #[test]
     fn it_should_error_on_duplicate_user_creation() {
         // Arrange
@@ -521,16 +909,8 @@ mod tests {
         // Arrange
         let username = String::from("nonexistent_user");
         let user = UserEntity {
-            user_id: None,
-            username: username.clone(),
-            encrypted_password: None,
-            salt: EncryptionSalt::generate(),
             is_kyc_verified: true,
-            kyc_type: KycType::Undefined,
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            ..example_user_entity(&username)
         };
         let mut user_repo = UserRepoImpl::new(MemoryUserStorage::new());
 
@@ -543,4 +923,62 @@ mod tests {
             UserKvStorageError::UserNotFound { .. }
         ));
     }
+
+    #[test]
+    fn it_should_keep_secrets_in_secret_store() {
+        // Arrange
+        let username = "hauju";
+        let store = Arc::new(MemorySecretStore::new());
+        let mut user_repo =
+            UserRepoImpl::new(MemoryUserStorage::new()).with_secret_store(Some(store.clone() as Arc<dyn SecretStore>));
+        let user = create_user_entity(username, Some(ENCRYPTED_WALLET_PASSWORD.clone()));
+        let share: Share = "ME-RS-N-Mi0yLUNBSVFBaGdESXFBRWk3b296TFVtbzNscG1jZEIx".parse().unwrap();
+
+        // Act
+        user_repo.create(&user).unwrap();
+        user_repo.set_local_share(username, Some(&share)).unwrap();
+
+        // Assert
+        let stored_user = user_repo.inner().get(username).unwrap();
+        assert!(stored_user.local_share.is_none());
+        assert!(stored_user.encrypted_password.is_none());
+
+        let retrieved_user = user_repo.get(username).unwrap();
+        assert_eq!(
+            retrieved_user.encrypted_password,
+            Some(ENCRYPTED_WALLET_PASSWORD.clone())
+        );
+        assert_eq!(&retrieved_user.local_share.unwrap(), share.to_string().expose_secret());
+
+        user_repo.set_local_share(username, None).unwrap();
+        assert!(user_repo.get(username).unwrap().local_share.is_none());
+
+        user_repo.delete(username).unwrap();
+        assert!(store.get(username, SecretKind::EncryptedPassword).unwrap().is_none());
+    }
+
+    #[test]
+    fn it_should_migrate_secrets_to_secret_store() {
+        // Arrange
+        let username = "hauju";
+        let mut user = create_user_entity(username, Some(ENCRYPTED_WALLET_PASSWORD.clone()));
+        user.local_share = Some(String::from("ME-RS-N-Mi0yLUNBSVFBaGdESXFBRWk3b296TFVtbzNscG1jZEIx"));
+        let mut storage = MemoryUserStorage::new();
+        storage.set(username, &user).unwrap();
+
+        let store = Arc::new(MemorySecretStore::new());
+        let mut user_repo = UserRepoImpl::new(storage).with_secret_store(Some(store.clone() as Arc<dyn SecretStore>));
+
+        // Act
+        let retrieved_user = user_repo.get(username).unwrap();
+        user_repo.set_kyc_state(username, true).unwrap();
+
+        // Assert
+        assert_eq!(retrieved_user.local_share, user.local_share);
+        assert!(user_repo.inner().get(username).unwrap().local_share.is_none());
+        assert!(store.get(username, SecretKind::LocalShare).unwrap().is_some());
+        let migrated_user = user_repo.get(username).unwrap();
+        assert_eq!(migrated_user.local_share, user.local_share);
+        assert_eq!(migrated_user.encrypted_password, user.encrypted_password);
+    }
 }
@@ -0,0 +1,118 @@
+//! Storage for the secrets of a user, namely the local share and the encrypted password.
+//!
+//! By default these secrets are stored as fields of the [`crate::types::users::UserEntity`] in the
+//! [`super::UserKvStorage`]. If a [`SecretStore`] is configured, the [`super::repository::UserRepoImpl`]
+//! keeps them in the secret store instead, which allows using a platform keystore such as the
+//! Android Keystore, the iOS Keychain or non-extractable WebCrypto keys.
+
+use super::error::{Result, UserKvStorageError};
+use secrecy::{ExposeSecret, SecretSlice};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// The kind of secret kept in a [`SecretStore`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecretKind {
+    /// The local share from the SSS scheme
+    LocalShare,
+    /// The wallet password, encrypted with the pin
+    EncryptedPassword,
+    /// The key used to encrypt the user database. It is shared by all users and stored with an
+    /// empty username.
+    DatabaseKey,
+}
+
+/// Storage for the secrets of a user, backed for example by a platform keystore
+#[cfg_attr(test, mockall::automock)]
+pub trait SecretStore: Send + Sync {
+    /// Get a secret of a user. Returns [`None`] if the secret does not exist.
+    fn get(&self, username: &str, kind: SecretKind) -> Result<Option<SecretSlice<u8>>>;
+
+    /// Store a secret of a user. This will overwrite any previous secret of the same kind.
+    fn set(&self, username: &str, kind: SecretKind, secret: &SecretSlice<u8>) -> Result<()>;
+
+    /// Remove a secret of a user. No error if the secret does not exist.
+    fn delete(&self, username: &str, kind: SecretKind) -> Result<()>;
+
+    /// Whether the secrets outlive the process. The key of a persistent user database is only
+    /// kept in a persistent secret store, since the database could not be decrypted after a
+    /// restart otherwise.
+    fn is_persistent(&self) -> bool {
+        true
+    }
+}
+
+/// Software implementation of [`SecretStore`] that keeps the secrets in memory. Intended for
+/// tests and platforms without a keystore.
+///
+/// The secrets are lost when the process exits, so the SDK refuses to encrypt a persistent user
+/// database with a key kept in this store.
+#[derive(Default)]
+pub struct MemorySecretStore {
+    secrets: RwLock<HashMap<(String, SecretKind), SecretSlice<u8>>>,
+}
+
+impl MemorySecretStore {
+    /// Create a new, empty [`MemorySecretStore`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn get(&self, username: &str, kind: SecretKind) -> Result<Option<SecretSlice<u8>>> {
+        let secrets = self
+            .secrets
+            .read()
+            .map_err(|e| UserKvStorageError::SecretStore(e.to_string()))?;
+        Ok(secrets
+            .get(&(username.to_string(), kind))
+            .map(|secret| secret.expose_secret().to_vec().into()))
+    }
+
+    fn set(&self, username: &str, kind: SecretKind, secret: &SecretSlice<u8>) -> Result<()> {
+        let mut secrets = self
+            .secrets
+            .write()
+            .map_err(|e| UserKvStorageError::SecretStore(e.to_string()))?;
+        secrets.insert((username.to_string(), kind), secret.expose_secret().to_vec().into());
+        Ok(())
+    }
+
+    fn delete(&self, username: &str, kind: SecretKind) -> Result<()> {
+        let mut secrets = self
+            .secrets
+            .write()
+            .map_err(|e| UserKvStorageError::SecretStore(e.to_string()))?;
+        secrets.remove(&(username.to_string(), kind));
+        Ok(())
+    }
+
+    fn is_persistent(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_secret_store() {
+        // Arrange
+        let store = MemorySecretStore::new();
+        let secret: SecretSlice<u8> = b"local share".to_vec().into();
+
+        // Act
+        store.set("alice", SecretKind::LocalShare, &secret).unwrap();
+
+        // Assert
+        let stored = store.get("alice", SecretKind::LocalShare).unwrap().unwrap();
+        assert_eq!(stored.expose_secret(), b"local share");
+        assert!(store.get("alice", SecretKind::EncryptedPassword).unwrap().is_none());
+        assert!(store.get("bob", SecretKind::LocalShare).unwrap().is_none());
+
+        store.delete("alice", SecretKind::LocalShare).unwrap();
+        assert!(store.get("alice", SecretKind::LocalShare).unwrap().is_none());
+    }
+}
@@ -0,0 +1,503 @@
+//! This module provides the implementation of the `UserRepo` using a SQLite database.
+//! In contrast to the other storages, the wallet transactions are kept in a separate table that is
+//! indexed by network key, hash and date, so single transactions can be inserted and queried
+//! without loading and rewriting the whole [`UserEntity`]. The entities in the `users` table never
+//! contain transactions, so reading and writing an entity does not depend on the size of the
+//! transaction history.
+//!
+//! If the storage is wrapped in an [`super::encrypted_storage::EncryptedUserStorage`], the
+//! transactions table is used through the [`TransactionIndex`] and holds encrypted rows instead.
+
+use super::error::{Result, UserKvStorageError};
+use super::{TransactionIndex, TransactionRow, UserBytesStorage};
+use crate::tx_version::VersionedWalletTransaction;
+use crate::types::users::UserEntity;
+use log::warn;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+#[doc = r"The default name of the SQLite DB file"]
+const SHARED_DB_NAME: &str = "sdk-user.sqlite";
+
+#[doc = r"The schema of the DB, applied every time the DB is opened"]
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS users (
+        username TEXT PRIMARY KEY NOT NULL,
+        data BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS transactions (
+        username TEXT NOT NULL,
+        network_key TEXT NOT NULL,
+        hash TEXT NOT NULL,
+        date INTEGER NOT NULL,
+        data BLOB NOT NULL,
+        PRIMARY KEY (username, network_key, hash)
+    );
+    CREATE INDEX IF NOT EXISTS transactions_by_date ON transactions (username, network_key, date DESC);
+";
+
+/// Implementation of [`super::UserKvStorage`] using a [`rusqlite`] database.
+pub struct SqliteUserStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteUserStorage {
+    /// Initialize a new instance and create a db file
+    pub fn new(path_prefix: &Path) -> Result<Self> {
+        let path = path_prefix.join(SHARED_DB_NAME);
+        warn!("Attempting to create user DB in path: {path:?}");
+
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Initialize a new instance with a non-persistent in-memory database
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|e| UserKvStorageError::Storage(format!("SQLite connection poisoned: {e}")))
+    }
+
+    /// Return [`UserKvStorageError::UserNotFound`] if the user does not exist
+    fn check_exists(&self, username: &str) -> Result<()> {
+        if !super::UserKvStorage::exists(self, username)? {
+            return Err(UserKvStorageError::UserNotFound {
+                username: username.to_owned(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl From<rusqlite::Error> for UserKvStorageError {
+    fn from(value: rusqlite::Error) -> Self {
+        UserKvStorageError::Storage(format!("rusqlite::Error: {:#?}", value))
+    }
+}
+
+/// The row of an unencrypted transaction, keyed by its network key and hash
+fn plaintext_row(transaction: &VersionedWalletTransaction) -> Result<TransactionRow> {
+    Ok(TransactionRow {
+        network_key: transaction.network_key().to_string(),
+        hash: transaction.transaction_hash().to_string(),
+        date: transaction.date().timestamp_millis(),
+        data: rmp_serde::to_vec(transaction)?,
+    })
+}
+
+/// Deserialize the `data` column of a list of unencrypted transaction rows
+fn decode_transactions(rows: Vec<Vec<u8>>) -> Result<Vec<VersionedWalletTransaction>> {
+    rows.iter().map(|data| Ok(rmp_serde::from_slice(data)?)).collect()
+}
+
+/// Insert a row or replace the row with the same key. The row id is kept on conflict so that the
+/// insertion order of the transactions is preserved.
+fn upsert_transaction_row(conn: &Connection, username: &str, row: &TransactionRow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO transactions (username, network_key, hash, date, data) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (username, network_key, hash) DO UPDATE SET date = excluded.date, data = excluded.data",
+        params![username, row.network_key, row.hash, row.date, row.data],
+    )?;
+    Ok(())
+}
+
+impl TransactionIndex for SqliteUserStorage {
+    fn upsert_row(&mut self, username: &str, row: &TransactionRow) -> Result<()> {
+        upsert_transaction_row(&*self.conn()?, username, row)
+    }
+
+    fn get_row(&self, username: &str, network_key: &str, hash: &str) -> Result<Option<Vec<u8>>> {
+        let conn = self.conn()?;
+        Ok(conn
+            .query_row(
+                "SELECT data FROM transactions WHERE username = ?1 AND network_key = ?2 AND hash = ?3",
+                [username, network_key, hash],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn get_rows(&self, username: &str, network_key: &str, start: usize, limit: usize) -> Result<Vec<Vec<u8>>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT data FROM transactions WHERE username = ?1 AND network_key = ?2
+             ORDER BY date DESC LIMIT ?3 OFFSET ?4",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    username,
+                    network_key,
+                    i64::try_from(limit).unwrap_or(i64::MAX),
+                    i64::try_from(start).unwrap_or(i64::MAX),
+                ],
+                |row| row.get(0),
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(rows)
+    }
+
+    fn get_all_rows(&self, username: &str) -> Result<Vec<Vec<u8>>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT data FROM transactions WHERE username = ?1 ORDER BY rowid")?;
+        let rows = stmt
+            .query_map([username], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(rows)
+    }
+
+    fn replace_rows(&mut self, username: &str, rows: &[TransactionRow]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM transactions WHERE username = ?1", [username])?;
+        for row in rows {
+            upsert_transaction_row(&tx, username, row)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl super::UserBytesStorage for SqliteUserStorage {
+    fn get_bytes(&self, username: &str) -> Result<Option<Vec<u8>>> {
+        let conn = self.conn()?;
+        Ok(conn
+            .query_row("SELECT data FROM users WHERE username = ?1", [username], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    fn set_bytes(&mut self, username: &str, bytes: &[u8]) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO users (username, data) VALUES (?1, ?2)",
+            params![username, bytes],
+        )?;
+        Ok(())
+    }
+
+    fn delete_bytes(&mut self, username: &str) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM users WHERE username = ?1", [username])?;
+        tx.execute("DELETE FROM transactions WHERE username = ?1", [username])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn usernames(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT username FROM users")?;
+        let usernames = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(usernames)
+    }
+
+    fn transaction_index(&self) -> Option<&dyn TransactionIndex> {
+        Some(self)
+    }
+
+    fn transaction_index_mut(&mut self) -> Option<&mut dyn TransactionIndex> {
+        Some(self)
+    }
+}
+
+impl super::UserKvStorage for SqliteUserStorage {
+    fn get(&self, username: &str) -> Result<UserEntity> {
+        let bytes = self
+            .get_bytes(username)?
+            .ok_or_else(|| UserKvStorageError::UserNotFound {
+                username: username.to_owned(),
+            })?;
+
+        Ok(rmp_serde::from_slice(&bytes)?)
+    }
+
+    fn delete(&mut self, username: &str) -> Result<()> {
+        self.delete_bytes(username)
+    }
+
+    fn exists(&self, username: &str) -> Result<bool> {
+        Ok(self.get_bytes(username)?.is_some())
+    }
+
+    fn set(&mut self, username: &str, value: &UserEntity) -> Result<()> {
+        // store the rest of the entity in the users table and insert its transactions, if any,
+        // into the transactions table
+        let mut user = value.clone();
+        let transactions = std::mem::take(&mut user.wallet_transactions_versioned);
+        let user_bytes = rmp_serde::to_vec(&user)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO users (username, data) VALUES (?1, ?2)",
+            params![username, user_bytes],
+        )?;
+        for transaction in &transactions {
+            upsert_transaction_row(&tx, username, &plaintext_row(transaction)?)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_users(&self) -> Result<Vec<String>> {
+        self.usernames()
+    }
+
+    fn insert_transaction(&mut self, username: &str, transaction: &VersionedWalletTransaction) -> Result<()> {
+        self.check_exists(username)?;
+        TransactionIndex::upsert_row(self, username, &plaintext_row(transaction)?)
+    }
+
+    fn get_transaction(
+        &self,
+        username: &str,
+        network_key: &str,
+        transaction_hash: &str,
+    ) -> Result<Option<VersionedWalletTransaction>> {
+        let data = self.get_row(username, network_key, transaction_hash)?;
+        Ok(data.map(|data| rmp_serde::from_slice(&data)).transpose()?)
+    }
+
+    fn get_transactions(
+        &self,
+        username: &str,
+        network_key: &str,
+        start: usize,
+        limit: usize,
+    ) -> Result<Vec<VersionedWalletTransaction>> {
+        decode_transactions(self.get_rows(username, network_key, start, limit)?)
+    }
+
+    fn get_all_transactions(&self, username: &str) -> Result<Vec<VersionedWalletTransaction>> {
+        self.check_exists(username)?;
+        decode_transactions(self.get_all_rows(username)?)
+    }
+
+    fn set_transactions(&mut self, username: &str, transactions: &[VersionedWalletTransaction]) -> Result<()> {
+        self.check_exists(username)?;
+        let rows = transactions.iter().map(plaintext_row).collect::<Result<Vec<_>>>()?;
+        self.replace_rows(username, &rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing_utils::{ETH_NETWORK_KEY, IOTA_NETWORK_KEY, example_user_entity};
+    use crate::user::UserKvStorage;
+    use chrono::{TimeZone, Utc};
+    use etopay_wallet::types::{CryptoAmount, WalletTransaction, WalletTxStatus};
+
+    fn user_entity(username: &str) -> UserEntity {
+        example_user_entity(username)
+    }
+
+    fn transaction(
+        hash: &str,
+        network_key: &str,
+        timestamp: i64,
+        status: WalletTxStatus,
+    ) -> VersionedWalletTransaction {
+        VersionedWalletTransaction::V2(WalletTransaction {
+            date: Utc.timestamp_opt(timestamp, 0).unwrap(),
+            block_number_hash: None,
+            transaction_hash: hash.to_string(),
+            receiver: String::new(),
+            sender: String::new(),
+            amount: CryptoAmount::from(1),
+            network_key: network_key.to_string(),
+            status,
+            explorer_url: None,
+            gas_fee: None,
+            is_sender: true,
+        })
+    }
+
+    #[test]
+    fn it_should_store_transactions_in_own_table() {
+        // Arrange
+        let mut storage = SqliteUserStorage::in_memory().unwrap();
+        let mut user = user_entity("alice");
+        user.wallet_transactions_versioned = vec![
+            transaction("tx1", ETH_NETWORK_KEY, 10, WalletTxStatus::Confirmed),
+            transaction("tx2", IOTA_NETWORK_KEY, 5, WalletTxStatus::Pending),
+        ];
+
+        // Act
+        storage.set("alice", &user).unwrap();
+
+        // Assert
+        assert_eq!(
+            storage.get_all_transactions("alice").unwrap(),
+            user.wallet_transactions_versioned
+        );
+        let loaded = storage.get("alice").unwrap();
+        assert!(loaded.wallet_transactions_versioned.is_empty());
+        let stored: UserEntity = rmp_serde::from_slice(&storage.get_bytes("alice").unwrap().unwrap()).unwrap();
+        assert!(stored.wallet_transactions_versioned.is_empty());
+        assert_eq!(storage.list_users().unwrap(), vec!["alice"]);
+
+        storage.delete("alice").unwrap();
+        assert!(!storage.exists("alice").unwrap());
+        assert!(
+            storage
+                .get_transaction("alice", ETH_NETWORK_KEY, "tx1")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn it_should_insert_and_query_transactions() {
+        // Arrange
+        let mut storage = SqliteUserStorage::in_memory().unwrap();
+        storage.set("alice", &user_entity("alice")).unwrap();
+
+        // Act
+        for (hash, timestamp) in [("tx1", 10), ("tx2", 30), ("tx3", 20)] {
+            storage
+                .insert_transaction(
+                    "alice",
+                    &transaction(hash, ETH_NETWORK_KEY, timestamp, WalletTxStatus::Pending),
+                )
+                .unwrap();
+        }
+        storage
+            .insert_transaction(
+                "alice",
+                &transaction("tx1", IOTA_NETWORK_KEY, 40, WalletTxStatus::Pending),
+            )
+            .unwrap();
+        let confirmed = transaction("tx3", ETH_NETWORK_KEY, 20, WalletTxStatus::Confirmed);
+        storage.insert_transaction("alice", &confirmed).unwrap();
+
+        // Assert
+        let page = storage.get_transactions("alice", ETH_NETWORK_KEY, 1, 5).unwrap();
+        let hashes: Vec<_> = page.iter().map(|t| t.transaction_hash()).collect();
+        assert_eq!(hashes, vec!["tx3", "tx1"]);
+        assert_eq!(
+            storage.get_transaction("alice", ETH_NETWORK_KEY, "tx3").unwrap(),
+            Some(confirmed)
+        );
+        assert_eq!(storage.get_all_transactions("alice").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn it_should_keep_transactions_when_setting_the_entity() {
+        // Arrange
+        let mut storage = SqliteUserStorage::in_memory().unwrap();
+        storage.set("alice", &user_entity("alice")).unwrap();
+        let stored = transaction("tx1", ETH_NETWORK_KEY, 10, WalletTxStatus::Pending);
+        storage.insert_transaction("alice", &stored).unwrap();
+
+        // Act
+        let mut user = storage.get("alice").unwrap();
+        user.selected_network = Some(ETH_NETWORK_KEY.to_string());
+        storage.set("alice", &user).unwrap();
+
+        // Assert
+        assert_eq!(storage.get("alice").unwrap(), user);
+        assert_eq!(storage.get_all_transactions("alice").unwrap(), vec![stored]);
+    }
+
+    #[test]
+    fn it_should_replace_transactions() {
+        // Arrange
+        let mut storage = SqliteUserStorage::in_memory().unwrap();
+        let mut user = user_entity("alice");
+        user.wallet_transactions_versioned = vec![
+            transaction("tx1", ETH_NETWORK_KEY, 10, WalletTxStatus::Pending),
+            transaction("tx2", ETH_NETWORK_KEY, 20, WalletTxStatus::Pending),
+        ];
+        storage.set("alice", &user).unwrap();
+        let transactions = vec![
+            transaction("tx3", ETH_NETWORK_KEY, 30, WalletTxStatus::Confirmed),
+            transaction("tx1", ETH_NETWORK_KEY, 10, WalletTxStatus::Confirmed),
+        ];
+
+        // Act
+        storage.set_transactions("alice", &transactions).unwrap();
+
+        // Assert
+        assert_eq!(storage.get_all_transactions("alice").unwrap(), transactions);
+        assert!(
+            storage
+                .get_transaction("alice", ETH_NETWORK_KEY, "tx2")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn it_should_encrypt_the_transactions_table() {
+        use crate::user::encrypted_storage::EncryptedUserStorage;
+        use secrecy::SecretSlice;
+
+        // Arrange
+        let mut storage = SqliteUserStorage::in_memory().unwrap();
+        let mut user = user_entity("alice");
+        let stored = transaction("tx1", ETH_NETWORK_KEY, 10, WalletTxStatus::Pending);
+        user.wallet_transactions_versioned = vec![stored.clone()];
+        storage.set("alice", &user).unwrap();
+
+        // Act
+        let mut encrypted = EncryptedUserStorage::open(storage, &SecretSlice::from(vec![7u8; 32])).unwrap();
+        let inserted = transaction("tx2", ETH_NETWORK_KEY, 20, WalletTxStatus::Pending);
+        encrypted.insert_transaction("alice", &inserted).unwrap();
+
+        // Assert
+        assert!(encrypted.get("alice").unwrap().wallet_transactions_versioned.is_empty());
+        assert_eq!(
+            encrypted.get_transaction("alice", ETH_NETWORK_KEY, "tx1").unwrap(),
+            Some(stored.clone())
+        );
+        assert_eq!(
+            encrypted.get_transactions("alice", ETH_NETWORK_KEY, 0, 10).unwrap(),
+            vec![inserted.clone(), stored.clone()]
+        );
+        assert_eq!(encrypted.get_all_transactions("alice").unwrap(), vec![stored, inserted]);
+
+        let storage = encrypted.into_inner();
+        let rows: Vec<(String, String, Vec<u8>)> = storage
+            .conn()
+            .unwrap()
+            .prepare("SELECT network_key, hash, data FROM transactions")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        for (network_key, hash, data) in rows {
+            assert_ne!(network_key, ETH_NETWORK_KEY);
+            assert!(hash != "tx1" && hash != "tx2");
+            assert!(rmp_serde::from_slice::<VersionedWalletTransaction>(&data).is_err());
+        }
+    }
+
+    #[test]
+    fn it_should_not_insert_transaction_for_missing_user() {
+        // Arrange
+        let mut storage = SqliteUserStorage::in_memory().unwrap();
+
+        // Act
+        let result = storage.insert_transaction(
+            "alice",
+            &transaction("tx1", ETH_NETWORK_KEY, 10, WalletTxStatus::Pending),
+        );
+
+        // Assert
+        assert!(matches!(result, Err(UserKvStorageError::UserNotFound { .. })));
+    }
+}
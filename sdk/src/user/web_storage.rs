@@ -1,3 +1,4 @@
+use super::UserBytesStorage;
 use super::UserKvStorageError;
 use super::error::Result;
 use crate::types::users::UserEntity;
@@ -58,14 +59,53 @@ impl From<base64::DecodeError> for UserKvStorageError {
     }
 }
 
-impl super::UserKvStorage for BrowserLocalStorage {
-    fn get(&self, username: &str) -> Result<UserEntity> {
-        if let Some(value) = self
+impl super::UserBytesStorage for BrowserLocalStorage {
+    fn get_bytes(&self, username: &str) -> Result<Option<Vec<u8>>> {
+        let value = self
             .get_storage()?
             .get_item(&self.storage_user_key(username))
-            .map_err(|e| UserKvStorageError::Storage(format!("Could not get storage key {username}: {e:#?}")))?
-        {
-            let bytes = BASE64_STANDARD.decode(value)?;
+            .map_err(|e| UserKvStorageError::Storage(format!("Could not get storage key {username}: {e:#?}")))?;
+
+        Ok(value.map(|text| BASE64_STANDARD.decode(text)).transpose()?)
+    }
+
+    fn set_bytes(&mut self, username: &str, bytes: &[u8]) -> Result<()> {
+        let text = BASE64_STANDARD.encode(bytes);
+
+        self.get_storage()?
+            .set_item(&self.storage_user_key(username), &text)
+            .map_err(|e| UserKvStorageError::Storage(format!("Could not set storage key {username}: {e:#?}")))
+    }
+
+    fn delete_bytes(&mut self, username: &str) -> Result<()> {
+        self.get_storage()?
+            .remove_item(&self.storage_user_key(username))
+            .map_err(|e| UserKvStorageError::Storage(format!("Could not remove storage key {username}: {e:#?} ")))
+    }
+
+    fn usernames(&self) -> Result<Vec<String>> {
+        let storage = self.get_storage()?;
+        let length = storage
+            .length()
+            .map_err(|e| UserKvStorageError::Storage(format!("Could not get storage length: {e:#?}")))?;
+
+        let prefix = format!("{STORAGE_KEY_PREFIX}.");
+        let mut usernames = Vec::new();
+        for index in 0..length {
+            let key = storage
+                .key(index)
+                .map_err(|e| UserKvStorageError::Storage(format!("Could not get storage key {index}: {e:#?}")))?;
+            if let Some(username) = key.as_deref().and_then(|key| key.strip_prefix(&prefix)) {
+                usernames.push(username.to_string());
+            }
+        }
+        Ok(usernames)
+    }
+}
+
+impl super::UserKvStorage for BrowserLocalStorage {
+    fn get(&self, username: &str) -> Result<UserEntity> {
+        if let Some(bytes) = self.get_bytes(username)? {
             let user: UserEntity = rmp_serde::from_slice(&bytes)?;
             Ok(user)
         } else {
@@ -76,9 +116,7 @@ impl super::UserKvStorage for BrowserLocalStorage {
     }
 
     fn delete(&mut self, username: &str) -> Result<()> {
-        self.get_storage()?
-            .remove_item(&self.storage_user_key(username))
-            .map_err(|e| UserKvStorageError::Storage(format!("Could not remove storage key {username}: {e:#?} ")))
+        self.delete_bytes(username)
     }
 
     fn exists(&self, username: &str) -> Result<bool> {
@@ -90,15 +128,11 @@ impl super::UserKvStorage for BrowserLocalStorage {
     }
 
     fn set(&mut self, username: &str, value: &UserEntity) -> Result<()> {
-        let storage = self.get_storage()?;
-
         let bytes = rmp_serde::to_vec(value)?;
-        let text = BASE64_STANDARD.encode(bytes);
-
-        storage
-            .set_item(&self.storage_user_key(username), &text)
-            .map_err(|e| UserKvStorageError::Storage(format!("Could not set storage key {username}: {e:#?}")))?;
+        self.set_bytes(username, &bytes)
+    }
 
-        Ok(())
+    fn list_users(&self) -> Result<Vec<String>> {
+        self.usernames()
     }
 }
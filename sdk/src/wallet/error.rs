@@ -27,6 +27,24 @@ pub enum WalletError {
     #[error("Pin or password incorrect.")]
     WrongPinOrPassword,
 
+    /// Wrong pin, the wallet is locked after the remaining attempts failed as well
+    #[error("Pin incorrect, {remaining_attempts} attempts remaining.")]
+    WrongPin {
+        /// Number of attempts left before the wallet is locked
+        remaining_attempts: u32,
+    },
+
+    /// Too many wrong pin attempts, the next attempt is only possible after a delay
+    #[error("Too many wrong pin attempts, try again in {retry_after_secs} seconds.")]
+    PinBackoff {
+        /// Number of seconds until the next attempt is possible
+        retry_after_secs: u64,
+    },
+
+    /// The wallet is locked because of too many wrong pin attempts
+    #[error("Wallet is locked after too many wrong pin attempts. Restore it from the mnemonic or a backup.")]
+    PinLocked,
+
     /// Error occurs if the wallet is not initialized
     #[error("Wallet init error: {0:?}")]
     WalletNotInitialized(ErrorKind),
@@ -35,6 +53,10 @@ pub enum WalletError {
     #[error("Unauthorized: Missing Access Token")]
     MissingAccessToken,
 
+    /// The same network was requested more than once
+    #[error("Network {0} was requested more than once")]
+    DuplicateNetwork(String),
+
     /// Error occurs is the transaction is invalid
     #[error("InvalidTransaction: {0}")]
     InvalidTransaction(String),
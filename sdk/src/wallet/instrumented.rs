@@ -0,0 +1,114 @@
+use crate::telemetry::{TelemetryEvent, TelemetryHook};
+use async_trait::async_trait;
+use chrono::Utc;
+use etopay_wallet::types::{CryptoAmount, GasCostEstimation, WalletTransaction};
+use etopay_wallet::{Result, TransactionIntent, WalletUser};
+use std::future::Future;
+
+/// Reports the duration and result of every call of the wrapped wallet as
+/// [`TelemetryEvent::RpcCall`] and wraps the calls in `tracing` spans if the feature is enabled.
+#[derive(Debug)]
+pub struct InstrumentedWallet {
+    network_key: String,
+    inner: Box<dyn WalletUser + Send + Sync>,
+    telemetry: TelemetryHook,
+}
+
+impl InstrumentedWallet {
+    pub(crate) fn new(network_key: String, inner: Box<dyn WalletUser + Send + Sync>, telemetry: TelemetryHook) -> Self {
+        Self {
+            network_key,
+            inner,
+            telemetry,
+        }
+    }
+
+    async fn observe<T>(&self, operation: &'static str, call: impl Future<Output = Result<T>>) -> Result<T> {
+        let started_at = Utc::now();
+        let result = call.await;
+        let duration_ms = (Utc::now() - started_at).num_milliseconds().max(0) as u64;
+        self.telemetry.record(|| TelemetryEvent::RpcCall {
+            network_key: self.network_key.clone(),
+            operation,
+            success: result.is_ok(),
+            duration_ms,
+        });
+        result
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl WalletUser for InstrumentedWallet {
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(network = %self.network_key)))]
+    async fn get_address(&self) -> Result<String> {
+        self.observe("get_address", self.inner.get_address()).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(network = %self.network_key)))]
+    async fn get_balance(&self) -> Result<CryptoAmount> {
+        self.observe("get_balance", self.inner.get_balance()).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(network = %self.network_key)))]
+    async fn send_amount(&self, intent: &TransactionIntent) -> Result<String> {
+        self.observe("send_amount", self.inner.send_amount(intent)).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(network = %self.network_key)))]
+    async fn get_wallet_tx_list(&self, start: usize, limit: usize) -> Result<Vec<String>> {
+        self.observe("get_wallet_tx_list", self.inner.get_wallet_tx_list(start, limit))
+            .await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(network = %self.network_key)))]
+    async fn get_wallet_tx(&self, tx_id: &str) -> Result<WalletTransaction> {
+        self.observe("get_wallet_tx", self.inner.get_wallet_tx(tx_id)).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(network = %self.network_key)))]
+    async fn estimate_gas_cost(&self, intent: &TransactionIntent) -> Result<GasCostEstimation> {
+        self.observe("estimate_gas_cost", self.inner.estimate_gas_cost(intent))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::testing::RecordingTelemetry;
+    use etopay_wallet::MockWalletUser;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_reports_rpc_calls() {
+        // Arrange
+        let telemetry = Arc::new(RecordingTelemetry::default());
+        let mut inner = MockWalletUser::new();
+        inner
+            .expect_get_address()
+            .once()
+            .returning(|| Ok(String::from("address")));
+        let wallet = InstrumentedWallet::new(
+            String::from("INSTRUMENTED_TEST"),
+            Box::new(inner),
+            TelemetryHook::new(Some(telemetry.clone())),
+        );
+
+        // Act
+        let address = wallet.get_address().await;
+
+        // Assert
+        assert_eq!(address.unwrap(), "address");
+        let events = telemetry.events.lock().unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            TelemetryEvent::RpcCall {
+                network_key,
+                operation: "get_address",
+                success: true,
+                ..
+            } if network_key == "INSTRUMENTED_TEST"
+        )));
+    }
+}
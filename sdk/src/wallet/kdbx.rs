@@ -1,18 +1,71 @@
+use crate::types::newtypes::Bip39Passphrase;
+use crate::types::users::{AddressBookEntry, AddressLabel};
+use chrono::{DateTime, Utc};
+use etopay_wallet::MnemonicDerivationOption;
 use etopay_wallet::bip39::Mnemonic;
-use kdbx_rs::database::Entry;
+use kdbx_rs::database::{Entry, Field};
 use kdbx_rs::errors::FailedUnlock;
 use kdbx_rs::{CompositeKey, Database, Kdbx};
 use log::info;
 use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
 
-/// load mnemonic from kdbx file
-pub fn load_mnemonic(backup: &[u8], password: &SecretString) -> Result<Mnemonic, KdbxStorageError> {
+/// The version of the backup format written by [`store_backup`].
+///
+/// * `1`: the backup only contains the mnemonic.
+/// * `2`: the backup additionally contains the [`BackupMetadata`] and the creation timestamp.
+pub const BACKUP_FORMAT_VERSION: u32 = 2;
+
+/// Title of the entry holding the mnemonic phrase as its password
+const MNEMONIC_ENTRY: &str = "mnemonic";
+/// Title of the optional entry holding the BIP39 passphrase as its password
+const PASSPHRASE_ENTRY: &str = "passphrase";
+/// Title of the entry holding the JSON-serialized [`BackupMetadata`] as its password
+const METADATA_ENTRY: &str = "metadata";
+/// Field of the metadata entry containing the format version
+const VERSION_FIELD: &str = "version";
+/// Field of the metadata entry containing the creation timestamp (RFC 3339)
+const CREATED_AT_FIELD: &str = "created_at";
+
+/// Wallet data stored next to the mnemonic in a backup
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupMetadata {
+    /// The derivation options used when the backup was created
+    pub derivation_options: MnemonicDerivationOption,
+    /// The key of the network that was active when the backup was created
+    pub active_network: Option<String>,
+    /// The keys of all networks the wallet was used on
+    pub networks: Vec<String>,
+    /// Labels attached to the user's own addresses
+    pub address_labels: Vec<AddressLabel>,
+    /// The user's address book
+    pub address_book: Vec<AddressBookEntry>,
+}
+
+/// The content of a wallet backup
+#[derive(Debug)]
+pub struct WalletBackup {
+    /// The version of the backup format
+    pub version: u32,
+    /// When the backup was created, [`None`] for backups of version `1`
+    pub created_at: Option<DateTime<Utc>>,
+    /// The mnemonic of the wallet
+    pub mnemonic: Mnemonic,
+    /// The BIP39 passphrase of the wallet, if any
+    pub passphrase: Option<Bip39Passphrase>,
+    /// Additional wallet data, empty for backups of version `1`
+    pub metadata: BackupMetadata,
+}
+
+/// load a wallet backup from kdbx file
+pub fn load_backup(backup: &[u8], password: &SecretString) -> Result<WalletBackup, KdbxStorageError> {
     info!("Loading kdbx file from bytes");
     let kdbx = kdbx_rs::from_reader(backup)?;
     let key = CompositeKey::from_password(password.expose_secret());
     let unlocked = kdbx.unlock(&key)?;
 
-    let Some(entry) = unlocked.find_entry(|entry| entry.title() == Some("mnemonic")) else {
+    let Some(entry) = unlocked.find_entry(|entry| entry.title() == Some(MNEMONIC_ENTRY)) else {
         return Err(KdbxStorageError::NotFound("Entry not found".to_string()));
     };
 
@@ -20,23 +73,87 @@ pub fn load_mnemonic(backup: &[u8], password: &SecretString) -> Result<Mnemonic,
         return Err(KdbxStorageError::NotFound("Mnemonic not found".to_string()));
     };
 
-    let mnemonic = Mnemonic::from_phrase(mnemonic, etopay_wallet::bip39::Language::English)?;
+    let mnemonic = super::mnemonic::parse_mnemonic(mnemonic)?;
+
+    let passphrase = unlocked
+        .find_entry(|entry| entry.title() == Some(PASSPHRASE_ENTRY))
+        .and_then(Entry::password)
+        .and_then(|passphrase| Bip39Passphrase::try_from_string(passphrase).ok());
+
+    // backups of version 1 only contain the mnemonic entry
+    let Some(entry) = unlocked.find_entry(|entry| entry.title() == Some(METADATA_ENTRY)) else {
+        info!("Backup does not contain metadata, assuming version 1");
+        return Ok(WalletBackup {
+            version: 1,
+            created_at: None,
+            mnemonic,
+            passphrase,
+            metadata: BackupMetadata::default(),
+        });
+    };
+
+    let version = entry
+        .find(VERSION_FIELD)
+        .and_then(Field::value)
+        .ok_or_else(|| KdbxStorageError::InvalidMetadata("missing version".to_string()))?
+        .parse::<u32>()
+        .map_err(|e| KdbxStorageError::InvalidMetadata(format!("invalid version: {e}")))?;
+    if version > BACKUP_FORMAT_VERSION {
+        return Err(KdbxStorageError::UnsupportedVersion(version));
+    }
+
+    let created_at = entry
+        .find(CREATED_AT_FIELD)
+        .and_then(Field::value)
+        .map(DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|e| KdbxStorageError::InvalidMetadata(format!("invalid creation timestamp: {e}")))?
+        .map(|date| date.with_timezone(&Utc));
 
-    Ok(mnemonic)
+    let metadata = serde_json::from_str(entry.password().unwrap_or_default())
+        .map_err(|e| KdbxStorageError::InvalidMetadata(e.to_string()))?;
+
+    Ok(WalletBackup {
+        version,
+        created_at,
+        mnemonic,
+        passphrase,
+        metadata,
+    })
 }
 
-/// store mnemonic in kdbx file
-pub fn store_mnemonic(mnemonic: &Mnemonic, password: &SecretString) -> Result<Vec<u8>, KdbxStorageError> {
+/// store mnemonic, passphrase and metadata in kdbx file
+pub fn store_backup(
+    mnemonic: &Mnemonic,
+    passphrase: Option<&Bip39Passphrase>,
+    metadata: &BackupMetadata,
+    password: &SecretString,
+) -> Result<Vec<u8>, KdbxStorageError> {
     info!("Creating kdbx file from mnemonic");
 
     let mut database = Database::default();
     database.set_name("etopay");
 
     let mut entry = Entry::default();
-    entry.set_title("mnemonic");
+    entry.set_title(MNEMONIC_ENTRY);
     entry.set_password(mnemonic.phrase());
     database.add_entry(entry);
 
+    if let Some(passphrase) = passphrase {
+        let mut entry = Entry::default();
+        entry.set_title(PASSPHRASE_ENTRY);
+        entry.set_password(passphrase.as_str());
+        database.add_entry(entry);
+    }
+
+    let metadata = serde_json::to_string(metadata).map_err(|e| KdbxStorageError::InvalidMetadata(e.to_string()))?;
+    let mut entry = Entry::default();
+    entry.set_title(METADATA_ENTRY);
+    entry.set_password(metadata);
+    entry.add_field(Field::new(VERSION_FIELD, &BACKUP_FORMAT_VERSION.to_string()));
+    entry.add_field(Field::new(CREATED_AT_FIELD, &Utc::now().to_rfc3339()));
+    database.add_entry(entry);
+
     let mut kdbx = Kdbx::from_database(database);
     kdbx.set_key(CompositeKey::from_password(password.expose_secret()))?;
 
@@ -69,6 +186,14 @@ pub enum KdbxStorageError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// The metadata stored in the backup could not be read or written
+    #[error("Invalid backup metadata: {0}")]
+    InvalidMetadata(String),
+
+    /// The backup was created with a newer, unknown version of the backup format
+    #[error("Unsupported backup version: {0}")]
+    UnsupportedVersion(u32),
+
     /// Error occurred while handling bip39 compliant mnemonics
     #[error("Bip39 error: {0:?}")]
     Bip39(#[from] etopay_wallet::bip39::ErrorKind),
@@ -85,21 +210,96 @@ mod tests {
     use super::*;
     use secrecy::SecretString;
 
+    fn example_metadata() -> BackupMetadata {
+        BackupMetadata {
+            derivation_options: MnemonicDerivationOption { account: 1, index: 2 },
+            active_network: Some("IOTA".to_string()),
+            networks: vec!["IOTA".to_string(), "ETH".to_string()],
+            address_labels: vec![AddressLabel {
+                network_key: "IOTA".to_string(),
+                address: "0x1234".to_string(),
+                label: "savings".to_string(),
+            }],
+            address_book: vec![AddressBookEntry {
+                network_key: "ETH".to_string(),
+                address: "0x5678".to_string(),
+                name: "alice".to_string(),
+            }],
+        }
+    }
+
     #[test]
-    fn test_store_and_load_mnemonic() {
+    fn test_store_and_load_backup() {
         // Arrange
         let mnemonic = Mnemonic::new(
             etopay_wallet::bip39::MnemonicType::Words24,
             etopay_wallet::bip39::Language::English,
         );
+        let metadata = example_metadata();
 
         let password = SecretString::new("password".into());
 
         // Act
-        let kdbx = store_mnemonic(&mnemonic, &password).unwrap();
-        let mnemonic_recovered = load_mnemonic(&kdbx, &password).unwrap();
+        let kdbx = store_backup(&mnemonic, None, &metadata, &password).unwrap();
+        let backup = load_backup(&kdbx, &password).unwrap();
+
+        // Assert
+        assert_eq!(backup.mnemonic.phrase(), mnemonic.phrase());
+        assert!(backup.passphrase.is_none());
+        assert_eq!(backup.version, BACKUP_FORMAT_VERSION);
+        assert!(backup.created_at.is_some());
+        assert_eq!(backup.metadata, metadata);
+    }
+
+    #[test]
+    fn test_store_and_load_backup_with_passphrase_and_non_english_mnemonic() {
+        // Arrange
+        let mnemonic = Mnemonic::new(
+            etopay_wallet::bip39::MnemonicType::Words24,
+            etopay_wallet::bip39::Language::Japanese,
+        );
+        let passphrase = Bip39Passphrase::try_from_string("25th word").unwrap();
+        let password = SecretString::new("password".into());
+
+        // Act
+        let kdbx = store_backup(&mnemonic, Some(&passphrase), &example_metadata(), &password).unwrap();
+        let backup = load_backup(&kdbx, &password).unwrap();
+
+        // Assert
+        assert_eq!(backup.mnemonic.phrase(), mnemonic.phrase());
+        assert_eq!(backup.mnemonic.language(), etopay_wallet::bip39::Language::Japanese);
+        assert_eq!(backup.passphrase.unwrap().as_str(), passphrase.as_str());
+    }
+
+    #[test]
+    fn test_load_version_1_backup() {
+        // Arrange: a backup as written before the format was versioned
+        let mnemonic = Mnemonic::new(
+            etopay_wallet::bip39::MnemonicType::Words24,
+            etopay_wallet::bip39::Language::English,
+        );
+        let password = SecretString::new("password".into());
+
+        let mut database = Database::default();
+        database.set_name("etopay");
+        let mut entry = Entry::default();
+        entry.set_title(MNEMONIC_ENTRY);
+        entry.set_password(mnemonic.phrase());
+        database.add_entry(entry);
+        let mut kdbx = Kdbx::from_database(database);
+        kdbx.set_key(CompositeKey::from_password(password.expose_secret()))
+            .unwrap();
+        let mut buffer = Vec::new();
+        kdbx.write(&mut buffer).unwrap();
+
+        // Act
+        let backup = load_backup(&buffer, &password).unwrap();
 
         // Assert
-        assert_eq!(mnemonic_recovered.phrase(), mnemonic.phrase());
+        assert_eq!(backup.mnemonic.phrase(), mnemonic.phrase());
+        assert_eq!(backup.version, 1);
+        assert_eq!(backup.created_at, None);
+        assert!(backup.passphrase.is_none());
+        assert_eq!(backup.metadata, BackupMetadata::default());
     }
 }
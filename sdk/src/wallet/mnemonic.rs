@@ -0,0 +1,77 @@
+use etopay_wallet::bip39::{ErrorKind, Mnemonic};
+
+/// The BIP39 wordlist language of a mnemonic
+pub use etopay_wallet::bip39::Language;
+
+/// All BIP39 wordlist languages that can be used for mnemonics, in the order in which they are
+/// tried when detecting the language of a mnemonic phrase.
+pub const SUPPORTED_LANGUAGES: [Language; 8] = [
+    Language::English,
+    Language::ChineseSimplified,
+    Language::ChineseTraditional,
+    Language::French,
+    Language::Italian,
+    Language::Japanese,
+    Language::Korean,
+    Language::Spanish,
+];
+
+/// Parse a mnemonic phrase in any of the [`SUPPORTED_LANGUAGES`], detecting the wordlist language
+/// from the words used. If the phrase is not valid in any language, the error from parsing it as an
+/// English phrase is returned.
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, ErrorKind> {
+    SUPPORTED_LANGUAGES
+        .iter()
+        .find_map(|language| Mnemonic::from_phrase(phrase, *language).ok())
+        .map_or_else(|| Mnemonic::from_phrase(phrase, Language::English), Ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etopay_wallet::bip39::MnemonicType;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::english(Language::English)]
+    #[case::chinese_simplified(Language::ChineseSimplified)]
+    #[case::chinese_traditional(Language::ChineseTraditional)]
+    #[case::french(Language::French)]
+    #[case::italian(Language::Italian)]
+    #[case::japanese(Language::Japanese)]
+    #[case::korean(Language::Korean)]
+    #[case::spanish(Language::Spanish)]
+    fn test_parse_mnemonic_detects_language(#[case] language: Language) {
+        // Arrange
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, language);
+
+        // Act
+        let parsed = parse_mnemonic(mnemonic.phrase()).unwrap();
+
+        // Assert
+        assert_eq!(parsed.entropy(), mnemonic.entropy());
+        assert_eq!(parsed.phrase(), mnemonic.phrase());
+    }
+
+    #[test]
+    fn test_parse_mnemonic_with_ideographic_spaces() {
+        // Arrange: Japanese mnemonics are usually written with ideographic spaces
+        let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::Japanese);
+        let phrase = mnemonic.phrase().replace(' ', "\u{3000}");
+
+        // Act
+        let parsed = parse_mnemonic(&phrase).unwrap();
+
+        // Assert
+        assert_eq!(parsed.language(), Language::Japanese);
+        assert_eq!(parsed.phrase(), mnemonic.phrase());
+    }
+
+    #[test]
+    fn test_parse_invalid_mnemonic() {
+        assert!(matches!(
+            parse_mnemonic("not a mnemonic"),
+            Err(ErrorKind::InvalidWord(_))
+        ));
+    }
+}
@@ -10,12 +10,18 @@ pub mod wallet_manager;
 /// Module containing code related to the SSS secret sharing scheme
 pub mod share;
 
+/// Module containing code related to mnemonics in the different BIP39 wordlist languages
+pub mod mnemonic;
+
 /// Module containing code related to the KDBX file format
 pub mod kdbx;
 
 /// Errors related to sdk wallet
 pub mod error;
 
+/// Wallet decorator reporting the calls to the telemetry
+pub(crate) mod instrumented;
+
 /// Versioned wallet transactions
 ///
 /// Defines an enum to represent different versions of wallet transaction data,
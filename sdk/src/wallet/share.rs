@@ -1,10 +1,10 @@
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use blake2::Digest;
-use etopay_wallet::bip39::Mnemonic;
+use etopay_wallet::bip39::{Language, Mnemonic};
 use secrecy::{ExposeSecret, SecretBox, SecretSlice, SecretString};
 use std::str::FromStr;
 
-use crate::types::crypto::Blake2b256;
+use crate::types::{crypto::Blake2b256, newtypes::Bip39Passphrase};
 
 /// A share that can be used with other [`Share`] to construct the secret.
 #[derive(Debug, Clone)] // for testing purposes we also derive PartialEq
@@ -77,7 +77,7 @@ impl Share {
     #[cfg(test)]
     pub(crate) fn mock_share() -> Self {
         Share {
-            payload_type: PayloadType::MnemonicEntropy,
+            payload_type: PayloadType::MnemonicEntropy(Language::English),
             encoding: Encoding::RustySecrets,
             encryption: Encryption::None,
             data: ShareData("test".to_string().into_bytes().into()),
@@ -85,18 +85,52 @@ impl Share {
     }
 }
 
-/// Version of the Share, used for allowing different formats in the future
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+/// Version of the Share, used for allowing different formats in the future.
+///
+/// Shares of an English mnemonic without passphrase keep the original `ME` identifier and are
+/// readable by every version. Versions that predate the other identifiers reject those shares with
+/// [`ShareError::InvalidShareFormat`] instead of decoding the entropy with the wrong wordlist.
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum PayloadType {
-    /// Payload contains the raw entropy bytes stored in the mnemonic.
-    MnemonicEntropy,
+    /// Payload contains the raw entropy bytes stored in the mnemonic, which uses the wordlist of
+    /// the given language.
+    MnemonicEntropy(Language),
+    /// Payload contains the length of the mnemonic entropy as a single byte, the raw entropy bytes
+    /// and the UTF-8 encoded BIP39 passphrase, such that the shares alone restore the wallet.
+    MnemonicEntropyWithPassphrase(Language),
 }
 
+/// Identifier prefix of [`PayloadType::MnemonicEntropy`]
+const MNEMONIC_ENTROPY_PREFIX: &str = "ME";
+
+/// Identifier prefix of [`PayloadType::MnemonicEntropyWithPassphrase`]
+const MNEMONIC_ENTROPY_WITH_PASSPHRASE_PREFIX: &str = "MP";
+
+/// Suffixes of the [`PayloadType::MnemonicEntropy`] identifier for each wordlist language. English
+/// has no suffix such that shares created before other languages were supported stay valid.
+const MNEMONIC_LANGUAGE_SUFFIXES: [(Language, &str); 8] = [
+    (Language::English, ""),
+    (Language::ChineseSimplified, "_ZHS"),
+    (Language::ChineseTraditional, "_ZHT"),
+    (Language::French, "_FR"),
+    (Language::Italian, "_IT"),
+    (Language::Japanese, "_JA"),
+    (Language::Korean, "_KO"),
+    (Language::Spanish, "_ES"),
+];
+
 impl std::fmt::Display for PayloadType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::MnemonicEntropy => write!(f, "ME"),
-        }
+        let (prefix, language) = match self {
+            Self::MnemonicEntropy(language) => (MNEMONIC_ENTROPY_PREFIX, language),
+            Self::MnemonicEntropyWithPassphrase(language) => (MNEMONIC_ENTROPY_WITH_PASSPHRASE_PREFIX, language),
+        };
+        let suffix = MNEMONIC_LANGUAGE_SUFFIXES
+            .iter()
+            .find(|(l, _)| l == language)
+            .map(|(_, suffix)| *suffix)
+            .unwrap_or_default();
+        write!(f, "{prefix}{suffix}")
     }
 }
 
@@ -104,12 +138,24 @@ impl FromStr for PayloadType {
     type Err = ShareError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "ME" => Ok(Self::MnemonicEntropy),
-            other => Err(ShareError::InvalidShareFormat(format!(
+        let language = |suffix: &str| {
+            MNEMONIC_LANGUAGE_SUFFIXES
+                .iter()
+                .find(|(_, s)| *s == suffix)
+                .map(|(language, _)| *language)
+        };
+        if let Some(language) = s.strip_prefix(MNEMONIC_ENTROPY_PREFIX).and_then(language) {
+            Ok(Self::MnemonicEntropy(language))
+        } else if let Some(language) = s
+            .strip_prefix(MNEMONIC_ENTROPY_WITH_PASSPHRASE_PREFIX)
+            .and_then(language)
+        {
+            Ok(Self::MnemonicEntropyWithPassphrase(language))
+        } else {
+            Err(ShareError::InvalidShareFormat(format!(
                 "Unrecognized Payload type: `{}`",
-                other
-            ))),
+                s
+            )))
         }
     }
 }
@@ -216,28 +262,62 @@ pub struct GeneratedShares {
     pub backup: Share,
 }
 
-/// Creates shares from a [`Mnemonic`] that can be resolved into a [`Mnemonic`] again when reconstructed.
+/// Creates shares from a [`Mnemonic`] and the optional BIP39 passphrase that can be resolved into
+/// both again when reconstructed. Without a passphrase the shares use the original
+/// [`PayloadType::MnemonicEntropy`] format.
 #[allow(clippy::result_large_err)]
 pub fn create_shares_from_mnemonic(
     mnemonic: &Mnemonic,
+    passphrase: Option<&Bip39Passphrase>,
     password: &SecretSlice<u8>,
 ) -> super::error::Result<GeneratedShares> {
     // convert the mnemonic string into the raw entropy it encodes
     let entropy = mnemonic.entropy();
 
-    create_shares_from_secret(PayloadType::MnemonicEntropy, &entropy.to_vec().into(), password).map_err(Into::into)
+    let (payload_type, secret) = match passphrase {
+        None => (PayloadType::MnemonicEntropy(mnemonic.language()), entropy.to_vec()),
+        Some(passphrase) => {
+            let entropy_len = u8::try_from(entropy.len())
+                .map_err(|_| ShareError::InvalidShareFormat(String::from("mnemonic entropy is too long")))?;
+            let mut secret = Vec::with_capacity(1 + entropy.len() + passphrase.as_str().len());
+            secret.push(entropy_len);
+            secret.extend_from_slice(entropy);
+            secret.extend_from_slice(passphrase.as_str().as_bytes());
+            (PayloadType::MnemonicEntropyWithPassphrase(mnemonic.language()), secret)
+        }
+    };
+
+    create_shares_from_secret(payload_type, &secret.into(), password).map_err(Into::into)
 }
 
-/// Reconstruct a [`Mnemonic`] from the shares. Can be used to initialize a wallet using the
+/// Reconstruct a [`Mnemonic`] and the BIP39 passphrase, if any, from the shares. Can be used to
+/// initialize a wallet using the
 /// [`iota_sdk::client::secret::mnemonic::MnemonicSecretManager::try_from_mnemonic`] function.
 #[allow(clippy::result_large_err)]
-pub fn reconstruct_mnemonic(shares: &[&Share], password: Option<&SecretSlice<u8>>) -> super::error::Result<Mnemonic> {
+pub fn reconstruct_mnemonic(
+    shares: &[&Share],
+    password: Option<&SecretSlice<u8>>,
+) -> super::error::Result<(Mnemonic, Option<Bip39Passphrase>)> {
     let (payload_type, secret) = reconstruct_secret(shares, password)?;
     match payload_type {
-        PayloadType::MnemonicEntropy => Ok(Mnemonic::from_entropy(
-            secret.expose_secret(),
-            etopay_wallet::bip39::Language::English,
-        )?),
+        PayloadType::MnemonicEntropy(language) => Ok((Mnemonic::from_entropy(secret.expose_secret(), language)?, None)),
+        PayloadType::MnemonicEntropyWithPassphrase(language) => {
+            let (entropy_len, rest) = secret
+                .expose_secret()
+                .split_first()
+                .ok_or_else(|| ShareError::InvalidShareFormat(String::from("empty secret")))?;
+            if rest.len() <= usize::from(*entropy_len) {
+                return Err(ShareError::InvalidShareFormat(String::from("secret is missing the passphrase")).into());
+            }
+            let (entropy, passphrase) = rest.split_at(usize::from(*entropy_len));
+            let passphrase = std::str::from_utf8(passphrase)
+                .map_err(|_| ShareError::InvalidShareFormat(String::from("passphrase is not valid UTF-8")))?;
+
+            Ok((
+                Mnemonic::from_entropy(entropy, language)?,
+                Some(Bip39Passphrase::try_from_string(passphrase)?),
+            ))
+        }
     }
 }
 
@@ -420,7 +500,7 @@ mod test {
     #[test]
     fn test_string_serialization() {
         let s = Share {
-            payload_type: super::PayloadType::MnemonicEntropy,
+            payload_type: super::PayloadType::MnemonicEntropy(Language::English),
             encoding: Encoding::RustySecrets,
             encryption: super::Encryption::None,
             data: ShareData("data".to_string().into_bytes().into()),
@@ -436,7 +516,8 @@ mod test {
         let secret = SecretBox::new("secret".to_string().into_bytes().into());
         let password = SecretBox::new("password".to_string().into_bytes().into());
 
-        let shares = create_shares_from_secret(PayloadType::MnemonicEntropy, &secret, &password).unwrap();
+        let shares =
+            create_shares_from_secret(PayloadType::MnemonicEntropy(Language::English), &secret, &password).unwrap();
 
         assert_eq!(
             reconstruct_secret(&[&shares.backup, &shares.local], Some(&password))
@@ -478,15 +559,20 @@ mod test {
         let secret = SecretBox::new("my hex string".to_string().into_bytes().into());
         let password = SecretBox::new("password".to_string().into_bytes().into());
 
-        let shares = create_shares_from_secret(PayloadType::MnemonicEntropy, &secret, &password).unwrap();
+        let shares =
+            create_shares_from_secret(PayloadType::MnemonicEntropy(Language::English), &secret, &password).unwrap();
 
         // reconstruct using backup and recovery
         let (_, reconstructed_secret) =
             reconstruct_secret(&[&shares.backup, &shares.recovery], Some(&password)).unwrap();
 
         // now create shares again and make sure we
-        let new_shares =
-            create_shares_from_secret(PayloadType::MnemonicEntropy, &reconstructed_secret, &password).unwrap();
+        let new_shares = create_shares_from_secret(
+            PayloadType::MnemonicEntropy(Language::English),
+            &reconstructed_secret,
+            &password,
+        )
+        .unwrap();
 
         // reconstruct using a mix of old and "new" shares
         let (_, final_secret) = reconstruct_secret(&[&shares.backup, &new_shares.local], Some(&password)).unwrap();
@@ -505,29 +591,33 @@ mod test {
         );
 
         // Perform and check
-        let shares = create_shares_from_mnemonic(&mnemonic, &password).unwrap();
+        let shares = create_shares_from_mnemonic(&mnemonic, None, &password).unwrap();
 
         assert_eq!(
             reconstruct_mnemonic(&[&shares.backup, &shares.local], Some(&password))
                 .unwrap()
+                .0
                 .entropy(),
             mnemonic.entropy(),
         );
         assert_eq!(
             reconstruct_mnemonic(&[&shares.backup, &shares.recovery], Some(&password))
                 .unwrap()
+                .0
                 .entropy(),
             mnemonic.entropy(),
         );
         assert_eq!(
             reconstruct_mnemonic(&[&shares.recovery, &shares.local], None)
                 .unwrap()
+                .0
                 .entropy(),
             mnemonic.entropy(),
         );
         assert_eq!(
             reconstruct_mnemonic(&[&shares.recovery, &shares.local, &shares.backup], Some(&password))
                 .unwrap()
+                .0
                 .entropy(),
             mnemonic.entropy()
         );
@@ -537,6 +627,69 @@ mod test {
         assert!(reconstruct_mnemonic(&[&shares.backup], Some(&password)).is_err());
     }
 
+    #[rstest::rstest]
+    #[case::english(Language::English, "ME")]
+    #[case::japanese(Language::Japanese, "ME_JA")]
+    #[case::chinese_simplified(Language::ChineseSimplified, "ME_ZHS")]
+    fn test_split_recover_mnemonic_keeps_language(#[case] language: Language, #[case] payload_type: &str) {
+        // Arrange
+        let password = SecretBox::new("password".to_string().into_bytes().into());
+        let mnemonic = Mnemonic::new(etopay_wallet::bip39::MnemonicType::Words24, language);
+
+        // Act
+        let shares = create_shares_from_mnemonic(&mnemonic, None, &password).unwrap();
+        let local = shares.local.to_string().expose_secret().parse::<Share>().unwrap();
+        let (reconstructed, _) = reconstruct_mnemonic(&[&shares.recovery, &local], None).unwrap();
+
+        // Assert
+        assert!(
+            shares
+                .local
+                .to_string()
+                .expose_secret()
+                .starts_with(&format!("{payload_type}-"))
+        );
+        assert_eq!(reconstructed.language(), language);
+        assert_eq!(reconstructed.phrase(), mnemonic.phrase());
+    }
+
+    #[test]
+    fn test_split_recover_mnemonic_with_passphrase() {
+        // Arrange
+        let password = SecretBox::new("password".to_string().into_bytes().into());
+        let mnemonic = Mnemonic::new(etopay_wallet::bip39::MnemonicType::Words24, Language::French);
+        let passphrase = Bip39Passphrase::try_from_string("25th wörd").unwrap();
+
+        // Act
+        let shares = create_shares_from_mnemonic(&mnemonic, Some(&passphrase), &password).unwrap();
+        let (reconstructed, reconstructed_passphrase) =
+            reconstruct_mnemonic(&[&shares.backup, &shares.recovery], Some(&password)).unwrap();
+
+        // Assert
+        assert!(shares.recovery.to_string().expose_secret().starts_with("MP_FR-"));
+        assert_eq!(reconstructed.phrase(), mnemonic.phrase());
+        assert_eq!(reconstructed_passphrase.unwrap().as_str(), passphrase.as_str());
+    }
+
+    #[test]
+    fn test_legacy_share_format_is_unchanged() {
+        // Arrange
+        let legacy = crate::testing_utils::NOT_ENCRYPTED_SHARE;
+        let mnemonic = Mnemonic::new(etopay_wallet::bip39::MnemonicType::Words24, Language::English);
+        let password = SecretBox::new("password".to_string().into_bytes().into());
+
+        // Act
+        let parsed = legacy.parse::<Share>().unwrap();
+        let shares = create_shares_from_mnemonic(&mnemonic, None, &password).unwrap();
+
+        // Assert
+        assert_eq!(parsed.payload_type, PayloadType::MnemonicEntropy(Language::English));
+        assert_eq!(parsed.to_string().expose_secret(), legacy);
+        for share in [&shares.recovery, &shares.local, &shares.backup] {
+            assert_eq!(share.to_string().expose_secret().split('-').next(), Some("ME"));
+        }
+    }
+
     #[test]
     fn test_split_recover_mnemonic_example() {
         let password: SecretSlice<u8> = "mnemonic share password".to_string().into_bytes().into();
@@ -564,7 +717,7 @@ mod test {
         let shares: Vec<&Share> = shares.iter().collect();
 
         assert_eq!(
-            reconstruct_mnemonic(&shares, Some(&password)).unwrap().to_string(),
+            reconstruct_mnemonic(&shares, Some(&password)).unwrap().0.to_string(),
             mnemonic_str,
         );
 
@@ -575,7 +728,7 @@ mod test {
         let shares: Vec<Share> = shares.iter().map(|&s| s.parse::<Share>().unwrap()).collect();
         let shares: Vec<&Share> = shares.iter().collect();
         assert_eq!(
-            reconstruct_mnemonic(&shares, Some(&password)).unwrap().to_string(),
+            reconstruct_mnemonic(&shares, Some(&password)).unwrap().0.to_string(),
             mnemonic_str
         );
 
@@ -585,7 +738,7 @@ mod test {
         ];
         let shares: Vec<Share> = shares.iter().map(|&s| s.parse::<Share>().unwrap()).collect();
         let shares: Vec<&Share> = shares.iter().collect();
-        assert_eq!(reconstruct_mnemonic(&shares, None).unwrap().to_string(), mnemonic_str);
+        assert_eq!(reconstruct_mnemonic(&shares, None).unwrap().0.to_string(), mnemonic_str);
     }
 
     #[test]
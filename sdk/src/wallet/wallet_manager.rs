@@ -1,25 +1,38 @@
 //! This module contains the definition and implementation of the WalletManager trait.
 
+use super::instrumented::InstrumentedWallet;
+use super::kdbx::BackupMetadata;
 use super::share::Share;
 use crate::core::{Config, UserRepoT};
-use crate::types::newtypes::{AccessToken, EncryptionPin, EncryptionSalt, PlainPassword};
+use crate::telemetry::{ShareDownloadOutcome, TelemetryEvent, TelemetryHook};
+use crate::types::newtypes::{AccessToken, Bip39Passphrase, EncryptionPin, EncryptionSalt, PlainPassword};
+use crate::types::users::{PinAttempts, PinPolicy, UserEntity};
 use crate::wallet::error::{ErrorKind, Result, WalletError};
 use api_types::api::networks::{ApiNetwork, ApiProtocol};
 use async_trait::async_trait;
-use etopay_wallet::bip39::{self, Mnemonic};
+use chrono::{DateTime, Utc};
+use etopay_wallet::bip39::{Language, Mnemonic};
 use etopay_wallet::{MnemonicDerivationOption, WalletImplEvm, WalletImplEvmErc20, WalletImplIotaRebased, WalletUser};
-use log::{info, warn};
+use futures_util::future::join_all;
+use log::{debug, info, warn};
 use rand::RngCore;
 use secrecy::SecretBox;
-use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Represents borrowing a [`WalletUser`] instance with a lifetime connected to the wallet manager.
 /// This prevents wallets to be stored and used later by another user.
 pub struct WalletBorrow<'a> {
-    inner: Box<dyn WalletUser + Send + Sync>,
-    /// with this we "attach" a lifetime to this object even though it is not "needed"
-    _lifetime: std::marker::PhantomData<&'a ()>,
+    inner: WalletRef<'a>,
+}
+
+/// A wallet that is either created for a single call, or borrowed from an unlocked session.
+enum WalletRef<'a> {
+    Owned(Box<dyn WalletUser + Send + Sync>),
+    Borrowed(&'a Arc<dyn WalletUser + Send + Sync>),
 }
 
 #[cfg(test)]
@@ -27,23 +40,36 @@ impl WalletBorrow<'_> {
     /// test function to create [`WalletBorrow`] instances in mock objects
     pub fn from(inner: impl WalletUser + Send + Sync + 'static) -> Self {
         Self {
-            inner: Box::new(inner),
-            _lifetime: PhantomData,
+            inner: WalletRef::Owned(Box::new(inner)),
         }
     }
 }
 
 impl Deref for WalletBorrow<'_> {
-    type Target = Box<dyn WalletUser + Send + Sync>;
+    type Target = dyn WalletUser + Send + Sync;
 
     fn deref(&self) -> &Self::Target {
-        &self.inner
+        match &self.inner {
+            WalletRef::Owned(wallet) => wallet.as_ref(),
+            WalletRef::Borrowed(wallet) => wallet.as_ref(),
+        }
     }
 }
-impl DerefMut for WalletBorrow<'_> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
-    }
+
+/// Report the result of a share download to the telemetry
+fn record_share_download<T, E>(
+    telemetry: &TelemetryHook,
+    share: &'static str,
+    result: &std::result::Result<Option<T>, E>,
+) {
+    telemetry.record(|| TelemetryEvent::ShareDownload {
+        share,
+        outcome: match result {
+            Ok(Some(_)) => ShareDownloadOutcome::Downloaded,
+            Ok(None) => ShareDownloadOutcome::NotAvailable,
+            Err(_) => ShareDownloadOutcome::Failed,
+        },
+    });
 }
 
 /// Creates a wallet and returns an instance to work upon
@@ -57,16 +83,43 @@ pub trait WalletManager: std::fmt::Debug {
     /// Set the recovery share
     fn set_recovery_share(&mut self, share: Option<Share>);
 
-    /// Generate a new mnemonic and create shares. Returns the new mnemonic.
+    /// Set the [`PinPolicy`] enforced whenever the pin is used to reconstruct the mnemonic.
+    fn set_pin_policy(&mut self, policy: PinPolicy);
+
+    /// Start an unlocked session: until the `ttl` expires or [`WalletManager::lock`] is called,
+    /// the wallets created by [`WalletManager::try_get`] are kept in memory and reused.
+    fn unlock(&mut self, ttl: Duration);
+
+    /// End the unlocked session and drop all wallets kept in memory.
+    fn lock(&mut self);
+
+    /// Check if there is an unlocked session that has not expired yet. An expired session is
+    /// dropped right away, together with the wallets kept in memory.
+    fn is_unlocked(&mut self) -> bool;
+
+    /// When the unlocked session expires, or [`None`] if the wallet is locked. Unlike
+    /// [`WalletManager::is_unlocked`], an expired session is not dropped.
+    fn unlocked_until(&self) -> Option<DateTime<Utc>>;
+
+    /// Drop the wallet kept in the unlocked session for the network, e.g. because the node urls of
+    /// the network changed. The next call to [`WalletManager::try_get`] creates it again.
+    fn forget_unlocked_wallet(&mut self, network_key: &str);
+
+    /// Generate a new mnemonic using the wordlist of the given language and create shares. The
+    /// optional BIP39 passphrase is stored in the shares together with the mnemonic. Returns the new
+    /// mnemonic.
     async fn create_wallet_from_new_mnemonic(
         &mut self,
         config: &Config,
         access_token: &Option<AccessToken>,
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
+        language: Language,
+        passphrase: &Option<Bip39Passphrase>,
     ) -> Result<String>;
 
-    /// Create shares from a mnemonic
+    /// Create shares from a mnemonic in any supported wordlist language, which is detected
+    /// automatically. The optional BIP39 passphrase is stored in the shares together with the mnemonic.
     async fn create_wallet_from_existing_mnemonic(
         &mut self,
         config: &Config,
@@ -74,9 +127,10 @@ pub trait WalletManager: std::fmt::Debug {
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
         mnemonic: &str,
+        passphrase: &Option<Bip39Passphrase>,
     ) -> Result<()>;
 
-    /// Create shares from a kdbx backup byte stream
+    /// Create shares from a kdbx backup byte stream. Returns the metadata stored in the backup.
     async fn create_wallet_from_backup(
         &mut self,
         config: &Config,
@@ -85,9 +139,9 @@ pub trait WalletManager: std::fmt::Debug {
         pin: &EncryptionPin,
         backup: &[u8],
         backup_password: &PlainPassword,
-    ) -> Result<()>;
+    ) -> Result<BackupMetadata>;
 
-    /// Create kdbx backup bytes from shares
+    /// Create kdbx backup bytes from shares, including the provided metadata
     async fn create_wallet_backup(
         &mut self,
         config: &Config,
@@ -95,6 +149,7 @@ pub trait WalletManager: std::fmt::Debug {
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
         backup_password: &PlainPassword,
+        metadata: &BackupMetadata,
     ) -> Result<Vec<u8>>;
 
     /// deletes the user's wallet
@@ -127,7 +182,8 @@ pub trait WalletManager: std::fmt::Debug {
 
     /// Tries to instantiate a [`WalletUser`] object from shares and/or returns a mutable reference bound to
     /// the lifetime of this object. The same instance may be reused across several calls to
-    /// `try_get`, hence the lifetime is bound to the lifetime of `self`.
+    /// `try_get`, hence the lifetime is bound to the lifetime of `self`. This is the case while the
+    /// wallet is unlocked with [`WalletManager::unlock`].
     async fn try_get<'a>(
         &'a mut self,
         config: &mut Config,
@@ -137,6 +193,29 @@ pub trait WalletManager: std::fmt::Debug {
         pin: &EncryptionPin,
         options: &MnemonicDerivationOption,
     ) -> Result<WalletBorrow<'a>>;
+
+    /// Like [`WalletManager::try_get`], but for several networks with the derivation options to use
+    /// for each. The mnemonic is reconstructed at most once. Returns one result per network in the
+    /// same order, or an error if the mnemonic could not be reconstructed, e.g. because of a wrong pin,
+    /// or if a network is given more than once. The missing wallets are created concurrently.
+    async fn try_get_many<'a>(
+        &'a mut self,
+        config: &mut Config,
+        access_token: &Option<AccessToken>,
+        repo: &mut UserRepoT,
+        networks: &[(ApiNetwork, MnemonicDerivationOption)],
+        pin: &EncryptionPin,
+    ) -> Result<Vec<Result<WalletBorrow<'a>>>>;
+
+    /// Get the wallet of the unlocked session for the network, if it was already created by
+    /// [`WalletManager::try_get`] with the same options. Never needs the pin, hence returns
+    /// [`None`] if the wallet is locked or the session expired. The returned wallet can be used
+    /// without borrowing the wallet manager, and is only dropped once the caller drops it.
+    fn try_get_unlocked(
+        &self,
+        network: &ApiNetwork,
+        options: &MnemonicDerivationOption,
+    ) -> Option<Arc<dyn WalletUser + Send + Sync>>;
 }
 
 /// Implementation of [`WalletManager`] that uses the SSS schema to store and retrieve the mnemonic
@@ -148,6 +227,56 @@ pub struct WalletManagerImpl {
 
     /// The recovery share that the user should download
     pub recovery_share: Option<Share>,
+
+    /// The unlocked session, if any
+    session: Option<UnlockedSession>,
+
+    /// The policy protecting the pin against brute-force attacks
+    pin_policy: PinPolicy,
+}
+
+/// Wallets kept in memory while the wallet is unlocked, so that the shares do not need to be
+/// downloaded and the keys derived again on every call.
+///
+/// The private keys of the wallets are zeroized when the wallets are dropped, which happens when
+/// the session is zeroized, locked, expired or dropped, and no wallet returned by
+/// [`WalletManager::try_get_unlocked`] is in use anymore.
+struct UnlockedSession {
+    /// When the session expires
+    expires_at: DateTime<Utc>,
+    /// The wallets per network key, with the derivation options they were created with
+    wallets: HashMap<String, (MnemonicDerivationOption, Arc<dyn WalletUser + Send + Sync>)>,
+}
+
+impl UnlockedSession {
+    fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+}
+
+impl Zeroize for UnlockedSession {
+    fn zeroize(&mut self) {
+        self.expires_at = DateTime::<Utc>::MIN_UTC;
+        // dropping the wallets zeroizes their private keys
+        self.wallets.clear();
+    }
+}
+
+impl Drop for UnlockedSession {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for UnlockedSession {}
+
+impl std::fmt::Debug for UnlockedSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnlockedSession")
+            .field("expires_at", &self.expires_at)
+            .field("networks", &self.wallets.keys().collect::<Vec<_>>())
+            .finish()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -175,6 +304,16 @@ impl WalletManagerImpl {
         Self {
             username: username.into(),
             recovery_share: None,
+            session: None,
+            pin_policy: PinPolicy::default(),
+        }
+    }
+
+    /// Drop the unlocked session if it expired
+    fn lock_if_expired(&mut self) {
+        if self.session.as_ref().is_some_and(UnlockedSession::is_expired) {
+            info!("Unlocked session expired, locking wallet");
+            self.lock();
         }
     }
 
@@ -185,7 +324,7 @@ impl WalletManagerImpl {
         access_token: &Option<AccessToken>,
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
-    ) -> Result<(Mnemonic, Status)> {
+    ) -> Result<(Mnemonic, Option<Bip39Passphrase>, Status)> {
         info!("Initializing wallet for user from shares");
 
         let username = &self.username;
@@ -193,14 +332,13 @@ impl WalletManagerImpl {
 
         let user = repo.get(username)?;
 
-        // make sure the provided pin is valid (even though we currenctly do not need it unless
-        // decrypting some of the shares, but in the future we might want to encrypt the local
-        // share too, for example)
-        // let _ = user
-        //     .encrypted_password
-        //     .as_ref()
-        //     .ok_or(crate::Error::WalletNotInitialized(crate::WalletNotInitializedKind::MissingPassword))?
-        //     .decrypt(pin, &user.salt)?;
+        // every wallet operation goes through here, so this is where wrong pins are counted and
+        // the wallet is locked, even if the shares at hand do not need the password
+        if user.encrypted_password.is_some() {
+            check_pin(repo, &user, pin, &self.pin_policy)?;
+        } else if user.pin_attempts.locked {
+            return Err(WalletError::PinLocked);
+        }
 
         // check the availability of each share (in priority order of ease-of-use and availability)
 
@@ -229,7 +367,9 @@ impl WalletManagerImpl {
 
             // try getting the oauth share (not encrypted)
             if let Some(access_token) = &access_token {
-                match crate::backend::shares::download_recovery_share(config, access_token, username).await {
+                let result = crate::backend::shares::download_recovery_share(config, access_token, username).await;
+                record_share_download(&config.telemetry, "recovery", &result);
+                match result {
                     Ok(Some(share)) => {
                         available_shares.push(share);
                         recovery_share_available_with_user_action = false; // this share is now available
@@ -249,7 +389,9 @@ impl WalletManagerImpl {
         if available_shares.len() < 2 {
             if let Some(access_token) = &access_token {
                 // try to get it from the backend
-                match crate::backend::shares::download_backup_share(config, access_token, username).await {
+                let result = crate::backend::shares::download_backup_share(config, access_token, username).await;
+                record_share_download(&config.telemetry, "backup", &result);
+                match result {
                     Ok(Some(share)) => {
                         available_shares.push(share);
                         password_required = true;
@@ -293,8 +435,8 @@ impl WalletManagerImpl {
 
             let shares_ref = available_shares.iter().collect::<Vec<&Share>>();
 
-            // now we can finally try to recreate the mnemonic from the shares
-            let mnemonic = crate::share::reconstruct_mnemonic(
+            // now we can finally try to recreate the mnemonic and passphrase from the shares
+            let (mnemonic, passphrase) = crate::share::reconstruct_mnemonic(
                 &shares_ref,
                 password.as_ref().map(PlainPassword::into_secret).as_ref(),
             )?;
@@ -306,6 +448,7 @@ impl WalletManagerImpl {
                 // in the backup share anyways (which is the only reason this needs a password)
                 let shares = crate::share::create_shares_from_mnemonic(
                     &mnemonic,
+                    passphrase.as_ref(),
                     &SecretBox::new(String::from("dummy password").as_bytes().into()),
                 )?;
 
@@ -319,6 +462,7 @@ impl WalletManagerImpl {
 
             Ok((
                 mnemonic,
+                passphrase,
                 Status {
                     local: local_used,
                     recovery: recovery_used,
@@ -342,6 +486,7 @@ impl WalletManagerImpl {
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
         mnemonic: &Mnemonic,
+        passphrase: Option<&Bip39Passphrase>,
     ) -> Result<()> {
         log::info!("Creating and uploading shares");
 
@@ -352,12 +497,20 @@ impl WalletManagerImpl {
         };
 
         let password = encrypted_password.decrypt(pin, &user.salt)?;
-        let shares = crate::share::create_shares_from_mnemonic(mnemonic, &password.into_secret())?;
+        let shares = crate::share::create_shares_from_mnemonic(mnemonic, passphrase, &password.into_secret())?;
 
         log::info!("Shares created, storing local share");
         repo.set_local_share(&user.username, Some(&shares.local))?;
+
+        // the wallet was restored, so any pin lock is lifted
+        if user.pin_attempts != PinAttempts::default() {
+            repo.set_pin_attempts(&user.username, PinAttempts::default())?;
+        }
         self.recovery_share = Some(shares.recovery.clone());
 
+        // wallets of an unlocked session were created from the previous mnemonic
+        self.lock();
+
         if let Some(access_token) = access_token {
             log::info!("Uploading shares");
             crate::backend::shares::upload_shares(config, access_token, &shares.backup, &shares.recovery).await?;
@@ -367,6 +520,81 @@ impl WalletManagerImpl {
         }
         Ok(())
     }
+
+    /// Reconstructs the mnemonic from the shares and instantiates the [`WalletUser`] for the network.
+    async fn create_wallet_user(
+        &mut self,
+        config: &Config,
+        access_token: &Option<AccessToken>,
+        repo: &mut UserRepoT,
+        network: &ApiNetwork,
+        pin: &EncryptionPin,
+        options: &MnemonicDerivationOption,
+    ) -> Result<Box<dyn WalletUser + Send + Sync>> {
+        let (mnemonic, passphrase, _status) = self.try_resemble_shares(config, access_token, repo, pin).await?;
+        let passphrase = passphrase.as_ref().map(Bip39Passphrase::as_str).unwrap_or_default();
+
+        // we have the mnemonic and can now instantiate the WalletImpl
+        Self::instantiate_wallet(mnemonic, passphrase, network, options, &config.telemetry).await
+    }
+
+    /// Instantiate the [`WalletUser`] implementation for the protocol of the network
+    async fn instantiate_wallet(
+        mnemonic: Mnemonic,
+        passphrase: &str,
+        network: &ApiNetwork,
+        options: &MnemonicDerivationOption,
+        telemetry: &TelemetryHook,
+    ) -> Result<Box<dyn WalletUser + Send + Sync>> {
+        let bo = match &network.protocol {
+            ApiProtocol::Evm { chain_id } => {
+                let wallet = WalletImplEvm::new(
+                    mnemonic,
+                    passphrase,
+                    &network.node_urls,
+                    *chain_id,
+                    network.decimals,
+                    network.coin_type,
+                    options,
+                )?;
+                Box::new(wallet) as Box<dyn WalletUser + Sync + Send>
+            }
+            ApiProtocol::EvmERC20 {
+                chain_id,
+                contract_address,
+            } => {
+                let wallet = WalletImplEvmErc20::new(
+                    mnemonic,
+                    passphrase,
+                    &network.node_urls,
+                    *chain_id,
+                    network.decimals,
+                    network.coin_type,
+                    contract_address,
+                    options,
+                )?;
+                Box::new(wallet) as Box<dyn WalletUser + Sync + Send>
+            }
+            ApiProtocol::IotaRebased { coin_type } => {
+                let wallet = WalletImplIotaRebased::new(
+                    mnemonic,
+                    passphrase,
+                    coin_type,
+                    network.decimals,
+                    &network.node_urls,
+                    options,
+                )
+                .await?;
+                Box::new(wallet) as Box<dyn WalletUser + Sync + Send>
+            }
+        };
+
+        Ok(Box::new(InstrumentedWallet::new(
+            network.key.clone(),
+            bo,
+            telemetry.clone(),
+        )))
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -378,6 +606,62 @@ impl WalletManager for WalletManagerImpl {
     fn set_recovery_share(&mut self, share: Option<Share>) {
         self.recovery_share = share;
     }
+
+    fn set_pin_policy(&mut self, policy: PinPolicy) {
+        self.pin_policy = policy;
+    }
+
+    fn unlock(&mut self, ttl: Duration) {
+        let ttl = chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        let expires_at = Utc::now().checked_add_signed(ttl).unwrap_or(DateTime::<Utc>::MAX_UTC);
+        info!("Unlocking wallet until {expires_at}");
+
+        match &mut self.session {
+            Some(session) => session.expires_at = expires_at,
+            None => {
+                self.session = Some(UnlockedSession {
+                    expires_at,
+                    wallets: HashMap::new(),
+                })
+            }
+        }
+    }
+
+    fn lock(&mut self) {
+        if let Some(mut session) = self.session.take() {
+            session.zeroize();
+            info!("Wallet locked");
+        }
+    }
+
+    fn is_unlocked(&mut self) -> bool {
+        self.lock_if_expired();
+        self.session.is_some()
+    }
+
+    fn forget_unlocked_wallet(&mut self, network_key: &str) {
+        if let Some(session) = &mut self.session {
+            if session.wallets.remove(network_key).is_some() {
+                debug!("Dropped unlocked wallet for network {network_key}");
+            }
+        }
+    }
+
+    fn unlocked_until(&self) -> Option<DateTime<Utc>> {
+        self.session.as_ref().map(|session| session.expires_at)
+    }
+
+    fn try_get_unlocked(
+        &self,
+        network: &ApiNetwork,
+        options: &MnemonicDerivationOption,
+    ) -> Option<Arc<dyn WalletUser + Send + Sync>> {
+        let session = self.session.as_ref().filter(|session| !session.is_expired())?;
+        match session.wallets.get(&network.key) {
+            Some((cached_options, wallet)) if cached_options == options => Some(wallet.clone()),
+            _ => None,
+        }
+    }
     /// Generate a new mnemonic and create shares. Returns the new mnemonic.
     async fn create_wallet_from_new_mnemonic(
         &mut self,
@@ -385,6 +669,8 @@ impl WalletManager for WalletManagerImpl {
         access_token: &Option<AccessToken>,
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
+        language: Language,
+        passphrase: &Option<Bip39Passphrase>,
     ) -> Result<String> {
         let bytes = {
             let mut rng = rand::rng();
@@ -393,8 +679,8 @@ impl WalletManager for WalletManagerImpl {
             bytes
         };
 
-        let mnemonic = Mnemonic::from_entropy(&bytes, bip39::Language::English)?;
-        self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic)
+        let mnemonic = Mnemonic::from_entropy(&bytes, language)?;
+        self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic, passphrase.as_ref())
             .await?;
 
         Ok(mnemonic.phrase().to_string())
@@ -408,9 +694,10 @@ impl WalletManager for WalletManagerImpl {
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
         mnemonic: &str,
+        passphrase: &Option<Bip39Passphrase>,
     ) -> Result<()> {
-        let mnemonic = Mnemonic::from_phrase(mnemonic, bip39::Language::English)?;
-        self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic)
+        let mnemonic = crate::mnemonic::parse_mnemonic(mnemonic)?;
+        self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic, passphrase.as_ref())
             .await
     }
 
@@ -423,10 +710,22 @@ impl WalletManager for WalletManagerImpl {
         pin: &EncryptionPin,
         backup: &[u8],
         backup_password: &PlainPassword,
-    ) -> Result<()> {
-        let mnemonic = crate::kdbx::load_mnemonic(backup, &backup_password.into_secret_string())?;
-        self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic)
-            .await
+    ) -> Result<BackupMetadata> {
+        let backup = crate::kdbx::load_backup(backup, &backup_password.into_secret_string())?;
+        info!(
+            "Restoring wallet from backup version {} created at {:?}",
+            backup.version, backup.created_at
+        );
+        self.create_and_upload_shares(
+            config,
+            access_token,
+            repo,
+            pin,
+            &backup.mnemonic,
+            backup.passphrase.as_ref(),
+        )
+        .await?;
+        Ok(backup.metadata)
     }
 
     /// Create kdbx backup bytes from shares
@@ -437,11 +736,14 @@ impl WalletManager for WalletManagerImpl {
         repo: &mut UserRepoT,
         pin: &EncryptionPin,
         backup_password: &PlainPassword,
+        metadata: &BackupMetadata,
     ) -> Result<Vec<u8>> {
-        let (mnemonic, _status) = self.try_resemble_shares(config, access_token, repo, pin).await?;
+        let (mnemonic, passphrase, _status) = self.try_resemble_shares(config, access_token, repo, pin).await?;
 
-        Ok(crate::kdbx::store_mnemonic(
+        Ok(crate::kdbx::store_backup(
             &mnemonic,
+            passphrase.as_ref(),
+            metadata,
             &backup_password.into_secret_string(),
         )?)
     }
@@ -464,6 +766,7 @@ impl WalletManager for WalletManagerImpl {
         // clear the local and recovery share
         repo.set_local_share(&self.username, None)?;
         self.recovery_share = None;
+        self.lock();
 
         // call backend if access_token exists
         if let Some(access_token) = access_token {
@@ -483,10 +786,11 @@ impl WalletManager for WalletManagerImpl {
     ) -> Result<bool> {
         // first use the existing pin and stored (encrypted) password to resemble the shares into
         // the mnemonic
-        let (existing_mnemonic, _status) = self.try_resemble_shares(config, access_token, repo, pin).await?;
+        let (existing_mnemonic, _passphrase, _status) =
+            self.try_resemble_shares(config, access_token, repo, pin).await?;
 
-        // perform a str-str comparison
-        Ok(mnemonic == existing_mnemonic.phrase())
+        // perform a str-str comparison of the normalized phrases
+        Ok(crate::mnemonic::parse_mnemonic(mnemonic).is_ok_and(|m| m.phrase() == existing_mnemonic.phrase()))
     }
 
     async fn change_wallet_password(
@@ -519,8 +823,8 @@ impl WalletManager for WalletManagerImpl {
         repo.update(&user)?;
 
         // and if we need to reconstruct the shares, do it!
-        if let Ok((mnemonic, _status)) = result {
-            self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic)
+        if let Ok((mnemonic, passphrase, _status)) = result {
+            self.create_and_upload_shares(config, access_token, repo, pin, &mnemonic, passphrase.as_ref())
                 .await?;
         }
 
@@ -536,47 +840,190 @@ impl WalletManager for WalletManagerImpl {
         pin: &EncryptionPin,
         options: &MnemonicDerivationOption,
     ) -> Result<WalletBorrow<'a>> {
-        let (mnemonic, _status) = self.try_resemble_shares(config, access_token, repo, pin).await?;
+        self.lock_if_expired();
 
-        // we have the mnemonic and can now instantiate the WalletImpl
-        let bo = match &network.protocol {
-            ApiProtocol::Evm { chain_id } => {
-                let wallet = WalletImplEvm::new(
-                    mnemonic,
-                    &network.node_urls,
-                    *chain_id,
-                    network.decimals,
-                    network.coin_type,
-                    options,
-                )?;
-                Box::new(wallet) as Box<dyn WalletUser + Sync + Send>
+        let Some(session) = &self.session else {
+            let wallet = self
+                .create_wallet_user(config, access_token, repo, network, pin, options)
+                .await?;
+            return Ok(WalletBorrow {
+                inner: WalletRef::Owned(wallet),
+            });
+        };
+
+        // reuse the wallet of the unlocked session, or create it if it does not exist yet
+        let cached = session
+            .wallets
+            .get(&network.key)
+            .is_some_and(|(cached_options, _)| cached_options == options);
+        if !cached {
+            let wallet = self
+                .create_wallet_user(config, access_token, repo, network, pin, options)
+                .await?;
+            if let Some(session) = &mut self.session {
+                session.wallets.insert(network.key.clone(), (*options, wallet.into()));
             }
-            ApiProtocol::EvmERC20 {
-                chain_id,
-                contract_address,
-            } => {
-                let wallet = WalletImplEvmErc20::new(
-                    mnemonic,
-                    &network.node_urls,
-                    *chain_id,
-                    network.decimals,
-                    network.coin_type,
-                    contract_address,
-                    options,
-                )?;
-                Box::new(wallet) as Box<dyn WalletUser + Sync + Send>
+        }
+
+        let (_, wallet) = self
+            .session
+            .as_mut()
+            .and_then(|session| session.wallets.get_mut(&network.key))
+            .ok_or(WalletError::WalletNotInitialized(ErrorKind::UseMnemonic))?;
+        Ok(WalletBorrow {
+            inner: WalletRef::Borrowed(wallet),
+        })
+    }
+
+    async fn try_get_many<'a>(
+        &'a mut self,
+        config: &mut Config,
+        access_token: &Option<AccessToken>,
+        repo: &mut UserRepoT,
+        networks: &[(ApiNetwork, MnemonicDerivationOption)],
+        pin: &EncryptionPin,
+    ) -> Result<Vec<Result<WalletBorrow<'a>>>> {
+        self.lock_if_expired();
+
+        let mut network_keys = HashSet::with_capacity(networks.len());
+        for (network, _) in networks {
+            if !network_keys.insert(network.key.as_str()) {
+                return Err(WalletError::DuplicateNetwork(network.key.clone()));
             }
-            ApiProtocol::IotaRebased { coin_type } => {
-                let wallet =
-                    WalletImplIotaRebased::new(mnemonic, coin_type, network.decimals, &network.node_urls, options)
-                        .await?;
-                Box::new(wallet) as Box<dyn WalletUser + Sync + Send>
+        }
+
+        // only create the wallets that are not kept in the unlocked session yet
+        let missing = networks
+            .iter()
+            .filter(|(network, options)| {
+                !self.session.as_ref().is_some_and(|session| {
+                    session
+                        .wallets
+                        .get(&network.key)
+                        .is_some_and(|(cached_options, _)| cached_options == options)
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut created = HashMap::with_capacity(missing.len());
+        if !missing.is_empty() {
+            let (mnemonic, passphrase, _status) = self.try_resemble_shares(config, access_token, repo, pin).await?;
+            let passphrase = passphrase.as_ref().map(Bip39Passphrase::as_str).unwrap_or_default();
+            let wallets = join_all(missing.iter().map(|(network, options)| {
+                Self::instantiate_wallet(mnemonic.clone(), passphrase, network, options, &config.telemetry)
+            }))
+            .await;
+            for ((network, options), wallet) in missing.into_iter().zip(wallets) {
+                created.insert(network.key.as_str(), (*options, wallet));
+            }
+        }
+
+        let Some(session) = &mut self.session else {
+            let mut wallets = Vec::with_capacity(networks.len());
+            for (network, _) in networks {
+                let wallet = match created.remove(network.key.as_str()) {
+                    Some((_, wallet)) => wallet.map(|wallet| WalletBorrow {
+                        inner: WalletRef::Owned(wallet),
+                    }),
+                    None => Err(WalletError::WalletNotInitialized(ErrorKind::UseMnemonic)),
+                };
+                wallets.push(wallet);
             }
+            return Ok(wallets);
         };
 
-        Ok(WalletBorrow {
-            inner: bo,
-            _lifetime: PhantomData,
+        let mut failures = HashMap::new();
+        for (network_key, (options, wallet)) in created {
+            match wallet {
+                Ok(wallet) => {
+                    session
+                        .wallets
+                        .insert(network_key.to_string(), (options, wallet.into()));
+                }
+                Err(e) => {
+                    failures.insert(network_key, e);
+                }
+            }
+        }
+
+        let session = &*session;
+        let mut wallets = Vec::with_capacity(networks.len());
+        for (network, _) in networks {
+            let wallet = match failures.remove(network.key.as_str()) {
+                Some(e) => Err(e),
+                None => match session.wallets.get(&network.key) {
+                    Some((_, wallet)) => Ok(WalletBorrow {
+                        inner: WalletRef::Borrowed(wallet),
+                    }),
+                    None => Err(WalletError::WalletNotInitialized(ErrorKind::UseMnemonic)),
+                },
+            };
+            wallets.push(wallet);
+        }
+        Ok(wallets)
+    }
+}
+
+/// Verifies the pin by decrypting the password of the user, enforcing the [`PinPolicy`]. Wrong
+/// attempts are persisted in the user repository, a correct pin resets them.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_pin(
+    repo: &mut UserRepoT,
+    user: &UserEntity,
+    pin: &EncryptionPin,
+    policy: &PinPolicy,
+) -> Result<()> {
+    let attempts = &user.pin_attempts;
+    if attempts.locked || attempts.failed_attempts >= policy.max_attempts {
+        return Err(WalletError::PinLocked);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    if let (Some(backoff), Some(last_failed_at)) = (policy.backoff(attempts.failed_attempts), attempts.last_failed_at) {
+        let retry_at = last_failed_at.saturating_add(i64::try_from(backoff.as_secs()).unwrap_or(i64::MAX));
+        if now < retry_at {
+            return Err(WalletError::PinBackoff {
+                retry_after_secs: retry_at.abs_diff(now),
+            });
+        }
+    }
+
+    let Some(encrypted_password) = &user.encrypted_password else {
+        return Err(WalletError::WalletNotInitialized(ErrorKind::MissingPassword));
+    };
+
+    if encrypted_password.decrypt(pin, &user.salt).is_ok() {
+        if *attempts != PinAttempts::default() {
+            repo.set_pin_attempts(&user.username, PinAttempts::default())?;
+        }
+        return Ok(());
+    }
+
+    let failed_attempts = attempts.failed_attempts.saturating_add(1);
+    let locked = failed_attempts >= policy.max_attempts;
+    warn!("Wrong pin entered, {failed_attempts} failed attempts");
+    repo.set_pin_attempts(
+        &user.username,
+        PinAttempts {
+            failed_attempts,
+            last_failed_at: Some(now),
+            locked,
+        },
+    )?;
+
+    if policy
+        .wipe_local_share_after
+        .is_some_and(|limit| failed_attempts >= limit)
+        && user.local_share.is_some()
+    {
+        warn!("Too many wrong pin attempts, deleting the local share");
+        repo.set_local_share(&user.username, None)?;
+    }
+
+    if locked {
+        Err(WalletError::PinLocked)
+    } else {
+        Err(WalletError::WrongPin {
+            remaining_attempts: policy.max_attempts - failed_attempts,
         })
     }
 }
@@ -587,10 +1034,13 @@ mod tests {
     use crate::{
         core::{Config, UserRepoT},
         kdbx::KdbxStorageError,
-        testing_utils::{ENCRYPTED_WALLET_PASSWORD, IOTA_NETWORK_KEY, PIN, SALT, WALLET_PASSWORD, example_api_network},
+        testing_utils::{
+            ENCRYPTED_WALLET_PASSWORD, ETH_NETWORK_KEY, IOTA_NETWORK_KEY, PIN, WALLET_PASSWORD, example_api_network,
+            example_user_entity,
+        },
         types::{
             newtypes::{AccessToken, EncryptionPin, EncryptionSalt, PlainPassword},
-            users::KycType,
+            users::UserEntity,
         },
         user::{MockUserRepo, memory_storage::MemoryUserStorage, repository::UserRepoImpl},
     };
@@ -612,17 +1062,9 @@ mod tests {
 
     fn get_user_repo() -> (&'static EncryptionPin, UserRepoT) {
         let mut repo = Box::new(UserRepoImpl::new(MemoryUserStorage::new())) as UserRepoT;
-        repo.create(&crate::types::users::UserEntity {
-            user_id: None,
-            username: USERNAME.to_string(),
+        repo.create(&UserEntity {
             encrypted_password: Some(ENCRYPTED_WALLET_PASSWORD.clone()),
-            salt: SALT.into(),
-            is_kyc_verified: false,
-            kyc_type: KycType::Undefined,
-            viviswap_state: None,
-            local_share: None,
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            ..example_user_entity(USERNAME)
         })
         .unwrap();
 
@@ -642,7 +1084,7 @@ mod tests {
 
         // Act
         let result = manager
-            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, mnemonic)
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, mnemonic, &None)
             .await;
 
         // Assert
@@ -654,6 +1096,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_create_wallet_from_new_japanese_mnemonic() {
+        // Arrange
+        let (config, _cleanup) = Config::new_test_with_cleanup();
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+
+        // Act
+        let mnemonic = manager
+            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin, Language::Japanese, &None)
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            crate::mnemonic::parse_mnemonic(&mnemonic).unwrap().language(),
+            Language::Japanese
+        );
+        let matches = manager
+            .check_mnemonic(&config, &None, &mut repo, pin, &mnemonic)
+            .await
+            .unwrap();
+        assert!(matches);
+    }
+
     #[rstest]
     #[case(&WALLET_PASSWORD, Ok(()))]
     #[case(&INVALID_BACKUP_PASSWORD, Err(WalletError::KdbxStorage(KdbxStorageError::UnlockError(UnlockError::HmacInvalid))))]
@@ -666,13 +1133,19 @@ mod tests {
 
         // Create wallet
         manager
-            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin)
+            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin, Language::English, &None)
             .await
             .expect("failed to create new wallet");
 
         // Create backup
+        let metadata = BackupMetadata {
+            derivation_options: MnemonicDerivationOption { account: 0, index: 1 },
+            active_network: Some(IOTA_NETWORK_KEY.to_string()),
+            networks: vec![IOTA_NETWORK_KEY.to_string()],
+            ..Default::default()
+        };
         let backup = manager
-            .create_wallet_backup(&config, &None, &mut repo, pin, &WALLET_PASSWORD)
+            .create_wallet_backup(&config, &None, &mut repo, pin, &WALLET_PASSWORD, &metadata)
             .await
             .expect("failed to create backup");
 
@@ -683,13 +1156,349 @@ mod tests {
 
         // Assert
         match should_succeed {
-            Ok(_) => restore_result.unwrap(),
+            Ok(_) => assert_eq!(restore_result.unwrap(), metadata),
             Err(ref expected_err) => {
                 assert_eq!(restore_result.err().unwrap().to_string(), expected_err.to_string());
             }
         }
     }
 
+    #[tokio::test]
+    async fn test_passphrase_is_applied_and_restored_from_backup_and_shares() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let passphrase = Some(Bip39Passphrase::try_from_string("25th word").unwrap());
+
+        let mut manager_without = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo_without) = get_user_repo();
+        manager_without
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo_without, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &passphrase)
+            .await
+            .unwrap();
+        let backup = manager
+            .create_wallet_backup(
+                &config,
+                &None,
+                &mut repo,
+                pin,
+                &WALLET_PASSWORD,
+                &BackupMetadata::default(),
+            )
+            .await
+            .unwrap();
+
+        let mut manager_restored = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo_restored) = get_user_repo();
+        manager_restored
+            .create_wallet_from_backup(&config, &None, &mut repo_restored, pin, &backup, &WALLET_PASSWORD)
+            .await
+            .unwrap();
+
+        // a new device only gets the shares, the passphrase has to come with them
+        let mut manager_from_shares = WalletManagerImpl::new(USERNAME);
+        manager_from_shares.set_recovery_share(manager.get_recovery_share());
+        let (pin, mut repo_from_shares) = get_user_repo();
+        let local_share = repo.get(USERNAME).unwrap().local_share.unwrap();
+        repo_from_shares
+            .set_local_share(USERNAME, Some(&local_share.parse().unwrap()))
+            .unwrap();
+
+        // Act
+        let address_without = manager_without
+            .try_get(
+                &mut config,
+                &None,
+                &mut repo_without,
+                &network,
+                pin,
+                &Default::default(),
+            )
+            .await
+            .unwrap()
+            .get_address()
+            .await
+            .unwrap();
+        let address = manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap()
+            .get_address()
+            .await
+            .unwrap();
+        let address_restored = manager_restored
+            .try_get(
+                &mut config,
+                &None,
+                &mut repo_restored,
+                &network,
+                pin,
+                &Default::default(),
+            )
+            .await
+            .unwrap()
+            .get_address()
+            .await
+            .unwrap();
+
+        let address_from_shares = manager_from_shares
+            .try_get(
+                &mut config,
+                &None,
+                &mut repo_from_shares,
+                &network,
+                pin,
+                &Default::default(),
+            )
+            .await
+            .unwrap()
+            .get_address()
+            .await
+            .unwrap();
+
+        // Assert
+        assert_ne!(address, address_without);
+        assert_eq!(address, address_restored);
+        assert_eq!(address, address_from_shares);
+    }
+
+    #[tokio::test]
+    async fn test_unlocked_session_reuses_wallet() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+
+        // Act
+        manager.unlock(Duration::from_secs(60));
+        let address = manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap()
+            .get_address()
+            .await
+            .unwrap();
+
+        // without the local share, the wallet can only be used from the unlocked session
+        repo.set_local_share(USERNAME, None).unwrap();
+        let cached_address = manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap()
+            .get_address()
+            .await
+            .unwrap();
+
+        // Assert
+        assert!(manager.is_unlocked());
+        assert_eq!(address, cached_address);
+
+        manager.lock();
+        assert!(!manager.is_unlocked());
+        let result = manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forget_unlocked_wallet() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+        manager.unlock(Duration::from_secs(60));
+        manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap();
+        assert!(manager.try_get_unlocked(&network, &Default::default()).is_some());
+
+        // Act
+        manager.forget_unlocked_wallet(&network.key);
+
+        // Assert
+        assert!(manager.is_unlocked());
+        assert!(manager.try_get_unlocked(&network, &Default::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_try_get_many_reuses_unlocked_wallets() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let mut second_network = example_api_network(ETH_NETWORK_KEY.to_string());
+        second_network.key = String::from("ETH_2");
+        let networks = [
+            (example_api_network(ETH_NETWORK_KEY.to_string()), Default::default()),
+            (second_network, MnemonicDerivationOption { account: 0, index: 1 }),
+        ];
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+
+        // Act
+        manager.unlock(Duration::from_secs(60));
+        let mut addresses = Vec::new();
+        for wallet in manager
+            .try_get_many(&mut config, &None, &mut repo, &networks, pin)
+            .await
+            .unwrap()
+        {
+            addresses.push(wallet.unwrap().get_address().await.unwrap());
+        }
+
+        // without the local share, the wallets can only be used from the unlocked session
+        repo.set_local_share(USERNAME, None).unwrap();
+        let mut cached_addresses = Vec::new();
+        for wallet in manager
+            .try_get_many(&mut config, &None, &mut repo, &networks, pin)
+            .await
+            .unwrap()
+        {
+            cached_addresses.push(wallet.unwrap().get_address().await.unwrap());
+        }
+
+        // Assert
+        assert_eq!(addresses.len(), 2);
+        assert_ne!(addresses[0], addresses[1]);
+        assert_eq!(addresses, cached_addresses);
+
+        manager.lock();
+        let result = manager
+            .try_get_many(&mut config, &None, &mut repo, &networks, pin)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_try_get_many_rejects_duplicate_networks() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let networks = [
+            (network.clone(), Default::default()),
+            (network, MnemonicDerivationOption { account: 0, index: 1 }),
+        ];
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+        manager.unlock(Duration::from_secs(60));
+
+        // Act
+        let result = manager
+            .try_get_many(&mut config, &None, &mut repo, &networks, pin)
+            .await;
+
+        // Assert
+        assert!(matches!(result, Err(WalletError::DuplicateNetwork(key)) if key == ETH_NETWORK_KEY));
+    }
+
+    #[tokio::test]
+    async fn test_unlocked_session_expires() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+
+        // Act
+        manager.unlock(Duration::ZERO);
+        manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap();
+        repo.set_local_share(USERNAME, None).unwrap();
+        let result = manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await;
+
+        // Assert
+        assert!(result.is_err());
+        assert!(!manager.is_unlocked());
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_is_dropped_when_checked() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+        manager.unlock(Duration::from_secs(60));
+        manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap();
+
+        // Act
+        if let Some(session) = &mut manager.session {
+            session.expires_at = Utc::now();
+        }
+        let unlocked = manager.is_unlocked();
+
+        // Assert
+        assert!(!unlocked);
+        assert!(manager.session.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zeroize_session_drops_wallets() {
+        // Arrange
+        let (mut config, _cleanup) = Config::new_test_with_cleanup();
+        let network = example_api_network(ETH_NETWORK_KEY.to_string());
+        let mut manager = WalletManagerImpl::new(USERNAME);
+        let (pin, mut repo) = get_user_repo();
+        manager
+            .create_wallet_from_existing_mnemonic(&config, &None, &mut repo, pin, MNEMONIC, &None)
+            .await
+            .unwrap();
+        manager.unlock(Duration::from_secs(60));
+        manager
+            .try_get(&mut config, &None, &mut repo, &network, pin, &Default::default())
+            .await
+            .unwrap();
+        let mut session = manager.session.take().unwrap();
+        assert_eq!(session.wallets.len(), 1);
+
+        // Act
+        session.zeroize();
+
+        // Assert
+        assert!(session.wallets.is_empty());
+        assert!(session.is_expired());
+    }
+
     #[tokio::test]
     async fn test_change_password() {
         //Arrange
@@ -699,7 +1508,7 @@ mod tests {
 
         // create a wallet
         manager
-            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin)
+            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin, Language::English, &None)
             .await
             .expect("should succeed to create new wallet");
 
@@ -735,7 +1544,7 @@ mod tests {
 
         // create a wallet
         manager
-            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin)
+            .create_wallet_from_new_mnemonic(&config, &None, &mut repo, pin, Language::English, &None)
             .await
             .expect("should succeed to create new wallet");
 
@@ -923,17 +1732,12 @@ mod tests {
         let pin = EncryptionPin::try_from_string("123456").unwrap();
         let encrypted_password =
             password.map(|s| PlainPassword::try_from_string(s).unwrap().encrypt(&pin, &salt).unwrap());
-        let user = crate::types::users::UserEntity {
-            user_id: None,
-            username: "share_user".to_string(),
+        let user = UserEntity {
             encrypted_password,
             salt,
             is_kyc_verified: true,
-            kyc_type: KycType::Undefined,
-            viviswap_state: None,
             local_share: local_share.map(|s| s.to_string()),
-            wallet_transactions: Vec::new(),
-            wallet_transactions_versioned: Vec::new(),
+            ..example_user_entity("share_user")
         };
 
         repo.expect_get().returning(move |_| Ok(user.clone()));
@@ -951,7 +1755,7 @@ mod tests {
         let result = manager
             .try_resemble_shares(&config, &access_token, &mut repo, &pin)
             .await
-            .map(|(_mnemonic, status)| status);
+            .map(|(_mnemonic, _passphrase, status)| status);
 
         // Assert
         match (&result, expected_result) {
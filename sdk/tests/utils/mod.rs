@@ -18,12 +18,12 @@ pub async fn init_sdk_with_cleanup(username: &str, existing_cleanup: CleanUp) ->
         std::env::var("RT_API_URL").expect("RT_API_URL should be set with the backend url for the tests to use");
 
     // construct the config to use for the SDK
-    let config = Config {
-        backend_url: backend_url.parse().expect("RT_API_URL must be a valid URL"),
-        path_prefix: Path::new(&existing_cleanup.path_prefix).into(),
-        auth_provider: "standalone".to_string(),
-        log_level: log::LevelFilter::Debug,
-    };
+    let config = Config::new(
+        Path::new(&existing_cleanup.path_prefix),
+        "standalone",
+        backend_url.parse().expect("RT_API_URL must be a valid URL"),
+        log::LevelFilter::Debug,
+    );
 
     let mut sdk = Sdk::new(config).expect("should not fail to initialize sdk"); // set the backend url if the environment variable is set
 